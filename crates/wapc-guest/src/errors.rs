@@ -21,18 +21,42 @@ use std::fmt;
 
 /// This crate's Error type
 #[derive(Debug)]
-pub struct Error(ErrorKind);
+pub struct Error {
+  kind: ErrorKind,
+  // the `binding/namespace/operation` of the host call that failed
+  call: Option<String>,
+}
 
 /// Create a new [Error] of the passed kind.
 #[must_use]
 pub fn new(kind: ErrorKind) -> Error {
-  Error(kind)
+  Error { kind, call: None }
+}
+
+// Create the error of a failed host call, keeping the context of the call
+pub(crate) fn host_call(binding: &str, namespace: &str, operation: &str, error: Vec<u8>) -> Error {
+  Error {
+    kind: ErrorKind::HostError(error),
+    call: Some(format!("{}/{}/{}", binding, namespace, operation)),
+  }
+}
+
+impl Error {
+  /// The `binding/namespace/operation` of the failed host call, e.g. `db/sql/query`, for the
+  /// errors of kind [ErrorKind::HostError].
+  #[must_use]
+  pub fn call(&self) -> Option<&str> {
+    self.call.as_deref()
+  }
 }
 
 /// The kinds of errors this crate returns.
 #[derive(Debug)]
 pub enum ErrorKind {
-  /// Error returned when a host call fails.
+  /// Error returned when a host call fails, holding the error text given by the host. When the
+  /// host enables the context of its errors, the text starts with the `binding/namespace/operation`
+  /// of the failed call, e.g. `db/sql/query: timeout`. Otherwise the call is available via
+  /// [Error::call].
   HostError(Vec<u8>),
 }
 
@@ -40,8 +64,27 @@ impl StdError for Error {}
 
 impl fmt::Display for Error {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-    match self.0 {
+    match self.kind {
       ErrorKind::HostError(ref e) => write!(f, "Host error: {}", String::from_utf8_lossy(e)),
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn host_error_is_displayed_as_sent_by_the_host() {
+    let error = host_call("db", "sql", "query", b"timeout".to_vec());
+    assert_eq!(error.to_string(), "Host error: timeout");
+    assert_eq!(error.call(), Some("db/sql/query"));
+  }
+
+  #[test]
+  fn host_error_keeps_the_context_sent_by_the_host() {
+    let error = host_call("db", "sql", "query", b"db/sql/query: timeout".to_vec());
+    assert_eq!(error.to_string(), "Host error: db/sql/query: timeout");
+    assert_eq!(error.call(), Some("db/sql/query"));
+  }
+}
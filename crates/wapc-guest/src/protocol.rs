@@ -121,7 +121,7 @@ pub fn host_call(binding: &str, ns: &str, op: &str, msg: &[u8]) -> CallResult {
       buf.set_len(errlen);
     }
 
-    Err(Box::new(errors::new(errors::ErrorKind::HostError(buf))))
+    Err(Box::new(errors::host_call(binding, ns, op, buf)))
   } else {
     // call succeeded
     #[allow(unsafe_code)]
@@ -1,25 +1,33 @@
 use std::fs::read;
 
-use wapc::WapcHost;
+use wapc::{WapcHost, WebAssemblyEngineProvider};
 use wapc_codec::messagepack::{deserialize, serialize};
 use wapc_pool::HostPoolBuilder;
+use wasmtime_provider::EnginePre;
+
+// Works with any pre initialized provider whose instances can back a WapcHost
+fn host_factory<P>(pre: P) -> impl Fn() -> WapcHost + Send + Sync + 'static
+where
+  P: EnginePre + Send + Sync + 'static,
+  P::Provider: WebAssemblyEngineProvider + 'static,
+{
+  move || WapcHost::new(Box::new(pre.rehydrate().unwrap()), None).unwrap()
+}
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
   let buf = read("./wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm")?;
 
-  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+  let pre = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
     .module_bytes(&buf)
-    .build()?;
+    .build_pre()?;
+  println!("Module hash: {}", pre.module_hash().unwrap_or("unknown"));
 
   let pool = HostPoolBuilder::new()
     .name("pool example")
-    .factory(move || {
-      let engine = engine.clone();
-      WapcHost::new(Box::new(engine), None).unwrap()
-    })
+    .factory(host_factory(pre))
     .max_threads(5)
-    .build();
+    .build()?;
 
   let bytes = pool.call("echo", serialize("Hello!")?).await?;
 
@@ -7,6 +7,59 @@ pub enum Error {
   #[error("Request failed: {0}")]
   RequestFailed(String),
 
+  /// Error returned when a worker panicked during the call.
+  #[error("Worker panicked during the call: {0}")]
+  WorkerPanicked(String),
+
+  /// Error returned when a call didn't return within its timeout.
+  #[error("Call timed out after {0:?}")]
+  CallTimeout(std::time::Duration),
+
+  /// Error returned when calling a pool that is shutting down or has shut down.
+  #[error("The pool is closed and accepts no new calls")]
+  PoolClosed,
+
+  /// Error returned to the calls not completed when the pool shuts down.
+  #[error("The pool shut down before completing the call")]
+  PoolShutdown,
+
+  /// Error returned when the pool is saturated and its [crate::SaturationPolicy] rejects the call.
+  #[error("The pool is saturated: every worker is busy and the queue is full")]
+  PoolSaturated,
+
+  /// Error returned when the concurrency limit of the operation is reached and the
+  /// [crate::SaturationPolicy] of the pool rejects the call.
+  #[error("Too many calls of {0} are running")]
+  ConcurrencyLimitReached(String),
+
+  /// Error returned when a sticky call is made on a pool without permanent workers.
+  #[error("Sticky calls need a pool with at least one permanent worker (min_threads)")]
+  NoStickyWorkers,
+
+  /// Error returned when the queue of the worker serving a sticky call stayed full.
+  #[error("The queue of the worker serving the sticky call is full")]
+  StickyQueueFull,
+
+  /// Error returned when some workers failed to replace their module.
+  #[error("Failed to replace the module of workers {}", .0.join(", "))]
+  ReplaceModuleFailed(Vec<String>),
+
+  /// Error returned when the warm-up call failed on some workers.
+  #[error("Failed to warm up workers {}", .0.join(", "))]
+  WarmUpFailed(Vec<String>),
+
+  /// Error returned when building a pool without a factory function.
+  #[error("A waPC host pool must have a factory function")]
+  MissingFactory,
+
+  /// Error returned when the settings of a [crate::HostPoolBuilder] are inconsistent.
+  #[error("Invalid HostPoolBuilder configuration: {0}")]
+  BuilderInvalidConfig(String),
+
+  /// Error returned when resizing a [crate::HostPool] with inconsistent limits.
+  #[error("Invalid pool size: {0}")]
+  InvalidPoolSize(String),
+
   /// Error returned when trying to shutdown a pool that's uninitialized or already shut down.
   #[error("No pool available. Have you initialized the HostPool or already shut it down?")]
   NoPool,
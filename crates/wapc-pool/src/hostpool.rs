@@ -1,14 +1,188 @@
 type Result<T> = std::result::Result<T, wapc::errors::Error>;
 
-use std::sync::Arc;
-use std::time::Duration;
+use std::cell::Cell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll, Wake};
+use std::time::{Duration, Instant};
 
-use crossbeam::channel::{Receiver as SyncReceiver, SendTimeoutError, Sender as SyncSender};
+use crossbeam::channel::{Receiver as SyncReceiver, Select, SendTimeoutError, Sender as SyncSender};
 use rusty_pool::ThreadPool;
-use tokio::sync::oneshot::Sender as OneshotSender;
-use wapc::WapcHost;
+use tokio::sync::oneshot::{Receiver as OneshotReceiver, Sender as OneshotSender};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use wapc::{HostCallback, WapcHost};
 
 use crate::errors::Error;
+use crate::instrument;
+use crate::metrics::{Counters, PoolMetrics, WorkerHealth, WorkerStats};
+use crate::watchdog::Executions;
+
+type CallResult = std::result::Result<Vec<u8>, wapc::errors::Error>;
+type DetailedResult = std::result::Result<CallDetails, wapc::errors::Error>;
+// The factories receive the host callback of the pool, prepared for the worker
+type Factory = dyn Fn(Box<HostCallback>) -> Result<WapcHost> + Send + Sync + 'static;
+
+/// The signature of the host callback shared by the workers of a pool, see
+/// [HostPoolBuilder::host_callback]. It receives the [WorkerContext] of the worker making the
+/// host call, followed by the arguments of a [HostCallback].
+pub type PoolHostCallback = dyn Fn(
+    &WorkerContext,
+    u64,
+    &str,
+    &str,
+    &str,
+    &[u8],
+  ) -> std::result::Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>
+  + Send
+  + Sync
+  + 'static;
+
+// The states of a call sent to the workers. A call is abandoned when its caller stops waiting
+// for it, it is then skipped if no worker picked it up yet
+const CALL_QUEUED: u8 = 0;
+const CALL_RUNNING: u8 = 1;
+const CALL_DONE: u8 = 2;
+const CALL_ABANDONED: u8 = 3;
+// The slot of a call picked up by an elastic worker, or not picked up yet
+const NO_SLOT: usize = usize::MAX;
+
+const DEFAULT_MAX_RESPAWNS: usize = 10;
+const DEFAULT_QUEUE_CAPACITY: usize = 1;
+const DEFAULT_DROP_TIMEOUT: Duration = Duration::from_millis(500);
+// How often a shutdown checks whether the workers it waits for exited
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+// A worker gives up after failing to create its host MAX_FACTORY_ATTEMPTS times, waiting twice
+// as long between each attempt
+const MAX_FACTORY_ATTEMPTS: u32 = 5;
+const FACTORY_BACKOFF: Duration = Duration::from_millis(10);
+// Every FAIRNESS_INTERVAL calls, a worker looks for the lower priority calls first
+const FAIRNESS_INTERVAL: u64 = 8;
+
+/// The priority of a call, the workers run the calls of higher priority first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Priority {
+  /// Latency-critical calls.
+  High,
+  /// The priority of [HostPool::call].
+  #[default]
+  Normal,
+  /// Batch calls, running when no other call waits.
+  Low,
+}
+
+impl Priority {
+  const ALL: [Self; 3] = [Self::High, Self::Normal, Self::Low];
+
+  // The index of its channel
+  const fn index(self) -> usize {
+    match self {
+      Self::High => 0,
+      Self::Normal => 1,
+      Self::Low => 2,
+    }
+  }
+}
+
+/// What a call does when every worker is busy, the queue is full and the pool cannot grow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SaturationPolicy {
+  /// Wait for room on the queue.
+  #[default]
+  Block,
+  /// Fail right away with [Error::PoolSaturated].
+  Error,
+  /// Fail with [Error::PoolSaturated] when the call could not be queued within the duration.
+  ErrorAfter(Duration),
+}
+
+/// What happens to the workers when a [HostPool] is dropped without being shut down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropPolicy {
+  /// Leave the workers running: they complete the queued calls, then exit in the background.
+  Detach,
+  /// Fail the queued calls with [Error::PoolShutdown] and wait up to the duration for the workers
+  /// to complete the calls they are running and exit.
+  JoinWithTimeout(Duration),
+  /// Fail the queued calls with [Error::PoolShutdown] without waiting for the workers: they exit
+  /// in the background once the calls they are running return.
+  Abort,
+}
+
+impl Default for DropPolicy {
+  fn default() -> Self {
+    Self::JoinWithTimeout(DEFAULT_DROP_TIMEOUT)
+  }
+}
+
+/// The worker making a host call, passed to the [PoolHostCallback].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WorkerContext {
+  /// The name of the pool.
+  pub pool: String,
+  /// The id of the worker, as in [CallDetails::worker].
+  pub worker: usize,
+}
+
+/// Where a call runs, see [HostPool::call_detailed].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Route {
+  /// Any worker, with the given [Priority].
+  Shared(Priority),
+  /// The worker serving the key, see [HostPool::call_sticky].
+  Sticky(u64),
+}
+
+impl Default for Route {
+  fn default() -> Self {
+    Self::Shared(Priority::Normal)
+  }
+}
+
+/// The result of a call along with how it ran, returned by [HostPool::call_detailed].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct CallDetails {
+  /// The response of the guest.
+  pub response: Vec<u8>,
+  /// The id of the worker that ran the call.
+  pub worker: usize,
+  /// The time the call waited for the worker.
+  pub queued: Duration,
+  /// The time the worker took to run the call.
+  pub executed: Duration,
+}
+
+// The probe run periodically by every worker, set with HostPoolBuilder::health_check
+#[derive(Debug)]
+struct HealthCheck {
+  op: String,
+  payload: Vec<u8>,
+  interval: Duration,
+  unhealthy_threshold: usize,
+}
+
+// The settings of a HostPool, set with the HostPoolBuilder
+#[derive(Debug, Clone)]
+struct Options {
+  min_threads: usize,
+  max_threads: usize,
+  max_wait: Duration,
+  max_idle: Duration,
+  max_respawns: usize,
+  queue_capacity: usize,
+  on_saturation: SaturationPolicy,
+  health_check: Option<Arc<HealthCheck>>,
+  op_limits: HashMap<String, usize>,
+  on_drop: DropPolicy,
+  max_calls_per_worker: Option<usize>,
+  slow_call_threshold: Option<Duration>,
+  partitions: HashMap<String, usize>,
+}
 
 /// The [HostPool] initializes a number of workers for the passed [WapcHost] factory function.
 ///
@@ -16,13 +190,60 @@ use crate::errors::Error;
 pub struct HostPool {
   /// The name of the [HostPool] (for debugging purposes).
   pub name: String,
-  pool: Option<ThreadPool>,
-  factory: Arc<dyn Fn() -> WapcHost + Send + Sync + 'static>,
-  max_threads: usize,
+  pool: Mutex<Option<ThreadPool>>,
+  factory: Arc<Factory>,
+  host_callback: Option<Arc<PoolHostCallback>>,
+  // the workers idle for max_idle exit while the pool has more than min_threads, see
+  // [HostPool::set_min_threads]
+  min_threads: Arc<AtomicUsize>,
+  // the pool grows up to max_threads, see [HostPool::resize]
+  max_threads: AtomicUsize,
   max_wait: Duration,
   max_idle: Duration,
-  tx: SyncSender<WorkerMessage>,
-  rx: SyncReceiver<WorkerMessage>,
+  max_respawns: usize,
+  on_saturation: SaturationPolicy,
+  health_check: Option<Arc<HealthCheck>>,
+  // the health of the workers, when they run a health check
+  health: Arc<Mutex<HashMap<usize, WorkerHealth>>>,
+  // the calls run by each live worker
+  stats: Arc<Mutex<HashMap<usize, WorkerStats>>>,
+  // the operations that can only run on a limited number of workers at a time
+  op_limits: HashMap<String, Arc<Semaphore>>,
+  on_drop: DropPolicy,
+  // the workers recreate their host after serving this many calls
+  max_calls_per_worker: Option<usize>,
+  // the calls running on the workers, when a slow call threshold is set
+  executions: Option<Arc<Executions>>,
+  // dropped when the pool shuts down, stopping the watchdog
+  watchdog: Mutex<Option<SyncSender<()>>>,
+  // dropped when the pool shuts down: the workers exit once the queued calls are done
+  tx: Mutex<Option<Senders>>,
+  // the queues of the calls, by priority
+  rx: [SyncReceiver<WorkerMessage>; 3],
+  // the queues of the sticky calls, one per permanent worker
+  slots_rx: Vec<SyncReceiver<WorkerMessage>>,
+  // the number of workers dedicated to each operation with its own queue
+  partitions: HashMap<String, usize>,
+  // the queues of the operations with their own queue
+  ops_rx: HashMap<String, SyncReceiver<WorkerMessage>>,
+  // the workers dedicated to an operation, they come on top of min_threads and max_threads
+  dedicated: Arc<Mutex<HashMap<String, HashSet<usize>>>>,
+  reserved: usize,
+  // the permanent worker running in each slot, none after it exited
+  slot_workers: Arc<Mutex<Vec<Option<usize>>>>,
+  // the replies of the calls not completed yet, failed when the pool shuts down
+  pending: Arc<Mutex<HashMap<u64, PendingReply>>>,
+  next_call: AtomicU64,
+  // workers stuck in a call abandoned by its caller, they exit once the call returns
+  stuck_workers: Arc<AtomicUsize>,
+  // the control channels of the running workers
+  workers: Arc<Mutex<HashMap<usize, SyncSender<Control>>>>,
+  next_worker: AtomicUsize,
+  // the module set by [HostPool::replace_module], loaded by the new workers
+  module: Arc<Mutex<Option<CurrentModule>>>,
+  panics: Arc<AtomicUsize>,
+  counters: Arc<Counters>,
+  ready: Arc<tokio::sync::watch::Sender<Readiness>>,
 }
 
 impl std::fmt::Debug for HostPool {
@@ -35,11 +256,88 @@ impl std::fmt::Debug for HostPool {
   }
 }
 
-type WorkerMessage = (
-  OneshotSender<std::result::Result<Vec<u8>, wapc::errors::Error>>,
-  String,
-  Vec<u8>,
-);
+#[derive(Debug, Default)]
+struct Readiness {
+  // the number of workers that created their host
+  ready: usize,
+  // the errors of the permanent workers that failed to create their host
+  failures: Vec<String>,
+}
+
+#[derive(Debug)]
+struct Senders {
+  queues: [SyncSender<WorkerMessage>; 3],
+  slots: Vec<SyncSender<WorkerMessage>>,
+  ops: HashMap<String, SyncSender<WorkerMessage>>,
+}
+
+struct WorkerMessage {
+  id: u64,
+  op: String,
+  payload: Vec<u8>,
+  state: Arc<CallState>,
+  queued_at: Instant,
+  // the span of the call, entered by the worker running it
+  #[cfg(feature = "tracing")]
+  span: tracing::Span,
+  // released once the call is done, or dropped from the queue
+  permit: Option<OwnedSemaphorePermit>,
+}
+
+// Shared by the caller and the worker running the call
+struct CallState {
+  phase: AtomicU8,
+  // the slot of the worker that picked up the call
+  slot: AtomicUsize,
+  // the id of the worker that picked up the call
+  worker: AtomicUsize,
+}
+
+struct PendingReply {
+  reply: OneshotSender<DetailedResult>,
+  state: Arc<CallState>,
+}
+
+#[derive(Clone)]
+struct CurrentModule {
+  generation: u64,
+  bytes: Arc<Vec<u8>>,
+}
+
+// Sent to every worker to swap its module
+struct ReplaceModule {
+  generation: u64,
+  bytes: Arc<Vec<u8>>,
+  reply: OneshotSender<std::result::Result<(), String>>,
+}
+
+// Sent to every worker to run a call, warming it up or broadcasting it
+struct Prime {
+  op: String,
+  payload: Vec<u8>,
+  reply: OneshotSender<CallResult>,
+}
+
+// The messages sent to a given worker
+enum Control {
+  ReplaceModule(ReplaceModule),
+  Prime(Prime),
+}
+
+enum Received {
+  Call(WorkerMessage),
+  Control(Control),
+  // time for the health check
+  Probe,
+  Closed(String),
+}
+
+// A call accepted by the workers
+struct PendingCall {
+  id: u64,
+  reply: OneshotReceiver<DetailedResult>,
+  state: Arc<CallState>,
+}
 
 impl HostPool {
   /// Instantiate a new HostPool.
@@ -55,317 +353,3106 @@ impl HostPool {
     N: AsRef<str>,
     F: Fn() -> WapcHost + Send + Sync + 'static,
   {
+    let options = Options {
+      min_threads,
+      max_threads,
+      max_wait,
+      max_idle,
+      max_respawns: DEFAULT_MAX_RESPAWNS,
+      queue_capacity: DEFAULT_QUEUE_CAPACITY,
+      on_saturation: SaturationPolicy::Block,
+      health_check: None,
+      op_limits: HashMap::new(),
+      on_drop: DropPolicy::default(),
+      max_calls_per_worker: None,
+      slow_call_threshold: None,
+      partitions: HashMap::new(),
+    };
+    Self::create(name, Arc::new(move |_| Ok(factory())), None, options)
+  }
+
+  fn create<N: AsRef<str>>(
+    name: N,
+    factory: Arc<Factory>,
+    host_callback: Option<Arc<PoolHostCallback>>,
+    options: Options,
+  ) -> Self {
+    let Options {
+      min_threads,
+      max_threads,
+      max_wait,
+      max_idle,
+      max_respawns,
+      queue_capacity,
+      on_saturation,
+      health_check,
+      op_limits,
+      on_drop,
+      max_calls_per_worker,
+      slow_call_threshold,
+      partitions,
+    } = options;
     debug!("Creating new wapc host pool with size {}", max_threads);
-    let arcfn = Arc::new(factory);
+    // the stuck workers still count as threads of the rusty_pool while their replacements run:
+    // the number of workers is bounded by the host pool instead
     let pool = rusty_pool::Builder::new()
       .name(name.as_ref().to_owned())
       .core_size(min_threads)
-      .max_size(max_threads)
+      .max_size(rusty_pool::MAX_SIZE)
       .keep_alive(Duration::from_millis(0))
       .build();
 
-    let (tx, rx) = crossbeam::channel::bounded::<WorkerMessage>(1);
+    let [(high_tx, high_rx), (normal_tx, normal_rx), (low_tx, low_rx)] =
+      Priority::ALL.map(|_| crossbeam::channel::bounded::<WorkerMessage>(queue_capacity));
+    let (slots_tx, slots_rx) = (0..min_threads)
+      .map(|_| crossbeam::channel::bounded::<WorkerMessage>(queue_capacity))
+      .unzip();
+    let (ops_tx, ops_rx) = partitions
+      .keys()
+      .map(|op| {
+        let (tx, rx) = crossbeam::channel::bounded::<WorkerMessage>(queue_capacity);
+        ((op.clone(), tx), (op.clone(), rx))
+      })
+      .unzip();
+    let executions = slow_call_threshold.map(|threshold| Arc::new(Executions::new(threshold)));
+    let watchdog = executions.as_ref().and_then(|executions| {
+      let (stop_tx, stop_rx) = crossbeam::channel::bounded(0);
+      let (pool, executions) = (name.as_ref().to_owned(), executions.clone());
+      match std::thread::Builder::new()
+        .name(format!("{}-watchdog", name.as_ref()))
+        .spawn(move || executions.watch(&pool, &stop_rx))
+      {
+        Ok(_) => Some(stop_tx),
+        Err(e) => {
+          error!("Error spawning the watchdog of host pool '{}': {}", name.as_ref(), e);
+          None
+        }
+      }
+    });
 
     let pool = Self {
       name: name.as_ref().to_owned(),
-      factory: arcfn,
-      pool: Some(pool),
-      max_threads,
+      factory,
+      host_callback,
+      pool: Mutex::new(Some(pool)),
+      min_threads: Arc::new(AtomicUsize::new(min_threads)),
+      max_threads: AtomicUsize::new(max_threads),
       max_wait,
       max_idle,
-      tx,
-      rx,
+      max_respawns,
+      on_saturation,
+      health_check,
+      health: Arc::new(Mutex::new(HashMap::new())),
+      stats: Arc::new(Mutex::new(HashMap::new())),
+      op_limits: op_limits
+        .into_iter()
+        .map(|(op, max)| (op, Arc::new(Semaphore::new(max))))
+        .collect(),
+      on_drop,
+      max_calls_per_worker,
+      executions,
+      watchdog: Mutex::new(watchdog),
+      tx: Mutex::new(Some(Senders {
+        queues: [high_tx, normal_tx, low_tx],
+        slots: slots_tx,
+        ops: ops_tx,
+      })),
+      rx: [high_rx, normal_rx, low_rx],
+      slots_rx,
+      reserved: partitions.values().sum(),
+      dedicated: Arc::new(Mutex::new(
+        partitions.keys().map(|op| (op.clone(), HashSet::new())).collect(),
+      )),
+      partitions,
+      ops_rx,
+      slot_workers: Arc::new(Mutex::new(vec![None; min_threads])),
+      pending: Arc::new(Mutex::new(HashMap::new())),
+      next_call: AtomicU64::new(0),
+      stuck_workers: Arc::new(AtomicUsize::new(0)),
+      workers: Arc::new(Mutex::new(HashMap::new())),
+      next_worker: AtomicUsize::new(0),
+      module: Arc::new(Mutex::new(None)),
+      panics: Arc::new(AtomicUsize::new(0)),
+      counters: Arc::new(Counters::default()),
+      ready: Arc::new(tokio::sync::watch::Sender::new(Readiness::default())),
     };
 
-    for _ in 0..min_threads {
-      pool.spawn(None).unwrap();
-    }
-
+    pool.replenish();
     pool
   }
 
-  /// Get the current number of active workers.
+  /// Get the current number of active workers, see [HostPool::current].
   #[must_use]
   pub fn num_active_workers(&self) -> usize {
-    self.pool.as_ref().map_or(0, |pool| pool.get_current_worker_count())
+    self.current()
   }
 
-  fn spawn(&self, max_idle: Option<Duration>) -> Result<()> {
-    self.pool.as_ref().map_or_else(
-      || Err(Error::NoPool.into()),
-      |pool| {
-        let name = self.name.clone();
-        let i = pool.get_current_worker_count();
-        let factory = self.factory.clone();
-        let rx = self.rx.clone();
-        pool.execute(move || {
-          trace!("Host thread {}.{} started...", name, i);
-          let host = factory();
-          loop {
-            let message = max_idle.map_or_else(
-              || rx.recv().map_err(|e| e.to_string()),
-              |duration| rx.recv_timeout(duration).map_err(|e| e.to_string()),
-            );
-            if let Err(e) = message {
-              debug!("Host thread {}.{} closing: {}", name, i, e);
-              break;
-            }
-            let (tx, op, payload) = message.unwrap();
-            trace!(
-              "Host thread {}.{} received call for {} with {} byte payload",
-              name,
-              i,
-              op,
-              payload.len()
-            );
-            let result = host.call(&op, &payload);
-            if tx.send(result).is_err() {
-              error!("Host thread {}.{} failed when returning a value...", name, i);
-            }
-          }
+  /// Get the number of live workers: the permanent ones and the ones spawned while the pool is
+  /// busy, until they are idle for `max_idle`. The workers stuck in a call abandoned by
+  /// [HostPool::call_with_timeout] are not counted.
+  #[must_use]
+  pub fn current(&self) -> usize {
+    lock(&self.workers)
+      .len()
+      .saturating_sub(self.stuck_workers.load(Ordering::Acquire))
+  }
 
-          trace!("Host thread {}.{} stopped.", name, i);
-        });
-        Ok(())
-      },
-    )
+  /// Get the minimum number of workers, `min_threads`. A permanent worker that exited after
+  /// panicking too many times is replaced on the next call.
+  #[must_use]
+  pub fn target_min(&self) -> usize {
+    self.min_threads.load(Ordering::Acquire)
   }
 
-  /// Call an operation on one of the workers.
-  pub async fn call<T: AsRef<str> + Sync + Send>(&self, op: T, payload: Vec<u8>) -> Result<Vec<u8>> {
-    let (tx, rx) = tokio::sync::oneshot::channel();
-    // Start the call with a timeout of max_wait.
-    let result = match self
-      .tx
-      .send_timeout((tx, op.as_ref().to_owned(), payload), self.max_wait)
-    {
-      Ok(_) => Ok(()),
-      Err(e) => {
-        // If we didn't get a response in time...
-        let args = match e {
-          SendTimeoutError::Timeout(args) => {
-            debug!("Timeout on pool '{}'", self.name);
-            args
-          }
-          SendTimeoutError::Disconnected(args) => {
-            warn!("Pool worker disconnected on pool '{}'", self.name);
-            args
-          }
-        };
-        // grow the pool...
-        if self.num_active_workers() < self.max_threads {
-          if let Err(e) = self.spawn(Some(self.max_idle)) {
-            error!("Error spawning worker for host pool '{}': {}", self.name, e);
-          };
-        }
-        // ...and wait.
-        self.tx.send(args)
+  /// Get the maximum number of workers the pool grows to, `max_threads`.
+  #[must_use]
+  pub fn target_max(&self) -> usize {
+    self.max_threads.load(Ordering::Acquire)
+  }
+
+  /// Change the maximum number of workers the pool grows to, keeping the running workers and
+  /// their hosts. When shrinking, the surplus workers are not interrupted: they exit once idle
+  /// for `max_idle`.
+  ///
+  /// Fails with [Error::InvalidPoolSize] when `max_threads` is zero or lower than `min_threads`.
+  pub fn resize(&self, max_threads: usize) -> Result<()> {
+    // the limits are changed while holding the workers, like in set_min_threads
+    let _workers = lock(&self.workers);
+    let min_threads = self.min_threads.load(Ordering::Acquire);
+    if max_threads == 0 || max_threads < min_threads {
+      return Err(
+        Error::InvalidPoolSize(format!(
+          "max_threads ({}) must be at least 1 and min_threads ({})",
+          max_threads, min_threads
+        ))
+        .into(),
+      );
+    }
+    debug!("Resizing host pool '{}' to {} threads", self.name, max_threads);
+    self.max_threads.store(max_threads, Ordering::Release);
+    Ok(())
+  }
+
+  /// Change the minimum number of workers. When raising it, workers are spawned right away up to
+  /// `min_threads`. When lowering it, the surplus workers exit once idle for `max_idle`.
+  ///
+  /// The permanent workers spawned when the pool was built keep serving the sticky calls: fails
+  /// with [Error::InvalidPoolSize] when `min_threads` is lower than their number, or greater than
+  /// `max_threads`.
+  pub fn set_min_threads(&self, min_threads: usize) -> Result<()> {
+    let missing = {
+      let workers = lock(&self.workers);
+      let max_threads = self.max_threads.load(Ordering::Acquire);
+      if min_threads < self.slots_rx.len() || min_threads > max_threads {
+        return Err(
+          Error::InvalidPoolSize(format!(
+            "min_threads ({}) must be between the {} permanent workers and max_threads ({})",
+            min_threads,
+            self.slots_rx.len(),
+            max_threads
+          ))
+          .into(),
+        );
       }
+      self.min_threads.store(min_threads, Ordering::Release);
+      let live = workers.len().saturating_sub(self.stuck_workers.load(Ordering::Acquire));
+      (min_threads + self.reserved).saturating_sub(live)
     };
-    if let Err(e) = result {
-      return Err(wapc::errors::Error::General(e.to_string()));
-    }
-    match rx.await {
-      Ok(res) => res,
-      Err(e) => Err(wapc::errors::Error::General(e.to_string())),
+    for _ in 0..missing {
+      self.spawn(Some(self.max_idle), None, None)?;
     }
+    Ok(())
   }
 
-  /// Shut down the host pool.
-  pub fn shutdown(&mut self) -> Result<()> {
-    let pool = self
-      .pool
-      .take()
-      .ok_or_else(|| wapc::errors::Error::from(Error::NoPool))?;
+  /// Get a snapshot of the activity of the pool.
+  pub fn metrics(&self) -> PoolMetrics {
+    let queue_depth = lock(&self.pending)
+      .values()
+      .filter(|pending| pending.state.phase.load(Ordering::Acquire) == CALL_QUEUED)
+      .count();
+    PoolMetrics {
+      queue_depth,
+      busy_workers: self.counters.busy_workers.load(Ordering::Acquire),
+      total_calls: self.counters.total_calls.load(Ordering::Acquire),
+      total_timeouts: self.counters.total_timeouts.load(Ordering::Acquire),
+      factory_failures: self.counters.factory_failures.load(Ordering::Acquire),
+      worker_recycles: self.counters.worker_recycles.load(Ordering::Acquire),
+      queue_wait: self.counters.queue_wait(),
+    }
+  }
 
-    pool.shutdown_join();
-    Ok(())
+  /// Get the health of each worker, ordered by worker id. Empty unless a health check is set
+  /// with [HostPoolBuilder::health_check].
+  #[must_use]
+  pub fn health(&self) -> Vec<WorkerHealth> {
+    let mut health: Vec<_> = lock(&self.health).values().copied().collect();
+    health.sort_by_key(|health| health.worker);
+    health
   }
-}
 
-#[must_use]
-/// Builder for a [HostPool]
-pub struct HostPoolBuilder {
-  name: Option<String>,
-  factory: Option<Box<dyn Fn() -> WapcHost + Send + Sync + 'static>>,
-  min_threads: usize,
-  max_threads: usize,
-  max_wait: Duration,
-  max_idle: Duration,
-}
+  /// Get the calls run by each worker and the last error they returned, ordered by worker id.
+  /// The workers that left the pool are not listed.
+  #[must_use]
+  pub fn worker_stats(&self) -> Vec<WorkerStats> {
+    let mut stats: Vec<_> = lock(&self.stats).values().cloned().collect();
+    stats.sort_by_key(|stats| stats.worker);
+    stats
+  }
 
-impl std::fmt::Debug for HostPoolBuilder {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    f.debug_struct("HostPoolBuilder")
-      .field("name", &self.name)
-      .field("factory", if self.factory.is_some() { &"Some(Fn)" } else { &"None" })
-      .field("min_threads", &self.min_threads)
-      .field("max_threads", &self.max_threads)
-      .field("max_wait", &self.max_wait)
-      .field("max_idle", &self.max_idle)
-      .finish()
+  /// Get the number of calls that panicked in a worker since the pool was created.
+  #[must_use]
+  pub fn panic_count(&self) -> usize {
+    self.panics.load(Ordering::Acquire)
   }
-}
 
-impl Default for HostPoolBuilder {
-  fn default() -> Self {
-    Self {
-      name: None,
-      factory: None,
-      min_threads: 1,
-      max_threads: 2,
-      max_wait: Duration::from_millis(100),
-      max_idle: Duration::from_secs(5 * 60),
+  // Spawn the permanent workers missing from their slot, and the missing dedicated workers
+  fn replenish(&self) {
+    let mut slot_workers = lock(&self.slot_workers);
+    for (slot, worker) in slot_workers.iter_mut().enumerate() {
+      if worker.is_none() {
+        match self.spawn(None, Some(slot), None) {
+          Ok(i) => *worker = i,
+          Err(e) => error!("Error spawning worker for host pool '{}': {}", self.name, e),
+        }
+      }
+    }
+    drop(slot_workers);
+    let mut dedicated = lock(&self.dedicated);
+    for (op, workers) in dedicated.iter_mut() {
+      let target = self.partitions.get(op).copied().unwrap_or_default();
+      for _ in workers.len()..target {
+        match self.spawn(None, None, Some(op)) {
+          Ok(i) => workers.extend(i),
+          Err(e) => error!("Error spawning worker for host pool '{}': {}", self.name, e),
+        }
+      }
     }
   }
-}
 
-impl HostPoolBuilder {
-  /// Instantiate a nnew [HostPoolBuilder] with default settings.
-  ///
-  /// ```
-  /// # use wapc_pool::HostPoolBuilder;
-  /// let builder = HostPoolBuilder::new();
-  /// ```
-  ///
-  pub fn new() -> Self {
-    Self::default()
+  // Spawn a worker, the permanent ones serve the sticky calls of their slot and the dedicated
+  // ones the calls of their operation only. Returns its id, none when the pool already runs
+  // max_threads workers: the workers are counted and the new one registered while holding them,
+  // concurrent callers cannot grow the pool past its limit
+  fn spawn(&self, max_idle: Option<Duration>, slot: Option<usize>, op: Option<&str>) -> Result<Option<usize>> {
+    lock(&self.pool).as_ref().map_or_else(
+      || Err(Error::NoPool.into()),
+      |pool| {
+        let mut workers = lock(&self.workers);
+        let live = workers.len().saturating_sub(self.stuck_workers.load(Ordering::Acquire));
+        if slot.is_none() && op.is_none() && live >= self.target_max() + self.reserved {
+          return Ok(None);
+        }
+        let (control_tx, control_rx) = crossbeam::channel::unbounded();
+        let worker = Worker {
+          name: self.name.clone(),
+          i: self.next_worker.fetch_add(1, Ordering::Relaxed),
+          factory: self.factory.clone(),
+          host_callback: self.host_callback.clone(),
+          max_idle,
+          min_threads: self.min_threads.clone(),
+          max_respawns: self.max_respawns,
+          reserved: self.reserved,
+          rx: op.is_none().then(|| self.rx.clone()),
+          slot: slot.and_then(|slot| Some((slot, self.slots_rx.get(slot)?.clone()))),
+          // the operations without dedicated workers are run by the others
+          ops: self
+            .ops_rx
+            .iter()
+            .filter(|(name, _)| op.map_or(self.partitions.get(*name) == Some(&0), |op| op == name.as_str()))
+            .map(|(_, rx)| rx.clone())
+            .collect(),
+          dedicated_to: op.map(str::to_owned),
+          dedicated: self.dedicated.clone(),
+          control_rx,
+          picks: Cell::new(0),
+          last_active: Cell::new(Instant::now()),
+          max_calls: self.max_calls_per_worker,
+          executions: self.executions.clone(),
+          served: Cell::new(0),
+          recycle_at: Cell::new(usize::MAX),
+          hosts: Cell::new(0),
+          health_check: self.health_check.clone(),
+          probes: self
+            .health_check
+            .as_ref()
+            .map_or_else(crossbeam::channel::never, |check| {
+              crossbeam::channel::tick(check.interval)
+            }),
+          health: self.health.clone(),
+          stats: self.stats.clone(),
+          pending: self.pending.clone(),
+          stuck_workers: self.stuck_workers.clone(),
+          workers: self.workers.clone(),
+          module: self.module.clone(),
+          slot_workers: self.slot_workers.clone(),
+          panics: self.panics.clone(),
+          counters: self.counters.clone(),
+          ready: self.ready.clone(),
+        };
+        // registered before loading the current module: a replacement happening in between is
+        // received on the control channel
+        let i = worker.i;
+        workers.insert(i, control_tx);
+        drop(workers);
+        pool.execute(move || worker.run());
+        Ok(Some(i))
+      },
+    )
   }
 
-  /// Set the name for the HostPool.
-  ///
-  /// ```
-  /// # use wapc_pool::HostPoolBuilder;
-  /// let builder = HostPoolBuilder::new().name("My Module");
-  /// ```
-  ///
-  pub fn name<T: AsRef<str>>(mut self, name: T) -> Self {
-    self.name = Some(name.as_ref().to_owned());
-    self
+  /// Call an operation on one of the workers.
+  pub async fn call<T: AsRef<str> + Sync + Send>(&self, op: T, payload: Vec<u8>) -> Result<Vec<u8>> {
+    self.call_with_priority(op, payload, Priority::Normal).await
   }
 
-  /// Set the [WapcHost] generator function to use when spawning new workers.
+  /// Call an operation on one of the workers, blocking the current thread until it returns. The
+  /// pool grows like with [HostPool::call], no asynchronous runtime is needed.
   ///
-  /// ```
-  /// # use wapc_pool::HostPoolBuilder;
-  /// # use wapc::WapcHost;
-  /// # let bytes = std::fs::read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm").unwrap();
-  /// let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
-  ///   .module_bytes(&bytes)
-  ///   .build()
-  ///   .unwrap();
-  /// let pool = HostPoolBuilder::new()
-  ///   .factory(move || {
-  ///     let engine = engine.clone();
-  ///     WapcHost::new(Box::new(engine), None).unwrap()
-  ///   })
-  ///   .build();
-  /// ```
+  /// # Panics
   ///
-  pub fn factory<F>(mut self, factory: F) -> Self
-  where
-    F: Fn() -> WapcHost + Send + Sync + 'static,
-  {
-    self.factory = Some(Box::new(factory));
-    self
+  /// Panics when called from an asynchronous execution context, such as a tokio worker thread:
+  /// use [HostPool::call] there instead.
+  pub fn call_blocking<T: AsRef<str>>(&self, op: T, payload: Vec<u8>) -> Result<Vec<u8>> {
+    let permit = self.acquire_blocking(op.as_ref())?;
+    let call = self.send(op.as_ref(), payload, Route::default(), permit)?;
+    match call.reply.blocking_recv() {
+      Ok(res) => res.map(|details| details.response),
+      Err(e) => Err(wapc::errors::Error::General(e.to_string())),
+    }
   }
 
-  /// Set the minimum, base number of threads to spawn.
-  ///
-  /// ```
-  /// # use wapc_pool::HostPoolBuilder;
-  /// let builder = HostPoolBuilder::new().min_threads(1);
-  /// ```
-  ///
-  pub fn min_threads(mut self, min: usize) -> Self {
-    self.min_threads = min;
-    self
+  /// Call an operation on one of the workers with the given [Priority]. The workers run the
+  /// queued calls of higher priority first, while picking the lower priority ones now and then
+  /// so they are not starved.
+  pub async fn call_with_priority<T: AsRef<str> + Sync + Send>(
+    &self,
+    op: T,
+    payload: Vec<u8>,
+    priority: Priority,
+  ) -> Result<Vec<u8>> {
+    Ok(self.call_detailed(op, payload, Route::Shared(priority)).await?.response)
   }
 
-  /// Set the upper limit on the number of threads to spawn.
-  ///
-  /// ```
-  /// # use wapc_pool::HostPoolBuilder;
-  /// let builder = HostPoolBuilder::new().max_threads(5);
-  /// ```
+  /// Call an operation on the permanent worker serving `key`: the calls with the same key run on
+  /// the same worker, one after the other, so the guest may keep state across them.
   ///
-  pub fn max_threads(mut self, max: usize) -> Self {
-    self.max_threads = max;
-    self
+  /// Each of the `min_threads` permanent workers has its own queue, holding up to
+  /// `queue_capacity` calls. When the queue of the worker stays full for `max_wait`, the call
+  /// fails with [Error::StickyQueueFull] rather than running on another worker. When the worker
+  /// is replaced, after a call timed out or panicked, its replacement serves the same keys. The
+  /// call fails with [Error::NoStickyWorkers] when the pool has no permanent worker.
+  pub async fn call_sticky<T: AsRef<str> + Sync + Send>(&self, key: u64, op: T, payload: Vec<u8>) -> Result<Vec<u8>> {
+    Ok(self.call_detailed(op, payload, Route::Sticky(key)).await?.response)
   }
 
-  /// Set the timeout for threads to self-close.
-  ///
-  /// ```
-  /// # use wapc_pool::HostPoolBuilder;
-  /// # use std::time::Duration;
-  /// let builder = HostPoolBuilder::new().max_idle(Duration::from_secs(60));
-  /// ```
-  ///
-  pub fn max_idle(mut self, timeout: Duration) -> Self {
-    self.max_idle = timeout;
-    self
+  /// Call an operation on the workers selected by `route`, returning the response along with the
+  /// worker that ran the call, the time it waited for the worker and the time it ran. The other
+  /// calls discard these details.
+  pub async fn call_detailed<T: AsRef<str> + Sync + Send>(
+    &self,
+    op: T,
+    payload: Vec<u8>,
+    route: Route,
+  ) -> Result<CallDetails> {
+    let permit = self.acquire(op.as_ref()).await?;
+    let call = self.send(op.as_ref(), payload, route, permit)?;
+    match call.reply.await {
+      Ok(res) => res,
+      Err(e) => Err(wapc::errors::Error::General(e.to_string())),
+    }
   }
 
-  /// Set the maximum amount of time to wait before spawning a new worker.
-  ///
-  /// ```
-  /// # use wapc_pool::HostPoolBuilder;
-  /// # use std::time::Duration;
-  /// let builder = HostPoolBuilder::new().max_wait(Duration::from_millis(100));
-  /// ```
+  /// Call an operation on one of the workers, failing with
+  /// [`wapc::errors::Error::GuestCallFailure`] when no result is returned within `timeout`.
   ///
-  pub fn max_wait(mut self, duration: Duration) -> Self {
-    self.max_wait = duration;
-    self
+  /// The worker running a call that timed out cannot be interrupted: it is abandoned and a
+  /// replacement is spawned. The abandoned worker exits once the call returns, if ever.
+  pub async fn call_with_timeout<T: AsRef<str> + Sync + Send>(
+    &self,
+    op: T,
+    payload: Vec<u8>,
+    timeout: Duration,
+  ) -> Result<Vec<u8>> {
+    let permit = self.acquire(op.as_ref()).await?;
+    let mut call = self.send(op.as_ref(), payload, Route::default(), permit)?;
+    match tokio::time::timeout(timeout, &mut call.reply).await {
+      Ok(Ok(res)) => return res.map(|details| details.response),
+      Ok(Err(e)) => return Err(wapc::errors::Error::General(e.to_string())),
+      Err(_) => {}
+    }
+
+    // the call moves from queued to running to done: the states are checked in the same order
+    if transition(&call.state.phase, CALL_QUEUED, CALL_ABANDONED) {
+      self.counters.total_timeouts.fetch_add(1, Ordering::AcqRel);
+      debug!(
+        "Call for {} on pool '{}' timed out before starting",
+        op.as_ref(),
+        self.name
+      );
+    } else if transition(&call.state.phase, CALL_RUNNING, CALL_ABANDONED) {
+      warn!(
+        "Call for {} on pool '{}' timed out, replacing its worker",
+        op.as_ref(),
+        self.name
+      );
+      self.counters.total_timeouts.fetch_add(1, Ordering::AcqRel);
+      self.stuck_workers.fetch_add(1, Ordering::AcqRel);
+      // a permanent worker is replaced in its slot, keeping the routing of the sticky calls
+      let slot = call.state.slot.load(Ordering::Acquire);
+      let worker = call.state.worker.load(Ordering::Acquire);
+      let spawned = if slot != NO_SLOT {
+        let mut slot_workers = lock(&self.slot_workers);
+        self.spawn(None, Some(slot), None).map(|i| slot_workers[slot] = i)
+      } else if lock(&self.dedicated)
+        .values_mut()
+        .any(|workers| workers.remove(&worker))
+      {
+        // a dedicated worker is replaced by another one dedicated to the same operation
+        self.replenish();
+        Ok(())
+      } else {
+        self.spawn(Some(self.max_idle), None, None).map(|_| ())
+      };
+      if let Err(e) = spawned {
+        error!("Error spawning worker for host pool '{}': {}", self.name, e);
+      }
+    } else {
+      // the worker completed the call in the meantime
+      return match call.reply.await {
+        Ok(res) => res.map(|details| details.response),
+        Err(e) => Err(wapc::errors::Error::General(e.to_string())),
+      };
+    }
+    lock(&self.pending).remove(&call.id);
+    Err(wapc::errors::Error::GuestCallFailure(
+      Error::CallTimeout(timeout).to_string(),
+    ))
   }
 
-  /// Builds a [HostPool] with the current configuration. Warning: this will panic if a factory function is not supplied.
+  /// Call an operation once per payload, fanning the calls across the workers. Returns the
+  /// result of each call in the order of the payloads: a failed call does not abort the others.
+  pub async fn call_many<T: AsRef<str> + Sync + Send>(
+    &self,
+    op: T,
+    payloads: Vec<Vec<u8>>,
+  ) -> Result<Vec<Result<Vec<u8>>>> {
+    self.call_many_with(op, payloads, Route::default(), None).await
+  }
+
+  /// Call an operation once per payload on the workers selected by `route`, like
+  /// [HostPool::call_many]. With `max_in_flight`, at most that many calls of the batch are queued
+  /// or running at once, the next call is sent when the oldest one returns. A `max_in_flight` of
+  /// zero is treated as one.
+  ///
+  /// Fails with [Error::PoolClosed] when the pool is closed before the batch starts. Afterwards,
+  /// the calls fail individually.
+  pub async fn call_many_with<T: AsRef<str> + Sync + Send>(
+    &self,
+    op: T,
+    payloads: Vec<Vec<u8>>,
+    route: Route,
+    max_in_flight: Option<usize>,
+  ) -> Result<Vec<Result<Vec<u8>>>> {
+    if lock(&self.tx).is_none() {
+      return Err(Error::PoolClosed.into());
+    }
+    let max_in_flight = max_in_flight.map_or(usize::MAX, |max| max.max(1));
+    let mut results = Vec::with_capacity(payloads.len());
+    let mut in_flight = VecDeque::new();
+    for payload in payloads {
+      if in_flight.len() == max_in_flight {
+        if let Some(call) = in_flight.pop_front() {
+          results.push(Self::response(call).await);
+        }
+      }
+      let call = match self.acquire(op.as_ref()).await {
+        Ok(permit) => self.send(op.as_ref(), payload, route, permit),
+        Err(e) => Err(e),
+      };
+      in_flight.push_back(call);
+    }
+    // the calls are awaited in the order they were sent, which keeps the results in order
+    for call in in_flight {
+      results.push(Self::response(call).await);
+    }
+    Ok(results)
+  }
+
+  async fn response(call: Result<PendingCall>) -> Result<Vec<u8>> {
+    match call?.reply.await {
+      Ok(res) => res.map(|details| details.response),
+      Err(e) => Err(wapc::errors::Error::General(e.to_string())),
+    }
+  }
+
+  // Hand the call over to a worker, growing the pool when none is available
+  fn send(
+    &self,
+    op: &str,
+    payload: Vec<u8>,
+    route: Route,
+    permit: Option<OwnedSemaphorePermit>,
+  ) -> Result<PendingCall> {
+    let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
+    let state = Arc::new(CallState {
+      phase: AtomicU8::new(CALL_QUEUED),
+      slot: AtomicUsize::new(NO_SLOT),
+      worker: AtomicUsize::new(NO_SLOT),
+    });
+    let id = self.next_call.fetch_add(1, Ordering::Relaxed);
+    // the reply is registered while holding the sender: a shutdown either rejects the call or
+    // sees its reply
+    let tx = {
+      let tx = lock(&self.tx);
+      let senders = tx.as_ref().ok_or(Error::PoolClosed)?;
+      let tx = match route {
+        Route::Shared(priority) => senders.ops.get(op).unwrap_or(&senders.queues[priority.index()]).clone(),
+        Route::Sticky(_) if senders.slots.is_empty() => return Err(Error::NoStickyWorkers.into()),
+        Route::Sticky(key) => senders.slots[sticky_slot(key, senders.slots.len())].clone(),
+      };
+      lock(&self.pending).insert(
+        id,
+        PendingReply {
+          reply: reply_tx,
+          state: state.clone(),
+        },
+      );
+      tx
+    };
+    // a permanent worker may have exited after panicking too many times
+    self.replenish();
+    self.counters.total_calls.fetch_add(1, Ordering::AcqRel);
+    let mut message = WorkerMessage {
+      id,
+      op: op.to_owned(),
+      #[cfg(feature = "tracing")]
+      span: instrument::call_span(&self.name, op, payload.len()),
+      payload,
+      state: state.clone(),
+      queued_at: Instant::now(),
+      permit,
+    };
+    // only the dedicated workers run the calls of their operation: the pool does not grow for them
+    let mut grown = matches!(route, Route::Shared(_)) && self.partitions.get(op).is_some_and(|workers| *workers > 0);
+    let started = Instant::now();
+    // Start the call with a timeout of max_wait.
+    while let Err(e) = tx.send_timeout(message, self.queue_wait(grown, started)) {
+      // If we didn't get a response in time...
+      message = match e {
+        SendTimeoutError::Timeout(message) => {
+          debug!("Timeout on pool '{}'", self.name);
+          message
+        }
+        SendTimeoutError::Disconnected(_) => {
+          lock(&self.pending).remove(&id);
+          return Err(wapc::errors::Error::General(format!(
+            "Pool workers disconnected on pool '{}'",
+            self.name
+          )));
+        }
+      };
+      if !lock(&self.pending).contains_key(&id) {
+        // the pool shut down and failed the call
+        break;
+      }
+      if let Route::Sticky(_) = route {
+        // only the worker of the slot can run the call
+        lock(&self.pending).remove(&id);
+        return Err(Error::StickyQueueFull.into());
+      }
+      if self.saturated(grown, started) {
+        lock(&self.pending).remove(&id);
+        return Err(Error::PoolSaturated.into());
+      }
+      // grow the pool...
+      if !grown && self.num_active_workers() < self.target_max() + self.reserved {
+        if let Err(e) = self.spawn(Some(self.max_idle), None, None) {
+          error!("Error spawning worker for host pool '{}': {}", self.name, e);
+        };
+      }
+      // ...and wait.
+      grown = true;
+    }
+    Ok(PendingCall {
+      id,
+      reply: reply_rx,
+      state,
+    })
+  }
+
+  // Wait for the concurrency limit of the operation, if any, like for room on a saturated pool
+  async fn acquire(&self, op: &str) -> Result<Option<OwnedSemaphorePermit>> {
+    let Some(limit) = self.op_limits.get(op) else {
+      return Ok(None);
+    };
+    let permit = match self.on_saturation {
+      SaturationPolicy::Block => limit.clone().acquire_owned().await.ok(),
+      SaturationPolicy::Error => limit.clone().try_acquire_owned().ok(),
+      SaturationPolicy::ErrorAfter(limit_wait) => tokio::time::timeout(limit_wait, limit.clone().acquire_owned())
+        .await
+        .ok()
+        .and_then(std::result::Result::ok),
+    };
+    permit
+      .map(Some)
+      .ok_or_else(|| Error::ConcurrencyLimitReached(op.to_owned()).into())
+  }
+
+  // Same as acquire, parking the thread instead of awaiting
+  fn acquire_blocking(&self, op: &str) -> Result<Option<OwnedSemaphorePermit>> {
+    let Some(limit) = self.op_limits.get(op) else {
+      return Ok(None);
+    };
+    let deadline = match self.on_saturation {
+      SaturationPolicy::Block => None,
+      SaturationPolicy::Error => Some(Instant::now()),
+      SaturationPolicy::ErrorAfter(limit_wait) => Some(Instant::now() + limit_wait),
+    };
+    block_on(limit.clone().acquire_owned(), deadline)
+      .and_then(std::result::Result::ok)
+      .map(Some)
+      .ok_or_else(|| Error::ConcurrencyLimitReached(op.to_owned()).into())
+  }
+
+  // The pool is saturated once it cannot grow: the time left to queue the call depends on the
+  // saturation policy
+  fn queue_wait(&self, grown: bool, started: Instant) -> Duration {
+    if !grown && self.num_active_workers() < self.target_max() + self.reserved {
+      return self.max_wait;
+    }
+    match self.on_saturation {
+      SaturationPolicy::Block => self.max_wait,
+      SaturationPolicy::Error => Duration::ZERO,
+      SaturationPolicy::ErrorAfter(limit) => limit.saturating_sub(started.elapsed()).min(self.max_wait),
+    }
+  }
+
+  // Whether the call failed to be queued on a saturated pool for as long as the policy allows
+  fn saturated(&self, grown: bool, started: Instant) -> bool {
+    if !grown && self.num_active_workers() < self.target_max() + self.reserved {
+      return false;
+    }
+    match self.on_saturation {
+      SaturationPolicy::Block => false,
+      SaturationPolicy::Error => true,
+      SaturationPolicy::ErrorAfter(limit) => started.elapsed() >= limit,
+    }
+  }
+
+  /// Replace the module of every worker with `bytes`, the workers spawned afterwards load it
+  /// too. Resolves once every worker swapped its module, the busy ones after completing the call
+  /// they are running.
+  ///
+  /// Fails with [Error::ReplaceModuleFailed] naming the workers that could not load the module,
+  /// they keep running the previous one.
+  pub async fn replace_module(&self, bytes: Vec<u8>) -> Result<()> {
+    if lock(&self.tx).is_none() {
+      return Err(Error::PoolClosed.into());
+    }
+    // sent while holding the current module: every worker receives the replacements in order
+    let replies: Vec<_> = {
+      let mut module = lock(&self.module);
+      let generation = module.as_ref().map_or(1, |current| current.generation + 1);
+      let bytes = Arc::new(bytes);
+      *module = Some(CurrentModule {
+        generation,
+        bytes: bytes.clone(),
+      });
+      lock(&self.workers)
+        .iter()
+        .filter_map(|(i, control)| {
+          let (reply, rx) = tokio::sync::oneshot::channel();
+          let replace = ReplaceModule {
+            generation,
+            bytes: bytes.clone(),
+            reply,
+          };
+          control.send(Control::ReplaceModule(replace)).ok().map(|()| (*i, rx))
+        })
+        .collect()
+    };
+
+    let mut failures = Vec::new();
+    for (i, reply) in replies {
+      match reply.await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => failures.push(format!("{}.{}: {}", self.name, i, e)),
+        // the worker exited without replacing its module
+        Err(_) => {}
+      }
+    }
+    if failures.is_empty() {
+      Ok(())
+    } else {
+      Err(Error::ReplaceModuleFailed(failures).into())
+    }
+  }
+
+  /// Wait until the `min_threads` workers of the pool created their host. When `op` is set, the
+  /// operation is then called once on every worker with the given payload, to warm up the
+  /// guests.
+  ///
+  /// Fails with [Error::WarmUpFailed] naming the workers that failed to create their host, or
+  /// whose call failed.
+  pub async fn warm_up(&self, op: Option<(&str, Vec<u8>)>) -> Result<()> {
+    // the workers that gave up are replaced and get another chance
+    self.ready.send_modify(|readiness| readiness.failures.clear());
+    self.replenish();
+    let failures = self
+      .ready
+      .subscribe()
+      .wait_for(|readiness| readiness.ready >= self.target_min() || !readiness.failures.is_empty())
+      .await
+      .map_err(|e| wapc::errors::Error::General(e.to_string()))?
+      .failures
+      .clone();
+    if !failures.is_empty() {
+      return Err(Error::WarmUpFailed(failures).into());
+    }
+    let Some((op, payload)) = op else {
+      return Ok(());
+    };
+    if lock(&self.tx).is_none() {
+      return Err(Error::PoolClosed.into());
+    }
+
+    let mut failures = Vec::new();
+    for (i, reply) in self.prime(op, &payload) {
+      match reply.await {
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => failures.push(format!("{}.{}: {}", self.name, i, e)),
+        // the worker exited without running the call
+        Err(_) => {}
+      }
+    }
+    if failures.is_empty() {
+      Ok(())
+    } else {
+      Err(Error::WarmUpFailed(failures).into())
+    }
+  }
+
+  /// Call an operation on every worker, for instance to reload a configuration or invalidate a
+  /// cache held by the guests. Returns the result of each worker, ordered by worker id.
+  ///
+  /// A worker not returning within `timeout` yields [`wapc::errors::Error::GuestCallFailure`],
+  /// like in [HostPool::call_with_timeout]. The workers spawned during the broadcast do not
+  /// receive the call.
+  pub async fn broadcast<T: AsRef<str> + Sync + Send>(
+    &self,
+    op: T,
+    payload: Vec<u8>,
+    timeout: Duration,
+  ) -> Result<Vec<Result<Vec<u8>>>> {
+    if lock(&self.tx).is_none() {
+      return Err(Error::PoolClosed.into());
+    }
+    let mut replies = self.prime(op.as_ref(), &payload);
+    replies.sort_by_key(|(i, _)| *i);
+    // the workers run the call concurrently: they share the deadline
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut results = Vec::with_capacity(replies.len());
+    for (_, reply) in replies {
+      results.push(match tokio::time::timeout_at(deadline, reply).await {
+        Ok(Ok(res)) => res,
+        Ok(Err(e)) => Err(wapc::errors::Error::General(e.to_string())),
+        Err(_) => Err(wapc::errors::Error::GuestCallFailure(
+          Error::CallTimeout(timeout).to_string(),
+        )),
+      });
+    }
+    Ok(results)
+  }
+
+  // Send the call to every running worker, returns the reply of each
+  fn prime(&self, op: &str, payload: &[u8]) -> Vec<(usize, OneshotReceiver<CallResult>)> {
+    lock(&self.workers)
+      .iter()
+      .filter_map(|(i, control)| {
+        let (reply, rx) = tokio::sync::oneshot::channel();
+        let prime = Prime {
+          op: op.to_owned(),
+          payload: payload.to_vec(),
+          reply,
+        };
+        control.send(Control::Prime(prime)).ok().map(|()| (*i, rx))
+      })
+      .collect()
+  }
+
+  /// Shut down the host pool. The calls not completed yet fail with [Error::PoolShutdown], then
+  /// the workers are joined once they complete the calls they are running.
+  ///
+  /// The workers stuck in a call abandoned by [HostPool::call_with_timeout] are not joined, they
+  /// exit once the call returns.
+  pub fn shutdown(&mut self) -> Result<()> {
+    let pool = self.close()?;
+    self.fail_pending();
+    // the stuck workers may never return: only the live ones are waited for
+    while self.current() > 0 {
+      pool.join_timeout(SHUTDOWN_POLL_INTERVAL);
+    }
+    pool.shutdown();
+    Ok(())
+  }
+
+  /// Shut down the host pool gracefully: new calls fail with [Error::PoolClosed] while the
+  /// workers complete the calls already queued, until `timeout` elapses. The calls not completed
+  /// by then fail with [Error::PoolShutdown].
+  ///
+  /// The workers still running a call after `timeout` are not joined, they exit once the call
+  /// returns.
+  pub fn shutdown_graceful(&self, timeout: Duration) -> Result<()> {
+    let pool = self.close()?;
+    pool.shutdown_join_timeout(timeout);
+    self.fail_pending();
+    Ok(())
+  }
+
+  // Stop accepting new calls, the workers exit once the channel is empty
+  fn close(&self) -> Result<ThreadPool> {
+    let pool = lock(&self.pool).take().ok_or(Error::NoPool)?;
+    lock(&self.tx).take();
+    lock(&self.watchdog).take();
+    debug!("Shutting down host pool '{}'", self.name);
+    Ok(pool)
+  }
+
+  fn fail_pending(&self) {
+    let pending: Vec<_> = lock(&self.pending).drain().map(|(_, pending)| pending).collect();
+    for pending in pending {
+      transition(&pending.state.phase, CALL_QUEUED, CALL_ABANDONED);
+      let _ = pending.reply.send(Err(Error::PoolShutdown.into()));
+    }
+    for rx in self.rx.iter().chain(&self.slots_rx).chain(self.ops_rx.values()) {
+      while rx.try_recv().is_ok() {}
+    }
+  }
+}
+
+impl Drop for HostPool {
+  fn drop(&mut self) {
+    if self.on_drop == DropPolicy::Detach {
+      return;
+    }
+    // the pool was already shut down
+    let Ok(pool) = self.close() else {
+      return;
+    };
+    self.fail_pending();
+    if let DropPolicy::JoinWithTimeout(timeout) = self.on_drop {
+      pool.shutdown_join_timeout(timeout);
+    }
+  }
+}
+
+// A worker thread running calls on its own host
+struct Worker {
+  name: String,
+  i: usize,
+  factory: Arc<Factory>,
+  host_callback: Option<Arc<PoolHostCallback>>,
+  max_idle: Option<Duration>,
+  min_threads: Arc<AtomicUsize>,
+  max_respawns: usize,
+  // the number of dedicated workers, on top of min_threads
+  reserved: usize,
+  // the queues of the calls by priority, none for the dedicated workers
+  rx: Option<[SyncReceiver<WorkerMessage>; 3]>,
+  // the slot of a permanent worker and the queue of its sticky calls
+  slot: Option<(usize, SyncReceiver<WorkerMessage>)>,
+  // the queues of the operations with their own queue run by the worker
+  ops: Vec<SyncReceiver<WorkerMessage>>,
+  // the operation of a dedicated worker
+  dedicated_to: Option<String>,
+  dedicated: Arc<Mutex<HashMap<String, HashSet<usize>>>>,
+  control_rx: SyncReceiver<Control>,
+  // the number of calls picked up
+  picks: Cell<u64>,
+  // when the worker last picked up a call, it exits once idle for max_idle
+  last_active: Cell<Instant>,
+  max_calls: Option<usize>,
+  // the number of calls run by the current host, it is recreated once it reaches recycle_at
+  served: Cell<usize>,
+  recycle_at: Cell<usize>,
+  // the number of hosts created by the worker
+  hosts: Cell<u64>,
+  executions: Option<Arc<Executions>>,
+  health_check: Option<Arc<HealthCheck>>,
+  probes: SyncReceiver<Instant>,
+  health: Arc<Mutex<HashMap<usize, WorkerHealth>>>,
+  stats: Arc<Mutex<HashMap<usize, WorkerStats>>>,
+  pending: Arc<Mutex<HashMap<u64, PendingReply>>>,
+  stuck_workers: Arc<AtomicUsize>,
+  workers: Arc<Mutex<HashMap<usize, SyncSender<Control>>>>,
+  module: Arc<Mutex<Option<CurrentModule>>>,
+  slot_workers: Arc<Mutex<Vec<Option<usize>>>>,
+  panics: Arc<AtomicUsize>,
+  counters: Arc<Counters>,
+  ready: Arc<tokio::sync::watch::Sender<Readiness>>,
+}
+
+impl Worker {
+  fn run(self) {
+    let (name, i) = (&self.name, self.i);
+    trace!("Host thread {}.{} started...", name, i);
+    let (mut host, mut generation) = match self.new_host() {
+      Ok(host) => host,
+      Err(e) => {
+        error!("Host thread {}.{} closing: {}", name, i, e);
+        instrument::worker_stopped(name, i, &e.to_string());
+        // the slot is vacated first: a warm-up either replaces the worker or sees its failure
+        self.leave();
+        if self.slot.is_some() {
+          let failure = format!("{}.{}: {}", name, i, e);
+          self.ready.send_modify(|readiness| readiness.failures.push(failure));
+        }
+        return;
+      }
+    };
+    self.ready.send_modify(|readiness| readiness.ready += 1);
+    instrument::worker_started(name, i);
+    lock(&self.stats).insert(i, WorkerStats::new(i));
+    if self.health_check.is_some() {
+      lock(&self.health).insert(i, WorkerHealth::new(i));
+    }
+    loop {
+      let mut message = match self.receive() {
+        Received::Call(message) => message,
+        Received::Control(Control::ReplaceModule(replace)) => {
+          let result = if replace.generation <= generation {
+            Ok(())
+          } else {
+            debug!("Host thread {}.{} replacing its module", name, i);
+            host.replace_module(&replace.bytes).map_err(|e| e.to_string())
+          };
+          if result.is_ok() {
+            generation = generation.max(replace.generation);
+          }
+          let _ = replace.reply.send(result);
+          continue;
+        }
+        Received::Control(Control::Prime(prime)) => {
+          let (result, panicked) = self.guarded_call(&host, &prime.op, &prime.payload);
+          let _ = prime.reply.send(result);
+          if panicked {
+            match self.respawn() {
+              Some(respawned) => (host, generation) = respawned,
+              None => break,
+            }
+          }
+          continue;
+        }
+        Received::Probe => {
+          if self.probe(&host) {
+            match self.new_host() {
+              Ok(recycled) => (host, generation) = recycled,
+              Err(e) => {
+                error!("Host thread {}.{} closing: {}", name, i, e);
+                instrument::worker_stopped(name, i, &e.to_string());
+                break;
+              }
+            }
+          }
+          continue;
+        }
+        Received::Closed(e) => {
+          debug!("Host thread {}.{} closing: {}", name, i, e);
+          instrument::worker_stopped(name, i, &e);
+          break;
+        }
+      };
+      match self.run_call(&host, &mut message) {
+        Some(true) => match self.respawn() {
+          Some(respawned) => (host, generation) = respawned,
+          None => break,
+        },
+        Some(false) if self.served.get() >= self.recycle_at.get() => {
+          debug!(
+            "Host thread {}.{} recycling its host after {} calls",
+            name,
+            i,
+            self.served.get()
+          );
+          drop(host);
+          self.counters.worker_recycles.fetch_add(1, Ordering::AcqRel);
+          instrument::worker_recycled(name, i);
+          match self.new_host() {
+            Ok(recycled) => (host, generation) = recycled,
+            Err(e) => {
+              error!("Host thread {}.{} closing: {}", name, i, e);
+              instrument::worker_stopped(name, i, &e.to_string());
+              break;
+            }
+          }
+        }
+        Some(false) => {}
+        None => break,
+      }
+    }
+
+    self.leave();
+    self.ready.send_modify(|readiness| readiness.ready -= 1);
+    trace!("Host thread {}.{} stopped.", name, i);
+  }
+
+  // Run a call picked up from a queue, returns whether it panicked, or None when the worker must
+  // close
+  fn run_call(&self, host: &WapcHost, message: &mut WorkerMessage) -> Option<bool> {
+    let (name, i) = (&self.name, self.i);
+    if !transition(&message.state.phase, CALL_QUEUED, CALL_RUNNING) {
+      trace!("Host thread {}.{} skipping abandoned call for {}", name, i, message.op);
+      return Some(false);
+    }
+    let slot = self.slot.as_ref().map_or(NO_SLOT, |(slot, _)| *slot);
+    message.state.slot.store(slot, Ordering::Release);
+    message.state.worker.store(i, Ordering::Release);
+    let permit = message.permit.take();
+    #[cfg(feature = "tracing")]
+    let _span = message.span.enter();
+    let queued = message.queued_at.elapsed();
+    self.counters.record_wait(queued);
+    instrument::queued(i, queued);
+    self.counters.busy_workers.fetch_add(1, Ordering::AcqRel);
+    trace!(
+      "Host thread {}.{} received call for {} with {} byte payload",
+      name,
+      i,
+      message.op,
+      message.payload.len()
+    );
+    let started = Instant::now();
+    if let Some(executions) = &self.executions {
+      executions.start(i, &message.op);
+    }
+    let (result, panicked) = self.guarded_call(host, &message.op, &message.payload);
+    let executed = started.elapsed();
+    if let Some(executions) = &self.executions {
+      executions.finish(i);
+    }
+    lock(&self.stats)
+      .entry(i)
+      .or_insert_with(|| WorkerStats::new(i))
+      .record(result.as_ref().err().map(ToString::to_string));
+    self.served.set(self.served.get() + 1);
+    // released before replying: the caller may start another call of the operation right away
+    drop(permit);
+    instrument::executed(i, executed, result.is_ok());
+    self.counters.busy_workers.fetch_sub(1, Ordering::AcqRel);
+    if !transition(&message.state.phase, CALL_RUNNING, CALL_DONE) {
+      // a replacement has been spawned, the state of the host is unknown
+      warn!(
+        "Host thread {}.{} closing after completing the abandoned call for {}",
+        name, i, message.op
+      );
+      self.stuck_workers.fetch_sub(1, Ordering::AcqRel);
+      instrument::worker_stopped(name, i, "abandoned call");
+      return None;
+    }
+    match lock(&self.pending).remove(&message.id) {
+      Some(pending) => {
+        let result = result.map(|response| CallDetails {
+          response,
+          worker: i,
+          queued,
+          executed,
+        });
+        if pending.reply.send(result).is_err() {
+          error!("Host thread {}.{} failed when returning a value...", name, i);
+        }
+      }
+      None => debug!(
+        "Host thread {}.{} completed the call for {} after the pool shut down",
+        name, i, message.op
+      ),
+    }
+    Some(panicked)
+  }
+
+  fn leave(&self) {
+    // vacate the slot, unless the worker was replaced already
+    if let Some((slot, _)) = &self.slot {
+      let mut slot_workers = lock(&self.slot_workers);
+      if slot_workers[*slot] == Some(self.i) {
+        slot_workers[*slot] = None;
+      }
+    }
+    if let Some(op) = &self.dedicated_to {
+      if let Some(workers) = lock(&self.dedicated).get_mut(op) {
+        workers.remove(&self.i);
+      }
+    }
+    lock(&self.health).remove(&self.i);
+    lock(&self.stats).remove(&self.i);
+    lock(&self.workers).remove(&self.i);
+  }
+
+  // Run the health check, returns whether the host is unhealthy and must be recycled
+  fn probe(&self, host: &WapcHost) -> bool {
+    let Some(check) = &self.health_check else {
+      return false;
+    };
+    let (result, panicked) = self.guarded_call(host, &check.op, &check.payload);
+    let mut health = lock(&self.health);
+    let health = health.entry(self.i).or_insert_with(|| WorkerHealth::new(self.i));
+    health.healthy = result.is_ok();
+    if let Err(e) = result {
+      health.consecutive_failures += 1;
+      warn!(
+        "Host thread {}.{} failed its health check ({} in a row): {}",
+        self.name, self.i, health.consecutive_failures, e
+      );
+    } else {
+      health.consecutive_failures = 0;
+    }
+    // the state of the host is unknown after a panic
+    if !panicked && health.consecutive_failures < check.unhealthy_threshold {
+      return false;
+    }
+    warn!("Host thread {}.{} recycling its host", self.name, self.i);
+    health.consecutive_failures = 0;
+    health.recycles += 1;
+    true
+  }
+
+  // Call the host, returns whether the call panicked
+  fn guarded_call(&self, host: &WapcHost, op: &str, payload: &[u8]) -> (CallResult, bool) {
+    match catch_unwind(AssertUnwindSafe(|| host.call(op, payload))) {
+      Ok(result) => (result, false),
+      Err(panic) => {
+        let panic = panic_message(&*panic);
+        error!(
+          "Host thread {}.{} panicked during the call for {}: {}",
+          self.name, self.i, op, panic
+        );
+        self.panics.fetch_add(1, Ordering::AcqRel);
+        (Err(Error::WorkerPanicked(panic).into()), true)
+      }
+    }
+  }
+
+  // The host may be left in any state by a panic: it is replaced, unless the workers panicked
+  // too many times already
+  fn respawn(&self) -> Option<(WapcHost, u64)> {
+    if self.panics.load(Ordering::Acquire) > self.max_respawns {
+      error!("Host thread {}.{} closing: too many panics", self.name, self.i);
+      instrument::worker_stopped(&self.name, self.i, "too many panics");
+      return None;
+    }
+    match self.new_host() {
+      Ok(respawned) => {
+        instrument::worker_respawned(&self.name, self.i);
+        Some(respawned)
+      }
+      Err(e) => {
+        error!("Host thread {}.{} closing: {}", self.name, self.i, e);
+        instrument::worker_stopped(&self.name, self.i, &e.to_string());
+        None
+      }
+    }
+  }
+
+  // Create a host running the current module, returns it with the generation of its module
+  fn new_host(&self) -> Result<(WapcHost, u64)> {
+    let host = self.create_host()?;
+    self.hosts.set(self.hosts.get() + 1);
+    self.served.set(0);
+    self.recycle_at.set(self.max_calls.map_or(usize::MAX, |max| {
+      // the workers started together serve up to a quarter more calls each, so they don't
+      // recycle their hosts all at once
+      let mut hasher = DefaultHasher::new();
+      (self.i, self.hosts.get()).hash(&mut hasher);
+      max.saturating_add((hasher.finish() % (max as u64 / 4 + 1)) as usize)
+    }));
+    let mut generation = 0;
+    if let Some(current) = lock(&self.module).clone() {
+      match host.replace_module(&current.bytes) {
+        Ok(()) => generation = current.generation,
+        Err(e) => error!(
+          "Host thread {}.{} failed to load the replacement module: {}",
+          self.name, self.i, e
+        ),
+      }
+    }
+    Ok((host, generation))
+  }
+
+  // The host callback of the pool, told which worker makes the host calls
+  fn callback(&self) -> Box<HostCallback> {
+    let context = WorkerContext {
+      pool: self.name.clone(),
+      worker: self.i,
+    };
+    self.host_callback.clone().map_or_else(
+      || -> Box<HostCallback> { Box::new(|_, _, _, _, _| Err("Missing host callback function!".into())) },
+      |callback| {
+        Box::new(move |id, binding, namespace, operation, payload| {
+          callback(&context, id, binding, namespace, operation, payload)
+        })
+      },
+    )
+  }
+
+  // Call the factory, retrying with backoff when it fails
+  fn create_host(&self) -> Result<WapcHost> {
+    let mut backoff = FACTORY_BACKOFF;
+    let mut attempt = 1;
+    loop {
+      let e = match (self.factory)(self.callback()) {
+        Ok(host) => return Ok(host),
+        Err(e) => e,
+      };
+      self.counters.factory_failures.fetch_add(1, Ordering::AcqRel);
+      if attempt == MAX_FACTORY_ATTEMPTS {
+        return Err(e);
+      }
+      warn!(
+        "Host thread {}.{} failed to create its host (attempt {}): {}",
+        self.name, self.i, attempt, e
+      );
+      std::thread::sleep(backoff);
+      backoff *= 2;
+      attempt += 1;
+    }
+  }
+
+  fn receive(&self) -> Received {
+    if let Ok(control) = self.control_rx.try_recv() {
+      return Received::Control(control);
+    }
+    if self.probes.try_recv().is_ok() {
+      return Received::Probe;
+    }
+    // the sticky calls can only run on this worker, they come first. The calls of the operations
+    // with their own queue run with the normal priority
+    let mut order: Vec<_> = self.rx.iter().flat_map(|rx| &rx[..2]).chain(&self.ops).collect();
+    order.extend(self.rx.iter().map(|rx| &rx[Priority::Low.index()]));
+    if (self.picks.get() + 1) % FAIRNESS_INTERVAL == 0 {
+      order.reverse();
+    }
+    let mut queues: Vec<_> = self.slot.iter().map(|(_, rx)| rx).chain(order).collect();
+    for rx in &queues {
+      if let Ok(message) = rx.try_recv() {
+        return self.picked(message);
+      }
+    }
+
+    // no call is queued: wait for one on any queue
+    let mut idle = self.max_idle.map_or_else(crossbeam::channel::never, |max_idle| {
+      crossbeam::channel::at(self.last_active.get() + max_idle)
+    });
+    loop {
+      let mut select = Select::new();
+      for rx in &queues {
+        select.recv(rx);
+      }
+      let control = select.recv(&self.control_rx);
+      let timeout = select.recv(&idle);
+      let probe = select.recv(&self.probes);
+      let operation = select.select();
+      let index = operation.index();
+      if index == control {
+        return operation
+          .recv(&self.control_rx)
+          .map_or_else(|e| Received::Closed(e.to_string()), Received::Control);
+      }
+      if index == timeout {
+        let _ = operation.recv(&idle);
+        if self.retire() {
+          return Received::Closed("idle timeout".to_owned());
+        }
+        // the pool needs the worker to keep min_threads workers
+        self.last_active.set(Instant::now());
+        idle = self.max_idle.map_or_else(crossbeam::channel::never, |max_idle| {
+          crossbeam::channel::at(self.last_active.get() + max_idle)
+        });
+        continue;
+      }
+      if index == probe {
+        let _ = operation.recv(&self.probes);
+        return Received::Probe;
+      }
+      // the selected operation is on one of the queues, added first
+      match operation.recv(queues[index]) {
+        Ok(message) => return self.picked(message),
+        // the other queues may still hold calls
+        Err(e) => {
+          queues.remove(index);
+          if queues.is_empty() {
+            return Received::Closed(e.to_string());
+          }
+        }
+      }
+    }
+  }
+
+  // Leave the pool after the idle timeout, unless it would have less than min_threads workers.
+  // Decided while holding the workers: the idle workers cannot leave all at once
+  fn retire(&self) -> bool {
+    let mut workers = lock(&self.workers);
+    let live = workers.len().saturating_sub(self.stuck_workers.load(Ordering::Acquire));
+    if live <= self.min_threads.load(Ordering::Acquire) + self.reserved {
+      return false;
+    }
+    workers.remove(&self.i);
+    true
+  }
+
+  fn picked(&self, message: WorkerMessage) -> Received {
+    self.picks.set(self.picks.get() + 1);
+    self.last_active.set(Instant::now());
+    Received::Call(message)
+  }
+}
+
+// The slot of the permanent worker serving the sticky calls with `key`
+fn sticky_slot(key: u64, slots: usize) -> usize {
+  let mut hasher = DefaultHasher::new();
+  key.hash(&mut hasher);
+  (hasher.finish() % slots as u64) as usize
+}
+
+struct ThreadWaker(std::thread::Thread);
+
+impl Wake for ThreadWaker {
+  fn wake(self: Arc<Self>) {
+    self.0.unpark();
+  }
+}
+
+// Run the future on the current thread, returns None when it is not ready by the deadline
+fn block_on<F: Future>(future: F, deadline: Option<Instant>) -> Option<F::Output> {
+  let mut future = std::pin::pin!(future);
+  let waker = Arc::new(ThreadWaker(std::thread::current())).into();
+  let mut context = Context::from_waker(&waker);
+  loop {
+    if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+      return Some(output);
+    }
+    match deadline {
+      None => std::thread::park(),
+      Some(deadline) => std::thread::park_timeout(deadline.checked_duration_since(Instant::now())?),
+    }
+  }
+}
+
+// Move the call from the `from` state to the `to` one, returns false when it is not in the
+// `from` state
+fn transition(state: &AtomicU8, from: u8, to: u8) -> bool {
+  state
+    .compare_exchange(from, to, Ordering::AcqRel, Ordering::Acquire)
+    .is_ok()
+}
+
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> String {
+  panic
+    .downcast_ref::<&str>()
+    .map(|message| (*message).to_owned())
+    .or_else(|| panic.downcast_ref::<String>().cloned())
+    .unwrap_or_else(|| "unknown panic".to_owned())
+}
+
+// The data behind the locks stays consistent when a holder panics
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+  mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[must_use]
+/// Builder for a [HostPool]
+pub struct HostPoolBuilder {
+  name: Option<String>,
+  factory: Option<Box<Factory>>,
+  host_callback: Option<Arc<PoolHostCallback>>,
+  min_threads: usize,
+  max_threads: usize,
+  max_wait: Duration,
+  max_idle: Duration,
+  max_respawns: usize,
+  queue_capacity: usize,
+  on_saturation: SaturationPolicy,
+  health_check: Option<HealthCheck>,
+  op_limits: HashMap<String, usize>,
+  on_drop: DropPolicy,
+  max_calls_per_worker: Option<usize>,
+  slow_call_threshold: Option<Duration>,
+  partitions: HashMap<String, usize>,
+}
+
+impl std::fmt::Debug for HostPoolBuilder {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("HostPoolBuilder")
+      .field("name", &self.name)
+      .field("factory", if self.factory.is_some() { &"Some(Fn)" } else { &"None" })
+      .field(
+        "host_callback",
+        if self.host_callback.is_some() {
+          &"Some(Fn)"
+        } else {
+          &"None"
+        },
+      )
+      .field("min_threads", &self.min_threads)
+      .field("max_threads", &self.max_threads)
+      .field("max_wait", &self.max_wait)
+      .field("max_idle", &self.max_idle)
+      .field("max_respawns", &self.max_respawns)
+      .field("queue_capacity", &self.queue_capacity)
+      .field("on_saturation", &self.on_saturation)
+      .field("health_check", &self.health_check)
+      .field("op_limits", &self.op_limits)
+      .field("on_drop", &self.on_drop)
+      .field("max_calls_per_worker", &self.max_calls_per_worker)
+      .field("slow_call_threshold", &self.slow_call_threshold)
+      .field("partitions", &self.partitions)
+      .finish()
+  }
+}
+
+impl Default for HostPoolBuilder {
+  fn default() -> Self {
+    Self {
+      name: None,
+      factory: None,
+      host_callback: None,
+      min_threads: 1,
+      max_threads: 2,
+      max_wait: Duration::from_millis(100),
+      max_idle: Duration::from_secs(5 * 60),
+      max_respawns: DEFAULT_MAX_RESPAWNS,
+      queue_capacity: DEFAULT_QUEUE_CAPACITY,
+      on_saturation: SaturationPolicy::Block,
+      health_check: None,
+      op_limits: HashMap::new(),
+      on_drop: DropPolicy::default(),
+      max_calls_per_worker: None,
+      slow_call_threshold: None,
+      partitions: HashMap::new(),
+    }
+  }
+}
+
+impl HostPoolBuilder {
+  /// Instantiate a nnew [HostPoolBuilder] with default settings.
   ///
   /// ```
   /// # use wapc_pool::HostPoolBuilder;
-  /// # use wapc::WapcHost;
-  /// # let bytes = std::fs::read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm").unwrap();
-  /// let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
-  ///   .module_bytes(&bytes)
-  ///   .build().unwrap();
-  /// let pool = HostPoolBuilder::new()
-  ///   .factory(move || {
-  ///     let engine = engine.clone();
-  ///     WapcHost::new(Box::new(engine), None).unwrap()
-  ///   })
-  ///   .build();
+  /// let builder = HostPoolBuilder::new();
   /// ```
   ///
-  pub fn build(mut self) -> HostPool {
-    #[allow(clippy::expect_used)]
-    let factory = self
-      .factory
-      .take()
-      .expect("A waPC host pool must have a factory function.");
-    HostPool::new(
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Set the name for the HostPool.
+  ///
+  /// ```
+  /// # use wapc_pool::HostPoolBuilder;
+  /// let builder = HostPoolBuilder::new().name("My Module");
+  /// ```
+  ///
+  pub fn name<T: AsRef<str>>(mut self, name: T) -> Self {
+    self.name = Some(name.as_ref().to_owned());
+    self
+  }
+
+  /// Set the [WapcHost] generator function to use when spawning new workers.
+  ///
+  /// ```
+  /// # use wapc_pool::HostPoolBuilder;
+  /// # use wapc::WapcHost;
+  /// # let bytes = std::fs::read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm").unwrap();
+  /// let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+  ///   .module_bytes(&bytes)
+  ///   .build()
+  ///   .unwrap();
+  /// let pool = HostPoolBuilder::new()
+  ///   .factory(move || {
+  ///     let engine = engine.clone();
+  ///     WapcHost::new(Box::new(engine), None).unwrap()
+  ///   })
+  ///   .build()
+  ///   .unwrap();
+  /// ```
+  ///
+  /// With wasmtime, `HostPoolBuilder::wasmtime_pre` (behind the `wasmtime` feature) sets this
+  /// factory from a `wasmtime_provider::WasmtimeEngineProviderPre`.
+  ///
+  /// The factory can also be written once for any [`wasmtime_provider::EnginePre`]
+  /// whose providers can back a [WapcHost]:
+  ///
+  /// ```
+  /// # use wapc_pool::HostPoolBuilder;
+  /// # use wapc::{WapcHost, WebAssemblyEngineProvider};
+  /// use wasmtime_provider::EnginePre;
+  ///
+  /// fn host_factory<P>(pre: P) -> impl Fn() -> WapcHost + Send + Sync + 'static
+  /// where
+  ///   P: EnginePre + Send + Sync + 'static,
+  ///   P::Provider: WebAssemblyEngineProvider + 'static,
+  /// {
+  ///   move || WapcHost::new(Box::new(pre.rehydrate().unwrap()), None).unwrap()
+  /// }
+  ///
+  /// # let bytes = std::fs::read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm").unwrap();
+  /// let pre = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+  ///   .module_bytes(&bytes)
+  ///   .build_pre()
+  ///   .unwrap();
+  /// let pool = HostPoolBuilder::new().factory(host_factory(pre)).build().unwrap();
+  /// ```
+  ///
+  pub fn factory<F>(mut self, factory: F) -> Self
+  where
+    F: Fn() -> WapcHost + Send + Sync + 'static,
+  {
+    self.factory = Some(Box::new(move |_| Ok(factory())));
+    self
+  }
+
+  /// Set a fallible [WapcHost] generator function to use when spawning new workers.
+  ///
+  /// A worker failing to create its host retries a few times, waiting longer after each failure,
+  /// then exits. The failures are counted in [crate::PoolMetrics::factory_failures], and
+  /// [HostPool::warm_up] fails when a permanent worker gave up.
+  ///
+  /// ```
+  /// # use wapc_pool::HostPoolBuilder;
+  /// # use wapc::WapcHost;
+  /// let pool = HostPoolBuilder::new()
+  ///   .try_factory(move || {
+  ///     let bytes = std::fs::read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm")?;
+  ///     let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+  ///       .module_bytes(&bytes)
+  ///       .build()?;
+  ///     WapcHost::new(Box::new(engine), None)
+  ///   })
+  ///   .build()
+  ///   .unwrap();
+  /// ```
+  ///
+  pub fn try_factory<F>(mut self, factory: F) -> Self
+  where
+    F: Fn() -> std::result::Result<WapcHost, wapc::errors::Error> + Send + Sync + 'static,
+  {
+    self.factory = Some(Box::new(move |_| factory()));
+    self
+  }
+
+  /// Set a [WapcHost] generator function receiving the host callback set with
+  /// [HostPoolBuilder::host_callback], prepared for the worker it creates the host of. Fails
+  /// like [HostPoolBuilder::try_factory].
+  ///
+  /// ```
+  /// # use wapc_pool::HostPoolBuilder;
+  /// # use wapc::WapcHost;
+  /// # let bytes = std::fs::read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm").unwrap();
+  /// let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+  ///   .module_bytes(&bytes)
+  ///   .build()
+  ///   .unwrap();
+  /// let pool = HostPoolBuilder::new()
+  ///   .host_callback(|context, _id, _binding, _namespace, _operation, _payload| {
+  ///     Ok(context.worker.to_string().into_bytes())
+  ///   })
+  ///   .factory_with(move |callback| WapcHost::new(Box::new(engine.clone()), Some(callback)))
+  ///   .build()
+  ///   .unwrap();
+  /// ```
+  ///
+  pub fn factory_with<F>(mut self, factory: F) -> Self
+  where
+    F: Fn(Box<HostCallback>) -> std::result::Result<WapcHost, wapc::errors::Error> + Send + Sync + 'static,
+  {
+    self.factory = Some(Box::new(factory));
+    self
+  }
+
+  /// Create the host of each worker from a pre initialized wasmtime provider, calling
+  /// [`wasmtime_provider::WasmtimeEngineProviderPre::rehydrate`] which is faster than cloning a
+  /// provider. The hosts share `callback`, without it they use the host callback set with
+  /// [HostPoolBuilder::host_callback]. Fails like [HostPoolBuilder::try_factory].
+  ///
+  /// ```
+  /// # use wapc_pool::HostPoolBuilder;
+  /// # let bytes = std::fs::read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm").unwrap();
+  /// let pre = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+  ///   .module_bytes(&bytes)
+  ///   .build_pre()
+  ///   .unwrap();
+  /// let pool = HostPoolBuilder::new().wasmtime_pre(pre, None).build().unwrap();
+  /// ```
+  ///
+  #[cfg(feature = "wasmtime")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "wasmtime")))]
+  pub fn wasmtime_pre(
+    self,
+    pre: wasmtime_provider::WasmtimeEngineProviderPre,
+    callback: Option<Box<HostCallback>>,
+  ) -> Self {
+    let callback: Option<Arc<HostCallback>> = callback.map(Arc::from);
+    self.factory_with(move |pool_callback| {
+      let callback = callback.clone().map_or(pool_callback, |callback| {
+        let callback: Box<HostCallback> = Box::new(move |id, binding, namespace, operation, payload| {
+          callback(id, binding, namespace, operation, payload)
+        });
+        callback
+      });
+      WapcHost::new(Box::new(pre.rehydrate()?), Some(callback))
+    })
+  }
+
+  /// Set the host callback shared by the workers, handed to the factory set with
+  /// [HostPoolBuilder::factory_with]. It is told the pool and the worker making each host call.
+  /// Without it, the host calls fail.
+  ///
+  /// ```
+  /// # use wapc_pool::HostPoolBuilder;
+  /// let builder = HostPoolBuilder::new().host_callback(|context, _id, binding, namespace, operation, _payload| {
+  ///   println!("{}.{} called {}/{}/{}", context.pool, context.worker, binding, namespace, operation);
+  ///   Ok(vec![])
+  /// });
+  /// ```
+  ///
+  pub fn host_callback<F>(mut self, callback: F) -> Self
+  where
+    F: Fn(
+        &WorkerContext,
+        u64,
+        &str,
+        &str,
+        &str,
+        &[u8],
+      ) -> std::result::Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>
+      + Send
+      + Sync
+      + 'static,
+  {
+    self.host_callback = Some(Arc::new(callback));
+    self
+  }
+
+  /// Set the minimum, base number of threads to spawn.
+  ///
+  /// ```
+  /// # use wapc_pool::HostPoolBuilder;
+  /// let builder = HostPoolBuilder::new().min_threads(1);
+  /// ```
+  ///
+  pub fn min_threads(mut self, min: usize) -> Self {
+    self.min_threads = min;
+    self
+  }
+
+  /// Set the upper limit on the number of threads to spawn.
+  ///
+  /// ```
+  /// # use wapc_pool::HostPoolBuilder;
+  /// let builder = HostPoolBuilder::new().max_threads(5);
+  /// ```
+  ///
+  pub fn max_threads(mut self, max: usize) -> Self {
+    self.max_threads = max;
+    self
+  }
+
+  /// Set the timeout for threads to self-close.
+  ///
+  /// ```
+  /// # use wapc_pool::HostPoolBuilder;
+  /// # use std::time::Duration;
+  /// let builder = HostPoolBuilder::new().max_idle(Duration::from_secs(60));
+  /// ```
+  ///
+  pub fn max_idle(mut self, timeout: Duration) -> Self {
+    self.max_idle = timeout;
+    self
+  }
+
+  /// Set the maximum amount of time to wait before spawning a new worker.
+  ///
+  /// ```
+  /// # use wapc_pool::HostPoolBuilder;
+  /// # use std::time::Duration;
+  /// let builder = HostPoolBuilder::new().max_wait(Duration::from_millis(100));
+  /// ```
+  ///
+  pub fn max_wait(mut self, duration: Duration) -> Self {
+    self.max_wait = duration;
+    self
+  }
+
+  /// Set how many calls can wait for a worker on the queue. Defaults to 1.
+  ///
+  /// A call waits up to `max_wait` for room on the queue before the pool grows by one worker,
+  /// a larger queue absorbs bursts without growing the pool but the queued calls wait longer.
+  /// With a capacity of 0 the queue holds no call: it is handed over to an idle worker directly
+  /// and the pool grows as soon as no worker picks it up within `max_wait`.
+  ///
+  /// ```
+  /// # use wapc_pool::HostPoolBuilder;
+  /// let builder = HostPoolBuilder::new().queue_capacity(16);
+  /// ```
+  ///
+  pub fn queue_capacity(mut self, capacity: usize) -> Self {
+    self.queue_capacity = capacity;
+    self
+  }
+
+  /// Set what a call does when the pool is saturated: every worker is busy, the queue is full and
+  /// the pool reached `max_threads`. Defaults to [SaturationPolicy::Block].
+  ///
+  /// With [SaturationPolicy::Error], the calls fail right away with [Error::PoolSaturated] so the
+  /// callers can shed the load instead of piling up.
+  ///
+  /// ```
+  /// # use wapc_pool::{HostPoolBuilder, SaturationPolicy};
+  /// let builder = HostPoolBuilder::new().on_saturation(SaturationPolicy::Error);
+  /// ```
+  ///
+  pub fn on_saturation(mut self, policy: SaturationPolicy) -> Self {
+    self.on_saturation = policy;
+    self
+  }
+
+  /// Set what happens to the workers when the pool is dropped without being shut down. Defaults
+  /// to [DropPolicy::JoinWithTimeout] with a bound of 500ms.
+  ///
+  /// ```
+  /// # use std::time::Duration;
+  /// # use wapc_pool::{DropPolicy, HostPoolBuilder};
+  /// let builder = HostPoolBuilder::new().on_drop(DropPolicy::JoinWithTimeout(Duration::from_secs(5)));
+  /// ```
+  ///
+  pub fn on_drop(mut self, policy: DropPolicy) -> Self {
+    self.on_drop = policy;
+    self
+  }
+
+  /// Set a health check: every `interval`, each worker calls `op` with `payload` when it is not
+  /// running a call. A worker whose health check fails `unhealthy_threshold` times in a row, or
+  /// panics, drops its host and creates a new one from the factory. The status of the workers is
+  /// reported by [HostPool::health].
+  ///
+  /// ```
+  /// # use std::time::Duration;
+  /// # use wapc_pool::HostPoolBuilder;
+  /// let builder = HostPoolBuilder::new().health_check("health", vec![], Duration::from_secs(10), 3);
+  /// ```
+  ///
+  pub fn health_check<T: AsRef<str>>(
+    mut self,
+    op: T,
+    payload: Vec<u8>,
+    interval: Duration,
+    unhealthy_threshold: usize,
+  ) -> Self {
+    self.health_check = Some(HealthCheck {
+      op: op.as_ref().to_owned(),
+      payload,
+      interval,
+      unhealthy_threshold,
+    });
+    self
+  }
+
+  /// Limit how many calls of the operation `op` run at a time, the other operations can use every
+  /// worker. The calls past the limit wait for a running one to complete, or fail with
+  /// [Error::ConcurrencyLimitReached] according to the [SaturationPolicy] of the pool.
+  ///
+  /// ```
+  /// # use wapc_pool::HostPoolBuilder;
+  /// let builder = HostPoolBuilder::new().op_concurrency_limit("fetch", 2);
+  /// ```
+  ///
+  pub fn op_concurrency_limit<T: AsRef<str>>(mut self, op: T, max: usize) -> Self {
+    self.op_limits.insert(op.as_ref().to_owned(), max);
+    self
+  }
+
+  /// Make the workers drop their host and create a new one from the factory after serving `max`
+  /// calls with it, to bound the memory of guests that leak or fragment it. Each worker serves up
+  /// to a quarter more calls so they do not recycle their hosts at the same time. The recycles
+  /// are counted in [crate::PoolMetrics::worker_recycles].
+  ///
+  /// ```
+  /// # use wapc_pool::HostPoolBuilder;
+  /// let builder = HostPoolBuilder::new().max_calls_per_worker(10_000);
+  /// ```
+  ///
+  pub fn max_calls_per_worker(mut self, max: usize) -> Self {
+    self.max_calls_per_worker = Some(max);
+    self
+  }
+
+  /// Warn about the calls running for longer than `threshold`, naming the pool, the worker, the
+  /// operation and the time it has been running. A call still running is reported again each
+  /// time its running time doubles. The calls are not interrupted.
+  ///
+  /// ```
+  /// # use std::time::Duration;
+  /// # use wapc_pool::HostPoolBuilder;
+  /// let builder = HostPoolBuilder::new().slow_call_threshold(Duration::from_secs(5));
+  /// ```
+  ///
+  pub fn slow_call_threshold(mut self, threshold: Duration) -> Self {
+    self.slow_call_threshold = Some(threshold);
+    self
+  }
+
+  /// Give each operation of `ops` its own queue, the other operations share the queues of the
+  /// pool. The calls of an operation then never wait behind the calls of another one on a
+  /// queue. They run with the [Priority::Normal] priority, whatever the priority of the call.
+  ///
+  /// The workers of the pool run the calls of these operations, unless they have workers of
+  /// their own, see [HostPoolBuilder::workers_for_op].
+  ///
+  /// ```
+  /// # use wapc_pool::HostPoolBuilder;
+  /// let builder = HostPoolBuilder::new().partition_by_operation(&["health", "transform"]);
+  /// ```
+  ///
+  pub fn partition_by_operation(mut self, ops: &[&str]) -> Self {
+    for op in ops {
+      self.partitions.entry((*op).to_owned()).or_insert(0);
+    }
+    self
+  }
+
+  /// Give the operation `op` its own queue, like [HostPoolBuilder::partition_by_operation], and
+  /// `workers` workers running its calls only. The other workers never run the calls of `op`,
+  /// and the pool does not grow for them: they wait for a dedicated worker or fail according to
+  /// the [SaturationPolicy] of the pool. The dedicated workers come on top of `min_threads` and
+  /// `max_threads`.
+  ///
+  /// ```
+  /// # use wapc_pool::HostPoolBuilder;
+  /// let builder = HostPoolBuilder::new().workers_for_op("transform", 2);
+  /// ```
+  ///
+  pub fn workers_for_op<T: AsRef<str>>(mut self, op: T, workers: usize) -> Self {
+    self.partitions.insert(op.as_ref().to_owned(), workers);
+    self
+  }
+
+  /// Set how many times the workers replace their host after a call panicked, across the
+  /// lifetime of the pool. A worker exits on a panic past this limit. Defaults to 10.
+  ///
+  /// ```
+  /// # use wapc_pool::HostPoolBuilder;
+  /// let builder = HostPoolBuilder::new().max_respawns(3);
+  /// ```
+  ///
+  pub fn max_respawns(mut self, max: usize) -> Self {
+    self.max_respawns = max;
+    self
+  }
+
+  /// Builds a [HostPool] with the current configuration. Fails with [Error::MissingFactory] when
+  /// no factory function is supplied, and with [Error::BuilderInvalidConfig] on inconsistent
+  /// settings.
+  ///
+  /// ```
+  /// # #[cfg(feature = "wasmtime")] {
+  /// # use wapc_pool::HostPoolBuilder;
+  /// # let bytes = std::fs::read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm").unwrap();
+  /// let pre = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+  ///   .module_bytes(&bytes)
+  ///   .build_pre()
+  ///   .unwrap();
+  /// let pool = HostPoolBuilder::new().wasmtime_pre(pre, None).build().unwrap();
+  /// # }
+  /// ```
+  ///
+  pub fn build(mut self) -> std::result::Result<HostPool, Error> {
+    let factory = self.factory.take().ok_or(Error::MissingFactory)?;
+    if self.max_threads == 0 {
+      return Err(Error::BuilderInvalidConfig("max_threads must be at least 1".to_owned()));
+    }
+    if self.min_threads > self.max_threads {
+      return Err(Error::BuilderInvalidConfig(format!(
+        "min_threads ({}) is greater than max_threads ({})",
+        self.min_threads, self.max_threads
+      )));
+    }
+    if self.max_wait.is_zero() {
+      return Err(Error::BuilderInvalidConfig("max_wait must not be zero".to_owned()));
+    }
+    if let Some(op) = self.op_limits.iter().find_map(|(op, max)| (*max == 0).then_some(op)) {
+      return Err(Error::BuilderInvalidConfig(format!(
+        "the concurrency limit of {} must not be zero",
+        op
+      )));
+    }
+    if self.max_calls_per_worker == Some(0) {
+      return Err(Error::BuilderInvalidConfig(
+        "max_calls_per_worker must not be zero".to_owned(),
+      ));
+    }
+    if self.slow_call_threshold.is_some_and(|threshold| threshold.is_zero()) {
+      return Err(Error::BuilderInvalidConfig(
+        "slow_call_threshold must not be zero".to_owned(),
+      ));
+    }
+    if let Some(check) = &self.health_check {
+      if check.interval.is_zero() || check.unhealthy_threshold == 0 {
+        return Err(Error::BuilderInvalidConfig(
+          "the health check interval and unhealthy threshold must not be zero".to_owned(),
+        ));
+      }
+    }
+    let options = Options {
+      min_threads: self.min_threads,
+      max_threads: self.max_threads,
+      max_wait: self.max_wait,
+      max_idle: self.max_idle,
+      max_respawns: self.max_respawns,
+      queue_capacity: self.queue_capacity,
+      on_saturation: self.on_saturation,
+      health_check: self.health_check.map(Arc::new),
+      op_limits: self.op_limits,
+      on_drop: self.on_drop,
+      max_calls_per_worker: self.max_calls_per_worker,
+      slow_call_threshold: self.slow_call_threshold,
+      partitions: self.partitions,
+    };
+    Ok(HostPool::create(
       self.name.unwrap_or_else(|| "waPC host pool".to_owned()),
-      factory,
-      self.min_threads,
-      self.max_threads,
-      self.max_wait,
-      self.max_idle,
-    )
+      Arc::from(factory),
+      self.host_callback,
+      options,
+    ))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+
+  use std::collections::HashSet;
+  use std::time::{Duration, Instant, SystemTime};
+
+  use tokio::join;
+  use wapc::WebAssemblyEngineProvider;
+
+  use super::*;
+
+  #[test_log::test(tokio::test)]
+  async fn test_basic() -> Result<()> {
+    #[derive(Default)]
+    struct Test {
+      host: Option<Arc<wapc::ModuleState>>,
+    }
+    impl WebAssemblyEngineProvider for Test {
+      fn init(
+        &mut self,
+        host: Arc<wapc::ModuleState>,
+      ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.host = Some(host);
+        Ok(())
+      }
+
+      fn call(
+        &mut self,
+        op_length: i32,
+        msg_length: i32,
+      ) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        println!("op len:{}", op_length);
+        println!("msg len:{}", msg_length);
+        std::thread::sleep(Duration::from_millis(100));
+        let host = self.host.take().unwrap();
+        host.set_guest_response(b"{}".to_vec());
+        self.host.replace(host);
+        Ok(1)
+      }
+
+      fn replace(&mut self, _bytes: &[u8]) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+      }
+    }
+    let pool = HostPoolBuilder::new()
+      .name("test")
+      .factory(move || WapcHost::new(Box::<Test>::default(), None).unwrap())
+      .min_threads(5)
+      .max_threads(5)
+      .build()?;
+
+    let now = Instant::now();
+    let result = pool.call("test", b"hello world".to_vec()).await.unwrap();
+    assert_eq!(result, b"{}");
+    let _res = join!(
+      pool.call("test", b"hello world".to_vec()),
+      pool.call("test", b"hello world".to_vec()),
+      pool.call("test", b"hello world".to_vec()),
+      pool.call("test", b"hello world".to_vec()),
+      pool.call("test", b"hello world".to_vec()),
+      pool.call("test", b"hello world".to_vec()),
+      pool.call("test", b"hello world".to_vec()),
+      pool.call("test", b"hello world".to_vec()),
+    );
+    let duration = now.elapsed();
+    println!("Took {}ms", duration.as_millis());
+    assert!(duration.as_millis() < 600);
+
+    Ok(())
+  }
+
+  #[test_log::test(tokio::test(flavor = "multi_thread", worker_threads = 8))]
+  async fn test_concurrent_growth_is_bounded() -> Result<()> {
+    #[derive(Default)]
+    struct Test {
+      host: Option<Arc<wapc::ModuleState>>,
+    }
+    impl WebAssemblyEngineProvider for Test {
+      fn init(
+        &mut self,
+        host: Arc<wapc::ModuleState>,
+      ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.host = Some(host);
+        Ok(())
+      }
+
+      fn call(&mut self, _: i32, _: i32) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        std::thread::sleep(Duration::from_millis(50));
+        self.host.as_ref().unwrap().set_guest_response(b"{}".to_vec());
+        Ok(1)
+      }
+
+      fn replace(&mut self, _bytes: &[u8]) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+      }
+    }
+    let pool = Arc::new(
+      HostPoolBuilder::new()
+        .name("test")
+        .factory(move || WapcHost::new(Box::<Test>::default(), None).unwrap())
+        .min_threads(1)
+        .max_threads(3)
+        .max_wait(Duration::from_millis(1))
+        .queue_capacity(0)
+        .build()?,
+    );
+
+    // the callers racing to grow the pool cannot spawn more than max_threads workers
+    let calls: Vec<_> = (0..64)
+      .map(|_| {
+        let pool = pool.clone();
+        tokio::spawn(async move { pool.call("test", b"hello world".to_vec()).await })
+      })
+      .collect();
+    for _ in 0..10 {
+      assert!(pool.num_active_workers() <= 3);
+      tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    for call in calls {
+      call.await.unwrap()?;
+    }
+    assert_eq!(pool.num_active_workers(), 3);
+
+    Ok(())
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn test_elasticity() -> Result<()> {
+    #[derive(Default)]
+    struct Test {
+      host: Option<Arc<wapc::ModuleState>>,
+    }
+    impl WebAssemblyEngineProvider for Test {
+      fn init(
+        &mut self,
+        host: Arc<wapc::ModuleState>,
+      ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.host = Some(host);
+        Ok(())
+      }
+
+      fn call(&mut self, _: i32, _: i32) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        std::thread::sleep(Duration::from_millis(100));
+        let host = self.host.take().unwrap();
+        assert_ne!(host.get_guest_request().unwrap().operation, "panic");
+        host.set_guest_response(b"{}".to_vec());
+        self.host.replace(host);
+        Ok(1)
+      }
+
+      fn replace(&mut self, _bytes: &[u8]) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+      }
+    }
+    let pool = HostPoolBuilder::new()
+      .name("test")
+      .factory(move || WapcHost::new(Box::<Test>::default(), None).unwrap())
+      .min_threads(1)
+      .max_threads(5)
+      .max_wait(Duration::from_millis(10))
+      .max_idle(Duration::from_secs(1))
+      .build()?;
+    assert_eq!(pool.num_active_workers(), 1);
+    let _ = futures::future::join_all(vec![
+      pool.call("test", b"hello world".to_vec()),
+      pool.call("test", b"hello world".to_vec()),
+      pool.call("test", b"hello world".to_vec()),
+    ])
+    .await;
+    assert_eq!(pool.num_active_workers(), 2);
+    let _ = futures::future::join_all(vec![
+      pool.call("test", b"hello world".to_vec()),
+      pool.call("test", b"hello world".to_vec()),
+      pool.call("test", b"hello world".to_vec()),
+      pool.call("test", b"hello world".to_vec()),
+      pool.call("test", b"hello world".to_vec()),
+      pool.call("test", b"hello world".to_vec()),
+      pool.call("test", b"hello world".to_vec()),
+      pool.call("test", b"hello world".to_vec()),
+      pool.call("test", b"hello world".to_vec()),
+    ])
+    .await;
+    assert_eq!(pool.num_active_workers(), 5);
+    std::thread::sleep(Duration::from_millis(1500));
+    assert_eq!(pool.num_active_workers(), 1);
+
+    // the same burst fits in a larger queue: no worker is spawned
+    let pool = HostPoolBuilder::new()
+      .name("test")
+      .factory(move || WapcHost::new(Box::<Test>::default(), None).unwrap())
+      .min_threads(1)
+      .max_threads(5)
+      .max_wait(Duration::from_millis(10))
+      .queue_capacity(8)
+      .build()?;
+    let _ = futures::future::join_all((0..9).map(|_| pool.call("test", b"hello world".to_vec()))).await;
+    assert_eq!(pool.num_active_workers(), 1);
+
+    // without a queue, the burst grows the pool up to its limit
+    let pool = HostPoolBuilder::new()
+      .name("test")
+      .factory(move || WapcHost::new(Box::<Test>::default(), None).unwrap())
+      .min_threads(1)
+      .max_threads(5)
+      .max_wait(Duration::from_millis(10))
+      .queue_capacity(0)
+      .build()?;
+    let _ = futures::future::join_all((0..9).map(|_| pool.call("test", b"hello world".to_vec()))).await;
+    assert_eq!(pool.num_active_workers(), 5);
+
+    // a permanent worker exiting after a panic is replaced on the next call
+    let pool = HostPoolBuilder::new()
+      .name("test")
+      .factory(move || WapcHost::new(Box::<Test>::default(), None).unwrap())
+      .min_threads(2)
+      .max_threads(2)
+      .max_respawns(0)
+      .build()?;
+    assert_eq!(pool.target_min(), 2);
+    assert!(pool.call("panic", b"hello world".to_vec()).await.is_err());
+    std::thread::sleep(Duration::from_millis(100));
+    assert_eq!(pool.current(), 1);
+    pool.call("test", b"hello world".to_vec()).await?;
+    assert_eq!(pool.current(), 2);
+
+    Ok(())
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn test_call_timeout() -> Result<()> {
+    // Hangs forever on the `hang` operation
+    #[derive(Default)]
+    struct Test {
+      host: Option<Arc<wapc::ModuleState>>,
+    }
+    impl WebAssemblyEngineProvider for Test {
+      fn init(
+        &mut self,
+        host: Arc<wapc::ModuleState>,
+      ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.host = Some(host);
+        Ok(())
+      }
+
+      fn call(&mut self, _: i32, _: i32) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        let host = self.host.as_ref().unwrap();
+        if host.get_guest_request().unwrap().operation == "hang" {
+          loop {
+            std::thread::sleep(Duration::from_secs(60));
+          }
+        }
+        host.set_guest_response(b"{}".to_vec());
+        Ok(1)
+      }
+
+      fn replace(&mut self, _bytes: &[u8]) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+      }
+    }
+    let pool = HostPoolBuilder::new()
+      .name("test")
+      .factory(move || WapcHost::new(Box::<Test>::default(), None).unwrap())
+      .min_threads(1)
+      .max_threads(1)
+      .build()?;
+
+    let result = pool
+      .call_with_timeout("test", b"hello world".to_vec(), Duration::from_secs(5))
+      .await?;
+    assert_eq!(result, b"{}");
+
+    let err = pool
+      .call_with_timeout("hang", b"hello world".to_vec(), Duration::from_millis(100))
+      .await
+      .unwrap_err();
+    assert!(matches!(err, wapc::errors::Error::GuestCallFailure(_)), "{:?}", err);
+
+    // the stuck worker has been replaced
+    assert_eq!(pool.num_active_workers(), 1);
+    for _ in 0..3 {
+      let result = pool
+        .call_with_timeout("test", b"hello world".to_vec(), Duration::from_secs(5))
+        .await?;
+      assert_eq!(result, b"{}");
+    }
+
+    // the stuck worker is not joined
+    let mut pool = pool;
+    let shutdown = tokio::task::spawn_blocking(move || pool.shutdown());
+    let joined = tokio::time::timeout(Duration::from_secs(5), shutdown).await;
+    assert!(joined.is_ok(), "the shutdown waited for the stuck worker");
+    joined.unwrap().unwrap()?;
+
+    Ok(())
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn test_panic_recovery() -> Result<()> {
+    // Panics on the `panic` operation
+    #[derive(Default)]
+    struct Test {
+      host: Option<Arc<wapc::ModuleState>>,
+    }
+    impl WebAssemblyEngineProvider for Test {
+      fn init(
+        &mut self,
+        host: Arc<wapc::ModuleState>,
+      ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.host = Some(host);
+        Ok(())
+      }
+
+      fn call(&mut self, _: i32, _: i32) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        let host = self.host.as_ref().unwrap();
+        assert_ne!(host.get_guest_request().unwrap().operation, "panic", "provider bug");
+        host.set_guest_response(b"{}".to_vec());
+        Ok(1)
+      }
+
+      fn replace(&mut self, _bytes: &[u8]) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+      }
+    }
+    let pool = HostPoolBuilder::new()
+      .name("test")
+      .factory(move || WapcHost::new(Box::<Test>::default(), None).unwrap())
+      .min_threads(1)
+      .max_threads(1)
+      .build()?;
+
+    for i in 1..=3 {
+      let err = pool.call("panic", b"hello world".to_vec()).await.unwrap_err();
+      assert!(err.to_string().contains("Worker panicked during the call"), "{}", err);
+      assert!(err.to_string().contains("provider bug"), "{}", err);
+      assert_eq!(pool.panic_count(), i);
+
+      let result = pool.call("test", b"hello world".to_vec()).await?;
+      assert_eq!(result, b"{}");
+      assert_eq!(pool.num_active_workers(), 1);
+    }
+
+    Ok(())
+  }
+
+  #[test_log::test(tokio::test(flavor = "multi_thread"))]
+  async fn test_metrics() -> Result<()> {
+    #[derive(Default)]
+    struct Test {
+      host: Option<Arc<wapc::ModuleState>>,
+    }
+    impl WebAssemblyEngineProvider for Test {
+      fn init(
+        &mut self,
+        host: Arc<wapc::ModuleState>,
+      ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.host = Some(host);
+        Ok(())
+      }
+
+      fn call(&mut self, _: i32, _: i32) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        let host = self.host.as_ref().unwrap();
+        if host.get_guest_request().unwrap().operation == "slow" {
+          std::thread::sleep(Duration::from_millis(300));
+        }
+        host.set_guest_response(b"{}".to_vec());
+        Ok(1)
+      }
+
+      fn replace(&mut self, _bytes: &[u8]) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+      }
+    }
+    let pool = Arc::new(
+      HostPoolBuilder::new()
+        .name("test")
+        .factory(move || WapcHost::new(Box::<Test>::default(), None).unwrap())
+        .min_threads(1)
+        .max_threads(1)
+        .build()?,
+    );
+    let idle = pool.metrics();
+    assert_eq!(idle.queue_depth, 0);
+    assert_eq!(idle.busy_workers, 0);
+    assert_eq!(idle.queue_wait, Duration::ZERO);
+
+    let calls: Vec<_> = (0..4)
+      .map(|_| {
+        let pool = pool.clone();
+        tokio::spawn(async move { pool.call("slow", b"hello world".to_vec()).await })
+      })
+      .collect();
+    // the callers block the threads of the runtime while they wait for room on the channel
+    std::thread::sleep(Duration::from_millis(100));
+    let saturated = pool.metrics();
+    assert_eq!(saturated.busy_workers, 1);
+    assert!(saturated.queue_depth >= 2, "{:?}", saturated);
+    for call in calls {
+      call.await.unwrap()?;
+    }
+    let drained = pool.metrics();
+    assert_eq!(drained.queue_depth, 0);
+    assert_eq!(drained.busy_workers, 0);
+    assert_eq!(drained.total_calls, 4);
+    assert_eq!(drained.total_timeouts, 0);
+    assert!(drained.queue_wait >= Duration::from_millis(100), "{:?}", drained);
+
+    let result = pool
+      .call_with_timeout("slow", b"hello world".to_vec(), Duration::from_millis(10))
+      .await;
+    assert!(result.is_err());
+    assert_eq!(pool.metrics().total_timeouts, 1);
+
+    // the calls no longer wait
+    for _ in 0..30 {
+      pool.call("fast", b"hello world".to_vec()).await?;
+    }
+    let recovered = pool.metrics();
+    assert!(recovered.queue_wait < drained.queue_wait / 10, "{:?}", recovered);
+
+    Ok(())
+  }
+
+  #[test_log::test(tokio::test(flavor = "multi_thread"))]
+  async fn test_priority() -> Result<()> {
+    #[derive(Default)]
+    struct Test {
+      host: Option<Arc<wapc::ModuleState>>,
+    }
+    impl WebAssemblyEngineProvider for Test {
+      fn init(
+        &mut self,
+        host: Arc<wapc::ModuleState>,
+      ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.host = Some(host);
+        Ok(())
+      }
+
+      fn call(&mut self, _: i32, _: i32) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        std::thread::sleep(Duration::from_millis(50));
+        self.host.as_ref().unwrap().set_guest_response(b"{}".to_vec());
+        Ok(1)
+      }
+
+      fn replace(&mut self, _bytes: &[u8]) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+      }
+    }
+    let pool = Arc::new(
+      HostPoolBuilder::new()
+        .name("test")
+        .factory(move || WapcHost::new(Box::<Test>::default(), None).unwrap())
+        .min_threads(1)
+        .max_threads(1)
+        .queue_capacity(16)
+        .build()?,
+    );
+
+    let batch: Vec<_> = (0..6)
+      .map(|_| {
+        let pool = pool.clone();
+        tokio::spawn(async move {
+          pool
+            .call_with_priority("batch", b"hello world".to_vec(), Priority::Low)
+            .await
+        })
+      })
+      .collect();
+    std::thread::sleep(Duration::from_millis(20));
+
+    let result = pool
+      .call_with_priority("interactive", b"hello world".to_vec(), Priority::High)
+      .await?;
+    assert_eq!(result, b"{}");
+    let completed = batch.iter().filter(|call| call.is_finished()).count();
+    assert!(completed <= 1, "{} batch calls ran first", completed);
+
+    for call in batch {
+      call.await.unwrap()?;
+    }
+
+    Ok(())
+  }
+
+  #[test_log::test(tokio::test(flavor = "multi_thread"))]
+  async fn test_graceful_shutdown() -> Result<()> {
+    #[derive(Default)]
+    struct Test {
+      host: Option<Arc<wapc::ModuleState>>,
+    }
+    impl WebAssemblyEngineProvider for Test {
+      fn init(
+        &mut self,
+        host: Arc<wapc::ModuleState>,
+      ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.host = Some(host);
+        Ok(())
+      }
+
+      fn call(&mut self, _: i32, _: i32) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        std::thread::sleep(Duration::from_millis(200));
+        self.host.as_ref().unwrap().set_guest_response(b"{}".to_vec());
+        Ok(1)
+      }
+
+      fn replace(&mut self, _bytes: &[u8]) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+      }
+    }
+    let pool = Arc::new(
+      HostPoolBuilder::new()
+        .name("test")
+        .factory(move || WapcHost::new(Box::<Test>::default(), None).unwrap())
+        .min_threads(1)
+        .max_threads(1)
+        .max_wait(Duration::from_millis(10))
+        .build()?,
+    );
+
+    let calls: Vec<_> = (0..4)
+      .map(|_| {
+        let pool = pool.clone();
+        tokio::spawn(async move { pool.call("test", b"hello world".to_vec()).await })
+      })
+      .collect();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let shutdown_pool = pool.clone();
+    let shutdown = tokio::task::spawn_blocking(move || shutdown_pool.shutdown_graceful(Duration::from_millis(300)));
+    tokio::time::timeout(Duration::from_secs(5), shutdown)
+      .await
+      .unwrap()
+      .unwrap()?;
+
+    let err = pool.call("test", b"hello world".to_vec()).await.unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      wapc::errors::Error::from(Error::PoolClosed).to_string()
+    );
+
+    let (mut completed, mut failed) = (0, 0);
+    for call in calls {
+      match tokio::time::timeout(Duration::from_secs(5), call)
+        .await
+        .unwrap()
+        .unwrap()
+      {
+        Ok(result) => {
+          assert_eq!(result, b"{}");
+          completed += 1;
+        }
+        Err(e) => {
+          assert_eq!(
+            e.to_string(),
+            wapc::errors::Error::from(Error::PoolShutdown).to_string()
+          );
+          failed += 1;
+        }
+      }
+    }
+    assert!(completed >= 1, "the first call completes before the deadline");
+    assert!(failed >= 1, "the last calls are still queued at the deadline");
+
+    Ok(())
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn test_warm_up() -> Result<()> {
+    // Fails on the `fail` operation
+    #[derive(Default)]
+    struct Test {
+      host: Option<Arc<wapc::ModuleState>>,
+    }
+    impl WebAssemblyEngineProvider for Test {
+      fn init(
+        &mut self,
+        host: Arc<wapc::ModuleState>,
+      ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.host = Some(host);
+        Ok(())
+      }
+
+      fn call(&mut self, _: i32, _: i32) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        let host = self.host.as_ref().unwrap();
+        if host.get_guest_request().unwrap().operation == "fail" {
+          return Err("cold guest".into());
+        }
+        host.set_guest_response(b"{}".to_vec());
+        Ok(1)
+      }
+
+      fn replace(&mut self, _bytes: &[u8]) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+      }
+    }
+    let created = Arc::new(AtomicUsize::new(0));
+    let factory_created = created.clone();
+    let pool = HostPoolBuilder::new()
+      .name("test")
+      .factory(move || {
+        std::thread::sleep(Duration::from_millis(200));
+        factory_created.fetch_add(1, Ordering::SeqCst);
+        WapcHost::new(Box::<Test>::default(), None).unwrap()
+      })
+      .min_threads(3)
+      .max_threads(3)
+      .build()?;
+
+    pool.warm_up(None).await?;
+    assert_eq!(created.load(Ordering::SeqCst), 3);
+
+    pool.warm_up(Some(("test", b"hello world".to_vec()))).await?;
+
+    let err = pool.warm_up(Some(("fail", b"hello world".to_vec()))).await.unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("Failed to warm up workers"), "{}", message);
+    for i in 0..3 {
+      assert!(message.contains(&format!("test.{}", i)), "{}", message);
+    }
+
+    Ok(())
+  }
+
+  #[test_log::test(tokio::test(flavor = "multi_thread"))]
+  async fn test_sticky() -> Result<()> {
+    // Responds with the number of calls it ran, sleeps on the `sleep` operation
+    #[derive(Default)]
+    struct Test {
+      host: Option<Arc<wapc::ModuleState>>,
+      calls: u8,
+    }
+    impl WebAssemblyEngineProvider for Test {
+      fn init(
+        &mut self,
+        host: Arc<wapc::ModuleState>,
+      ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.host = Some(host);
+        Ok(())
+      }
+
+      fn call(&mut self, _: i32, _: i32) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        let host = self.host.as_ref().unwrap();
+        if host.get_guest_request().unwrap().operation == "sleep" {
+          std::thread::sleep(Duration::from_millis(500));
+        }
+        self.calls += 1;
+        host.set_guest_response(vec![self.calls]);
+        Ok(1)
+      }
+
+      fn replace(&mut self, _bytes: &[u8]) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+      }
+    }
+    let pool = HostPoolBuilder::new()
+      .name("test")
+      .factory(move || WapcHost::new(Box::<Test>::default(), None).unwrap())
+      .min_threads(3)
+      .max_threads(3)
+      .build()?;
+
+    let first = pool.call_detailed("test", vec![], Route::Sticky(42)).await?;
+    for calls in 2..5 {
+      let details = pool.call_detailed("test", vec![], Route::Sticky(42)).await?;
+      assert_eq!(details.worker, first.worker);
+      assert_eq!(details.response, vec![calls]);
+    }
+    let mut workers = HashSet::new();
+    for key in 0..30 {
+      workers.insert(pool.call_detailed("test", vec![], Route::Sticky(key)).await?.worker);
+    }
+    assert_eq!(workers.len(), 3);
+
+    // the replacement of a stuck worker serves its keys
+    let pool = HostPoolBuilder::new()
+      .name("test")
+      .factory(move || WapcHost::new(Box::<Test>::default(), None).unwrap())
+      .min_threads(1)
+      .max_threads(1)
+      .build()?;
+    let first = pool.call_detailed("test", vec![], Route::Sticky(42)).await?;
+    let result = pool
+      .call_with_timeout("sleep", vec![], Duration::from_millis(100))
+      .await;
+    assert!(result.is_err());
+    let details = pool.call_detailed("test", vec![], Route::Sticky(42)).await?;
+    assert_ne!(details.worker, first.worker);
+    assert_eq!(details.response, vec![1]);
+
+    let pool = HostPoolBuilder::new()
+      .name("test")
+      .factory(move || WapcHost::new(Box::<Test>::default(), None).unwrap())
+      .min_threads(0)
+      .max_threads(1)
+      .build()?;
+    let err = pool.call_sticky(42, "test", vec![]).await.unwrap_err();
+    assert!(err.to_string().contains("permanent worker"), "{}", err);
+
+    Ok(())
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn test_try_factory() -> Result<()> {
+    #[derive(Default)]
+    struct Test {
+      host: Option<Arc<wapc::ModuleState>>,
+    }
+    impl WebAssemblyEngineProvider for Test {
+      fn init(
+        &mut self,
+        host: Arc<wapc::ModuleState>,
+      ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.host = Some(host);
+        Ok(())
+      }
+
+      fn call(&mut self, _: i32, _: i32) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        self.host.as_ref().unwrap().set_guest_response(b"{}".to_vec());
+        Ok(1)
+      }
+
+      fn replace(&mut self, _bytes: &[u8]) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+      }
+    }
+    // fails the first two attempts
+    let attempts = AtomicUsize::new(0);
+    let pool = HostPoolBuilder::new()
+      .name("test")
+      .try_factory(move || {
+        if attempts.fetch_add(1, Ordering::SeqCst) < 2 {
+          return Err(wapc::errors::Error::General("transient failure".to_owned()));
+        }
+        WapcHost::new(Box::<Test>::default(), None)
+      })
+      .min_threads(1)
+      .max_threads(1)
+      .build()?;
+    pool.warm_up(None).await?;
+    assert_eq!(pool.metrics().factory_failures, 2);
+    assert_eq!(pool.call("test", b"hello world".to_vec()).await?, b"{}");
+
+    let pool = HostPoolBuilder::new()
+      .name("test")
+      .try_factory(|| Err(wapc::errors::Error::General("persistent failure".to_owned())))
+      .min_threads(1)
+      .max_threads(1)
+      .build()?;
+    let err = pool.warm_up(None).await.unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("Failed to warm up workers"), "{}", message);
+    assert!(message.contains("persistent failure"), "{}", message);
+
+    Ok(())
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn test_broadcast() -> Result<()> {
+    // Responds with the number of calls it ran
+    #[derive(Default)]
+    struct Test {
+      host: Option<Arc<wapc::ModuleState>>,
+      calls: u8,
+    }
+    impl WebAssemblyEngineProvider for Test {
+      fn init(
+        &mut self,
+        host: Arc<wapc::ModuleState>,
+      ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.host = Some(host);
+        Ok(())
+      }
+
+      fn call(&mut self, _: i32, _: i32) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        self.calls += 1;
+        self.host.as_ref().unwrap().set_guest_response(vec![self.calls]);
+        Ok(1)
+      }
+
+      fn replace(&mut self, _bytes: &[u8]) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+      }
+    }
+    let pool = HostPoolBuilder::new()
+      .name("test")
+      .factory(move || WapcHost::new(Box::<Test>::default(), None).unwrap())
+      .min_threads(3)
+      .max_threads(3)
+      .build()?;
+    pool.warm_up(None).await?;
+
+    for calls in 1..3 {
+      let results = pool.broadcast("count", vec![], Duration::from_secs(1)).await?;
+      assert_eq!(results.len(), 3);
+      for result in results {
+        assert_eq!(result?, vec![calls]);
+      }
+    }
+
+    Ok(())
+  }
+
+  #[test_log::test(tokio::test(flavor = "multi_thread"))]
+  async fn test_call_many() -> Result<()> {
+    // Echoes the payload, fails on `fail` and counts the calls running at the same time
+    struct Test {
+      host: Option<Arc<wapc::ModuleState>>,
+      running: Arc<AtomicUsize>,
+      max_running: Arc<AtomicUsize>,
+    }
+    impl WebAssemblyEngineProvider for Test {
+      fn init(
+        &mut self,
+        host: Arc<wapc::ModuleState>,
+      ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.host = Some(host);
+        Ok(())
+      }
+
+      fn call(&mut self, _: i32, _: i32) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        let host = self.host.as_ref().unwrap();
+        let payload = host.get_guest_request().unwrap().msg;
+        let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_running.fetch_max(running, Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(10));
+        self.running.fetch_sub(1, Ordering::SeqCst);
+        if payload == b"fail" {
+          return Err("failed".into());
+        }
+        host.set_guest_response(payload);
+        Ok(1)
+      }
+
+      fn replace(&mut self, _bytes: &[u8]) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+      }
+    }
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = Arc::new(AtomicUsize::new(0));
+    let builder = {
+      let max_running = max_running.clone();
+      HostPoolBuilder::new()
+        .name("test")
+        .factory(move || {
+          let engine = Test {
+            host: None,
+            running: running.clone(),
+            max_running: max_running.clone(),
+          };
+          WapcHost::new(Box::new(engine), None).unwrap()
+        })
+        .min_threads(4)
+        .max_threads(4)
+    };
+    let mut pool = builder.build()?;
+    pool.warm_up(None).await?;
+
+    let payloads: Vec<_> = (0..20)
+      .map(|i| if i == 7 { b"fail".to_vec() } else { vec![i] })
+      .collect();
+    let results = pool.call_many("test", payloads.clone()).await?;
+    assert_eq!(results.len(), 20);
+    for (i, result) in results.into_iter().enumerate() {
+      if i == 7 {
+        assert!(result.is_err());
+      } else {
+        assert_eq!(result?, payloads[i]);
+      }
+    }
+    assert!(max_running.load(Ordering::SeqCst) > 1);
+
+    max_running.store(0, Ordering::SeqCst);
+    let results = pool
+      .call_many_with("test", payloads.clone(), Route::default(), Some(2))
+      .await?;
+    assert_eq!(results.iter().filter(|result| result.is_err()).count(), 1);
+    assert_eq!(max_running.load(Ordering::SeqCst), 2);
+
+    pool.shutdown()?;
+    assert!(pool.call_many("test", payloads).await.is_err());
+
+    Ok(())
+  }
+
+  #[test_log::test(tokio::test(flavor = "multi_thread"))]
+  async fn test_saturation() -> Result<()> {
+    // Sleeps on the `sleep` operation
+    #[derive(Default)]
+    struct Test {
+      host: Option<Arc<wapc::ModuleState>>,
+    }
+    impl WebAssemblyEngineProvider for Test {
+      fn init(
+        &mut self,
+        host: Arc<wapc::ModuleState>,
+      ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.host = Some(host);
+        Ok(())
+      }
+
+      fn call(&mut self, _: i32, _: i32) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        let host = self.host.as_ref().unwrap();
+        if host.get_guest_request().unwrap().operation == "sleep" {
+          std::thread::sleep(Duration::from_millis(500));
+        }
+        host.set_guest_response(b"{}".to_vec());
+        Ok(1)
+      }
+
+      fn replace(&mut self, _bytes: &[u8]) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+      }
+    }
+    for (policy, min_wait, max_wait) in [
+      (SaturationPolicy::Error, Duration::ZERO, Duration::from_millis(50)),
+      (
+        SaturationPolicy::ErrorAfter(Duration::from_millis(200)),
+        Duration::from_millis(200),
+        Duration::from_millis(300),
+      ),
+    ] {
+      let pool = Arc::new(
+        HostPoolBuilder::new()
+          .name("test")
+          .factory(move || WapcHost::new(Box::<Test>::default(), None).unwrap())
+          .min_threads(1)
+          .max_threads(1)
+          .on_saturation(policy)
+          .build()?,
+      );
+      pool.warm_up(None).await?;
+
+      // the worker runs the first call, the second one fills the queue
+      let calls: Vec<_> = (0..2)
+        .map(|_| {
+          let pool = pool.clone();
+          let call = tokio::spawn(async move { pool.call("sleep", b"hello world".to_vec()).await });
+          std::thread::sleep(Duration::from_millis(50));
+          call
+        })
+        .collect();
+
+      let start = Instant::now();
+      let err = pool.call("test", b"hello world".to_vec()).await.unwrap_err();
+      let elapsed = start.elapsed();
+      assert!(err.to_string().contains("The pool is saturated"), "{}", err);
+      assert!(elapsed >= min_wait && elapsed < max_wait, "{:?}", elapsed);
+      for call in calls {
+        call.await.unwrap()?;
+      }
+    }
+
+    Ok(())
+  }
+
+  #[test_log::test(tokio::test(flavor = "multi_thread"))]
+  async fn test_call_detailed() -> Result<()> {
+    // Sleeps on the `sleep` operation
+    #[derive(Default)]
+    struct Test {
+      host: Option<Arc<wapc::ModuleState>>,
+    }
+    impl WebAssemblyEngineProvider for Test {
+      fn init(
+        &mut self,
+        host: Arc<wapc::ModuleState>,
+      ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.host = Some(host);
+        Ok(())
+      }
+
+      fn call(&mut self, _: i32, _: i32) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        let host = self.host.as_ref().unwrap();
+        if host.get_guest_request().unwrap().operation == "sleep" {
+          std::thread::sleep(Duration::from_millis(300));
+        }
+        host.set_guest_response(b"{}".to_vec());
+        Ok(1)
+      }
+
+      fn replace(&mut self, _bytes: &[u8]) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+      }
+    }
+    let pool = Arc::new(
+      HostPoolBuilder::new()
+        .name("test")
+        .factory(move || WapcHost::new(Box::<Test>::default(), None).unwrap())
+        .min_threads(1)
+        .max_threads(1)
+        .build()?,
+    );
+    pool.warm_up(None).await?;
+
+    let idle = pool.call_detailed("sleep", vec![], Route::default()).await?;
+    assert_eq!(idle.response, b"{}");
+    assert_eq!(idle.worker, 0);
+    assert!(idle.queued < Duration::from_millis(50), "{:?}", idle);
+    assert!(idle.executed >= Duration::from_millis(300), "{:?}", idle);
+
+    // the call waits for the worker to complete the previous one
+    let busy = {
+      let pool = pool.clone();
+      tokio::spawn(async move { pool.call("sleep", vec![]).await })
+    };
+    std::thread::sleep(Duration::from_millis(100));
+    let queued = pool.call_detailed("test", vec![], Route::default()).await?;
+    busy.await.unwrap()?;
+    assert!(queued.queued >= Duration::from_millis(150), "{:?}", queued);
+    assert!(queued.executed < Duration::from_millis(50), "{:?}", queued);
+
+    Ok(())
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn test_host_callback() -> Result<()> {
+    // Responds with the response of a host call
+    #[derive(Default)]
+    struct Test {
+      host: Option<Arc<wapc::ModuleState>>,
+    }
+    impl WebAssemblyEngineProvider for Test {
+      fn init(
+        &mut self,
+        host: Arc<wapc::ModuleState>,
+      ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.host = Some(host);
+        Ok(())
+      }
+
+      fn call(&mut self, _: i32, _: i32) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        let host = self.host.as_ref().unwrap();
+        host.do_host_call("binding", "namespace", "worker", b"").unwrap();
+        host.set_guest_response(host.get_host_response().unwrap());
+        Ok(1)
+      }
+
+      fn replace(&mut self, _bytes: &[u8]) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+      }
+    }
+    let contexts = Arc::new(Mutex::new(HashSet::new()));
+    let callback_contexts = contexts.clone();
+    let pool = HostPoolBuilder::new()
+      .name("test")
+      .host_callback(move |context, _, _, _, _, _| {
+        lock(&callback_contexts).insert(context.clone());
+        Ok(vec![u8::try_from(context.worker)?])
+      })
+      .factory_with(|callback| WapcHost::new(Box::<Test>::default(), Some(callback)))
+      .min_threads(2)
+      .max_threads(2)
+      .build()?;
+    pool.warm_up(None).await?;
+
+    let results = pool.broadcast("test", vec![], Duration::from_secs(1)).await?;
+    let workers: Vec<_> = results.into_iter().collect::<Result<_>>()?;
+    assert_eq!(workers, vec![vec![0], vec![1]]);
+    let expected: HashSet<_> = (0..2)
+      .map(|worker| WorkerContext {
+        pool: "test".to_owned(),
+        worker,
+      })
+      .collect();
+    assert_eq!(*lock(&contexts), expected);
+
+    Ok(())
   }
-}
 
-#[cfg(test)]
-mod tests {
+  #[test_log::test]
+  fn test_health_check() -> Result<()> {
+    // Fails the `probe` operation after passing it twice
+    #[derive(Default)]
+    struct Test {
+      host: Option<Arc<wapc::ModuleState>>,
+      probes: usize,
+    }
+    impl WebAssemblyEngineProvider for Test {
+      fn init(
+        &mut self,
+        host: Arc<wapc::ModuleState>,
+      ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.host = Some(host);
+        Ok(())
+      }
 
-  use std::time::{Duration, Instant};
+      fn call(&mut self, _: i32, _: i32) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        let host = self.host.as_ref().unwrap();
+        if host.get_guest_request().unwrap().operation == "probe" {
+          self.probes += 1;
+          if self.probes > 2 {
+            return Err("degraded".into());
+          }
+        }
+        host.set_guest_response(b"{}".to_vec());
+        Ok(1)
+      }
 
-  use tokio::join;
-  use wapc::WebAssemblyEngineProvider;
+      fn replace(&mut self, _bytes: &[u8]) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+      }
+    }
+    let created = Arc::new(AtomicUsize::new(0));
+    let factory_created = created.clone();
+    let pool = HostPoolBuilder::new()
+      .name("test")
+      .factory(move || {
+        factory_created.fetch_add(1, Ordering::SeqCst);
+        WapcHost::new(Box::<Test>::default(), None).unwrap()
+      })
+      .min_threads(1)
+      .max_threads(1)
+      .health_check("probe", vec![], Duration::from_millis(30), 2)
+      .build()?;
 
-  use super::*;
+    // 2 probes pass, the next 2 fail
+    std::thread::sleep(Duration::from_millis(200));
+    let health = pool.health();
+    assert_eq!(health.len(), 1);
+    assert_eq!(health[0].worker, 0);
+    assert!(health[0].recycles >= 1, "{:?}", health);
+    assert!(created.load(Ordering::SeqCst) >= 2);
+    assert_eq!(pool.call_blocking("test", b"hello world".to_vec())?, b"{}");
+
+    Ok(())
+  }
 
   #[test_log::test(tokio::test)]
-  async fn test_basic() -> Result<()> {
+  async fn test_worker_stats() -> Result<()> {
+    // Fails the calls with the `fail` payload
     #[derive(Default)]
     struct Test {
       host: Option<Arc<wapc::ModuleState>>,
@@ -379,17 +3466,13 @@ mod tests {
         Ok(())
       }
 
-      fn call(
-        &mut self,
-        op_length: i32,
-        msg_length: i32,
-      ) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
-        println!("op len:{}", op_length);
-        println!("msg len:{}", msg_length);
-        std::thread::sleep(Duration::from_millis(100));
-        let host = self.host.take().unwrap();
+      fn call(&mut self, _: i32, _: i32) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        let host = self.host.as_ref().unwrap();
+        if host.get_guest_request().unwrap().msg == b"fail" {
+          host.set_guest_error("bad payload".to_owned());
+          return Ok(0);
+        }
         host.set_guest_response(b"{}".to_vec());
-        self.host.replace(host);
         Ok(1)
       }
 
@@ -400,32 +3483,95 @@ mod tests {
     let pool = HostPoolBuilder::new()
       .name("test")
       .factory(move || WapcHost::new(Box::<Test>::default(), None).unwrap())
-      .min_threads(5)
-      .max_threads(5)
-      .build();
+      .min_threads(2)
+      .max_threads(2)
+      .build()?;
+    pool.warm_up(None).await?;
 
-    let now = Instant::now();
-    let result = pool.call("test", b"hello world".to_vec()).await.unwrap();
-    assert_eq!(result, b"{}");
-    let _res = join!(
-      pool.call("test", b"hello world".to_vec()),
-      pool.call("test", b"hello world".to_vec()),
-      pool.call("test", b"hello world".to_vec()),
-      pool.call("test", b"hello world".to_vec()),
-      pool.call("test", b"hello world".to_vec()),
-      pool.call("test", b"hello world".to_vec()),
-      pool.call("test", b"hello world".to_vec()),
-      pool.call("test", b"hello world".to_vec()),
-    );
-    let duration = now.elapsed();
-    println!("Took {}ms", duration.as_millis());
-    assert!(duration.as_millis() < 600);
+    for key in 0..4 {
+      pool.call_sticky(key, "test", vec![]).await?;
+    }
+    // the sticky calls all run on the same worker
+    let before = SystemTime::now();
+    let worker = pool.call_detailed("test", vec![], Route::Sticky(42)).await?.worker;
+    for payload in [b"fail", b"fail", b"test", b"fail", b"fail"] {
+      let result = pool.call_detailed("test", payload.to_vec(), Route::Sticky(42)).await;
+      assert_eq!(result.is_err(), payload == b"fail");
+    }
+
+    let stats = pool.worker_stats();
+    assert_eq!(stats.iter().map(|stats| stats.worker).collect::<Vec<_>>(), vec![0, 1]);
+    let failing: Vec<_> = stats.iter().filter(|stats| stats.failed_calls > 0).collect();
+    assert_eq!(failing.len(), 1, "{:?}", stats);
+    let failing = failing[0];
+    assert_eq!(failing.worker, worker);
+    assert!(failing.total_calls >= 6, "{:?}", failing);
+    assert_eq!(failing.failed_calls, 4);
+    assert_eq!(failing.consecutive_failures, 2);
+    let last_error = failing.last_error.as_ref().unwrap();
+    assert!(last_error.message.contains("bad payload"), "{:?}", last_error);
+    assert!(last_error.at >= before);
+    assert!(stats
+      .iter()
+      .all(|stats| stats.worker == worker || stats.last_error.is_none()));
+
+    Ok(())
+  }
+
+  #[test_log::test]
+  fn test_max_calls_per_worker() -> Result<()> {
+    // Returns the number of calls served by the host
+    #[derive(Default)]
+    struct Test {
+      host: Option<Arc<wapc::ModuleState>>,
+      calls: u8,
+    }
+    impl WebAssemblyEngineProvider for Test {
+      fn init(
+        &mut self,
+        host: Arc<wapc::ModuleState>,
+      ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.host = Some(host);
+        Ok(())
+      }
+
+      fn call(&mut self, _: i32, _: i32) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        self.calls += 1;
+        self.host.as_ref().unwrap().set_guest_response(vec![self.calls]);
+        Ok(1)
+      }
+
+      fn replace(&mut self, _bytes: &[u8]) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+      }
+    }
+    let created = Arc::new(AtomicUsize::new(0));
+    let factory_created = created.clone();
+    // below 4 calls, the workers are not staggered
+    let pool = HostPoolBuilder::new()
+      .name("test")
+      .factory(move || {
+        factory_created.fetch_add(1, Ordering::SeqCst);
+        WapcHost::new(Box::<Test>::default(), None).unwrap()
+      })
+      .min_threads(1)
+      .max_threads(1)
+      .max_calls_per_worker(3)
+      .build()?;
+
+    let mut responses = Vec::new();
+    for _ in 0..4 {
+      responses.extend(pool.call_blocking("test", b"hello world".to_vec())?);
+    }
+    assert_eq!(responses, [1, 2, 3, 1]);
+    assert_eq!(created.load(Ordering::SeqCst), 2);
+    assert_eq!(pool.metrics().worker_recycles, 1);
 
     Ok(())
   }
 
   #[test_log::test(tokio::test)]
-  async fn test_elasticity() -> Result<()> {
+  async fn test_resize() -> Result<()> {
     #[derive(Default)]
     struct Test {
       host: Option<Arc<wapc::ModuleState>>,
@@ -441,9 +3587,7 @@ mod tests {
 
       fn call(&mut self, _: i32, _: i32) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
         std::thread::sleep(Duration::from_millis(100));
-        let host = self.host.take().unwrap();
-        host.set_guest_response(b"{}".to_vec());
-        self.host.replace(host);
+        self.host.as_ref().unwrap().set_guest_response(b"{}".to_vec());
         Ok(1)
       }
 
@@ -455,33 +3599,343 @@ mod tests {
       .name("test")
       .factory(move || WapcHost::new(Box::<Test>::default(), None).unwrap())
       .min_threads(1)
-      .max_threads(5)
+      .max_threads(2)
       .max_wait(Duration::from_millis(10))
-      .max_idle(Duration::from_secs(1))
-      .build();
-    assert_eq!(pool.num_active_workers(), 1);
-    let _ = futures::future::join_all(vec![
-      pool.call("test", b"hello world".to_vec()),
-      pool.call("test", b"hello world".to_vec()),
-      pool.call("test", b"hello world".to_vec()),
-    ])
-    .await;
+      .max_idle(Duration::from_millis(500))
+      .build()?;
+    let burst = || futures::future::join_all((0..12).map(|_| pool.call("test", b"hello world".to_vec())));
+    burst().await;
     assert_eq!(pool.num_active_workers(), 2);
-    let _ = futures::future::join_all(vec![
-      pool.call("test", b"hello world".to_vec()),
-      pool.call("test", b"hello world".to_vec()),
-      pool.call("test", b"hello world".to_vec()),
-      pool.call("test", b"hello world".to_vec()),
-      pool.call("test", b"hello world".to_vec()),
-      pool.call("test", b"hello world".to_vec()),
-      pool.call("test", b"hello world".to_vec()),
-      pool.call("test", b"hello world".to_vec()),
-      pool.call("test", b"hello world".to_vec()),
-    ])
-    .await;
-    assert_eq!(pool.num_active_workers(), 5);
-    std::thread::sleep(Duration::from_millis(1500));
+
+    // the pool grows past its previous ceiling
+    pool.resize(6)?;
+    assert_eq!(pool.target_max(), 6);
+    burst().await;
+    assert_eq!(pool.num_active_workers(), 6);
+
+    // the surplus workers exit once idle
+    pool.resize(3)?;
+    burst().await;
+    assert_eq!(pool.num_active_workers(), 6);
+    std::thread::sleep(Duration::from_millis(800));
+    assert_eq!(pool.num_active_workers(), 1);
+    burst().await;
+    assert_eq!(pool.num_active_workers(), 3);
+
+    // the workers above min_threads are spawned right away and kept when idle
+    pool.set_min_threads(3)?;
+    std::thread::sleep(Duration::from_millis(800));
+    assert_eq!(pool.num_active_workers(), 3);
+    pool.set_min_threads(1)?;
+    std::thread::sleep(Duration::from_millis(800));
     assert_eq!(pool.num_active_workers(), 1);
+    pool.set_min_threads(2)?;
+    assert_eq!(pool.num_active_workers(), 2);
+
+    assert!(pool.resize(0).is_err());
+    assert!(pool.resize(1).is_err());
+    assert!(pool.set_min_threads(0).is_err());
+    assert!(pool.set_min_threads(4).is_err());
+    assert_eq!((pool.target_min(), pool.target_max()), (2, 3));
+
+    Ok(())
+  }
+
+  #[test_log::test(tokio::test(flavor = "multi_thread"))]
+  async fn test_partition_by_operation() -> Result<()> {
+    // Sleeps on the `transform` operation
+    #[derive(Default)]
+    struct Test {
+      host: Option<Arc<wapc::ModuleState>>,
+    }
+    impl WebAssemblyEngineProvider for Test {
+      fn init(
+        &mut self,
+        host: Arc<wapc::ModuleState>,
+      ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.host = Some(host);
+        Ok(())
+      }
+
+      fn call(&mut self, _: i32, _: i32) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        let host = self.host.as_ref().unwrap();
+        if host.get_guest_request().unwrap().operation == "transform" {
+          std::thread::sleep(Duration::from_millis(200));
+        }
+        host.set_guest_response(b"{}".to_vec());
+        Ok(1)
+      }
+
+      fn replace(&mut self, _bytes: &[u8]) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+      }
+    }
+    let pool = Arc::new(
+      HostPoolBuilder::new()
+        .name("test")
+        .factory(move || WapcHost::new(Box::<Test>::default(), None).unwrap())
+        .min_threads(1)
+        .max_threads(1)
+        .queue_capacity(16)
+        .workers_for_op("transform", 2)
+        .build()?,
+    );
+    pool.warm_up(None).await?;
+    assert_eq!(pool.num_active_workers(), 3);
+
+    let transforms: Vec<_> = (0..10)
+      .map(|_| {
+        let pool = pool.clone();
+        tokio::spawn(async move { pool.call_detailed("transform", vec![], Route::default()).await })
+      })
+      .collect();
+    std::thread::sleep(Duration::from_millis(50));
+
+    // the shared worker keeps running the other operations while `transform` is saturated
+    for _ in 0..5 {
+      let health = pool.call_detailed("health", vec![], Route::default()).await?;
+      assert_eq!(health.worker, 0);
+      assert!(
+        health.queued + health.executed < Duration::from_millis(50),
+        "{:?}",
+        health
+      );
+      std::thread::sleep(Duration::from_millis(50));
+    }
+
+    let mut workers = HashSet::new();
+    for transform in transforms {
+      workers.insert(transform.await.unwrap()?.worker);
+    }
+    assert_eq!(workers, HashSet::from([1, 2]));
+    // the pool did not grow for the saturated operation
+    assert_eq!(pool.num_active_workers(), 3);
+
+    Ok(())
+  }
+
+  // Counts the `limited` calls running at the same time
+  struct LimitedTest {
+    host: Option<Arc<wapc::ModuleState>>,
+    running: Arc<AtomicUsize>,
+    max_running: Arc<AtomicUsize>,
+  }
+
+  impl WebAssemblyEngineProvider for LimitedTest {
+    fn init(
+      &mut self,
+      host: Arc<wapc::ModuleState>,
+    ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+      self.host = Some(host);
+      Ok(())
+    }
+
+    fn call(&mut self, _: i32, _: i32) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+      let host = self.host.as_ref().unwrap();
+      if host.get_guest_request().unwrap().operation == "limited" {
+        let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_running.fetch_max(running, Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(100));
+        self.running.fetch_sub(1, Ordering::SeqCst);
+      }
+      host.set_guest_response(b"{}".to_vec());
+      Ok(1)
+    }
+
+    fn replace(&mut self, _bytes: &[u8]) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+      Ok(())
+    }
+  }
+
+  fn limited_pool(threads: usize, limit: usize, max_running: &Arc<AtomicUsize>) -> HostPoolBuilder {
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = max_running.clone();
+    HostPoolBuilder::new()
+      .name("test")
+      .factory(move || {
+        let engine = LimitedTest {
+          host: None,
+          running: running.clone(),
+          max_running: max_running.clone(),
+        };
+        WapcHost::new(Box::new(engine), None).unwrap()
+      })
+      .min_threads(threads)
+      .max_threads(threads)
+      .op_concurrency_limit("limited", limit)
+  }
+
+  #[test_log::test(tokio::test(flavor = "multi_thread"))]
+  async fn test_op_concurrency_limit() -> Result<()> {
+    let max_running = Arc::new(AtomicUsize::new(0));
+    let pool = Arc::new(limited_pool(5, 2, &max_running).queue_capacity(5).build()?);
+    pool.warm_up(None).await?;
+
+    let calls: Vec<_> = (0..5)
+      .map(|_| {
+        let pool = pool.clone();
+        tokio::spawn(async move { pool.call("limited", b"hello world".to_vec()).await })
+      })
+      .collect();
+    // the other operations are not limited
+    let others: Vec<_> = (0..3)
+      .map(|_| {
+        let pool = pool.clone();
+        tokio::spawn(async move { pool.call("test", b"hello world".to_vec()).await })
+      })
+      .collect();
+    for call in calls.into_iter().chain(others) {
+      call.await.unwrap()?;
+    }
+    assert_eq!(max_running.load(Ordering::SeqCst), 2);
+
+    Ok(())
+  }
+
+  #[test_log::test(tokio::test(flavor = "multi_thread"))]
+  async fn test_op_concurrency_limit_error() -> Result<()> {
+    // the calls past the limit fail right away with the Error policy
+    let max_running = Arc::new(AtomicUsize::new(0));
+    let pool = Arc::new(
+      limited_pool(2, 1, &max_running)
+        .on_saturation(SaturationPolicy::Error)
+        .build()?,
+    );
+    pool.warm_up(None).await?;
+    let limited = {
+      let pool = pool.clone();
+      tokio::spawn(async move { pool.call("limited", b"hello world".to_vec()).await })
+    };
+    std::thread::sleep(Duration::from_millis(50));
+    let err = pool.call("limited", b"hello world".to_vec()).await.unwrap_err();
+    assert!(err.to_string().contains("Too many calls of limited"), "{}", err);
+    assert_eq!(
+      pool
+        .call_blocking("limited", b"hello world".to_vec())
+        .unwrap_err()
+        .to_string(),
+      err.to_string()
+    );
+    limited.await.unwrap()?;
+    assert_eq!(pool.call("limited", b"hello world".to_vec()).await?, b"{}");
+    assert_eq!(max_running.load(Ordering::SeqCst), 1);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_build_validation() {
+    // the pools are not built: no host is created
+    let factory = || -> WapcHost { unreachable!() };
+    let result = HostPoolBuilder::new().build();
+    assert!(matches!(result, Err(Error::MissingFactory)));
+    let result = HostPoolBuilder::new().factory(factory).max_threads(0).build();
+    assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+    let result = HostPoolBuilder::new()
+      .factory(factory)
+      .min_threads(3)
+      .max_threads(2)
+      .build();
+    assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+    let result = HostPoolBuilder::new().factory(factory).max_wait(Duration::ZERO).build();
+    assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+    let result = HostPoolBuilder::new().factory(factory).max_calls_per_worker(0).build();
+    assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+    let result = HostPoolBuilder::new()
+      .factory(factory)
+      .slow_call_threshold(Duration::ZERO)
+      .build();
+    assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn test_shutdown_twice() -> Result<()> {
+    let bytes = std::fs::read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm").unwrap();
+    let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+      .module_bytes(&bytes)
+      .build()
+      .unwrap();
+    let mut pool = HostPoolBuilder::new()
+      .factory(move || WapcHost::new(Box::new(engine.clone()), None).unwrap())
+      .build()?;
+
+    pool.shutdown()?;
+    assert!(pool.shutdown().is_err());
+    assert!(pool.call("echo", b"hello".to_vec()).await.is_err());
+    Ok(())
+  }
+
+  // Drops a pool with two workers running 200ms calls and six more calls queued, returns the
+  // number of workers still alive right after the drop and 300ms later
+  async fn drop_busy_pool(policy: DropPolicy) -> Result<(usize, usize)> {
+    // Sleeps on every call, counts the hosts alive
+    struct Test {
+      host: Option<Arc<wapc::ModuleState>>,
+      alive: Arc<AtomicUsize>,
+    }
+    impl Drop for Test {
+      fn drop(&mut self) {
+        self.alive.fetch_sub(1, Ordering::SeqCst);
+      }
+    }
+    impl WebAssemblyEngineProvider for Test {
+      fn init(
+        &mut self,
+        host: Arc<wapc::ModuleState>,
+      ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.host = Some(host);
+        Ok(())
+      }
+
+      fn call(&mut self, _: i32, _: i32) -> std::result::Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        std::thread::sleep(Duration::from_millis(200));
+        self.host.as_ref().unwrap().set_guest_response(b"{}".to_vec());
+        Ok(1)
+      }
+
+      fn replace(&mut self, _bytes: &[u8]) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+      }
+    }
+    let alive = Arc::new(AtomicUsize::new(0));
+    let pool = {
+      let alive = alive.clone();
+      HostPoolBuilder::new()
+        .name("test")
+        .factory(move || {
+          alive.fetch_add(1, Ordering::SeqCst);
+          let engine = Test {
+            host: None,
+            alive: alive.clone(),
+          };
+          WapcHost::new(Box::new(engine), None).unwrap()
+        })
+        .min_threads(2)
+        .max_threads(2)
+        .queue_capacity(6)
+        .on_drop(policy)
+        .build()?
+    };
+    pool.warm_up(None).await?;
+
+    let calls = pool.call_many("test", vec![vec![]; 8]);
+    assert!(tokio::time::timeout(Duration::from_millis(50), calls).await.is_err());
+    drop(pool);
+    let after_drop = alive.load(Ordering::SeqCst);
+    std::thread::sleep(Duration::from_millis(300));
+    Ok((after_drop, alive.load(Ordering::SeqCst)))
+  }
+
+  #[test_log::test(tokio::test(flavor = "multi_thread"))]
+  async fn test_drop_policy() -> Result<()> {
+    // the running calls complete, the queued ones are dropped
+    let (after_drop, later) = drop_busy_pool(DropPolicy::JoinWithTimeout(Duration::from_secs(1))).await?;
+    assert_eq!((after_drop, later), (0, 0));
+    let (after_drop, later) = drop_busy_pool(DropPolicy::Abort).await?;
+    assert_eq!((after_drop, later), (2, 0));
+    // the workers run the queued calls in the background
+    let (after_drop, later) = drop_busy_pool(DropPolicy::Detach).await?;
+    assert_eq!((after_drop, later), (2, 2));
 
     Ok(())
   }
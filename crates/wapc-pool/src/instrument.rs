@@ -0,0 +1,65 @@
+// Tracing spans and events describing the calls going through the pool and the lifecycle of the
+// workers. Only the sizes of the payloads are recorded, never their contents.
+
+use std::time::Duration;
+
+#[cfg(feature = "tracing")]
+pub(crate) fn call_span(pool: &str, operation: &str, len: usize) -> tracing::Span {
+  tracing::debug_span!("wapc_pool.call", pool = %pool, op = %operation, payload_len = len)
+}
+
+// Emitted in the span of the call when a worker picks it up
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn queued(worker: usize, queued: Duration) {
+  #[cfg(feature = "tracing")]
+  tracing::debug!(name: "wapc_pool.queued", worker, queued_us = queued.as_micros());
+}
+
+// Emitted in the span of the call when the worker completes it
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn executed(worker: usize, executed: Duration, success: bool) {
+  #[cfg(feature = "tracing")]
+  tracing::debug!(
+    name: "wapc_pool.executed",
+    worker,
+    executed_us = executed.as_micros(),
+    success
+  );
+}
+
+// Emitted by the watchdog for a call running past the slow call threshold
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn slow_call(pool: &str, worker: usize, operation: &str, elapsed: Duration) {
+  #[cfg(feature = "tracing")]
+  tracing::warn!(
+    name: "wapc_pool.slow_call",
+    pool = %pool,
+    worker,
+    op = %operation,
+    elapsed_ms = elapsed.as_millis()
+  );
+}
+
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn worker_started(pool: &str, worker: usize) {
+  #[cfg(feature = "tracing")]
+  tracing::debug!(name: "wapc_pool.worker_started", pool = %pool, worker);
+}
+
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn worker_respawned(pool: &str, worker: usize) {
+  #[cfg(feature = "tracing")]
+  tracing::debug!(name: "wapc_pool.worker_respawned", pool = %pool, worker);
+}
+
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn worker_recycled(pool: &str, worker: usize) {
+  #[cfg(feature = "tracing")]
+  tracing::debug!(name: "wapc_pool.worker_recycled", pool = %pool, worker);
+}
+
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn worker_stopped(pool: &str, worker: usize, reason: &str) {
+  #[cfg(feature = "tracing")]
+  tracing::debug!(name: "wapc_pool.worker_stopped", pool = %pool, worker, reason = %reason);
+}
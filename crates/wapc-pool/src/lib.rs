@@ -77,6 +77,7 @@
   while_true,
   missing_docs
 )]
+#![cfg_attr(docsrs, feature(doc_cfg))]
 #![doc(html_logo_url = "https://avatars0.githubusercontent.com/u/54989751?s=200&v=4")]
 #![doc = include_str!("../README.md")]
 
@@ -85,4 +86,18 @@ extern crate log;
 
 pub mod errors;
 mod hostpool;
-pub use hostpool::{HostPool, HostPoolBuilder};
+mod instrument;
+mod metrics;
+mod watchdog;
+pub use hostpool::{
+  CallDetails,
+  DropPolicy,
+  HostPool,
+  HostPoolBuilder,
+  PoolHostCallback,
+  Priority,
+  Route,
+  SaturationPolicy,
+  WorkerContext,
+};
+pub use metrics::{PoolMetrics, WorkerError, WorkerHealth, WorkerStats};
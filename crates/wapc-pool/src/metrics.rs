@@ -0,0 +1,132 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime};
+
+/// A snapshot of the activity of a [crate::HostPool], returned by [crate::HostPool::metrics].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[must_use]
+pub struct PoolMetrics {
+  /// The number of calls waiting for a worker: queued on the channel or waiting for room on it.
+  pub queue_depth: usize,
+  /// The number of workers running a call.
+  pub busy_workers: usize,
+  /// The number of calls accepted by the pool.
+  pub total_calls: u64,
+  /// The number of calls that timed out in [crate::HostPool::call_with_timeout].
+  pub total_timeouts: u64,
+  /// The number of times a worker failed to create its host.
+  pub factory_failures: u64,
+  /// The number of times a worker replaced its host after serving
+  /// [crate::HostPoolBuilder::max_calls_per_worker] calls.
+  pub worker_recycles: u64,
+  /// The moving average of the time the calls waited for a worker, the recent calls weigh more.
+  pub queue_wait: Duration,
+}
+
+/// The health of a worker running the health check of its [crate::HostPool], returned by
+/// [crate::HostPool::health].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[must_use]
+pub struct WorkerHealth {
+  /// The id of the worker.
+  pub worker: usize,
+  /// Whether the last health check succeeded, or none ran yet.
+  pub healthy: bool,
+  /// The number of health checks failed in a row since the host was last created.
+  pub consecutive_failures: usize,
+  /// The number of times the worker replaced its unhealthy host.
+  pub recycles: usize,
+}
+
+impl WorkerHealth {
+  pub(crate) const fn new(worker: usize) -> Self {
+    Self {
+      worker,
+      healthy: true,
+      consecutive_failures: 0,
+      recycles: 0,
+    }
+  }
+}
+
+/// The calls run by a worker of a [crate::HostPool] and how they failed, returned by
+/// [crate::HostPool::worker_stats].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct WorkerStats {
+  /// The id of the worker.
+  pub worker: usize,
+  /// The number of calls run by the worker.
+  pub total_calls: u64,
+  /// The number of calls that failed or panicked.
+  pub failed_calls: u64,
+  /// The number of calls failed in a row, reset by the first call that succeeds.
+  pub consecutive_failures: u64,
+  /// The error of the last call that failed, if any.
+  pub last_error: Option<WorkerError>,
+}
+
+/// The error of a call that failed on a worker, see [WorkerStats].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerError {
+  /// The error returned to the caller.
+  pub message: String,
+  /// When the call failed.
+  pub at: SystemTime,
+}
+
+impl WorkerStats {
+  pub(crate) const fn new(worker: usize) -> Self {
+    Self {
+      worker,
+      total_calls: 0,
+      failed_calls: 0,
+      consecutive_failures: 0,
+      last_error: None,
+    }
+  }
+
+  // Count a call, with its error if it failed
+  pub(crate) fn record(&mut self, error: Option<String>) {
+    self.total_calls += 1;
+    match error {
+      Some(message) => {
+        self.failed_calls += 1;
+        self.consecutive_failures += 1;
+        self.last_error = Some(WorkerError {
+          message,
+          at: SystemTime::now(),
+        });
+      }
+      None => self.consecutive_failures = 0,
+    }
+  }
+}
+
+// The counters updated by the pool and its workers
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+  pub(crate) busy_workers: AtomicUsize,
+  pub(crate) total_calls: AtomicU64,
+  pub(crate) total_timeouts: AtomicU64,
+  pub(crate) factory_failures: AtomicU64,
+  pub(crate) worker_recycles: AtomicU64,
+  queue_wait_ns: AtomicU64,
+}
+
+// Each new wait time weighs 1/WAIT_SMOOTHING in the average
+const WAIT_SMOOTHING: u128 = 5;
+
+impl Counters {
+  pub(crate) fn record_wait(&self, wait: Duration) {
+    let _ = self
+      .queue_wait_ns
+      .fetch_update(Ordering::AcqRel, Ordering::Acquire, |average| {
+        let average = (u128::from(average) * (WAIT_SMOOTHING - 1) + wait.as_nanos()) / WAIT_SMOOTHING;
+        Some(u64::try_from(average).unwrap_or(u64::MAX))
+      });
+  }
+
+  pub(crate) fn queue_wait(&self) -> Duration {
+    Duration::from_nanos(self.queue_wait_ns.load(Ordering::Acquire))
+  }
+}
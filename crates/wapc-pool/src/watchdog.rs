@@ -0,0 +1,71 @@
+// Warns about the calls running for too long on the workers. The calls are not interrupted, the
+// warnings only tell which worker is stuck in which operation.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crossbeam::channel::{Receiver as SyncReceiver, RecvTimeoutError};
+
+use crate::hostpool::lock;
+use crate::instrument;
+
+// A call running on a worker
+struct Execution {
+  op: String,
+  started: Instant,
+  // the call is reported again once running this long
+  warn_after: Duration,
+}
+
+// The calls running on the workers of a pool, by worker id
+pub(crate) struct Executions {
+  threshold: Duration,
+  running: Mutex<HashMap<usize, Execution>>,
+}
+
+impl Executions {
+  pub(crate) fn new(threshold: Duration) -> Self {
+    Self {
+      threshold,
+      running: Mutex::new(HashMap::new()),
+    }
+  }
+
+  pub(crate) fn start(&self, worker: usize, op: &str) {
+    let execution = Execution {
+      op: op.to_owned(),
+      started: Instant::now(),
+      warn_after: self.threshold,
+    };
+    lock(&self.running).insert(worker, execution);
+  }
+
+  pub(crate) fn finish(&self, worker: usize) {
+    lock(&self.running).remove(&worker);
+  }
+
+  // Warn about the calls running past the threshold, then each time their running time doubles
+  fn scan(&self, pool: &str) {
+    for (worker, execution) in lock(&self.running).iter_mut() {
+      let elapsed = execution.started.elapsed();
+      if elapsed < execution.warn_after {
+        continue;
+      }
+      warn!(
+        "Host thread {}.{} has been running the call for {} for {:?}",
+        pool, worker, execution.op, elapsed
+      );
+      instrument::slow_call(pool, *worker, &execution.op, elapsed);
+      execution.warn_after = elapsed * 2;
+    }
+  }
+
+  // Scan the running calls a few times per threshold, until `stop` is disconnected
+  pub(crate) fn watch(&self, pool: &str, stop: &SyncReceiver<()>) {
+    let interval = self.threshold / 2;
+    while stop.recv_timeout(interval) == Err(RecvTimeoutError::Timeout) {
+      self.scan(pool);
+    }
+  }
+}
@@ -1,7 +1,6 @@
 use std::fs::read;
 use std::time::{Duration, Instant};
 
-use futures::future::try_join_all;
 use wapc::{errors, WapcHost};
 use wapc_codec::messagepack::{deserialize, serialize};
 use wapc_pool::HostPoolBuilder;
@@ -14,28 +13,21 @@ async fn benchmark() -> Result<(), errors::Error> {
   let num_threads: u32 = 10;
   let num_calls: u32 = 100;
 
-  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+  let pre = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
     .module_bytes(&buf)
-    .build()?;
+    .build_pre()?;
   let pool = HostPoolBuilder::new()
     .name("wasmtime-test")
-    .factory(move || {
-      let engine = engine.clone();
-      WapcHost::new(Box::new(engine), None).unwrap()
-    })
+    .try_factory(move || WapcHost::new(Box::new(pre.rehydrate()?), None))
     .min_threads(num_threads as _)
     .max_threads(num_threads as _)
-    .build();
-
-  println!("Waiting for threads to spin up");
-  std::thread::sleep(Duration::from_millis(3000));
+    .build()?;
 
   let hello = "hello world".to_owned();
 
-  // Prime all the engines
+  // Wait for the threads to spin up and prime all the engines
   println!("Priming WASM engines");
-  let priming_futs = (0..num_threads).map(|_| pool.call("echo", serialize(&hello).unwrap()));
-  futures::future::join_all(priming_futs).await;
+  pool.warm_up(Some(("echo", serialize(&hello).unwrap()))).await?;
   println!("Priming finished");
 
   // Establish a baseline
@@ -47,10 +39,11 @@ async fn benchmark() -> Result<(), errors::Error> {
   let result: String = deserialize(&callresult).unwrap();
   assert_eq!(result, "hello world");
 
+  let payloads = (0..num_calls)
+    .map(|num| serialize(format!("hello world: {}", num)).unwrap())
+    .collect();
   let now = Instant::now();
-  let result =
-    try_join_all((0..num_calls).map(|num| pool.call("echo", serialize(format!("hello world: {}", num)).unwrap())))
-      .await;
+  let results = pool.call_many("echo", payloads).await?;
   let duration_all = now.elapsed();
 
   println!(
@@ -63,12 +56,9 @@ async fn benchmark() -> Result<(), errors::Error> {
   let buffer = Duration::from_micros((per_thread_margin * (num_calls / num_threads)) as _);
   let expected_max = base_duration * (num_calls / num_threads);
 
-  assert!(result.is_ok());
-  let returns = result.unwrap();
-
   // Assert correct ordering
-  for (i, bytes) in returns.iter().enumerate() {
-    let result: String = deserialize(bytes).unwrap();
+  for (i, result) in results.into_iter().enumerate() {
+    let result: String = deserialize(&result?).unwrap();
     assert_eq!(result, format!("hello world: {}", i));
   }
 
@@ -81,3 +71,66 @@ async fn benchmark() -> Result<(), errors::Error> {
 
   Ok(())
 }
+
+// Time the calls forwarding a large payload to a host callback that only inspects it
+async fn time_large_host_calls(reuse_host_call_buffer: bool) -> Result<Duration, errors::Error> {
+  let buf = read("../../wasm/crates/wasm-basic/build/wasm_basic.wasm")?;
+
+  let num_threads: u32 = 4;
+  let num_calls: u32 = 40;
+  // the guest expects a text payload
+  let payload: Vec<u8> = (0..4 * 1024 * 1024).map(|i| b'a' + (i % 26) as u8).collect();
+  let sum = payload.iter().fold(0u8, |acc, b| acc.wrapping_add(*b));
+
+  let pre = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .reuse_host_call_buffer(reuse_host_call_buffer)
+    .build_pre()?;
+  let pool = HostPoolBuilder::new()
+    .name("wasmtime-test-large-payloads")
+    .try_factory(move || {
+      WapcHost::new(
+        Box::new(pre.rehydrate()?),
+        Some(Box::new(move |_, _, _, _, received| {
+          let received_sum = received.iter().fold(0u8, |acc, b| acc.wrapping_add(*b));
+          if received_sum == sum {
+            Ok(vec![])
+          } else {
+            Err("corrupted payload".into())
+          }
+        })),
+      )
+    })
+    .min_threads(num_threads as _)
+    .max_threads(num_threads as _)
+    .build()?;
+
+  // Prime all the engines, letting the guest memories grow
+  pool.warm_up(Some(("ping", payload.clone()))).await?;
+
+  let now = Instant::now();
+  let results = pool.call_many("ping", vec![payload.clone(); num_calls as _]).await?;
+  let elapsed = now.elapsed();
+
+  // the guest echoes the payload forwarded to the host
+  for result in results {
+    let result = result?;
+    assert_eq!(result.len(), payload.len());
+    assert!(result == payload, "the payload did not round-trip");
+  }
+  Ok(elapsed)
+}
+
+#[test_log::test(tokio::test)]
+async fn benchmark_large_host_call_payloads() -> Result<(), errors::Error> {
+  let copied = time_large_host_calls(false).await?;
+  let reused = time_large_host_calls(true).await?;
+
+  println!(
+    "4 MB host call payloads: {}μs with a new buffer per call, {}μs with a reused buffer",
+    copied.as_micros(),
+    reused.as_micros()
+  );
+
+  Ok(())
+}
@@ -0,0 +1,38 @@
+use std::fs::read;
+
+use wapc::{errors, WapcHost};
+use wapc_codec::messagepack::{deserialize, serialize};
+use wapc_pool::HostPoolBuilder;
+
+// No async runtime is involved
+#[test_log::test]
+fn call_blocking() -> Result<(), errors::Error> {
+  let buf = read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm")?;
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .build()?;
+  let pool = HostPoolBuilder::new()
+    .name("blocking-test")
+    .factory(move || {
+      let engine = engine.clone();
+      WapcHost::new(Box::new(engine), None).unwrap()
+    })
+    .min_threads(1)
+    .max_threads(4)
+    .build()?;
+
+  let pool = &pool;
+  std::thread::scope(|scope| {
+    let calls: Vec<_> = (0..8)
+      .map(|num| {
+        scope.spawn(move || {
+          let message = format!("hello world: {}", num);
+          let result = pool.call_blocking("echo", serialize(&message).unwrap())?;
+          assert_eq!(deserialize::<String>(&result).unwrap(), message);
+          Ok::<_, errors::Error>(())
+        })
+      })
+      .collect();
+    calls.into_iter().try_for_each(|call| call.join().unwrap())
+  })
+}
@@ -0,0 +1,78 @@
+use std::fs::read;
+use std::time::Duration;
+
+use wapc::{errors, WapcHost};
+use wapc_codec::messagepack::serialize;
+use wapc_pool::{HostPool, HostPoolBuilder};
+
+const MODULE1: &str = "../../wasm/crates/wasm-calc-hash/module1/build/module1_hash.wasm";
+const MODULE2: &str = "../../wasm/crates/wasm-calc-hash/module2/build/module2_hash.wasm";
+
+// module1 responds with the hash of the name, module2 with a hardcoded one
+async fn calc_hash(pool: &HostPool) -> Result<Vec<u8>, errors::Error> {
+  pool.call("serdes_example", serialize(("John Doe",)).unwrap()).await
+}
+
+#[test_log::test(tokio::test)]
+async fn replace_module_on_all_workers() -> Result<(), errors::Error> {
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&read(MODULE1)?)
+    .build()?;
+  let pool = HostPoolBuilder::new()
+    .name("replace-test")
+    .factory(move || {
+      let engine = engine.clone();
+      WapcHost::new(Box::new(engine), Some(Box::new(|_, _, _, _, _| Ok(vec![])))).unwrap()
+    })
+    .min_threads(2)
+    .max_threads(6)
+    .max_wait(Duration::from_millis(1))
+    .build()?;
+
+  let hash1 = calc_hash(&pool).await?;
+
+  pool.replace_module(read(MODULE2)?).await?;
+  // the calls run on the initial workers and on the ones spawned afterwards
+  let hashes = futures::future::try_join_all((0..20).map(|_| calc_hash(&pool))).await?;
+  let hash2 = hashes[0].clone();
+  assert_ne!(hash1, hash2);
+  assert!(hashes.iter().all(|hash| *hash == hash2));
+
+  pool.replace_module(read(MODULE1)?).await?;
+  let hashes = futures::future::try_join_all((0..20).map(|_| calc_hash(&pool))).await?;
+  assert!(hashes.iter().all(|hash| *hash == hash1));
+  Ok(())
+}
+
+#[test_log::test(tokio::test)]
+async fn replace_module_failure() -> Result<(), errors::Error> {
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&read(MODULE1)?)
+    .build()?;
+  let pool = HostPoolBuilder::new()
+    .factory(move || {
+      let engine = engine.clone();
+      WapcHost::new(Box::new(engine), Some(Box::new(|_, _, _, _, _| Ok(vec![])))).unwrap()
+    })
+    .min_threads(2)
+    .max_threads(2)
+    .build()?;
+  let hash = calc_hash(&pool).await?;
+
+  let err = pool.replace_module(b"not a wasm module".to_vec()).await.unwrap_err();
+  let message = err.to_string();
+  assert!(
+    message.contains("Failed to replace the module of workers"),
+    "{}",
+    message
+  );
+  assert!(
+    message.contains("waPC host pool.0") && message.contains("waPC host pool.1"),
+    "{}",
+    message
+  );
+
+  // the workers keep running the previous module
+  assert_eq!(calc_hash(&pool).await?, hash);
+  Ok(())
+}
@@ -0,0 +1,74 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use log::{Level, Log, Metadata, Record};
+use wapc::{errors, ModuleState, WapcHost, WebAssemblyEngineProvider};
+use wapc_pool::HostPoolBuilder;
+
+// Keeps the warnings logged on every thread
+struct Recorder(Mutex<Vec<String>>);
+
+impl Log for Recorder {
+  fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+    metadata.level() <= Level::Warn
+  }
+
+  fn log(&self, record: &Record<'_>) {
+    if self.enabled(record.metadata()) {
+      self.0.lock().unwrap().push(record.args().to_string());
+    }
+  }
+
+  fn flush(&self) {}
+}
+
+static RECORDER: Recorder = Recorder(Mutex::new(Vec::new()));
+
+// Sleeps 500ms on every call
+#[derive(Default)]
+struct Sleeper {
+  host: Option<Arc<ModuleState>>,
+}
+
+impl WebAssemblyEngineProvider for Sleeper {
+  fn init(&mut self, host: Arc<ModuleState>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    self.host = Some(host);
+    Ok(())
+  }
+
+  fn call(&mut self, _: i32, _: i32) -> Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+    std::thread::sleep(Duration::from_millis(500));
+    self.host.as_ref().unwrap().set_guest_response(b"{}".to_vec());
+    Ok(1)
+  }
+
+  fn replace(&mut self, _bytes: &[u8]) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    Ok(())
+  }
+}
+
+#[test]
+fn slow_call_warning() -> Result<(), errors::Error> {
+  log::set_logger(&RECORDER).unwrap();
+  log::set_max_level(log::LevelFilter::Warn);
+
+  let pool = HostPoolBuilder::new()
+    .name("slow-test")
+    .factory(|| WapcHost::new(Box::<Sleeper>::default(), None).unwrap())
+    .min_threads(1)
+    .max_threads(1)
+    .slow_call_threshold(Duration::from_millis(100))
+    .build()?;
+  assert_eq!(pool.call_blocking("transform", b"hello world".to_vec())?, b"{}");
+
+  let warnings = RECORDER.0.lock().unwrap();
+  let slow: Vec<_> = warnings
+    .iter()
+    .filter(|warning| warning.starts_with("Host thread slow-test.0") && warning.contains("transform"))
+    .collect();
+  assert!(!slow.is_empty(), "{:?}", warnings);
+  // warned at 100ms, then 200ms and 400ms at most
+  assert!(slow.len() <= 3, "{:?}", slow);
+
+  Ok(())
+}
@@ -0,0 +1,128 @@
+#![cfg(feature = "tracing")]
+
+use std::fmt::Debug;
+use std::fs::read;
+use std::sync::{Arc, Mutex};
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+use wapc::{errors, WapcHost};
+use wapc_codec::messagepack::serialize;
+use wapc_pool::HostPoolBuilder;
+
+// A span or an event, with its fields and the name of its parent span
+#[derive(Debug)]
+struct Record {
+  name: String,
+  parent: Option<String>,
+  fields: Vec<(String, String)>,
+}
+
+impl Record {
+  fn field(&self, name: &str) -> Option<&str> {
+    self.fields.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+  }
+}
+
+#[derive(Default)]
+struct FieldCollector(Vec<(String, String)>);
+
+impl Visit for FieldCollector {
+  fn record_str(&mut self, field: &Field, value: &str) {
+    self.0.push((field.name().to_owned(), value.to_owned()));
+  }
+
+  fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+    self.0.push((field.name().to_owned(), format!("{:?}", value)));
+  }
+}
+
+// Keeps the spans and the events emitted on every thread
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<Record>>>);
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Recorder {
+  fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+    let mut fields = FieldCollector::default();
+    attrs.record(&mut fields);
+    let parent = ctx
+      .span(id)
+      .and_then(|span| span.parent())
+      .map(|span| span.name().to_owned());
+    self.0.lock().unwrap().push(Record {
+      name: attrs.metadata().name().to_owned(),
+      parent,
+      fields: fields.0,
+    });
+  }
+
+  fn on_event(&self, event: &tracing::Event<'_>, ctx: Context<'_, S>) {
+    let mut fields = FieldCollector::default();
+    event.record(&mut fields);
+    self.0.lock().unwrap().push(Record {
+      name: event.metadata().name().to_owned(),
+      parent: ctx.event_span(event).map(|span| span.name().to_owned()),
+      fields: fields.0,
+    });
+  }
+}
+
+#[test]
+fn call_span_hierarchy() -> Result<(), errors::Error> {
+  // the workers run on their own threads
+  let recorder = Recorder::default();
+  tracing::subscriber::set_global_default(tracing_subscriber::registry().with(recorder.clone())).unwrap();
+
+  let buf = read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm")?;
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .build()?;
+  let pool = HostPoolBuilder::new()
+    .name("tracing-test")
+    .factory(move || {
+      let engine = engine.clone();
+      WapcHost::new(Box::new(engine), None).unwrap()
+    })
+    .min_threads(1)
+    .max_threads(1)
+    .build()?;
+
+  let payload = "secret payload";
+  tracing::info_span!("request").in_scope(|| pool.call_blocking("echo", serialize(payload).unwrap()))?;
+
+  let records = recorder.0.lock().unwrap();
+  let call = records
+    .iter()
+    .find(|record| record.name == "wapc_pool.call")
+    .expect("the call span should have been emitted");
+  assert_eq!(call.parent.as_deref(), Some("request"));
+  assert_eq!(call.field("pool"), Some("tracing-test"));
+  assert_eq!(call.field("op"), Some("echo"));
+
+  let queued = records
+    .iter()
+    .find(|record| record.name == "wapc_pool.queued")
+    .expect("the queued event should have been emitted");
+  assert_eq!(queued.parent.as_deref(), Some("wapc_pool.call"));
+  assert_eq!(queued.field("worker"), Some("0"));
+
+  let executed = records
+    .iter()
+    .find(|record| record.name == "wapc_pool.executed")
+    .expect("the executed event should have been emitted");
+  assert_eq!(executed.parent.as_deref(), Some("wapc_pool.call"));
+  assert_eq!(executed.field("success"), Some("true"));
+
+  assert!(records.iter().any(|record| record.name == "wapc_pool.worker_started"));
+
+  // the payloads are never recorded
+  assert!(records
+    .iter()
+    .all(|record| record.fields.iter().all(|(_, value)| !value.contains(payload))));
+
+  Ok(())
+}
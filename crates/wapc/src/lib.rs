@@ -107,7 +107,7 @@ pub use wapchost::traits::{ModuleHostAsync, WebAssemblyEngineProviderAsync};
 #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
 pub use wapchost::WapcHostAsync;
 
-pub use wasi::WasiParams;
+pub use wasi::{CapturedOutput, StdioPolicy, WasiParams};
 
 /// The host module name / namespace that guest modules must use for imports
 pub const HOST_NAMESPACE: &str = "wapc";
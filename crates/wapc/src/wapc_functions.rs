@@ -27,6 +27,8 @@ pub const GUEST_CALL: &str = "__guest_call";
 pub const WAPC_INIT: &str = "wapc_init";
 /// The waPC protocol function `_start`
 pub const TINYGO_START: &str = "_start";
+/// The function exported by WASI reactors to initialize their state
+pub const WASI_INITIALIZE: &str = "_initialize";
 
 /// Start functions to attempt to call - order is important
-pub const REQUIRED_STARTS: [&str; 2] = [TINYGO_START, WAPC_INIT];
+pub const REQUIRED_STARTS: [&str; 3] = [TINYGO_START, WASI_INITIALIZE, WAPC_INIT];
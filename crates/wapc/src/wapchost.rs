@@ -20,3 +20,16 @@ pub use host::WapcHost;
 
 #[cfg(feature = "async")]
 pub use host_async::WapcHostAsync;
+
+// Turn a response of the host callback larger than `max_response` into an error
+pub(crate) fn check_host_response(
+  response: Vec<u8>,
+  max_response: Option<usize>,
+) -> std::result::Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+  match max_response {
+    Some(max) if response.len() > max => {
+      Err(format!("host response of {} bytes exceeds limit {}", response.len(), max).into())
+    }
+    _ => Ok(response),
+  }
+}
@@ -1,10 +1,16 @@
 use std::{
   cell::RefCell,
+  collections::HashMap,
   sync::{atomic::Ordering, Arc},
 };
 
 use crate::wapchost::{
-  errors, modulestate::ModuleState, traits::WebAssemblyEngineProvider, HostCallback, Invocation, Result,
+  errors,
+  modulestate::ModuleState,
+  traits::WebAssemblyEngineProvider,
+  HostCallback,
+  Invocation,
+  Result,
   GLOBAL_MODULE_COUNT,
 };
 
@@ -34,6 +40,14 @@ impl WapcHost {
 
     let state = Arc::new(ModuleState::new(host_callback, id));
 
+    Self::new_with_state(engine, state)
+  }
+
+  /// Creates a new instance of a waPC-compliant host runtime around an existing [`ModuleState`].
+  ///
+  /// This is meant to be paired with engine providers that have already been bound to `state`
+  /// (see [`ModuleState::with_callback`]), allowing them to skip most of their initialization.
+  pub fn new_with_state(engine: Box<dyn WebAssemblyEngineProvider>, state: Arc<ModuleState>) -> Result<Self> {
     let mh = WapcHost {
       engine: RefCell::new(engine),
       state: state.clone(),
@@ -58,6 +72,13 @@ impl WapcHost {
     self.state.id
   }
 
+  /// Choose whether the errors returned by the host callback are given to the guest together
+  /// with the context of the failed host call, as `{binding}/{namespace}/{operation}: {error}`.
+  /// This is disabled by default, so that the guests receive the exact error text.
+  pub fn set_host_error_context(&self, enabled: bool) {
+    self.state.set_host_error_context(enabled);
+  }
+
   /// Invokes the `__guest_call` function within the guest module as per the waPC specification.
   /// Provide an operation name and an opaque payload of bytes and the function returns a `Result`
   /// containing either an error or an opaque reply of bytes.
@@ -131,4 +152,25 @@ impl WapcHost {
       Err(e) => Err(errors::Error::ReplacementFailed(e.to_string())),
     }
   }
+
+  /// Same as [`replace_module`](WapcHost::replace_module), `module` has been precompiled by the
+  /// engine provider, which avoids paying the compilation cost during the swap. The
+  /// precompiled module must have been created with an engine configured like the one of the
+  /// provider, otherwise [`errors::Error::ReplacementFailed`] is returned.
+  ///
+  /// **Warning:** the engine providers may load the native code of precompiled modules without
+  /// validating it, `module` must come from a trusted source.
+  pub fn replace_module_precompiled(&self, module: &[u8]) -> Result<()> {
+    match self.engine.borrow_mut().replace_precompiled(module) {
+      Ok(_) => Ok(()),
+      Err(e) => Err(errors::Error::ReplacementFailed(e.to_string())),
+    }
+  }
+
+  /// Returns the statistics collected by the engine provider, refer to its documentation
+  /// for the available keys
+  #[must_use]
+  pub fn stats(&self) -> HashMap<String, u64> {
+    self.engine.borrow().stats()
+  }
 }
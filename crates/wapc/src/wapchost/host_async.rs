@@ -1,10 +1,17 @@
-use std::sync::{atomic::Ordering, Arc};
+use std::{
+  collections::HashMap,
+  sync::{atomic::Ordering, Arc},
+};
 
 use tokio::sync::Mutex;
 
 use crate::{
   wapchost::{
-    errors, modulestate_async::ModuleStateAsync, traits::WebAssemblyEngineProviderAsync, Invocation, Result,
+    errors,
+    modulestate_async::ModuleStateAsync,
+    traits::WebAssemblyEngineProviderAsync,
+    Invocation,
+    Result,
     GLOBAL_MODULE_COUNT,
   },
   HostCallbackAsync,
@@ -39,6 +46,17 @@ impl WapcHostAsync {
 
     let state = Arc::new(ModuleStateAsync::new(host_callback, id));
 
+    Self::new_with_state(engine, state).await
+  }
+
+  /// Creates a new instance of a waPC-compliant host runtime around an existing [`ModuleStateAsync`].
+  ///
+  /// This is meant to be paired with engine providers that have already been bound to `state`
+  /// (see [`ModuleStateAsync::with_callback`]), allowing them to skip most of their initialization.
+  pub async fn new_with_state(
+    engine: Box<dyn WebAssemblyEngineProviderAsync + Send>,
+    state: Arc<ModuleStateAsync>,
+  ) -> Result<Self> {
     let mh = WapcHostAsync {
       engine: Mutex::new(engine),
       state: state.clone(),
@@ -63,6 +81,13 @@ impl WapcHostAsync {
     self.state.id
   }
 
+  /// Choose whether the errors returned by the host callback are given to the guest together
+  /// with the context of the failed host call, as `{binding}/{namespace}/{operation}: {error}`.
+  /// This is disabled by default, so that the guests receive the exact error text.
+  pub fn set_host_error_context(&self, enabled: bool) {
+    self.state.set_host_error_context(enabled);
+  }
+
   /// Invokes the `__guest_call` function within the guest module as per the waPC specification.
   /// Provide an operation name and an opaque payload of bytes and the function returns a `Result`
   /// containing either an error or an opaque reply of bytes.
@@ -136,4 +161,24 @@ impl WapcHostAsync {
       Err(e) => Err(errors::Error::ReplacementFailed(e.to_string())),
     }
   }
+
+  /// Same as [`replace_module`](WapcHostAsync::replace_module), `module` has been precompiled by the
+  /// engine provider, which avoids paying the compilation cost during the swap. The
+  /// precompiled module must have been created with an engine configured like the one of the
+  /// provider, otherwise [`errors::Error::ReplacementFailed`] is returned.
+  ///
+  /// **Warning:** the engine providers may load the native code of precompiled modules without
+  /// validating it, `module` must come from a trusted source.
+  pub async fn replace_module_precompiled(&self, module: &[u8]) -> Result<()> {
+    match self.engine.lock().await.replace_precompiled(module).await {
+      Ok(_) => Ok(()),
+      Err(e) => Err(errors::Error::ReplacementFailed(e.to_string())),
+    }
+  }
+
+  /// Returns the statistics collected by the engine provider, refer to its documentation
+  /// for the available keys
+  pub async fn stats(&self) -> HashMap<String, u64> {
+    self.engine.lock().await.stats()
+  }
 }
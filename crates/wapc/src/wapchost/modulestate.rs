@@ -1,6 +1,9 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use log::info;
 use parking_lot::RwLock;
 
+use crate::wapchost::{check_host_response, GLOBAL_MODULE_COUNT};
 use crate::{HostCallback, Invocation};
 
 #[derive(Default)]
@@ -15,6 +18,8 @@ pub struct ModuleState {
   pub(super) host_error: RwLock<Option<String>>,
   pub(super) host_callback: Option<Box<HostCallback>>,
   pub(super) id: u64,
+  // when set, the host errors are given to the guest with the context of the host call
+  pub(super) host_error_context: AtomicBool,
 }
 
 impl ModuleState {
@@ -27,11 +32,32 @@ impl ModuleState {
       host_response: RwLock::new(None),
       guest_error: RwLock::new(None),
       host_error: RwLock::new(None),
+      host_error_context: AtomicBool::new(false),
     }
   }
+
+  /// Creates a new [`ModuleState`] with a unique id. Use this to bind an engine provider to its
+  /// state before handing both to [`WapcHost::new_with_state`](crate::WapcHost::new_with_state).
+  #[must_use]
+  pub fn with_callback(host_callback: Option<Box<HostCallback>>) -> ModuleState {
+    Self::new(host_callback, GLOBAL_MODULE_COUNT.fetch_add(1, Ordering::SeqCst))
+  }
 }
 
 impl ModuleState {
+  /// Choose whether the errors returned by the host callback are prefixed with the
+  /// `{binding}/{namespace}/{operation}` of the failed host call before being handed to the
+  /// guest. This is disabled by default.
+  pub fn set_host_error_context(&self, enabled: bool) {
+    self.host_error_context.store(enabled, Ordering::Relaxed);
+  }
+
+  /// The unique identifier of the module bound to this state
+  #[must_use]
+  pub fn id(&self) -> u64 {
+    self.id
+  }
+
   /// Retrieves the value, if any, of the current guest request
   pub fn get_guest_request(&self) -> Option<Invocation> {
     self.guest_request.read().clone()
@@ -69,6 +95,20 @@ impl ModuleState {
     namespace: &str,
     operation: &str,
     payload: &[u8],
+  ) -> Result<i32, Box<dyn std::error::Error>> {
+    self.do_host_call_with_limit(binding, namespace, operation, payload, None)
+  }
+
+  /// Same as [`do_host_call`](ModuleState::do_host_call), a response of the host callback
+  /// larger than `max_response` bytes is turned into a host error, instead of being handed
+  /// to the guest
+  pub fn do_host_call_with_limit(
+    &self,
+    binding: &str,
+    namespace: &str,
+    operation: &str,
+    payload: &[u8],
+    max_response: Option<usize>,
   ) -> Result<i32, Box<dyn std::error::Error>> {
     let id = {
       *self.host_response.write() = None;
@@ -79,13 +119,19 @@ impl ModuleState {
       || Err("Missing host callback function!".into()),
       |f| f(id, binding, namespace, operation, payload),
     );
+    let result = result.and_then(|response| check_host_response(response, max_response));
     Ok(match result {
       Ok(v) => {
         *self.host_response.write() = Some(v);
         1
       }
       Err(e) => {
-        *self.host_error.write() = Some(format!("{}", e));
+        let error = if self.host_error_context.load(Ordering::Relaxed) {
+          format!("{}/{}/{}: {}", binding, namespace, operation, e)
+        } else {
+          e.to_string()
+        };
+        *self.host_error.write() = Some(error);
         0
       }
     })
@@ -107,6 +153,7 @@ impl std::fmt::Debug for ModuleState {
       .field("host_error", &self.host_error)
       .field("host_callback", &self.host_callback.as_ref().map(|_| Some("Some(Fn)")))
       .field("id", &self.id)
+      .field("host_error_context", &self.host_error_context)
       .finish()
   }
 }
@@ -1,6 +1,9 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use log::info;
 use tokio::sync::RwLock;
 
+use crate::wapchost::{check_host_response, GLOBAL_MODULE_COUNT};
 use crate::{HostCallbackAsync, Invocation};
 
 #[derive(Default)]
@@ -17,6 +20,8 @@ pub struct ModuleStateAsync {
   pub(crate) host_error: RwLock<Option<String>>,
   pub(crate) host_callback: Option<Box<HostCallbackAsync>>,
   pub(crate) id: u64,
+  // when set, the host errors are given to the guest with the context of the host call
+  pub(crate) host_error_context: AtomicBool,
 }
 
 impl ModuleStateAsync {
@@ -29,11 +34,32 @@ impl ModuleStateAsync {
       host_response: RwLock::new(None),
       guest_error: RwLock::new(None),
       host_error: RwLock::new(None),
+      host_error_context: AtomicBool::new(false),
     }
   }
+
+  /// Creates a new [`ModuleStateAsync`] with a unique id. Use this to bind an engine provider to its
+  /// state before handing both to [`WapcHostAsync::new_with_state`](crate::WapcHostAsync::new_with_state).
+  #[must_use]
+  pub fn with_callback(host_callback: Option<Box<HostCallbackAsync>>) -> ModuleStateAsync {
+    Self::new(host_callback, GLOBAL_MODULE_COUNT.fetch_add(1, Ordering::SeqCst))
+  }
 }
 
 impl ModuleStateAsync {
+  /// Choose whether the errors returned by the host callback are prefixed with the
+  /// `{binding}/{namespace}/{operation}` of the failed host call before being handed to the
+  /// guest. This is disabled by default.
+  pub fn set_host_error_context(&self, enabled: bool) {
+    self.host_error_context.store(enabled, Ordering::Relaxed);
+  }
+
+  /// The unique identifier of the module bound to this state
+  #[must_use]
+  pub fn id(&self) -> u64 {
+    self.id
+  }
+
   /// Retrieves the value, if any, of the current guest request
   pub async fn get_guest_request(&self) -> Option<Invocation> {
     self.guest_request.read().await.clone()
@@ -71,6 +97,22 @@ impl ModuleStateAsync {
     namespace: String,
     operation: String,
     payload: Vec<u8>,
+  ) -> Result<i32, Box<dyn std::error::Error>> {
+    self
+      .do_host_call_with_limit(binding, namespace, operation, payload, None)
+      .await
+  }
+
+  /// Same as [`do_host_call`](ModuleStateAsync::do_host_call), a response of the host callback
+  /// larger than `max_response` bytes is turned into a host error, instead of being handed
+  /// to the guest
+  pub async fn do_host_call_with_limit(
+    &self,
+    binding: String,
+    namespace: String,
+    operation: String,
+    payload: Vec<u8>,
+    max_response: Option<usize>,
   ) -> Result<i32, Box<dyn std::error::Error>> {
     let id = {
       *self.host_response.write().await = None;
@@ -79,15 +121,21 @@ impl ModuleStateAsync {
     };
     let result = match self.host_callback.as_ref() {
       None => Err("Missing host callback function!".into()),
-      Some(f) => f(id, binding, namespace, operation, payload).await,
+      Some(f) => f(id, binding.clone(), namespace.clone(), operation.clone(), payload).await,
     };
+    let result = result.and_then(|response| check_host_response(response, max_response));
     Ok(match result {
       Ok(v) => {
         *self.host_response.write().await = Some(v);
         1
       }
       Err(e) => {
-        *self.host_error.write().await = Some(format!("{}", e));
+        let error = if self.host_error_context.load(Ordering::Relaxed) {
+          format!("{}/{}/{}: {}", binding, namespace, operation, e)
+        } else {
+          e.to_string()
+        };
+        *self.host_error.write().await = Some(error);
         0
       }
     })
@@ -109,6 +157,7 @@ impl std::fmt::Debug for ModuleStateAsync {
       .field("host_error", &self.host_error)
       .field("host_callback", &self.host_callback.as_ref().map(|_| Some("Some(Fn)")))
       .field("id", &self.id)
+      .field("host_error_context", &self.host_error_context)
       .finish()
   }
 }
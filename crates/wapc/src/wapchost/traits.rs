@@ -1,4 +1,4 @@
-use std::{error::Error, sync::Arc};
+use std::{collections::HashMap, error::Error, sync::Arc};
 
 #[cfg(feature = "async")]
 use async_trait::async_trait;
@@ -79,6 +79,18 @@ pub trait WebAssemblyEngineProvider {
   /// Called by the host to replace the WebAssembly module bytes of the previously initialized module. Engine must return an
   /// error if it does not support bytes replacement.
   fn replace(&mut self, bytes: &[u8]) -> Result<(), Box<dyn Error + Send + Sync>>;
+  /// Called by the host to replace the module of the previously initialized module with one that
+  /// has already been compiled by the engine, skipping the compilation. Engine must return an
+  /// error if it does not support precompiled modules, which is what the default implementation does.
+  fn replace_precompiled(&mut self, bytes: &[u8]) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let _ = bytes;
+    Err("the engine provider does not support precompiled modules".into())
+  }
+  /// Statistics collected by the engine provider, like the resources used by the most recent call.
+  /// The keys depend on the engine provider, the default implementation returns an empty map.
+  fn stats(&self) -> HashMap<String, u64> {
+    HashMap::new()
+  }
 }
 
 /// An async engine provider is any code that encapsulates low-level WebAssembly interactions such
@@ -98,4 +110,16 @@ pub trait WebAssemblyEngineProviderAsync {
   /// Called by the host to replace the WebAssembly module bytes of the previously initialized module. Engine must return an
   /// error if it does not support bytes replacement.
   async fn replace(&mut self, bytes: &[u8]) -> Result<(), Box<dyn Error + Send + Sync>>;
+  /// Called by the host to replace the module of the previously initialized module with one that
+  /// has already been compiled by the engine, skipping the compilation. Engine must return an
+  /// error if it does not support precompiled modules, which is what the default implementation does.
+  async fn replace_precompiled(&mut self, bytes: &[u8]) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let _ = bytes;
+    Err("the engine provider does not support precompiled modules".into())
+  }
+  /// Statistics collected by the engine provider, like the resources used by the most recent call.
+  /// The keys depend on the engine provider, the default implementation returns an empty map.
+  fn stats(&self) -> HashMap<String, u64> {
+    HashMap::new()
+  }
 }
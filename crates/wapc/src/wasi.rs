@@ -1,5 +1,21 @@
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
 /// Parameters defining the options for enabling WASI on a module (if applicable)
+///
+/// More options may be added over time: start from [`WasiParams::default`] or [`WasiParams::new`]
+/// and set the options via the methods below.
+///
+/// ```
+/// let params = wapc::WasiParams::default()
+///   .env_vars(vec![("GREETING".to_owned(), "hello".to_owned())])
+///   .inherit_env(true)
+///   .env_allowlist(vec!["HOME".to_owned()]);
+/// assert!(params.inherit_env);
+/// ```
 #[derive(Debug, Default, Clone, Eq, PartialEq)]
+#[non_exhaustive]
 #[must_use]
 pub struct WasiParams {
   /// Command line arguments to expose to WASI.
@@ -10,6 +26,16 @@ pub struct WasiParams {
   pub env_vars: Vec<(String, String)>,
   /// Directories that WASI has access to.
   pub preopened_dirs: Vec<String>,
+  /// Expose the environment variables of the host process. The variables listed inside of
+  /// `env_vars` take precedence over the inherited ones.
+  pub inherit_env: bool,
+  /// When inheriting the host environment, expose only the variables listed here.
+  pub env_allowlist: Option<Vec<String>>,
+  /// Expose the command line arguments of the host process. They are ignored when `argv`
+  /// is not empty.
+  pub inherit_args: bool,
+  /// Where the standard streams of the guest are connected to.
+  pub stdio: StdioPolicy,
 }
 
 impl WasiParams {
@@ -25,6 +51,114 @@ impl WasiParams {
       map_dirs,
       preopened_dirs,
       env_vars,
+      ..Default::default()
     }
   }
+
+  /// Set the command line arguments exposed to WASI.
+  pub fn argv(mut self, argv: Vec<String>) -> Self {
+    self.argv = argv;
+    self
+  }
+
+  /// Set the mapping of directories.
+  pub fn map_dirs(mut self, map_dirs: Vec<(String, String)>) -> Self {
+    self.map_dirs = map_dirs;
+    self
+  }
+
+  /// Set the environment variables and values exposed to WASI.
+  pub fn env_vars(mut self, env_vars: Vec<(String, String)>) -> Self {
+    self.env_vars = env_vars;
+    self
+  }
+
+  /// Set the directories WASI has access to.
+  pub fn preopened_dirs(mut self, preopened_dirs: Vec<String>) -> Self {
+    self.preopened_dirs = preopened_dirs;
+    self
+  }
+
+  /// Choose whether the environment variables of the host process are exposed.
+  pub fn inherit_env(mut self, enabled: bool) -> Self {
+    self.inherit_env = enabled;
+    self
+  }
+
+  /// Expose only the listed variables when inheriting the host environment.
+  pub fn env_allowlist(mut self, allowlist: Vec<String>) -> Self {
+    self.env_allowlist = Some(allowlist);
+    self
+  }
+
+  /// Choose whether the command line arguments of the host process are exposed.
+  pub fn inherit_args(mut self, enabled: bool) -> Self {
+    self.inherit_args = enabled;
+    self
+  }
+
+  /// Set where the standard streams of the guest are connected to.
+  pub fn stdio(mut self, stdio: StdioPolicy) -> Self {
+    self.stdio = stdio;
+    self
+  }
 }
+
+/// Defines where the standard input, output and error of a WASI guest are connected to
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub enum StdioPolicy {
+  /// Use the standard streams of the host process.
+  #[default]
+  Inherit,
+  /// Discard everything written by the guest. The guest reads an empty standard input.
+  Null,
+  /// Write both the standard output and the standard error of the guest into the given buffer.
+  /// The guest reads an empty standard input.
+  Capture(CapturedOutput),
+}
+
+/// A buffer holding the output captured from a WASI guest, see [`StdioPolicy::Capture`]
+///
+/// Clones share the same underlying buffer.
+#[derive(Debug, Default, Clone)]
+#[must_use]
+pub struct CapturedOutput(Arc<Mutex<Vec<u8>>>);
+
+impl CapturedOutput {
+  /// Create a new, empty, buffer.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Returns a copy of the output captured so far.
+  #[must_use]
+  pub fn contents(&self) -> Vec<u8> {
+    self.0.lock().clone()
+  }
+
+  /// Returns the output captured so far, leaving the buffer empty.
+  #[must_use]
+  pub fn take(&self) -> Vec<u8> {
+    std::mem::take(&mut *self.0.lock())
+  }
+}
+
+impl std::io::Write for CapturedOutput {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    self.0.lock().extend_from_slice(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    Ok(())
+  }
+}
+
+// Two captures are the same when they write into the same buffer
+impl PartialEq for CapturedOutput {
+  fn eq(&self, other: &Self) -> bool {
+    Arc::ptr_eq(&self.0, &other.0)
+  }
+}
+
+impl Eq for CapturedOutput {}
@@ -0,0 +1,170 @@
+// Minimal support of the WebAssembly binary format: wasm3 doesn't expose the structure of the
+// modules it parses
+
+// The header of a module: magic number and version
+pub(crate) const HEADER_LEN: usize = 8;
+
+pub(crate) const TYPE_SECTION_ID: u8 = 1;
+pub(crate) const IMPORT_SECTION_ID: u8 = 2;
+pub(crate) const FUNCTION_SECTION_ID: u8 = 3;
+pub(crate) const MEMORY_SECTION_ID: u8 = 5;
+pub(crate) const GLOBAL_SECTION_ID: u8 = 6;
+pub(crate) const EXPORT_SECTION_ID: u8 = 7;
+pub(crate) const CODE_SECTION_ID: u8 = 10;
+
+pub(crate) const IMPORT_KIND_FUNC: u8 = 0;
+const IMPORT_KIND_TABLE: u8 = 1;
+const IMPORT_KIND_MEMORY: u8 = 2;
+pub(crate) const IMPORT_KIND_GLOBAL: u8 = 3;
+const IMPORT_KIND_TAG: u8 = 4;
+
+// The limits of a memory or a table
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Limits {
+  pub(crate) flags: u8,
+  pub(crate) min: u64,
+  pub(crate) max: Option<u64>,
+}
+
+const LIMITS_HAS_MAX: u8 = 1;
+
+impl Limits {
+  pub(crate) fn encode(&self, out: &mut Vec<u8>) {
+    let flags = if self.max.is_some() {
+      self.flags | LIMITS_HAS_MAX
+    } else {
+      self.flags & !LIMITS_HAS_MAX
+    };
+    out.push(flags);
+    write_leb128(out, self.min);
+    if let Some(max) = self.max {
+      write_leb128(out, max);
+    }
+  }
+}
+
+// A section of the module
+pub(crate) struct Section<'a> {
+  pub(crate) id: u8,
+  pub(crate) content: &'a [u8],
+  // the whole section, id and size included
+  pub(crate) raw: &'a [u8],
+}
+
+// An entry of the import section
+pub(crate) struct Import<'a> {
+  pub(crate) module: &'a str,
+  pub(crate) name: &'a str,
+  pub(crate) kind: u8,
+}
+
+// Reads the values of the WebAssembly binary format, all the methods return None once the end
+// of the bytes is reached
+pub(crate) struct Reader<'a> {
+  bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+  pub(crate) const fn new(bytes: &'a [u8]) -> Self {
+    Self { bytes }
+  }
+
+  pub(crate) const fn is_empty(&self) -> bool {
+    self.bytes.is_empty()
+  }
+
+  // The bytes not read yet
+  pub(crate) const fn remaining(&self) -> &'a [u8] {
+    self.bytes
+  }
+
+  pub(crate) fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+    if len > self.bytes.len() {
+      return None;
+    }
+    let (taken, rest) = self.bytes.split_at(len);
+    self.bytes = rest;
+    Some(taken)
+  }
+
+  pub(crate) fn byte(&mut self) -> Option<u8> {
+    self.take(1).map(|b| b[0])
+  }
+
+  pub(crate) fn leb128(&mut self) -> Option<u64> {
+    let mut value = 0_u64;
+    for shift in (0..64).step_by(7) {
+      let byte = self.byte()?;
+      value |= u64::from(byte & 0x7f) << shift;
+      if byte & 0x80 == 0 {
+        return Some(value);
+      }
+    }
+    None
+  }
+
+  pub(crate) fn name(&mut self) -> Option<&'a str> {
+    let len = self.leb128()? as usize;
+    std::str::from_utf8(self.take(len)?).ok()
+  }
+
+  pub(crate) fn limits(&mut self) -> Option<Limits> {
+    let flags = self.byte()?;
+    let min = self.leb128()?;
+    let max = if flags & LIMITS_HAS_MAX != 0 {
+      Some(self.leb128()?)
+    } else {
+      None
+    };
+    Some(Limits { flags, min, max })
+  }
+
+  // Read an import, its description is skipped
+  pub(crate) fn import(&mut self) -> Option<Import<'a>> {
+    let module = self.name()?;
+    let name = self.name()?;
+    let kind = self.byte()?;
+    match kind {
+      IMPORT_KIND_FUNC => {
+        self.leb128()?;
+      }
+      IMPORT_KIND_TABLE => {
+        self.byte()?;
+        self.limits()?;
+      }
+      IMPORT_KIND_MEMORY => {
+        self.limits()?;
+      }
+      IMPORT_KIND_GLOBAL => {
+        self.take(2)?;
+      }
+      IMPORT_KIND_TAG => {
+        self.byte()?;
+        self.leb128()?;
+      }
+      _ => return None,
+    }
+    Some(Import { module, name, kind })
+  }
+
+  pub(crate) fn section(&mut self) -> Option<Section<'a>> {
+    let start = self.bytes;
+    let id = self.byte()?;
+    let size = self.leb128()? as usize;
+    let content = self.take(size)?;
+    let raw = &start[..start.len() - self.bytes.len()];
+    Some(Section { id, content, raw })
+  }
+}
+
+pub(crate) fn write_leb128(out: &mut Vec<u8>, mut value: u64) {
+  loop {
+    let byte = (value & 0x7f) as u8;
+    value >>= 7;
+    if value == 0 {
+      out.push(byte);
+      return;
+    }
+    out.push(byte | 0x80);
+  }
+}
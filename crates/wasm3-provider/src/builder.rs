@@ -0,0 +1,236 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use wapc::{WasiParams, HOST_NAMESPACE};
+use wasm3::error::Trap;
+use wasm3::{CallContext, WasmArgs, WasmType};
+
+use crate::errors::{Error, Result};
+use crate::host_functions::HostFunction;
+use crate::{ProviderConfig, Wasm3EngineProvider, MAX_MEMORY_LIMIT_PAGES, MAX_STACK_SIZE_BYTES, MIN_STACK_SIZE_BYTES};
+
+/// Used to build [`Wasm3EngineProvider`] instances.
+#[derive(Debug, Default)]
+pub struct Wasm3EngineProviderBuilder<'a> {
+  module_bytes: Option<&'a [u8]>,
+  stack_size: Option<u32>,
+  call_deadline: Option<Duration>,
+  memory_limit_pages: Option<u32>,
+  max_ops_per_call: Option<u64>,
+  enable_wasi: Option<bool>,
+  wasi_params: Option<WasiParams>,
+  strict_imports: Option<bool>,
+  required_imports: Option<Vec<String>>,
+  start_functions: Option<Vec<String>>,
+  host_functions: Vec<HostFunction>,
+}
+
+impl<'a> Wasm3EngineProviderBuilder<'a> {
+  /// Create a builder instance
+  #[must_use]
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  /// Provide contents of the WebAssembly module
+  #[must_use]
+  pub fn module_bytes(mut self, module_bytes: &'a [u8]) -> Self {
+    self.module_bytes = Some(module_bytes);
+    self
+  }
+
+  /// Size in bytes of the runtime stack, between [`MIN_STACK_SIZE_BYTES`] and
+  /// [`MAX_STACK_SIZE_BYTES`]. Defaults to [`DEFAULT_STACK_SIZE_BYTES`](crate::DEFAULT_STACK_SIZE_BYTES).
+  #[must_use]
+  pub fn stack_size(mut self, stack_size_bytes: u32) -> Self {
+    self.stack_size = Some(stack_size_bytes);
+    self
+  }
+
+  /// Interrupt the guest calls lasting longer than `deadline`, requires
+  /// [`max_ops_per_call`](Self::max_ops_per_call). Refer to [`Wasm3Options::call_deadline`]
+  #[must_use]
+  pub fn call_deadline(mut self, deadline: Duration) -> Self {
+    self.call_deadline = Some(deadline);
+    self
+  }
+
+  /// Limit the linear memory of the guest to `pages` pages of 64 KiB, between 1 and
+  /// [`MAX_MEMORY_LIMIT_PAGES`]. Independent of the size of the runtime stack, refer to
+  /// [`Wasm3Options::memory_limit_pages`](crate::Wasm3Options::memory_limit_pages)
+  #[must_use]
+  pub fn memory_limit_pages(mut self, pages: u32) -> Self {
+    self.memory_limit_pages = Some(pages);
+    self
+  }
+
+  /// Interrupt the guest calls performing more than `max_ops` operations, refer to
+  /// [`Wasm3Options::max_ops_per_call`](crate::Wasm3Options::max_ops_per_call)
+  #[must_use]
+  pub fn max_ops_per_call(mut self, max_ops: u64) -> Self {
+    self.max_ops_per_call = Some(max_ops);
+    self
+  }
+
+  /// Link the WASI functions, enabled by default. Refer to
+  /// [`Wasm3Options::enable_wasi`](crate::Wasm3Options::enable_wasi)
+  #[must_use]
+  pub fn enable_wasi(mut self, enabled: bool) -> Self {
+    self.enable_wasi = Some(enabled);
+    self
+  }
+
+  /// Expose the command line arguments and the environment variables of `wasi_params` to the
+  /// guest. Preopened directories and stdio policies other than
+  /// [`wapc::StdioPolicy::Inherit`] are not supported.
+  #[must_use]
+  pub fn wasi_params(mut self, wasi_params: WasiParams) -> Self {
+    self.wasi_params = Some(wasi_params);
+    self
+  }
+
+  /// Fail the initialization when the module imports waPC functions that cannot be linked, for
+  /// example because of a wrong signature. Disabled by default: these functions are only logged.
+  #[must_use]
+  pub fn strict_imports(mut self, strict: bool) -> Self {
+    self.strict_imports = Some(strict);
+    self
+  }
+
+  /// Names of the waPC functions the module must import, the initialization fails when any of
+  /// them is missing. Defaults to [`DEFAULT_REQUIRED_IMPORTS`](crate::DEFAULT_REQUIRED_IMPORTS).
+  #[must_use]
+  pub fn required_imports(mut self, required_imports: &[&str]) -> Self {
+    self.required_imports = Some(required_imports.iter().map(|s| (*s).to_owned()).collect());
+    self
+  }
+
+  /// Names of the functions invoked, in order, once the module is instantiated. The functions
+  /// not exported by the module are skipped. Defaults to
+  /// [`wapc_functions::REQUIRED_STARTS`](wapc::wapc_functions::REQUIRED_STARTS).
+  #[must_use]
+  pub fn start_functions(mut self, start_functions: &[&str]) -> Self {
+    self.start_functions = Some(start_functions.iter().map(|s| (*s).to_owned()).collect());
+    self
+  }
+
+  /// Link `closure` as the function `name` of the `module` namespace, for the guests importing
+  /// it. The function is linked into every runtime created by the provider: on
+  /// initialization, hot swap, ... Its parameters and result are the ones supported by
+  /// [`wasm3::Module::link_closure`]. The `wapc` namespace is reserved to the waPC functions.
+  #[must_use]
+  pub fn link_closure<Args, Ret, F>(mut self, module: &str, name: &str, closure: F) -> Self
+  where
+    Args: WasmArgs + 'static,
+    Ret: WasmType + 'static,
+    F: for<'cc> Fn(CallContext<'cc>, Args) -> std::result::Result<Ret, Trap> + Send + Sync + 'static,
+  {
+    self.host_functions.push(HostFunction::new(module, name, closure));
+    self
+  }
+
+  /// Create a [`Wasm3EngineProvider`] instance
+  pub fn build(&self) -> Result<Wasm3EngineProvider> {
+    let (module_bytes, config) = self.provider_config()?;
+    Ok(Wasm3EngineProvider::from_config(module_bytes, config))
+  }
+
+  /// Create a [`Wasm3EngineProviderPre`](crate::Wasm3EngineProviderPre) instance, used to create
+  /// many providers sharing the same module and configuration
+  pub fn build_pre(&self) -> Result<crate::Wasm3EngineProviderPre> {
+    let (module_bytes, config) = self.provider_config()?;
+    Ok(crate::Wasm3EngineProviderPre::new(module_bytes, config))
+  }
+
+  /// Create a [`Wasm3EngineProviderAsync`](crate::Wasm3EngineProviderAsync) instance
+  #[cfg(feature = "async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+  pub fn build_async(&self) -> Result<crate::Wasm3EngineProviderAsync> {
+    let (module_bytes, config) = self.provider_config()?;
+    Ok(crate::Wasm3EngineProviderAsync::from_config(module_bytes, config))
+  }
+
+  // Validate the settings of the builder
+  fn provider_config(&self) -> Result<(Arc<Vec<u8>>, ProviderConfig)> {
+    let module_bytes = self
+      .module_bytes
+      .ok_or_else(|| Error::BuilderInvalidConfig("`module_bytes` must be provided".to_owned()))?;
+
+    let mut config = ProviderConfig::default();
+    if let Some(stack_size_bytes) = self.stack_size {
+      if !(MIN_STACK_SIZE_BYTES..=MAX_STACK_SIZE_BYTES).contains(&stack_size_bytes) {
+        return Err(Error::InvalidStackSize(stack_size_bytes));
+      }
+      config.options = config.options.stack_size_bytes(stack_size_bytes);
+    }
+    if let Some(deadline) = self.call_deadline {
+      if deadline.is_zero() {
+        return Err(Error::BuilderInvalidConfig("`call_deadline` cannot be zero".to_owned()));
+      }
+      config.options = config.options.call_deadline(deadline);
+    }
+    if let Some(pages) = self.memory_limit_pages {
+      if !(1..=MAX_MEMORY_LIMIT_PAGES).contains(&pages) {
+        return Err(Error::InvalidMemoryLimit(pages));
+      }
+      config.options = config.options.memory_limit_pages(pages);
+    }
+    if let Some(max_ops) = self.max_ops_per_call {
+      if max_ops == 0 {
+        return Err(Error::BuilderInvalidConfig(
+          "`max_ops_per_call` cannot be zero".to_owned(),
+        ));
+      }
+      config.options = config.options.max_ops_per_call(max_ops);
+    }
+    if self.call_deadline.is_some() && self.max_ops_per_call.is_none() {
+      return Err(Error::UnboundedCallDeadline);
+    }
+    if let Some(enabled) = self.enable_wasi {
+      config.options = config.options.enable_wasi(enabled);
+    }
+    if let Some(wasi_params) = &self.wasi_params {
+      if !config.options.enable_wasi {
+        return Err(Error::BuilderInvalidConfig(
+          "`wasi_params` cannot be used when WASI is disabled".to_owned(),
+        ));
+      }
+      crate::wasi::validate(wasi_params)?;
+      config.wasi_params = Some(wasi_params.clone());
+    }
+    if let Some(strict_imports) = self.strict_imports {
+      config.strict_imports = strict_imports;
+    }
+    if let Some(required_imports) = &self.required_imports {
+      config.required_imports = required_imports.clone();
+    }
+    for (i, function) in self.host_functions.iter().enumerate() {
+      if function.namespace == HOST_NAMESPACE {
+        return Err(Error::BuilderInvalidConfig(format!(
+          "`link_closure` cannot link {:?}, the `{}` namespace is reserved",
+          function, HOST_NAMESPACE
+        )));
+      }
+      if self.host_functions[..i]
+        .iter()
+        .any(|f| f.namespace == function.namespace && f.name == function.name)
+      {
+        return Err(Error::BuilderInvalidConfig(format!(
+          "`link_closure` links {:?} more than once",
+          function
+        )));
+      }
+    }
+    config.host_functions = self.host_functions.clone();
+    if let Some(start_functions) = &self.start_functions {
+      if start_functions.iter().any(String::is_empty) {
+        return Err(Error::BuilderInvalidConfig(
+          "`start_functions` cannot contain empty names".to_owned(),
+        ));
+      }
+      config.start_functions = start_functions.clone();
+    }
+
+    Ok((Arc::new(module_bytes.to_vec()), config))
+  }
+}
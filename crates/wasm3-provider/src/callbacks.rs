@@ -1,8 +1,53 @@
-use std::sync::Arc;
+use std::fmt;
 
-use wapc::ModuleState;
+use wasm3::error::Trap;
 use wasm3::CallContext;
 
+use crate::host::GuestHost;
+
+// A guest memory access rejected by the callbacks, the guest traps
+#[derive(Debug)]
+pub(crate) enum CallbackError {
+  // A pointer/length pair provided by the guest doesn't fit into its memory
+  OutOfBounds {
+    what: &'static str,
+    ptr: i32,
+    len: i32,
+    memory_len: usize,
+  },
+  // A string provided by the guest is not valid UTF-8
+  InvalidUtf8(&'static str),
+}
+
+impl CallbackError {
+  pub(crate) const fn trap(&self) -> Trap {
+    match self {
+      Self::OutOfBounds { .. } => Trap::OutOfBoundsMemoryAccess,
+      Self::InvalidUtf8(_) => Trap::Abort,
+    }
+  }
+}
+
+impl fmt::Display for CallbackError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::OutOfBounds {
+        what,
+        ptr,
+        len,
+        memory_len,
+      } => write!(
+        f,
+        "{} pointer/length pair ({}, {}) is out of the bounds of the guest memory of {} bytes",
+        what, ptr, len, memory_len
+      ),
+      Self::InvalidUtf8(what) => write!(f, "{} is not valid UTF-8", what),
+    }
+  }
+}
+
+pub(crate) type CallbackResult<T> = Result<T, CallbackError>;
+
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn host_call(
   ctx: &CallContext,
@@ -14,77 +59,107 @@ pub(crate) fn host_call(
   op_len: i32,
   ptr: i32,
   len: i32,
-  host: &Arc<ModuleState>,
-) -> i32 {
-  let vec = get_vec_from_memory(ctx, ptr, len);
-  let bd_vec = get_vec_from_memory(ctx, bd_ptr, bd_len);
-  let bd = ::std::str::from_utf8(&bd_vec).unwrap();
-  let ns_vec = get_vec_from_memory(ctx, ns_ptr, ns_len);
-  let ns = ::std::str::from_utf8(&ns_vec).unwrap();
-  let op_vec = get_vec_from_memory(ctx, op_ptr, op_len);
-  let op = ::std::str::from_utf8(&op_vec).unwrap();
+  host: &dyn GuestHost,
+) -> CallbackResult<i32> {
+  let vec = get_vec_from_memory(ctx, ptr, len, "__host_call payload")?;
+  let bd_vec = get_vec_from_memory(ctx, bd_ptr, bd_len, "__host_call binding")?;
+  let bd = get_str(&bd_vec, "__host_call binding")?;
+  let ns_vec = get_vec_from_memory(ctx, ns_ptr, ns_len, "__host_call namespace")?;
+  let ns = get_str(&ns_vec, "__host_call namespace")?;
+  let op_vec = get_vec_from_memory(ctx, op_ptr, op_len, "__host_call operation")?;
+  let op = get_str(&op_vec, "__host_call operation")?;
 
   let result = host.do_host_call(bd, ns, op, &vec);
-  result.map_or(0, |r| r)
+  Ok(result.map_or(0, |r| r))
 }
 
-pub(crate) fn guest_request(ctx: &CallContext, op_ptr: i32, ptr: i32, host: &Arc<ModuleState>) {
+pub(crate) fn guest_request(ctx: &CallContext, op_ptr: i32, ptr: i32, host: &dyn GuestHost) -> CallbackResult<()> {
   if let Some(inv) = host.get_guest_request() {
-    write_bytes_to_memory(ctx, ptr, &inv.msg);
-    write_bytes_to_memory(ctx, op_ptr, inv.operation.as_bytes());
+    write_bytes_to_memory(ctx, ptr, &inv.msg, "__guest_request payload")?;
+    write_bytes_to_memory(ctx, op_ptr, inv.operation.as_bytes(), "__guest_request operation")?;
   }
+  Ok(())
 }
 
-pub(crate) fn host_response(ctx: &CallContext, ptr: i32, host: &Arc<ModuleState>) {
+pub(crate) fn host_response(ctx: &CallContext, ptr: i32, host: &dyn GuestHost) -> CallbackResult<()> {
   if let Some(ref r) = host.get_host_response() {
-    write_bytes_to_memory(ctx, ptr, r);
+    write_bytes_to_memory(ctx, ptr, r, "__host_response")?;
   }
+  Ok(())
 }
 
-pub(crate) fn host_response_length(_ctx: &CallContext, host: &Arc<ModuleState>) -> i32 {
+pub(crate) fn host_response_length(_ctx: &CallContext, host: &dyn GuestHost) -> i32 {
   host.get_host_response().unwrap_or_default().len() as i32
 }
 
-pub(crate) fn console_log(ctx: &CallContext, ptr: i32, len: i32, host: &Arc<ModuleState>) {
-  let vec = get_vec_from_memory(ctx, ptr, len);
-  let msg = std::str::from_utf8(&vec).unwrap();
+pub(crate) fn console_log(ctx: &CallContext, ptr: i32, len: i32, host: &dyn GuestHost) -> CallbackResult<()> {
+  let vec = get_vec_from_memory(ctx, ptr, len, "__console_log")?;
+  let msg = get_str(&vec, "__console_log")?;
   host.do_console_log(msg);
+  Ok(())
 }
 
 // Sets the guest response by telling the host "you can find the response binary here, and it's x bytes"
-pub(crate) fn guest_response(ctx: &CallContext, ptr: i32, len: i32, host: &Arc<ModuleState>) {
-  let vec = get_vec_from_memory(ctx, ptr, len);
+pub(crate) fn guest_response(ctx: &CallContext, ptr: i32, len: i32, host: &dyn GuestHost) -> CallbackResult<()> {
+  let vec = get_vec_from_memory(ctx, ptr, len, "__guest_response")?;
   host.set_guest_response(vec);
+  Ok(())
 }
 
 // Sets the guest error by telling the host "you can find the error binary here, and it's x bytes"
-pub(crate) fn guest_error(ctx: &CallContext, ptr: i32, len: i32, host: &Arc<ModuleState>) {
-  let vec = get_vec_from_memory(ctx, ptr, len);
-  host.set_guest_error(String::from_utf8(vec).unwrap());
+pub(crate) fn guest_error(ctx: &CallContext, ptr: i32, len: i32, host: &dyn GuestHost) -> CallbackResult<()> {
+  let vec = get_vec_from_memory(ctx, ptr, len, "__guest_error")?;
+  let msg = String::from_utf8(vec).map_err(|_| CallbackError::InvalidUtf8("__guest_error"))?;
+  host.set_guest_error(msg);
+  Ok(())
 }
 
 // Writes the host error, if any, to the linear memory at the location supplied by the guest
-pub(crate) fn host_error(ctx: &CallContext, ptr: i32, host: &Arc<ModuleState>) {
+pub(crate) fn host_error(ctx: &CallContext, ptr: i32, host: &dyn GuestHost) -> CallbackResult<()> {
   if let Some(ref e) = host.get_host_error() {
-    write_bytes_to_memory(ctx, ptr, e.as_bytes());
+    write_bytes_to_memory(ctx, ptr, e.as_bytes(), "__host_error")?;
   }
+  Ok(())
 }
 
 // Returns the length of the host error, 0 if there is none.
-pub(crate) fn host_error_length(host: &Arc<ModuleState>) -> i32 {
+pub(crate) fn host_error_length(host: &dyn GuestHost) -> i32 {
   host.get_host_error().unwrap_or_default().len() as _
 }
 
-fn get_vec_from_memory(ctx: &CallContext, ptr: i32, len: i32) -> Vec<u8> {
+fn get_str<'a>(vec: &'a [u8], what: &'static str) -> CallbackResult<&'a str> {
+  std::str::from_utf8(vec).map_err(|_| CallbackError::InvalidUtf8(what))
+}
+
+// The range of the guest memory described by the pair, pointers and lengths are unsigned for
+// the guest
+fn guest_range(ptr: i32, len: i32, memory_len: usize, what: &'static str) -> CallbackResult<std::ops::Range<usize>> {
+  let start = ptr as u32 as usize;
+  start
+    .checked_add(len as u32 as usize)
+    .filter(|end| *end <= memory_len)
+    .map(|end| start..end)
+    .ok_or(CallbackError::OutOfBounds {
+      what,
+      ptr,
+      len,
+      memory_len,
+    })
+}
+
+fn get_vec_from_memory(ctx: &CallContext, ptr: i32, len: i32, what: &'static str) -> CallbackResult<Vec<u8>> {
   #[allow(unsafe_code)]
   let data = unsafe { &*ctx.memory() };
 
-  data[ptr as usize..][..len as usize].to_vec()
+  let range = guest_range(ptr, len, data.len(), what)?;
+  Ok(data[range].to_vec())
 }
 
-fn write_bytes_to_memory(ctx: &CallContext, ptr: i32, slice: &[u8]) {
+fn write_bytes_to_memory(ctx: &CallContext, ptr: i32, slice: &[u8], what: &'static str) -> CallbackResult<()> {
   #[allow(unsafe_code)]
-  unsafe {
-    (&mut *ctx.memory_mut())[ptr as usize..][..slice.len()].copy_from_slice(slice);
-  };
+  let data = unsafe { &mut *ctx.memory_mut() };
+
+  let range = guest_range(ptr, slice.len() as i32, data.len(), what)?;
+  data[range].copy_from_slice(slice);
+  Ok(())
 }
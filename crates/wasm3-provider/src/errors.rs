@@ -1,9 +1,98 @@
+/// Prefix of the guest error reported when the guest ran out of memory
+pub const OUT_OF_MEMORY_PREFIX: &str = "wapc:oom:";
+
+/// A specialized [`std::result::Result`] type for the wasm3 provider
+pub type Result<T> = std::result::Result<T, Error>;
+
 /// This crate's generic Error type
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
   /// Error returned from the wasm3 rust wrapper.
   #[error("WASM3: {0}")]
   Wasm3(String),
+  /// Error returned when the wasm3 environment cannot be created.
+  #[error("Could not create a wasm3 environment: {0}")]
+  Environment(String),
+  /// Error returned when the size of the runtime stack is out of the accepted range.
+  #[error("Invalid runtime stack size of {0} bytes")]
+  InvalidStackSize(u32),
+  /// Error returned when a WASI feature is not supported by the wasm3 provider.
+  #[error("WASI {0} are not supported by the wasm3 provider")]
+  WasiUnsupported(String),
+  /// Error returned when the builder is given an invalid configuration.
+  #[error("Invalid configuration: {0}")]
+  BuilderInvalidConfig(String),
+  /// Error returned when a guest call is interrupted because it exceeded its deadline.
+  #[error("guest code interrupted, func execution deadline of {0:?} exceeded")]
+  CallDeadlineExceeded(std::time::Duration),
+  /// Error returned when a call deadline is set without a limit of operations per call, which
+  /// bounds the guests abandoned once their deadline is exceeded.
+  #[error("A call deadline requires a limit of operations per call")]
+  UnboundedCallDeadline,
+  /// Error returned when a guest call is interrupted because it exceeded its limit of
+  /// operations, refer to [`Wasm3Options::max_ops_per_call`](crate::Wasm3Options::max_ops_per_call).
+  #[error("guest code interrupted, func execution deadline of {0} operations exceeded")]
+  OpsDeadlineExceeded(u64),
+  /// Error returned when the module doesn't import some of the required waPC functions or, when
+  /// the imports are strict, imports waPC functions that cannot be linked. Lists all the problems.
+  #[error("Module will not work with waPC: {}", .0.join(", "))]
+  MissingRequiredImport(Vec<String>),
+  /// Error returned when the module doesn't export the `__guest_call` function.
+  #[error("Could not find __guest_call function in WebAssembly module")]
+  GuestCallNotFound,
+  /// Error returned when the wasm3 runtime cannot be created.
+  #[error("Could not create a wasm3 runtime: {0}")]
+  RuntimeCreation(String),
+  /// Error returned when a starter function of the module fails.
+  #[error("Failed during starter initialization '{starter}': {err}")]
+  StarterFailed {
+    /// name of the starter function
+    starter: String,
+    /// error reported
+    err: String,
+  },
+  /// Error returned when the limit of the linear memory is out of the accepted range.
+  #[error("Invalid linear memory limit of {0} pages")]
+  InvalidMemoryLimit(u32),
+  /// Error returned when the initial linear memory of the module is above the limit.
+  #[error("The module requires {initial_pages} pages of linear memory, above the limit of {limit_pages} pages")]
+  MemoryLimitExceeded {
+    /// initial size of the linear memory declared by the module
+    initial_pages: u64,
+    /// limit of the linear memory
+    limit_pages: u32,
+  },
+  /// Error returned when the guest traps once its linear memory reached the limit. Starts with
+  /// [`OUT_OF_MEMORY_PREFIX`].
+  #[error("{OUT_OF_MEMORY_PREFIX} {0}")]
+  GuestOutOfMemory(String),
+  /// Error returned when the guest traps during a call. The traps of the starters are reported
+  /// by [`Error::StarterFailed`].
+  ///
+  /// The wasm3 binding exposes no backtrace: the message holds the wasm3 error, along with the
+  /// details recorded by the waPC host function that raised the trap, if any.
+  #[error("Guest trapped while executing operation '{operation}': {message}")]
+  Trap {
+    /// name of the waPC operation invoked by the call
+    operation: String,
+    /// description of the trap
+    message: String,
+  },
+  /// Error returned when a call is made before the initialization of the provider.
+  #[error("Module call failure - no module was initialized")]
+  NotInitialized,
+  /// Error returned when the thread running the runtime of an async provider terminated.
+  #[error("The thread running the wasm3 runtime terminated")]
+  WorkerTerminated,
+  /// Error returned when the module cannot be hot swapped, the previous one is kept.
+  #[error("Replacing the module failed: {0}")]
+  ReplacementFailed(String),
+}
+
+impl From<Error> for wapc::errors::Error {
+  fn from(e: Error) -> Self {
+    wapc::errors::Error::ProviderFailure(Box::new(e))
+  }
 }
 
 impl From<wasm3::error::Error> for Error {
@@ -16,11 +105,11 @@ impl From<wasm3::error::Error> for Error {
 // pointer. This trait is to normalize `Result`'s coming from wasm3 into ones that
 // are easier to manage.
 pub(crate) trait SendSyncResult<T> {
-  fn to_wapc(self) -> Result<T, Error>;
+  fn to_wapc(self) -> Result<T>;
 }
 
-impl<T> SendSyncResult<T> for Result<T, wasm3::error::Error> {
-  fn to_wapc(self) -> Result<T, Error> {
+impl<T> SendSyncResult<T> for std::result::Result<T, wasm3::error::Error> {
+  fn to_wapc(self) -> Result<T> {
     self.map_err(|e| e.into())
   }
 }
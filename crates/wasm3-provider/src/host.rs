@@ -0,0 +1,58 @@
+use std::error::Error;
+
+use wapc::{Invocation, ModuleState};
+
+// The waPC state read and updated by the host functions of a runtime. wasm3 invokes the host
+// functions synchronously: the state of the async waPC host is accessed by blocking on its
+// async runtime
+pub(crate) trait GuestHost: Send + Sync {
+  fn get_guest_request(&self) -> Option<Invocation>;
+  fn get_host_response(&self) -> Option<Vec<u8>>;
+  fn set_guest_response(&self, response: Vec<u8>);
+  fn set_guest_error(&self, error: String);
+  fn get_host_error(&self) -> Option<String>;
+  fn do_host_call(
+    &self,
+    binding: &str,
+    namespace: &str,
+    operation: &str,
+    payload: &[u8],
+  ) -> Result<i32, Box<dyn Error>>;
+  fn do_console_log(&self, msg: &str);
+}
+
+impl GuestHost for ModuleState {
+  fn get_guest_request(&self) -> Option<Invocation> {
+    ModuleState::get_guest_request(self)
+  }
+
+  fn get_host_response(&self) -> Option<Vec<u8>> {
+    ModuleState::get_host_response(self)
+  }
+
+  fn set_guest_response(&self, response: Vec<u8>) {
+    ModuleState::set_guest_response(self, response);
+  }
+
+  fn set_guest_error(&self, error: String) {
+    ModuleState::set_guest_error(self, error);
+  }
+
+  fn get_host_error(&self) -> Option<String> {
+    ModuleState::get_host_error(self)
+  }
+
+  fn do_host_call(
+    &self,
+    binding: &str,
+    namespace: &str,
+    operation: &str,
+    payload: &[u8],
+  ) -> Result<i32, Box<dyn Error>> {
+    ModuleState::do_host_call(self, binding, namespace, operation, payload)
+  }
+
+  fn do_console_log(&self, msg: &str) {
+    ModuleState::do_console_log(self, msg);
+  }
+}
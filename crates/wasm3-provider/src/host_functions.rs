@@ -0,0 +1,52 @@
+use std::fmt;
+use std::sync::Arc;
+
+use wasm3::error::Trap;
+use wasm3::{CallContext, Module, WasmArgs, WasmType};
+
+type LinkFn = dyn Fn(&mut Module<'_>, &str, &str) -> wasm3::error::Result<()> + Send + Sync;
+
+// A host function registered with
+// `Wasm3EngineProviderBuilder::link_closure`, linked into every runtime created by the provider
+#[derive(Clone)]
+pub(crate) struct HostFunction {
+  pub(crate) namespace: String,
+  pub(crate) name: String,
+  link: Arc<LinkFn>,
+}
+
+impl HostFunction {
+  pub(crate) fn new<Args, Ret, F>(namespace: &str, name: &str, closure: F) -> Self
+  where
+    Args: WasmArgs + 'static,
+    Ret: WasmType + 'static,
+    F: for<'cc> Fn(CallContext<'cc>, Args) -> Result<Ret, Trap> + Send + Sync + 'static,
+  {
+    let closure = Arc::new(closure);
+    Self {
+      namespace: namespace.to_owned(),
+      name: name.to_owned(),
+      link: Arc::new(move |module: &mut Module<'_>, namespace: &str, name: &str| {
+        let closure = closure.clone();
+        module.link_closure(namespace, name, move |ctx: CallContext<'_>, args: Args| {
+          closure(ctx, args)
+        })
+      }),
+    }
+  }
+}
+
+impl fmt::Debug for HostFunction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}.{}", self.namespace, self.name)
+  }
+}
+
+// Link the host functions imported by `module`, the other ones are skipped
+pub(crate) fn link(module: &mut Module<'_>, host_functions: &[HostFunction]) {
+  for function in host_functions {
+    if let Err(e) = (function.link)(module, &function.namespace, &function.name) {
+      debug!("Host function {:?} not linked: {}", function, e);
+    }
+  }
+}
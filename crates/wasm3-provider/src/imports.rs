@@ -0,0 +1,83 @@
+use wapc::{wapc_functions, HOST_NAMESPACE};
+
+use crate::binary::{Reader, HEADER_LEN, IMPORT_KIND_FUNC, IMPORT_SECTION_ID};
+use crate::errors;
+use crate::wasi::WASI_NAMESPACES;
+use crate::ProviderConfig;
+
+/// The waPC functions the module must import unless configured otherwise, refer to
+/// [`Wasm3EngineProviderBuilder::required_imports`](crate::Wasm3EngineProviderBuilder::required_imports)
+pub const DEFAULT_REQUIRED_IMPORTS: [&str; 3] = [
+  wapc_functions::GUEST_REQUEST_FN,
+  wapc_functions::GUEST_RESPONSE_FN,
+  wapc_functions::GUEST_ERROR_FN,
+];
+
+// Check the functions imported by the module found inside of `bytes` against the settings of
+// `config`: the required waPC functions, the `linked` ones when the imports are strict, and the
+// WASI ones when WASI is disabled. All the problems are reported at once
+pub(crate) fn check(bytes: &[u8], linked: &[&str], config: &ProviderConfig) -> errors::Result<()> {
+  let imports = function_imports(bytes).ok_or_else(|| errors::Error::Wasm3("malformed import section".to_owned()))?;
+  let imported: Vec<&str> = imports
+    .iter()
+    .filter(|(module, _)| module == HOST_NAMESPACE)
+    .map(|(_, name)| name.as_str())
+    .collect();
+  let mut problems = Vec::new();
+
+  for name in &config.required_imports {
+    if !imported.contains(&name.as_str()) {
+      problems.push(format!("{} is not imported", name));
+    }
+  }
+  if config.strict_imports {
+    for name in imported.iter().filter(|name| !linked.contains(name)) {
+      problems.push(format!("{} is imported but cannot be linked", name));
+    }
+  }
+  if !config.options.enable_wasi {
+    // the host functions linked explicitly may stand in for the WASI ones
+    for (module, name) in imports.iter().filter(|(module, name)| {
+      WASI_NAMESPACES.contains(&module.as_str())
+        && !config
+          .host_functions
+          .iter()
+          .any(|f| f.namespace == *module && f.name == *name)
+    }) {
+      problems.push(format!("{}.{} is imported but WASI is disabled", module, name));
+    }
+  }
+
+  if problems.is_empty() {
+    Ok(())
+  } else {
+    error!("Module cannot work with waPC: {}", problems.join(", "));
+    Err(errors::Error::MissingRequiredImport(problems))
+  }
+}
+
+// Namespaces and names of the functions the module imports, None when the module is malformed
+fn function_imports(bytes: &[u8]) -> Option<Vec<(String, String)>> {
+  let mut reader = Reader::new(bytes.get(HEADER_LEN..)?);
+
+  while !reader.is_empty() {
+    let section = reader.section()?;
+    if section.id == IMPORT_SECTION_ID {
+      return import_section(section.content);
+    }
+  }
+  Some(Vec::new())
+}
+
+fn import_section(section: &[u8]) -> Option<Vec<(String, String)>> {
+  let mut reader = Reader::new(section);
+  let mut imports = Vec::new();
+
+  for _ in 0..reader.leb128()? {
+    let import = reader.import()?;
+    if import.kind == IMPORT_KIND_FUNC {
+      imports.push((import.module.to_owned(), import.name.to_owned()));
+    }
+  }
+  Some(imports)
+}
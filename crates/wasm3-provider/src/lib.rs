@@ -1,5 +1,6 @@
 #![deny(
   clippy::expect_used,
+  clippy::panic,
   clippy::explicit_deref_methods,
   clippy::option_if_let_else,
   clippy::await_holding_lock,
@@ -78,219 +79,832 @@
   missing_docs
 )]
 #![doc = include_str!("../README.md")]
+#![cfg_attr(docsrs, feature(doc_cfg))]
 
 /// This crate's error module
 pub mod errors;
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::error::Error;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
 
-use parking_lot::Mutex;
-use wapc::{wapc_functions, ModuleState, WebAssemblyEngineProvider, HOST_NAMESPACE};
+use wapc::{wapc_functions, ModuleState, WasiParams, WebAssemblyEngineProvider, HOST_NAMESPACE};
 use wasm3::error::Trap;
-use wasm3::{CallContext, Environment, Module, Runtime};
+use wasm3::{CallContext, Environment, Function, Module, Runtime};
 
 use crate::errors::SendSyncResult;
+use crate::host::GuestHost;
+use crate::host_functions::HostFunction;
 
 #[macro_use]
 extern crate log;
 
+mod builder;
+pub use builder::Wasm3EngineProviderBuilder;
+
+mod binary;
 mod callbacks;
+mod host;
+mod host_functions;
+mod imports;
+pub use imports::DEFAULT_REQUIRED_IMPORTS;
+mod pre;
+pub use pre::Wasm3EngineProviderPre;
+mod memory_limit;
+pub use memory_limit::MAX_MEMORY_LIMIT_PAGES;
+mod metering;
+mod stats;
+pub use stats::MemoryStats;
+#[cfg(feature = "async")]
+mod provider_async;
+#[cfg(feature = "async")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+pub use provider_async::Wasm3EngineProviderAsync;
+mod wasi;
 
 const WASI_UNSTABLE: &str = "wasi_unstable";
 
+/// Size of the runtime stack used by [Wasm3EngineProvider::new].
+pub const DEFAULT_STACK_SIZE_BYTES: u32 = 1024 * 120;
+/// Smallest runtime stack accepted by the provider.
+pub const MIN_STACK_SIZE_BYTES: u32 = 1024 * 8;
+/// Largest runtime stack accepted by the provider.
+pub const MAX_STACK_SIZE_BYTES: u32 = 1024 * 1024 * 256;
+
+/// Options used to create the wasm3 runtime of a [Wasm3EngineProvider].
+#[derive(Debug, Clone, Copy)]
+#[must_use]
+pub struct Wasm3Options {
+  stack_size_bytes: u32,
+  call_deadline: Option<Duration>,
+  memory_limit_pages: Option<u32>,
+  max_ops_per_call: Option<u64>,
+  enable_wasi: bool,
+}
+
+impl Default for Wasm3Options {
+  fn default() -> Self {
+    Self {
+      stack_size_bytes: DEFAULT_STACK_SIZE_BYTES,
+      call_deadline: None,
+      memory_limit_pages: None,
+      max_ops_per_call: None,
+      enable_wasi: true,
+    }
+  }
+}
+
+impl Wasm3Options {
+  /// Size in bytes of the runtime stack, between [MIN_STACK_SIZE_BYTES] and [MAX_STACK_SIZE_BYTES].
+  /// Recursive guests may need a larger stack than [DEFAULT_STACK_SIZE_BYTES].
+  pub fn stack_size_bytes(mut self, stack_size_bytes: u32) -> Self {
+    self.stack_size_bytes = stack_size_bytes;
+    self
+  }
+
+  /// Interrupt the guest calls lasting longer than `deadline`. Requires
+  /// [`max_ops_per_call`](Self::max_ops_per_call).
+  ///
+  /// wasm3 cannot interrupt a running guest: the calls are then executed on a worker thread of
+  /// the provider and abandoned once the deadline is exceeded. The runtime of the abandoned call
+  /// is replaced by a new one, hence the state of the guest is lost. The abandoned guest keeps
+  /// running in the background until it exhausts its operations or makes its next waPC host
+  /// call, which traps, then its thread exits. The next calls run on a new worker thread.
+  pub fn call_deadline(mut self, deadline: Duration) -> Self {
+    self.call_deadline = Some(deadline);
+    self
+  }
+
+  /// Number of 64 KiB pages the linear memory of the guest can grow to, between 1 and
+  /// [MAX_MEMORY_LIMIT_PAGES]. The limit is independent of the size of the runtime stack.
+  ///
+  /// The `memory.grow` instructions going above the limit fail, as when the guest runs out of
+  /// memory. A guest trapping once its memory reached the limit is reported as
+  /// [`Error::GuestOutOfMemory`](errors::Error::GuestOutOfMemory), while a module whose initial
+  /// memory is above the limit cannot be instantiated. Only the memories defined by the module
+  /// are limited, not the imported ones.
+  pub fn memory_limit_pages(mut self, pages: u32) -> Self {
+    self.memory_limit_pages = Some(pages);
+    self
+  }
+
+  /// Interrupt the guest calls performing more than `max_ops` operations, a deterministic bound
+  /// on their execution.
+  ///
+  /// wasm3 has no instruction metering: the module is instrumented instead, every function call
+  /// and every loop iteration of the guest counts as one operation. The interrupted calls fail
+  /// with [`Error::OpsDeadlineExceeded`](errors::Error::OpsDeadlineExceeded), whose message
+  /// matches the deadline errors of the wasmtime provider. Unlike
+  /// [`call_deadline`](Self::call_deadline), the runtime and the state of the guest are kept.
+  pub fn max_ops_per_call(mut self, max_ops: u64) -> Self {
+    self.max_ops_per_call = Some(max_ops);
+    self
+  }
+
+  /// Link the WASI functions of wasm3, along with the overrides of the provider. Enabled by
+  /// default.
+  ///
+  /// When disabled, nothing is linked into the WASI namespaces: a module importing WASI
+  /// functions fails the initialization with
+  /// [`Error::MissingRequiredImport`](errors::Error::MissingRequiredImport), unless they are
+  /// provided by [`Wasm3EngineProviderBuilder::link_closure`].
+  pub fn enable_wasi(mut self, enabled: bool) -> Self {
+    self.enable_wasi = enabled;
+    self
+  }
+}
+
 /// [Wasm3EngineProvider] implements the [WebAssemblyEngineProvider] trait and normalizes the interface to the wasm3 engine.
 #[must_use]
 #[allow(missing_debug_implementations)]
 pub struct Wasm3EngineProvider {
   inner: Option<InnerProvider>,
-  modbytes: Mutex<Vec<u8>>,
+  // the host of the initialized provider, used to instantiate the module again when a call with
+  // a deadline lost its runtime
+  host: Option<Arc<dyn GuestHost>>,
+  worker: Option<DeadlineWorker>,
+  modbytes: Arc<Vec<u8>>,
+  config: ProviderConfig,
+  // largest linear memory of the runtimes replaced since the initialization
+  replaced_peak_memory_bytes: u64,
 }
 
 impl Wasm3EngineProvider {
   /// Instantiate a new wasm3 provider with the supplied wasm module.
+  ///
+  /// Refer to [Wasm3EngineProviderBuilder] to configure the provider.
   pub fn new(buf: &[u8]) -> Wasm3EngineProvider {
+    Self::from_bytes(buf.to_vec())
+  }
+
+  /// Instantiate a new wasm3 provider with the supplied wasm module, without copying it.
+  ///
+  /// wasm3 consumes the parsed module when loading it into a runtime: the bytes are kept, and
+  /// parsed again, to create the runtimes used by [`replace`](WebAssemblyEngineProvider::replace)
+  /// and by the calls exceeding their deadline.
+  pub fn from_bytes(module_bytes: Vec<u8>) -> Wasm3EngineProvider {
+    Self::from_config(Arc::new(module_bytes), ProviderConfig::default())
+  }
+
+  /// Instantiate a new wasm3 provider with the supplied wasm module and runtime options.
+  ///
+  /// The options are validated when the provider is initialized by the waPC host.
+  pub fn new_with_options(buf: &[u8], options: Wasm3Options) -> Wasm3EngineProvider {
+    Self::from_config(
+      Arc::new(buf.to_vec()),
+      ProviderConfig {
+        options,
+        ..Default::default()
+      },
+    )
+  }
+
+  /// Instantiate a new wasm3 provider with the supplied wasm module, exposing the command line
+  /// arguments and the environment variables of `wasi_params` to the guest.
+  ///
+  /// Preopened directories and stdio policies other than [`wapc::StdioPolicy::Inherit`] are not
+  /// supported: the initialization of the provider fails when they are requested.
+  pub fn new_with_wasi(buf: &[u8], wasi_params: WasiParams) -> Wasm3EngineProvider {
+    Self::from_config(
+      Arc::new(buf.to_vec()),
+      ProviderConfig {
+        wasi_params: Some(wasi_params),
+        ..Default::default()
+      },
+    )
+  }
+
+  pub(crate) fn from_config(modbytes: Arc<Vec<u8>>, config: ProviderConfig) -> Wasm3EngineProvider {
     Wasm3EngineProvider {
       inner: None,
-      modbytes: Mutex::new(buf.to_vec()),
+      host: None,
+      worker: None,
+      modbytes,
+      config,
+      replaced_peak_memory_bytes: 0,
+    }
+  }
+
+  // Instantiate the module, its waPC functions read and update the state of `host`
+  pub(crate) fn init_with(&mut self, host: Arc<dyn GuestHost>) -> errors::Result<()> {
+    info!("Initializing Wasm3 Engine");
+
+    let inner = instantiate(&self.modbytes, host.clone(), &self.config)?;
+    self.inner = Some(inner);
+    self.host = Some(host);
+    self.replaced_peak_memory_bytes = 0;
+
+    Ok(())
+  }
+
+  /// The bytes of the module, shared with the providers cloned from this one
+  #[must_use]
+  pub fn module_bytes(&self) -> &[u8] {
+    &self.modbytes
+  }
+
+  /// Memory used by the guest, `None` until the provider has been initialized by the waPC host.
+  ///
+  /// The entries of [`stats`](WebAssemblyEngineProvider::stats) prefixed by `memory.` report
+  /// the same values.
+  #[must_use]
+  pub fn memory_stats(&self) -> Option<MemoryStats> {
+    let memory_size_bytes = self.inner.as_ref()?.memory_size_bytes();
+    Some(MemoryStats {
+      stack_size_bytes: self.config.options.stack_size_bytes,
+      memory_limit_bytes: self
+        .config
+        .options
+        .memory_limit_pages
+        .map(|pages| u64::from(pages) * memory_limit::PAGE_SIZE_BYTES),
+      memory_size_bytes,
+      peak_memory_size_bytes: memory_size_bytes.max(self.replaced_peak_memory_bytes),
+    })
+  }
+
+  // Remember the memory used by a runtime about to be replaced
+  fn record_replaced_memory(&mut self, inner: &InnerProvider) {
+    self.replaced_peak_memory_bytes = self.replaced_peak_memory_bytes.max(inner.memory_size_bytes());
+  }
+
+  // Run the guest call on the worker thread, the runtime is abandoned once `deadline` is exceeded
+  fn call_with_deadline(
+    &mut self,
+    deadline: Duration,
+    op_length: i32,
+    msg_length: i32,
+  ) -> Result<i32, Box<dyn Error + Send + Sync + 'static>> {
+    let inner = match self.inner.take() {
+      Some(inner) => inner,
+      // the runtime of the previous call has been abandoned and could not be replaced
+      None => {
+        let host = self.host.clone().ok_or(errors::Error::NotInitialized)?;
+        instantiate(&self.modbytes, host, &self.config)?
+      }
+    };
+    let worker = match self.worker.take() {
+      Some(worker) => worker,
+      None => match DeadlineWorker::spawn() {
+        Ok(worker) => worker,
+        Err(e) => {
+          self.inner = Some(inner);
+          return Err(e.into());
+        }
+      },
+    };
+    // the memory used by the guest is unknown once the call is abandoned
+    self.record_replaced_memory(&inner);
+    let host = inner.host.clone();
+
+    let call = DeadlineCall {
+      inner,
+      op_length,
+      msg_length,
+    };
+    if let Err(mpsc::SendError(call)) = worker.calls.send(call) {
+      self.inner = Some(call.inner);
+      return Err(errors::Error::WorkerTerminated.into());
+    }
+    match worker.results.recv_timeout(deadline) {
+      Ok((call, res)) => {
+        self.inner = Some(call.inner);
+        self.worker = Some(worker);
+        Ok(res?)
+      }
+      Err(mpsc::RecvTimeoutError::Timeout) => {
+        error!(
+          "Guest call exceeded its deadline of {:?}, replacing the runtime",
+          deadline
+        );
+        // the worker is dropped along with the abandoned call, its thread exits once the guest
+        // stops. A failed instantiation is retried by the next call
+        host.revoke();
+        self.inner = instantiate(&self.modbytes, host.host, &self.config)
+          .map_err(|e| error!("Could not replace the runtime of the abandoned call: {}", e))
+          .ok();
+        Err(errors::Error::CallDeadlineExceeded(deadline).into())
+      }
+      // the guest call panicked along with the worker thread, the runtime is lost
+      Err(mpsc::RecvTimeoutError::Disconnected) => Err(errors::Error::WorkerTerminated.into()),
+    }
+  }
+}
+
+/// Creates an uninitialized provider, sharing the module bytes and the configuration of this
+/// one. The state of the guest is not copied.
+///
+/// The providers cannot be moved across threads, refer to [`Wasm3EngineProviderPre`] to create
+/// them from the factory of a pool of waPC hosts.
+impl Clone for Wasm3EngineProvider {
+  fn clone(&self) -> Self {
+    Self::from_config(self.modbytes.clone(), self.config.clone())
+  }
+}
+
+// The settings used to instantiate the modules
+#[derive(Clone)]
+pub(crate) struct ProviderConfig {
+  pub(crate) options: Wasm3Options,
+  pub(crate) wasi_params: Option<WasiParams>,
+  pub(crate) strict_imports: bool,
+  pub(crate) required_imports: Vec<String>,
+  pub(crate) start_functions: Vec<String>,
+  pub(crate) host_functions: Vec<HostFunction>,
+}
+
+impl Default for ProviderConfig {
+  fn default() -> Self {
+    Self {
+      options: Wasm3Options::default(),
+      wasi_params: None,
+      strict_imports: false,
+      required_imports: DEFAULT_REQUIRED_IMPORTS.iter().map(|s| (*s).to_owned()).collect(),
+      start_functions: wapc_functions::REQUIRED_STARTS
+        .iter()
+        .map(|s| (*s).to_owned())
+        .collect(),
+      host_functions: Vec::new(),
     }
   }
 }
 
+type GuestCallFn = Function<'static, (i32, i32), i32>;
+
+// The functions exported by a module instrumented by `metering::instrument`
+struct Meter {
+  max_ops: u64,
+  set_ops_left_fn: Function<'static, i64, ()>,
+  ops_left_fn: Function<'static, (), i64>,
+}
+
+impl Meter {
+  fn reset(&self) -> errors::Result<()> {
+    self
+      .set_ops_left_fn
+      .call(i64::try_from(self.max_ops).unwrap_or(i64::MAX))
+      .map_err(|e| errors::Error::Wasm3(e.to_string()))
+  }
+
+  fn exhausted(&self) -> bool {
+    matches!(self.ops_left_fn.call(), Ok(0))
+  }
+}
+
 struct InnerProvider {
-  rt: Runtime,
+  // declared before the runtime they borrow, hence dropped first
+  guest_call_fn: GuestCallFn,
+  meter: Option<Meter>,
+  rt: Box<Runtime>,
+  host: RevocableHost,
+  memory_limit_pages: Option<u32>,
+}
+
+impl InnerProvider {
+  fn new(rt: Runtime, host: RevocableHost, options: &Wasm3Options) -> errors::Result<Self> {
+    let rt = Box::new(rt);
+    let guest_call_fn = find_guest_call_fn(&rt)?;
+    let meter = options
+      .max_ops_per_call
+      .map(|max_ops| find_meter(&rt, max_ops))
+      .transpose()?;
+    Ok(Self {
+      guest_call_fn,
+      meter,
+      rt,
+      host,
+      memory_limit_pages: options.memory_limit_pages,
+    })
+  }
+
+  fn memory_size_bytes(&self) -> u64 {
+    self.rt.memory().len() as u64
+  }
+
+  // Name of the waPC operation of the current call
+  fn operation(&self) -> String {
+    self
+      .host
+      .host
+      .get_guest_request()
+      .map(|invocation| invocation.operation)
+      .unwrap_or_default()
+  }
+
+  fn call(&self, op_length: i32, msg_length: i32) -> errors::Result<i32> {
+    if let Some(meter) = &self.meter {
+      meter.reset()?;
+    }
+    self.guest_call_fn.call(op_length, msg_length).map_err(|e| {
+      let msg = self.host.trap_message(&e);
+      if let Some(meter) = self.meter.as_ref().filter(|meter| meter.exhausted()) {
+        error!("Guest call exceeded its limit of {} operations", meter.max_ops);
+        return errors::Error::OpsDeadlineExceeded(meter.max_ops);
+      }
+      match self.memory_limit_pages {
+        // the guest most likely trapped because it could not allocate memory
+        Some(limit) if self.memory_size_bytes() >= u64::from(limit) * memory_limit::PAGE_SIZE_BYTES => {
+          errors::Error::GuestOutOfMemory(format!(
+            "guest trapped with its linear memory at the limit of {} pages: {}",
+            limit, msg
+          ))
+        }
+        _ => errors::Error::Trap {
+          operation: self.operation(),
+          message: msg,
+        },
+      }
+    })
+  }
+}
+
+// Resolve `__guest_call` once per runtime instead of once per call
+#[allow(unsafe_code)]
+fn find_guest_call_fn(rt: &Runtime) -> errors::Result<GuestCallFn> {
+  let func = rt
+    .find_function::<(i32, i32), i32>(wapc_functions::GUEST_CALL)
+    .map_err(|_| errors::Error::GuestCallNotFound)?;
+  // SAFETY: the function borrows the runtime, which is boxed by `InnerProvider`: its address
+  // doesn't change when the provider is moved. The function is dropped before the runtime and
+  // never handed out of `InnerProvider`.
+  Ok(unsafe { std::mem::transmute::<Function<'_, (i32, i32), i32>, GuestCallFn>(func) })
+}
+
+// Resolve the functions added by the metering instrumentation, refer to `find_guest_call_fn`
+#[allow(unsafe_code)]
+fn find_meter(rt: &Runtime, max_ops: u64) -> errors::Result<Meter> {
+  let missing = |_| errors::Error::Wasm3("the metering functions cannot be found".to_owned());
+  let set_ops_left_fn = rt
+    .find_function::<i64, ()>(metering::SET_OPS_LEFT_FN)
+    .map_err(missing)?;
+  let ops_left_fn = rt.find_function::<(), i64>(metering::OPS_LEFT_FN).map_err(missing)?;
+  // SAFETY: same as `find_guest_call_fn`, the functions are owned by `InnerProvider`
+  Ok(unsafe {
+    Meter {
+      max_ops,
+      set_ops_left_fn: std::mem::transmute::<Function<'_, i64, ()>, Function<'static, i64, ()>>(set_ops_left_fn),
+      ops_left_fn: std::mem::transmute::<Function<'_, (), i64>, Function<'static, (), i64>>(ops_left_fn),
+    }
+  })
+}
+
+// Moves a runtime to the thread running a guest call with a deadline
+struct DeadlineCall {
+  inner: InnerProvider,
+  op_length: i32,
+  msg_length: i32,
+}
+
+// SAFETY: the runtime is never accessed by two threads at once: the provider waits for the call
+// to complete before taking the runtime back, or abandons it for good once the deadline is
+// exceeded. The wasm3 environment of the runtime is not shared with any other runtime.
+#[allow(unsafe_code)]
+unsafe impl Send for DeadlineCall {}
+
+// The long-lived thread running the guest calls with a deadline. The runtime of every call is
+// sent back along with its result
+struct DeadlineWorker {
+  calls: mpsc::Sender<DeadlineCall>,
+  results: mpsc::Receiver<(DeadlineCall, errors::Result<i32>)>,
+}
+
+impl DeadlineWorker {
+  fn spawn() -> std::io::Result<Self> {
+    let (calls, pending) = mpsc::channel::<DeadlineCall>();
+    let (completed, results) = mpsc::channel();
+    std::thread::Builder::new()
+      .name("wasm3-guest-call".to_owned())
+      .spawn(move || {
+        for call in pending {
+          let res = call.inner.call(call.op_length, call.msg_length);
+          if completed.send((call, res)).is_err() {
+            break;
+          }
+        }
+      })?;
+    Ok(Self { calls, results })
+  }
+}
+
+// The host the waPC functions of a runtime are bound to. Once revoked, the waPC functions trap
+// instead of reaching the host: an abandoned guest cannot interfere with the next calls
+#[derive(Clone)]
+struct RevocableHost {
+  host: Arc<dyn GuestHost>,
+  revoked: Arc<AtomicBool>,
+  // details of the last trap raised by a waPC function, wasm3 traps carry no message
+  trap_details: Arc<Mutex<Option<String>>>,
+}
+
+impl RevocableHost {
+  fn new(host: Arc<dyn GuestHost>) -> Self {
+    Self {
+      host,
+      revoked: Arc::new(AtomicBool::new(false)),
+      trap_details: Arc::new(Mutex::new(None)),
+    }
+  }
+
+  // Record the details of the guest memory access rejected by a waPC function
+  fn trap(&self, err: &callbacks::CallbackError) -> Trap {
+    error!("Guest memory access rejected: {}", err);
+    if let Ok(mut details) = self.trap_details.lock() {
+      *details = Some(err.to_string());
+    }
+    err.trap()
+  }
+
+  // Describe the error of a guest function, along with the details of the trap raised by a
+  // waPC function if any
+  fn trap_message(&self, err: &wasm3::error::Error) -> String {
+    match self.trap_details.lock().ok().and_then(|mut details| details.take()) {
+      Some(details) => format!("{}: {}", err, details),
+      None => err.to_string(),
+    }
+  }
+
+  fn live(&self) -> Result<&dyn GuestHost, Trap> {
+    if self.revoked.load(Ordering::Acquire) {
+      return Err(Trap::Abort);
+    }
+    Ok(self.host.as_ref())
+  }
+
+  fn revoke(&self) {
+    self.revoked.store(true, Ordering::Release);
+  }
 }
 
 impl WebAssemblyEngineProvider for Wasm3EngineProvider {
-  // TODO: refactor to avoid skipping this lint
-  #[allow(clippy::too_many_lines)]
   fn init(&mut self, host: Arc<ModuleState>) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
-    info!("Initializing Wasm3 Engine");
+    Ok(self.init_with(host)?)
+  }
 
-    let env = match Environment::new() {
-      Ok(env) => env,
-      Err(e) => {
-        panic!("Could not create a wasm3 environment: {}.", e)
+  fn call(&mut self, op_length: i32, msg_length: i32) -> Result<i32, Box<dyn Error + Send + Sync + 'static>> {
+    if let Some(deadline) = self.config.options.call_deadline {
+      return self.call_with_deadline(deadline, op_length, msg_length);
+    }
+    let inner = self.inner.as_ref().ok_or(errors::Error::NotInitialized)?;
+    Ok(inner.call(op_length, msg_length)?)
+  }
+
+  fn replace(&mut self, bytes: &[u8]) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    info!(
+      "HOT SWAP - Replacing existing WebAssembly module with new buffer, {} bytes",
+      bytes.len()
+    );
+
+    // The new module is fully initialized before being swapped in: the current one keeps
+    // working when anything fails
+    if let Some(i) = self.inner.take() {
+      match instantiate(bytes, i.host.host.clone(), &self.config) {
+        Ok(inner) => {
+          self.record_replaced_memory(&i);
+          self.inner = Some(inner);
+        }
+        Err(e) => {
+          self.inner = Some(i);
+          return Err(errors::Error::ReplacementFailed(e.to_string()).into());
+        }
       }
-    };
-    let rt = env.create_runtime(1024 * 120).to_wapc()?;
-    let bytes = self.modbytes.lock();
-    let module = Module::parse(&env, bytes.as_ref()).to_wapc()?;
+    }
+    self.modbytes = Arc::new(bytes.to_vec());
+
+    Ok(())
+  }
+
+  fn stats(&self) -> HashMap<String, u64> {
+    self.memory_stats().as_ref().map(MemoryStats::stats).unwrap_or_default()
+  }
+}
+
+// Create a new runtime for the module found inside of `bytes`, link the waPC host functions
+// bound to `host` and invoke the starters of the module
+fn instantiate(bytes: &[u8], host: Arc<dyn GuestHost>, config: &ProviderConfig) -> errors::Result<InnerProvider> {
+  let stack_size_bytes = config.options.stack_size_bytes;
+  if !(MIN_STACK_SIZE_BYTES..=MAX_STACK_SIZE_BYTES).contains(&stack_size_bytes) {
+    error!("Invalid wasm3 runtime stack size: {} bytes", stack_size_bytes);
+    return Err(errors::Error::InvalidStackSize(stack_size_bytes));
+  }
+
+  if config.options.call_deadline.is_some() && config.options.max_ops_per_call.is_none() {
+    error!("A wasm3 call deadline requires a limit of operations per call");
+    return Err(errors::Error::UnboundedCallDeadline);
+  }
+
+  let mut bytes = Cow::Borrowed(bytes);
+  if let Some(limit_pages) = config.options.memory_limit_pages {
+    if !(1..=MAX_MEMORY_LIMIT_PAGES).contains(&limit_pages) {
+      error!("Invalid wasm3 linear memory limit: {} pages", limit_pages);
+      return Err(errors::Error::InvalidMemoryLimit(limit_pages));
+    }
+    bytes = Cow::Owned(memory_limit::limit_memory(&bytes, limit_pages)?);
+  }
+  if config.options.max_ops_per_call.is_some() {
+    bytes = Cow::Owned(metering::instrument(&bytes)?);
+  }
 
-    let mut module = rt.load_module(module).to_wapc()?;
+  let host = RevocableHost::new(host);
+  let env = Environment::new().map_err(|e| {
+    error!("Could not create a wasm3 environment: {}.", e);
+    errors::Error::Environment(e.to_string())
+  })?;
+  let rt = env
+    .create_runtime(stack_size_bytes)
+    .map_err(|e| errors::Error::RuntimeCreation(e.to_string()))?;
+  let module = Module::parse(&env, &bytes[..]).to_wapc()?;
+
+  let mut module = rt.load_module(module).to_wapc()?;
+  if config.options.enable_wasi {
     module.link_wasi().to_wapc()?;
-    let h = host.clone();
-    if let Err(_e) = module.link_closure(
+    if let Some(wasi_params) = &config.wasi_params {
+      wasi::link_wasi_params(&mut module, wasi_params)?;
+    }
+    // the output written by the guest is forwarded to the host log
+    wasi::link_fd_write(&mut module, &host.host);
+  }
+  let linked = link_wapc_functions(&mut module, &host);
+  host_functions::link(&mut module, &config.host_functions);
+  imports::check(&bytes, &linked, config)?;
+
+  // Fail the initialization if we can't find the guest call function
+  if let Err(_e) = module.find_function::<(i32, i32), i32>(wapc_functions::GUEST_CALL) {
+    error!("Could not find __guest_call function in WebAssembly module");
+    return Err(errors::Error::GuestCallNotFound);
+  }
+
+  // Invoke all the starters in order (if they exist)
+  for starter in &config.start_functions {
+    let func = module.find_function::<(), ()>(starter);
+    if let Ok(func) = func {
+      if let Err(e) = func.call() {
+        let err = host.trap_message(&e);
+        error!("Failed during invocation of starter function '{}': {}.", starter, err);
+        return Err(errors::Error::StarterFailed {
+          starter: starter.clone(),
+          err,
+        });
+      }
+    }
+  }
+
+  InnerProvider::new(rt, host, &config.options)
+}
+
+// Link the waPC host functions imported by `module`, they are bound to `host`. Returns the
+// names of the functions linked
+fn link_wapc_functions(module: &mut Module<'_>, host: &RevocableHost) -> Vec<&'static str> {
+  let mut linked = Vec::new();
+  let h = host.clone();
+  if module
+    .link_closure(
       HOST_NAMESPACE,
       wapc_functions::HOST_CALL,
       move |ctx: CallContext,
             (bd_ptr, bd_len, ns_ptr, ns_len, op_ptr, op_len, ptr, len): (i32, i32, i32, i32, i32, i32, i32, i32)|
             -> Result<i32, Trap> {
-        Ok(callbacks::host_call(
-          &ctx, bd_ptr, bd_len, ns_ptr, ns_len, op_ptr, op_len, ptr, len, &h,
-        ))
+        callbacks::host_call(
+          &ctx,
+          bd_ptr,
+          bd_len,
+          ns_ptr,
+          ns_len,
+          op_ptr,
+          op_len,
+          ptr,
+          len,
+          h.live()?,
+        )
+        .map_err(|e| h.trap(&e))
       },
-    ) {
-      warn!("Guest module did not import __host_call - functionality may be limited");
-    }
+    )
+    .is_ok()
+  {
+    linked.push(wapc_functions::HOST_CALL);
+  } else {
+    warn!("Guest module did not import __host_call - functionality may be limited");
+  }
 
-    let h = host.clone();
-    if let Err(_e) = module.link_closure(
+  let h = host.clone();
+  if module
+    .link_closure(
       HOST_NAMESPACE,
       wapc_functions::GUEST_REQUEST_FN,
       move |ctx: CallContext, (op_ptr, ptr): (i32, i32)| {
-        callbacks::guest_request(&ctx, op_ptr, ptr, &h);
-        Ok(())
+        callbacks::guest_request(&ctx, op_ptr, ptr, h.live()?).map_err(|e| h.trap(&e))
       },
-    ) {
-      error!("Module did not import __guest_request - will not work with waPC");
-      return Err("Module did not import __guest_request - will not work with waPC".into());
-    }
+    )
+    .is_ok()
+  {
+    linked.push(wapc_functions::GUEST_REQUEST_FN);
+  } else {
+    warn!("Module did not import {}", wapc_functions::GUEST_REQUEST_FN);
+  }
 
-    let h = host.clone();
-    if let Err(_e) = module.link_closure(
+  let h = host.clone();
+  if module
+    .link_closure(
       HOST_NAMESPACE,
       wapc_functions::HOST_CONSOLE_LOG,
       move |ctx: CallContext, (ptr, len): (i32, i32)| {
-        callbacks::console_log(&ctx, ptr, len, &h);
-        Ok(())
+        callbacks::console_log(&ctx, ptr, len, h.live()?).map_err(|e| h.trap(&e))
       },
-    ) {
-      warn!("Module did not import __console_log");
-    }
+    )
+    .is_ok()
+  {
+    linked.push(wapc_functions::HOST_CONSOLE_LOG);
+  } else {
+    warn!("Module did not import __console_log");
+  }
 
-    let h = host.clone();
-    if let Err(_e) = module.link_closure(
+  let h = host.clone();
+  if module
+    .link_closure(
       HOST_NAMESPACE,
       wapc_functions::HOST_RESPONSE_FN,
-      move |ctx: CallContext, ptr: i32| {
-        callbacks::host_response(&ctx, ptr, &h);
-        Ok(())
-      },
-    ) {
-      warn!("Module did not import __host_response");
-    }
+      move |ctx: CallContext, ptr: i32| callbacks::host_response(&ctx, ptr, h.live()?).map_err(|e| h.trap(&e)),
+    )
+    .is_ok()
+  {
+    linked.push(wapc_functions::HOST_RESPONSE_FN);
+  } else {
+    warn!("Module did not import __host_response");
+  }
 
-    let h = host.clone();
-    if let Err(_e) = module.link_closure(
+  let h = host.clone();
+  if module
+    .link_closure(
       HOST_NAMESPACE,
       wapc_functions::HOST_RESPONSE_LEN_FN,
-      move |ctx: CallContext, ()| -> Result<i32, Trap> { Ok(callbacks::host_response_length(&ctx, &h)) },
-    ) {
-      warn!("Module did not import __host_response_len");
-    }
+      move |ctx: CallContext, ()| -> Result<i32, Trap> { Ok(callbacks::host_response_length(&ctx, h.live()?)) },
+    )
+    .is_ok()
+  {
+    linked.push(wapc_functions::HOST_RESPONSE_LEN_FN);
+  } else {
+    warn!("Module did not import __host_response_len");
+  }
 
-    let h = host.clone();
-    if let Err(_e) = module.link_closure(
+  let h = host.clone();
+  if module
+    .link_closure(
       HOST_NAMESPACE,
       wapc_functions::GUEST_RESPONSE_FN,
       move |ctx: CallContext, (ptr, len): (i32, i32)| {
-        callbacks::guest_response(&ctx, ptr, len, &h);
-        Ok(())
+        callbacks::guest_response(&ctx, ptr, len, h.live()?).map_err(|e| h.trap(&e))
       },
-    ) {
-      error!("Module did not import __guest_response");
-      return Err("Module did not import __guest_response".into());
-    }
+    )
+    .is_ok()
+  {
+    linked.push(wapc_functions::GUEST_RESPONSE_FN);
+  } else {
+    warn!("Module did not import {}", wapc_functions::GUEST_RESPONSE_FN);
+  }
 
-    let h = host.clone();
-    if let Err(_e) = module.link_closure(
+  let h = host.clone();
+  if module
+    .link_closure(
       HOST_NAMESPACE,
       wapc_functions::GUEST_ERROR_FN,
       move |ctx: CallContext, (ptr, len): (i32, i32)| {
-        callbacks::guest_error(&ctx, ptr, len, &h);
-        Ok(())
+        callbacks::guest_error(&ctx, ptr, len, h.live()?).map_err(|e| h.trap(&e))
       },
-    ) {
-      error!("Module did not import __guest_error");
-      return Err("Module did not import __guest_error".into());
-    }
+    )
+    .is_ok()
+  {
+    linked.push(wapc_functions::GUEST_ERROR_FN);
+  } else {
+    warn!("Module did not import {}", wapc_functions::GUEST_ERROR_FN);
+  }
 
-    let h = host.clone();
-    if let Err(_e) = module.link_closure(
+  let h = host.clone();
+  if module
+    .link_closure(
       HOST_NAMESPACE,
       wapc_functions::HOST_ERROR_FN,
-      move |ctx: CallContext, ptr: i32| {
-        callbacks::host_error(&ctx, ptr, &h);
-        Ok(())
-      },
-    ) {
-      warn!("Module did not import __host_error");
-    }
+      move |ctx: CallContext, ptr: i32| callbacks::host_error(&ctx, ptr, h.live()?).map_err(|e| h.trap(&e)),
+    )
+    .is_ok()
+  {
+    linked.push(wapc_functions::HOST_ERROR_FN);
+  } else {
+    warn!("Module did not import __host_error");
+  }
 
-    let h = host;
-    if let Err(_e) = module.link_closure(
+  let h = host.clone();
+  if module
+    .link_closure(
       HOST_NAMESPACE,
       wapc_functions::HOST_ERROR_LEN_FN,
-      move |_ctx: CallContext, ()| -> Result<i32, Trap> { Ok(callbacks::host_error_length(&h)) },
-    ) {
-      warn!("Module did not import __host_error_len");
-    }
-
-    let _ = module.link_closure(
-      WASI_UNSTABLE,
-      "fd_write",
-      move |_ctx: CallContext, (_, _, _, _): (i32, i32, i32, i32)| -> Result<i32, Trap> {
-        warn!("Use of prohibited (WASI) fd_write function - suppressing output");
-        Ok(0)
-      },
-    ); // don't care if this function is missing
-
-    // Fail the initialization if we can't find the guest call function
-    if let Err(_e) = module.find_function::<(i32, i32), i32>(wapc_functions::GUEST_CALL) {
-      error!("Could not find __guest_call function in WebAssembly module");
-      return Err("Could not find __guest_call function in WebAssembly module".into());
-    }
-
-    // Invoke all the starters in order (if they exist)
-    for starter in wapc_functions::REQUIRED_STARTS.iter() {
-      let func = module.find_function::<(), ()>(starter);
-      if let Ok(func) = func {
-        if let Err(e) = func.call() {
-          error!("Failed during invocation of starter function '{}': {}.", starter, e);
-          return Err(format!("Failed during starter initialization '{}': {}", starter, e).into());
-        }
-      }
-    }
-
-    self.inner = Some(InnerProvider { rt });
-
-    Ok(())
+      move |_ctx: CallContext, ()| -> Result<i32, Trap> { Ok(callbacks::host_error_length(h.live()?)) },
+    )
+    .is_ok()
+  {
+    linked.push(wapc_functions::HOST_ERROR_LEN_FN);
+  } else {
+    warn!("Module did not import __host_error_len");
   }
 
-  fn call(&mut self, op_length: i32, msg_length: i32) -> Result<i32, Box<dyn Error + Send + Sync + 'static>> {
-    if let Some(ref i) = self.inner {
-      let func = i
-        .rt
-        .find_function::<(i32, i32), i32>(wapc_functions::GUEST_CALL)
-        .to_wapc()?;
-      let res = func.call(op_length, msg_length).to_wapc()?;
-      Ok(res)
-    } else {
-      Err("Module call failure - no module was initialized".into())
-    }
-  }
-
-  fn replace(&mut self, _bytes: &[u8]) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
-    unimplemented!()
-  }
+  linked
 }
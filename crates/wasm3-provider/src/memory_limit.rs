@@ -0,0 +1,50 @@
+use crate::binary::{write_leb128, Reader, HEADER_LEN, MEMORY_SECTION_ID};
+use crate::errors;
+
+/// Size in bytes of a page of linear memory
+pub(crate) const PAGE_SIZE_BYTES: u64 = 64 * 1024;
+/// Largest number of pages of linear memory accepted by
+/// [`Wasm3Options::memory_limit_pages`](crate::Wasm3Options::memory_limit_pages), 4 GiB
+pub const MAX_MEMORY_LIMIT_PAGES: u32 = 65536;
+
+// wasm3 has no setting bounding the linear memory of a runtime: the maximum declared by the
+// memory section of the module is lowered to the limit instead, wasm3 fails the `memory.grow`
+// instructions going above it. The memories imported by the module are left untouched
+pub(crate) fn limit_memory(bytes: &[u8], limit_pages: u32) -> errors::Result<Vec<u8>> {
+  let malformed = || errors::Error::Wasm3("malformed memory section".to_owned());
+
+  let mut reader = Reader::new(bytes.get(HEADER_LEN..).ok_or_else(malformed)?);
+  let mut limited = bytes[..HEADER_LEN].to_vec();
+  while !reader.is_empty() {
+    let section = reader.section().ok_or_else(malformed)?;
+    if section.id != MEMORY_SECTION_ID {
+      limited.extend_from_slice(section.raw);
+      continue;
+    }
+
+    let mut content = Vec::new();
+    let mut memories = Reader::new(section.content);
+    let count = memories.leb128().ok_or_else(malformed)?;
+    write_leb128(&mut content, count);
+    for _ in 0..count {
+      let mut limits = memories.limits().ok_or_else(malformed)?;
+      if limits.min > u64::from(limit_pages) {
+        return Err(errors::Error::MemoryLimitExceeded {
+          initial_pages: limits.min,
+          limit_pages,
+        });
+      }
+      limits.max = Some(
+        limits
+          .max
+          .map_or(u64::from(limit_pages), |max| max.min(u64::from(limit_pages))),
+      );
+      limits.encode(&mut content);
+    }
+
+    limited.push(MEMORY_SECTION_ID);
+    write_leb128(&mut limited, content.len() as u64);
+    limited.extend_from_slice(&content);
+  }
+  Ok(limited)
+}
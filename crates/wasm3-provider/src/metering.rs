@@ -0,0 +1,289 @@
+use crate::binary::{
+  write_leb128,
+  Reader,
+  CODE_SECTION_ID,
+  EXPORT_SECTION_ID,
+  FUNCTION_SECTION_ID,
+  GLOBAL_SECTION_ID,
+  HEADER_LEN,
+  IMPORT_KIND_FUNC,
+  IMPORT_KIND_GLOBAL,
+  IMPORT_SECTION_ID,
+  TYPE_SECTION_ID,
+};
+use crate::errors;
+
+// Exported by the instrumented module: sets the number of operations left
+pub(crate) const SET_OPS_LEFT_FN: &str = "__wapc_metering_set_ops_left";
+// Exported by the instrumented module: returns the number of operations left
+pub(crate) const OPS_LEFT_FN: &str = "__wapc_metering_ops_left";
+
+const OP_UNREACHABLE: u8 = 0x00;
+const OP_LOOP: u8 = 0x03;
+const OP_IF: u8 = 0x04;
+const OP_END: u8 = 0x0b;
+const OP_LOCAL_GET: u8 = 0x20;
+const OP_GLOBAL_GET: u8 = 0x23;
+const OP_GLOBAL_SET: u8 = 0x24;
+const OP_I64_CONST: u8 = 0x42;
+const OP_I64_EQZ: u8 = 0x50;
+const OP_I64_SUB: u8 = 0x7d;
+
+const BLOCK_TYPE_EMPTY: u8 = 0x40;
+const FUNC_TYPE: u8 = 0x60;
+const VAL_TYPE_I64: u8 = 0x7e;
+const GLOBAL_MUTABLE: u8 = 0x01;
+const EXPORT_KIND_FUNC: u8 = 0x00;
+
+// wasm3 has no instruction metering: the module is rewritten instead. A mutable i64 global
+// holds the number of operations left, it is decremented on entry of every function and at the
+// start of every loop iteration, and the guest hits `unreachable` once it reaches zero. The
+// module exports two functions reading and setting the global, it starts with no limit
+pub(crate) fn instrument(bytes: &[u8]) -> errors::Result<Vec<u8>> {
+  instrument_module(bytes)
+    .ok_or_else(|| errors::Error::Wasm3("cannot meter the module: malformed or unsupported code".to_owned()))
+}
+
+fn instrument_module(bytes: &[u8]) -> Option<Vec<u8>> {
+  let mut reader = Reader::new(bytes.get(HEADER_LEN..)?);
+  let mut sections: Vec<(u8, Vec<u8>)> = Vec::new();
+  while !reader.is_empty() {
+    let section = reader.section()?;
+    sections.push((section.id, section.content.to_vec()));
+  }
+
+  let (imported_funcs, imported_globals) = imported_counts(&sections)?;
+  let types = entry_count(&sections, TYPE_SECTION_ID)?;
+  let set_ops_left_fn = imported_funcs + entry_count(&sections, FUNCTION_SECTION_ID)?;
+  let counter = imported_globals + entry_count(&sections, GLOBAL_SECTION_ID)?;
+
+  let code = instrument_code(&sections, counter)?;
+  if let Some((_, content)) = sections.iter_mut().find(|(id, _)| *id == CODE_SECTION_ID) {
+    *content = code;
+  }
+
+  append_entries(
+    &mut sections,
+    TYPE_SECTION_ID,
+    &[vec![FUNC_TYPE, 1, VAL_TYPE_I64, 0], vec![FUNC_TYPE, 0, 1, VAL_TYPE_I64]],
+  )?;
+  append_entries(&mut sections, FUNCTION_SECTION_ID, &[leb128(types), leb128(types + 1)])?;
+  // no limit until the host sets one: -1 takes 2^64 decrements to reach zero
+  append_entries(
+    &mut sections,
+    GLOBAL_SECTION_ID,
+    &[vec![VAL_TYPE_I64, GLOBAL_MUTABLE, OP_I64_CONST, 0x7f, OP_END]],
+  )?;
+  append_entries(
+    &mut sections,
+    EXPORT_SECTION_ID,
+    &[
+      export(SET_OPS_LEFT_FN, set_ops_left_fn),
+      export(OPS_LEFT_FN, set_ops_left_fn + 1),
+    ],
+  )?;
+  let mut set_ops_left = vec![0, OP_LOCAL_GET, 0, OP_GLOBAL_SET];
+  write_leb128(&mut set_ops_left, counter);
+  set_ops_left.push(OP_END);
+  let mut ops_left = vec![0, OP_GLOBAL_GET];
+  write_leb128(&mut ops_left, counter);
+  ops_left.push(OP_END);
+  append_entries(&mut sections, CODE_SECTION_ID, &[sized(set_ops_left), sized(ops_left)])?;
+
+  let mut instrumented = bytes[..HEADER_LEN].to_vec();
+  for (id, content) in sections {
+    instrumented.push(id);
+    write_leb128(&mut instrumented, content.len() as u64);
+    instrumented.extend_from_slice(&content);
+  }
+  Some(instrumented)
+}
+
+// Number of functions and globals imported by the module, they come first in their index spaces
+fn imported_counts(sections: &[(u8, Vec<u8>)]) -> Option<(u64, u64)> {
+  let (mut funcs, mut globals) = (0, 0);
+  if let Some((_, content)) = sections.iter().find(|(id, _)| *id == IMPORT_SECTION_ID) {
+    let mut reader = Reader::new(content);
+    for _ in 0..reader.leb128()? {
+      match reader.import()?.kind {
+        IMPORT_KIND_FUNC => funcs += 1,
+        IMPORT_KIND_GLOBAL => globals += 1,
+        _ => {}
+      }
+    }
+  }
+  Some((funcs, globals))
+}
+
+fn entry_count(sections: &[(u8, Vec<u8>)], section_id: u8) -> Option<u64> {
+  sections
+    .iter()
+    .find(|(id, _)| *id == section_id)
+    .map_or(Some(0), |(_, content)| Reader::new(content).leb128())
+}
+
+// Append `entries` to the section, which is created when the module doesn't have it
+fn append_entries(sections: &mut Vec<(u8, Vec<u8>)>, section_id: u8, entries: &[Vec<u8>]) -> Option<()> {
+  let position = sections.iter().position(|(id, _)| *id == section_id);
+  let (count, existing) = match position {
+    Some(i) => {
+      let mut reader = Reader::new(&sections[i].1);
+      (reader.leb128()?, reader.remaining().to_vec())
+    }
+    None => (0, Vec::new()),
+  };
+
+  let mut content = leb128(count + entries.len() as u64);
+  content.extend_from_slice(&existing);
+  for entry in entries {
+    content.extend_from_slice(entry);
+  }
+
+  match position {
+    Some(i) => sections[i].1 = content,
+    None => {
+      // the custom sections can go anywhere, the others are ordered
+      let next = sections
+        .iter()
+        .position(|(id, _)| *id != 0 && section_order(*id) > section_order(section_id))
+        .unwrap_or(sections.len());
+      sections.insert(next, (section_id, content));
+    }
+  }
+  Some(())
+}
+
+const fn section_order(id: u8) -> u8 {
+  match id {
+    // the tag section comes before the global section
+    13 => 6,
+    6..=9 => id + 1,
+    // the data count section comes before the code section
+    12 => 11,
+    10 | 11 => id + 2,
+    _ => id,
+  }
+}
+
+// Meter the entry of every function and every loop iteration of the code section
+fn instrument_code(sections: &[(u8, Vec<u8>)], counter: u64) -> Option<Vec<u8>> {
+  let Some((_, content)) = sections.iter().find(|(id, _)| *id == CODE_SECTION_ID) else {
+    return Some(Vec::new());
+  };
+  let metering = metering_code(counter);
+  let mut reader = Reader::new(content);
+  let count = reader.leb128()?;
+  let mut code = leb128(count);
+
+  for _ in 0..count {
+    let size = reader.leb128()? as usize;
+    let mut body = Reader::new(reader.take(size)?);
+    let locals_start = body.remaining();
+    for _ in 0..body.leb128()? {
+      body.leb128()?;
+      body.byte()?;
+    }
+    let mut instrumented = locals_start[..locals_start.len() - body.remaining().len()].to_vec();
+    instrumented.extend_from_slice(&metering);
+
+    while !body.is_empty() {
+      let start = body.remaining();
+      let opcode = instruction(&mut body)?;
+      instrumented.extend_from_slice(&start[..start.len() - body.remaining().len()]);
+      if opcode == OP_LOOP {
+        instrumented.extend_from_slice(&metering);
+      }
+    }
+    code.extend_from_slice(&sized(instrumented));
+  }
+  Some(code)
+}
+
+// Trap when no operation is left, consume one otherwise
+fn metering_code(counter: u64) -> Vec<u8> {
+  let mut code = vec![OP_GLOBAL_GET];
+  write_leb128(&mut code, counter);
+  code.extend_from_slice(&[
+    OP_I64_EQZ,
+    OP_IF,
+    BLOCK_TYPE_EMPTY,
+    OP_UNREACHABLE,
+    OP_END,
+    OP_GLOBAL_GET,
+  ]);
+  write_leb128(&mut code, counter);
+  code.extend_from_slice(&[OP_I64_CONST, 1, OP_I64_SUB, OP_GLOBAL_SET]);
+  write_leb128(&mut code, counter);
+  code
+}
+
+// Skip an instruction along with its immediates, returns its opcode. None for the instructions
+// wasm3 doesn't support either: SIMD, exceptions, ...
+fn instruction(reader: &mut Reader<'_>) -> Option<u8> {
+  let opcode = reader.byte()?;
+  match opcode {
+    // no immediate
+    0x00 | 0x01 | 0x05 | 0x0b | 0x0f | 0x1a | 0x1b | 0x45..=0xc4 | 0xd1 => {}
+    // a single LEB128 immediate: block types, labels, indices, constants, ...
+    0x02..=0x04 | 0x0c | 0x0d | 0x10 | 0x12 | 0x20..=0x26 | 0x3f..=0x42 | 0xd0 | 0xd2 => {
+      reader.leb128()?;
+    }
+    // br_table: the labels and the default one
+    0x0e => {
+      for _ in 0..=reader.leb128()? {
+        reader.leb128()?;
+      }
+    }
+    // call_indirect and return_call_indirect: type and table, loads and stores: alignment and offset
+    0x11 | 0x13 | 0x28..=0x3e => {
+      reader.leb128()?;
+      reader.leb128()?;
+    }
+    // typed select
+    0x1c => {
+      let count = reader.leb128()? as usize;
+      reader.take(count)?;
+    }
+    0x43 => {
+      reader.take(4)?;
+    }
+    0x44 => {
+      reader.take(8)?;
+    }
+    // saturating truncations, bulk memory and table instructions
+    0xfc => match reader.leb128()? {
+      0..=7 => {}
+      9 | 11 | 13 | 15..=17 => {
+        reader.leb128()?;
+      }
+      8 | 10 | 12 | 14 => {
+        reader.leb128()?;
+        reader.leb128()?;
+      }
+      _ => return None,
+    },
+    _ => return None,
+  }
+  Some(opcode)
+}
+
+fn export(name: &str, func: u64) -> Vec<u8> {
+  let mut entry = leb128(name.len() as u64);
+  entry.extend_from_slice(name.as_bytes());
+  entry.push(EXPORT_KIND_FUNC);
+  write_leb128(&mut entry, func);
+  entry
+}
+
+// Prefix `bytes` with their size
+fn sized(bytes: Vec<u8>) -> Vec<u8> {
+  let mut sized = leb128(bytes.len() as u64);
+  sized.extend(bytes);
+  sized
+}
+
+fn leb128(value: u64) -> Vec<u8> {
+  let mut bytes = Vec::new();
+  write_leb128(&mut bytes, value);
+  bytes
+}
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use crate::{ProviderConfig, Wasm3EngineProvider};
+
+/// A pre initialized [`Wasm3EngineProvider`]
+///
+/// Can be used to quickly create new instances of [`Wasm3EngineProvider`], sharing the module
+/// bytes and the configuration. Unlike the providers, it can be moved across threads: use it
+/// inside of the factory of a pool of waPC hosts.
+///
+/// wasm3 consumes the parsed module when loading it into a runtime: each provider parses the
+/// module when it is initialized.
+///
+/// Refer to [`Wasm3EngineProviderBuilder::build_pre`](crate::Wasm3EngineProviderBuilder::build_pre)
+/// to create an instance of this struct.
+#[allow(missing_debug_implementations)]
+#[derive(Clone)]
+pub struct Wasm3EngineProviderPre {
+  modbytes: Arc<Vec<u8>>,
+  config: ProviderConfig,
+}
+
+impl Wasm3EngineProviderPre {
+  pub(crate) fn new(modbytes: Arc<Vec<u8>>, config: ProviderConfig) -> Self {
+    Self { modbytes, config }
+  }
+
+  /// The bytes of the module, shared with the providers created by this instance
+  #[must_use]
+  pub fn module_bytes(&self) -> &[u8] {
+    &self.modbytes
+  }
+
+  /// Create an instance of [`Wasm3EngineProvider`] ready to be consumed
+  pub fn rehydrate(&self) -> Wasm3EngineProvider {
+    Wasm3EngineProvider::from_config(self.modbytes.clone(), self.config.clone())
+  }
+
+  /// Create an instance of [`Wasm3EngineProviderAsync`](crate::Wasm3EngineProviderAsync) ready
+  /// to be consumed
+  #[cfg(feature = "async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+  pub fn rehydrate_async(&self) -> crate::Wasm3EngineProviderAsync {
+    crate::Wasm3EngineProviderAsync::from_config(self.modbytes.clone(), self.config.clone())
+  }
+}
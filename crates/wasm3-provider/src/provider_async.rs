@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{mpsc, Arc, Mutex};
+
+use async_trait::async_trait;
+use tokio::runtime::Handle;
+use tokio::sync::oneshot;
+use wapc::{Invocation, ModuleStateAsync, WebAssemblyEngineProviderAsync};
+
+use crate::errors;
+use crate::host::GuestHost;
+use crate::{ProviderConfig, Wasm3EngineProvider};
+
+type WorkerResult<T> = Result<T, Box<dyn Error + Send + Sync + 'static>>;
+
+// The requests handled by the thread running the wasm3 runtime
+enum Command {
+  Call {
+    op_length: i32,
+    msg_length: i32,
+    reply: oneshot::Sender<WorkerResult<i32>>,
+  },
+  Replace {
+    bytes: Vec<u8>,
+    reply: oneshot::Sender<WorkerResult<()>>,
+  },
+}
+
+/// [Wasm3EngineProviderAsync] implements the [WebAssemblyEngineProviderAsync] trait on top of
+/// a [Wasm3EngineProvider].
+///
+/// wasm3 runtimes cannot be moved across threads: each initialized provider owns a dedicated
+/// thread, running the blocking wasm3 calls, the async functions wait for it without blocking
+/// the async runtime. The provider must be initialized from within a tokio runtime: the waPC
+/// host functions invoked by the guest block the dedicated thread on it. With a
+/// `current_thread` runtime, the calls must be awaited from
+/// [`Runtime::block_on`](tokio::runtime::Runtime::block_on), as done by `#[tokio::main]` and
+/// `#[tokio::test]`, for the host callbacks to make progress.
+///
+/// Refer to [`Wasm3EngineProviderBuilder::build_async`](crate::Wasm3EngineProviderBuilder::build_async)
+/// to configure the provider.
+#[must_use]
+#[allow(missing_debug_implementations)]
+pub struct Wasm3EngineProviderAsync {
+  worker: Option<mpsc::Sender<Command>>,
+  modbytes: Arc<Vec<u8>>,
+  config: ProviderConfig,
+  // the stats of the provider run by the worker, updated after each request
+  stats: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+impl Wasm3EngineProviderAsync {
+  /// Instantiate a new async wasm3 provider with the supplied wasm module.
+  pub fn new(buf: &[u8]) -> Wasm3EngineProviderAsync {
+    Self::from_config(Arc::new(buf.to_vec()), ProviderConfig::default())
+  }
+
+  pub(crate) fn from_config(modbytes: Arc<Vec<u8>>, config: ProviderConfig) -> Wasm3EngineProviderAsync {
+    Wasm3EngineProviderAsync {
+      worker: None,
+      modbytes,
+      config,
+      stats: Arc::default(),
+    }
+  }
+
+  fn worker(&self) -> errors::Result<&mpsc::Sender<Command>> {
+    self.worker.as_ref().ok_or(errors::Error::NotInitialized)
+  }
+}
+
+// Run the provider until the async provider is dropped, or initialized again
+fn run_worker(
+  mut provider: Wasm3EngineProvider,
+  host: Arc<dyn GuestHost>,
+  init_reply: oneshot::Sender<WorkerResult<()>>,
+  commands: &mpsc::Receiver<Command>,
+  stats: &Mutex<HashMap<String, u64>>,
+) {
+  let update_stats = |provider: &Wasm3EngineProvider| {
+    if let Ok(mut stats) = stats.lock() {
+      *stats = wapc::WebAssemblyEngineProvider::stats(provider);
+    }
+  };
+
+  if let Err(e) = provider.init_with(host) {
+    let _ = init_reply.send(Err(e.into()));
+    return;
+  }
+  update_stats(&provider);
+  let _ = init_reply.send(Ok(()));
+
+  for command in commands {
+    match command {
+      Command::Call {
+        op_length,
+        msg_length,
+        reply,
+      } => {
+        let res = wapc::WebAssemblyEngineProvider::call(&mut provider, op_length, msg_length);
+        update_stats(&provider);
+        let _ = reply.send(res);
+      }
+      Command::Replace { bytes, reply } => {
+        let res = wapc::WebAssemblyEngineProvider::replace(&mut provider, &bytes);
+        update_stats(&provider);
+        let _ = reply.send(res);
+      }
+    }
+  }
+}
+
+#[async_trait]
+impl WebAssemblyEngineProviderAsync for Wasm3EngineProviderAsync {
+  async fn init(&mut self, host: Arc<ModuleStateAsync>) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    let host: Arc<dyn GuestHost> = Arc::new(AsyncGuestHost {
+      state: host,
+      handle: Handle::current(),
+    });
+    let provider_bytes = self.modbytes.clone();
+    let provider_config = self.config.clone();
+    let stats = self.stats.clone();
+    let (init_reply, init_result) = oneshot::channel();
+    let (sender, receiver) = mpsc::channel();
+
+    std::thread::Builder::new()
+      .name("wasm3-provider".to_owned())
+      .spawn(move || {
+        let provider = Wasm3EngineProvider::from_config(provider_bytes, provider_config);
+        run_worker(provider, host, init_reply, &receiver, &stats);
+      })?;
+
+    init_result.await.map_err(|_| errors::Error::WorkerTerminated)??;
+    self.worker = Some(sender);
+    Ok(())
+  }
+
+  async fn call(&mut self, op_length: i32, msg_length: i32) -> Result<i32, Box<dyn Error + Send + Sync + 'static>> {
+    let (reply, result) = oneshot::channel();
+    self
+      .worker()?
+      .send(Command::Call {
+        op_length,
+        msg_length,
+        reply,
+      })
+      .map_err(|_| errors::Error::WorkerTerminated)?;
+    result.await.map_err(|_| errors::Error::WorkerTerminated)?
+  }
+
+  async fn replace(&mut self, bytes: &[u8]) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    if let Some(worker) = &self.worker {
+      let (reply, result) = oneshot::channel();
+      worker
+        .send(Command::Replace {
+          bytes: bytes.to_vec(),
+          reply,
+        })
+        .map_err(|_| errors::Error::WorkerTerminated)?;
+      result.await.map_err(|_| errors::Error::WorkerTerminated)??;
+    }
+    self.modbytes = Arc::new(bytes.to_vec());
+
+    Ok(())
+  }
+
+  fn stats(&self) -> HashMap<String, u64> {
+    self.stats.lock().map(|stats| stats.clone()).unwrap_or_default()
+  }
+}
+
+// The state of an async waPC host, accessed from the thread running the wasm3 runtime. The
+// locks of the state are released before returning
+struct AsyncGuestHost {
+  state: Arc<ModuleStateAsync>,
+  handle: Handle,
+}
+
+impl GuestHost for AsyncGuestHost {
+  fn get_guest_request(&self) -> Option<Invocation> {
+    self.handle.block_on(self.state.get_guest_request())
+  }
+
+  fn get_host_response(&self) -> Option<Vec<u8>> {
+    self.handle.block_on(self.state.get_host_response())
+  }
+
+  fn set_guest_response(&self, response: Vec<u8>) {
+    self.handle.block_on(self.state.set_guest_response(response));
+  }
+
+  fn set_guest_error(&self, error: String) {
+    self.handle.block_on(self.state.set_guest_error(error));
+  }
+
+  fn get_host_error(&self) -> Option<String> {
+    self.handle.block_on(self.state.get_host_error())
+  }
+
+  fn do_host_call(
+    &self,
+    binding: &str,
+    namespace: &str,
+    operation: &str,
+    payload: &[u8],
+  ) -> Result<i32, Box<dyn Error>> {
+    self.handle.block_on(self.state.do_host_call(
+      binding.to_owned(),
+      namespace.to_owned(),
+      operation.to_owned(),
+      payload.to_vec(),
+    ))
+  }
+
+  fn do_console_log(&self, msg: &str) {
+    self.state.do_console_log(msg);
+  }
+}
@@ -0,0 +1,33 @@
+use std::collections::HashMap;
+
+/// Memory used by the guest of a provider, refer to
+/// [`Wasm3EngineProvider::memory_stats`](crate::Wasm3EngineProvider::memory_stats)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryStats {
+  /// Size in bytes of the runtime stack
+  pub stack_size_bytes: u32,
+  /// Size in bytes the linear memory can grow to, `None` when it's bounded only by the maximum
+  /// declared by the module
+  pub memory_limit_bytes: Option<u64>,
+  /// Current size in bytes of the linear memory
+  pub memory_size_bytes: u64,
+  /// Largest size in bytes of the linear memory since the provider was initialized, the
+  /// runtimes replaced by a hot swap or after a call deadline included. wasm3 linear memories
+  /// never shrink: for a single runtime this is the current size
+  pub peak_memory_size_bytes: u64,
+}
+
+impl MemoryStats {
+  // Entries of the provider `stats()` map
+  pub(crate) fn stats(&self) -> HashMap<String, u64> {
+    let mut stats = HashMap::from([
+      ("memory.stack_size_bytes".to_owned(), u64::from(self.stack_size_bytes)),
+      ("memory.size_bytes".to_owned(), self.memory_size_bytes),
+      ("memory.peak_size_bytes".to_owned(), self.peak_memory_size_bytes),
+    ]);
+    if let Some(limit) = self.memory_limit_bytes {
+      stats.insert("memory.limit_bytes".to_owned(), limit);
+    }
+    stats
+  }
+}
@@ -0,0 +1,175 @@
+use std::sync::Arc;
+
+use wapc::{StdioPolicy, WasiParams};
+use wasm3::error::Trap;
+use wasm3::{CallContext, Module};
+
+use crate::errors;
+use crate::host::GuestHost;
+
+/// The WASI namespaces whose functions are overridden
+pub(crate) const WASI_NAMESPACES: [&str; 2] = ["wasi_snapshot_preview1", crate::WASI_UNSTABLE];
+
+const ERRNO_SUCCESS: i32 = 0;
+const ERRNO_BADF: i32 = 8;
+const ERRNO_FAULT: i32 = 21;
+
+const STDOUT: i32 = 1;
+const STDERR: i32 = 2;
+
+// The WASI support of wasm3 cannot be configured: expose the arguments and the environment
+// variables of `wasi_params` by overriding the functions that read them
+pub(crate) fn link_wasi_params(module: &mut Module<'_>, wasi_params: &WasiParams) -> errors::Result<()> {
+  validate(wasi_params)?;
+
+  let args: Vec<Vec<u8>> = compute_args(wasi_params).into_iter().map(nul_terminated).collect();
+  let env: Vec<Vec<u8>> = compute_env(wasi_params)
+    .into_iter()
+    .map(|(key, value)| nul_terminated(format!("{}={}", key, value)))
+    .collect();
+
+  for namespace in WASI_NAMESPACES {
+    link_strings(module, namespace, "args_sizes_get", "args_get", &args);
+    link_strings(module, namespace, "environ_sizes_get", "environ_get", &env);
+  }
+
+  Ok(())
+}
+
+// Reject the WASI features the provider cannot offer
+pub(crate) fn validate(wasi_params: &WasiParams) -> errors::Result<()> {
+  if !wasi_params.preopened_dirs.is_empty() || !wasi_params.map_dirs.is_empty() {
+    return Err(errors::Error::WasiUnsupported("preopened directories".to_owned()));
+  }
+  if wasi_params.stdio != StdioPolicy::Inherit {
+    return Err(errors::Error::WasiUnsupported(
+      "stdio policies other than `Inherit`".to_owned(),
+    ));
+  }
+  Ok(())
+}
+
+// Link the pair of functions returning the sizes of `strings`, then their contents. The
+// functions not imported by the module are skipped
+fn link_strings(module: &mut Module<'_>, namespace: &str, sizes_fn: &str, get_fn: &str, strings: &[Vec<u8>]) {
+  let count = strings.len() as u32;
+  let buf_size: u32 = strings.iter().map(|s| s.len() as u32).sum();
+  let _ = module.link_closure(
+    namespace,
+    sizes_fn,
+    move |ctx: CallContext, (count_ptr, buf_size_ptr): (i32, i32)| -> Result<i32, Trap> {
+      let written = write_to_memory(&ctx, count_ptr as u32, &count.to_le_bytes())
+        && write_to_memory(&ctx, buf_size_ptr as u32, &buf_size.to_le_bytes());
+      Ok(if written { ERRNO_SUCCESS } else { ERRNO_FAULT })
+    },
+  );
+
+  let strings = strings.to_vec();
+  let _ = module.link_closure(
+    namespace,
+    get_fn,
+    move |ctx: CallContext, (ptrs_ptr, buf_ptr): (i32, i32)| -> Result<i32, Trap> {
+      let mut ptr = buf_ptr as u32;
+      for (i, s) in strings.iter().enumerate() {
+        let written = write_to_memory(&ctx, (ptrs_ptr as u32).wrapping_add(i as u32 * 4), &ptr.to_le_bytes())
+          && write_to_memory(&ctx, ptr, s);
+        if !written {
+          return Ok(ERRNO_FAULT);
+        }
+        ptr = ptr.wrapping_add(s.len() as u32);
+      }
+      Ok(ERRNO_SUCCESS)
+    },
+  );
+}
+
+// Forward the lines written by the guest to its standard output and error to the console log
+// of `host`. The functions not imported by the module are skipped
+pub(crate) fn link_fd_write(module: &mut Module<'_>, host: &Arc<dyn GuestHost>) {
+  for namespace in WASI_NAMESPACES {
+    let h = host.clone();
+    let _ = module.link_closure(
+      namespace,
+      "fd_write",
+      move |ctx: CallContext, (fd, iovs_ptr, iovs_len, nwritten_ptr): (i32, i32, i32, i32)| -> Result<i32, Trap> {
+        if fd != STDOUT && fd != STDERR {
+          return Ok(ERRNO_BADF);
+        }
+        let Some(bytes) = read_iovecs(&ctx, iovs_ptr as u32, iovs_len as u32) else {
+          return Ok(ERRNO_FAULT);
+        };
+        for line in String::from_utf8_lossy(&bytes).lines() {
+          h.do_console_log(line);
+        }
+        let written = write_to_memory(&ctx, nwritten_ptr as u32, &(bytes.len() as u32).to_le_bytes());
+        Ok(if written { ERRNO_SUCCESS } else { ERRNO_FAULT })
+      },
+    );
+  }
+}
+
+// Concatenate the buffers described by the `len` iovecs found at `ptr`, returns None when any
+// of them is out of the bounds of the guest memory
+fn read_iovecs(ctx: &CallContext, ptr: u32, len: u32) -> Option<Vec<u8>> {
+  #[allow(unsafe_code)]
+  let memory = unsafe { &*ctx.memory() };
+
+  let mut bytes = Vec::new();
+  for i in 0..len as usize {
+    let iovec = ptr as usize + i * 8;
+    let buf = u32::from_le_bytes(memory.get(iovec..iovec + 4)?.try_into().ok()?) as usize;
+    let buf_len = u32::from_le_bytes(memory.get(iovec + 4..iovec + 8)?.try_into().ok()?) as usize;
+    bytes.extend_from_slice(memory.get(buf..buf + buf_len)?);
+  }
+  Some(bytes)
+}
+
+// Copy `slice` into the guest memory, returns false when it doesn't fit
+fn write_to_memory(ctx: &CallContext, ptr: u32, slice: &[u8]) -> bool {
+  #[allow(unsafe_code)]
+  let memory = unsafe { &mut *ctx.memory_mut() };
+  let start = ptr as usize;
+
+  memory
+    .get_mut(start..start + slice.len())
+    .map(|dest| dest.copy_from_slice(slice))
+    .is_some()
+}
+
+fn nul_terminated(s: String) -> Vec<u8> {
+  let mut bytes = s.into_bytes();
+  bytes.push(0);
+  bytes
+}
+
+// Combine the environment of the host process, when inherited, with the variables explicitly
+// provided. The latter take precedence over the inherited ones
+fn compute_env(wasi_params: &WasiParams) -> Vec<(String, String)> {
+  let mut env: Vec<(String, String)> = Vec::new();
+
+  if wasi_params.inherit_env {
+    env.extend(std::env::vars_os().filter_map(|(key, value)| {
+      let key = key.into_string().ok()?;
+      let value = value.into_string().ok()?;
+      let allowed = wasi_params
+        .env_allowlist
+        .as_ref()
+        .is_none_or(|allowlist| allowlist.contains(&key));
+      let overridden = wasi_params.env_vars.iter().any(|(explicit, _)| *explicit == key);
+      (allowed && !overridden).then_some((key, value))
+    }));
+  }
+  env.extend(wasi_params.env_vars.iter().cloned());
+
+  env
+}
+
+// The command line arguments of the host process are used only when no argument has been
+// explicitly provided
+fn compute_args(wasi_params: &WasiParams) -> Vec<String> {
+  if wasi_params.inherit_args && wasi_params.argv.is_empty() {
+    std::env::args_os().filter_map(|arg| arg.into_string().ok()).collect()
+  } else {
+    wasi_params.argv.clone()
+  }
+}
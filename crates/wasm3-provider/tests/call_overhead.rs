@@ -0,0 +1,77 @@
+use std::fs::read;
+use std::time::{Duration, Instant};
+
+use wapc::{errors, wapc_functions, WapcHost};
+use wapc_codec::messagepack::{deserialize, serialize};
+use wasm3::{Environment, Module};
+use wasm3_provider::Wasm3EngineProvider;
+
+const MODULE: &str = "../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm";
+const ROUNDS: u32 = 1_000;
+const LOOKUP_ROUNDS: u32 = 100_000;
+
+// A guest exporting only a `__guest_call` function, which doesn't need any waPC import
+const GUEST_CALL_WAT: &str = r#"(module
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    i32.const 1))"#;
+
+// Average time spent by `call`, after warming it up
+fn average_call_time(mut call: impl FnMut() -> i32) -> Duration {
+  for _ in 0..100 {
+    std::hint::black_box(call());
+  }
+  let start = Instant::now();
+  for _ in 0..LOOKUP_ROUNDS {
+    std::hint::black_box(call());
+  }
+  start.elapsed() / LOOKUP_ROUNDS
+}
+
+// Average time spent by a call to the echo guest, the `__guest_call` function is resolved
+// once per module instead of once per call
+#[test]
+fn echo_call_overhead() -> Result<(), errors::Error> {
+  let engine = Wasm3EngineProvider::from_bytes(read(MODULE)?);
+  let host = WapcHost::new(Box::new(engine), Some(Box::new(|_, _, _, _, _| Ok(vec![]))))?;
+  let payload = serialize("hello world").unwrap();
+
+  // warm up
+  host.call("echo", &payload)?;
+
+  let start = Instant::now();
+  for _ in 0..ROUNDS {
+    host.call("echo", &payload)?;
+  }
+  let average = start.elapsed() / ROUNDS;
+  println!("average echo call time: {average:?}");
+
+  let result: String = deserialize(&host.call("echo", &payload)?).unwrap();
+  assert_eq!(result, "hello world");
+  assert!(average < Duration::from_millis(50));
+  Ok(())
+}
+
+// Compare resolving `__guest_call` once per runtime, as the provider does, with resolving it
+// before every call
+#[test]
+fn cached_guest_call_is_not_slower_than_a_lookup_per_call() {
+  let env = Environment::new().unwrap();
+  let rt = env.create_runtime(64 * 1024).unwrap();
+  let wasm = wat::parse_str(GUEST_CALL_WAT).unwrap();
+  rt.load_module(Module::parse(&env, &wasm[..]).unwrap()).unwrap();
+
+  let uncached = average_call_time(|| {
+    rt.find_function::<(i32, i32), i32>(wapc_functions::GUEST_CALL)
+      .unwrap()
+      .call(0, 0)
+      .unwrap()
+  });
+  let func = rt.find_function::<(i32, i32), i32>(wapc_functions::GUEST_CALL).unwrap();
+  let cached = average_call_time(|| func.call(0, 0).unwrap());
+  println!("average __guest_call time: {cached:?} cached, {uncached:?} looked up on every call");
+
+  assert!(
+    cached <= uncached,
+    "the cached function ({cached:?}) is slower than a lookup per call ({uncached:?})"
+  );
+}
@@ -0,0 +1,67 @@
+use std::fs::read;
+use std::sync::Arc;
+
+use wapc::{ModuleState, WapcHost, WebAssemblyEngineProvider};
+use wasm3_provider::errors::Error;
+use wasm3_provider::Wasm3EngineProviderBuilder;
+
+const WASM_BASIC: &str = "../../wasm/crates/wasm-basic/build/wasm_basic.wasm";
+const WASI_BASIC: &str = "../../wasm/crates/wasi-basic/build/wasi_basic.wasm";
+
+fn create_host(path: &str, enable_wasi: bool) -> Result<WapcHost, Box<dyn std::error::Error + Send + Sync>> {
+  let buf = read(path)?;
+  let engine = Wasm3EngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .enable_wasi(enable_wasi)
+    .build()?;
+  Ok(WapcHost::new(
+    Box::new(engine),
+    Some(Box::new(move |_a, _b, _c, _d, _e| Ok(vec![]))),
+  )?)
+}
+
+#[test]
+fn wasm_basic_runs_with_or_without_wasi() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+  for enable_wasi in [true, false] {
+    let host = create_host(WASM_BASIC, enable_wasi)?;
+    assert_eq!(host.call("ping", b"hello")?, b"hello");
+  }
+  Ok(())
+}
+
+#[test]
+fn wasi_basic_runs_with_wasi() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+  let host = create_host(WASI_BASIC, true)?;
+  assert_eq!(host.call("ping", b"hello")?, b"hello");
+  Ok(())
+}
+
+#[test]
+fn wasi_basic_is_rejected_without_wasi() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+  let buf = read(WASI_BASIC)?;
+  let mut engine = Wasm3EngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .enable_wasi(false)
+    .build()?;
+
+  let err = engine.init(Arc::new(ModuleState::with_callback(None))).unwrap_err();
+  match err.downcast_ref::<Error>() {
+    Some(Error::MissingRequiredImport(problems)) => {
+      assert!(problems.contains(&"wasi_snapshot_preview1.fd_write is imported but WASI is disabled".to_owned()));
+    }
+    _ => panic!("the module should have been rejected: {}", err),
+  }
+  Ok(())
+}
+
+#[test]
+fn wasi_params_require_wasi() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+  let buf = read(WASM_BASIC)?;
+  let result = Wasm3EngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .enable_wasi(false)
+    .wasi_params(wapc::WasiParams::default())
+    .build();
+  assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+  Ok(())
+}
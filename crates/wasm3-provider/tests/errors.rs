@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use wapc::{ModuleState, WapcHost, WebAssemblyEngineProvider};
+use wasm3_provider::errors::Error;
+use wasm3_provider::Wasm3EngineProvider;
+
+// Doesn't import the waPC functions required by the protocol
+const NO_IMPORTS_GUEST: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (i32.const 1)))
+"#;
+
+// Imports the waPC functions but doesn't export `__guest_call`
+const NO_GUEST_CALL_GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_request" (func $guest_request (param i32 i32)))
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (import "wapc" "__guest_error" (func $guest_error (param i32 i32)))
+  (memory (export "memory") 1))
+"#;
+
+// Traps on every call
+const TRAPPING_GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_request" (func $guest_request (param i32 i32)))
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (import "wapc" "__guest_error" (func $guest_error (param i32 i32)))
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (unreachable)))
+"#;
+
+// Traps inside of a helper function on every call
+const NESTED_TRAP_GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_request" (func $guest_request (param i32 i32)))
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (import "wapc" "__guest_error" (func $guest_error (param i32 i32)))
+  (memory (export "memory") 1)
+  (func $explode
+    (unreachable))
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (call $explode)
+    (i32.const 1)))
+"#;
+
+// Traps in its starter
+const TRAPPING_STARTER_GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_request" (func $guest_request (param i32 i32)))
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (import "wapc" "__guest_error" (func $guest_error (param i32 i32)))
+  (memory (export "memory") 1)
+  (func (export "wapc_init")
+    (unreachable))
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (i32.const 1)))
+"#;
+
+fn init(wat: &str) -> Result<Wasm3EngineProvider, Error> {
+  let mut engine = Wasm3EngineProvider::new(&wat::parse_str(wat).unwrap());
+  engine
+    .init(Arc::new(ModuleState::with_callback(None)))
+    .map_err(|e| *e.downcast::<Error>().unwrap())?;
+  Ok(engine)
+}
+
+#[test]
+fn missing_required_import() {
+  let result = init(NO_IMPORTS_GUEST);
+  match result {
+    Err(Error::MissingRequiredImport(problems)) => assert_eq!(
+      problems,
+      [
+        "__guest_request is not imported",
+        "__guest_response is not imported",
+        "__guest_error is not imported"
+      ]
+    ),
+    _ => panic!("the module should have been rejected"),
+  }
+}
+
+#[test]
+fn guest_call_not_found() {
+  let result = init(NO_GUEST_CALL_GUEST);
+  assert!(matches!(result, Err(Error::GuestCallNotFound)));
+}
+
+#[test]
+fn guest_trap() -> Result<(), Error> {
+  let mut engine = init(TRAPPING_GUEST)?;
+  let err = engine.call(0, 0).unwrap_err();
+  assert!(matches!(err.downcast_ref::<Error>(), Some(Error::Trap { .. })));
+  Ok(())
+}
+
+#[test]
+fn guest_trap_names_the_operation() {
+  let engine = Wasm3EngineProvider::new(&wat::parse_str(NESTED_TRAP_GUEST).unwrap());
+  let host = WapcHost::new(Box::new(engine), None).unwrap();
+
+  let err = host.call("explode_now", b"payload").unwrap_err();
+  assert!(
+    err.to_string().contains("while executing operation 'explode_now'"),
+    "{}",
+    err
+  );
+}
+
+#[test]
+fn starter_trap() {
+  let result = init(TRAPPING_STARTER_GUEST);
+  match result {
+    Err(Error::StarterFailed { starter, .. }) => assert_eq!(starter, "wapc_init"),
+    _ => panic!("the starter should have trapped"),
+  }
+}
+
+#[test]
+fn call_before_init() {
+  let mut engine = Wasm3EngineProvider::new(&wat::parse_str(TRAPPING_GUEST).unwrap());
+  let err = engine.call(0, 0).unwrap_err();
+  assert!(matches!(err.downcast_ref::<Error>(), Some(Error::NotInitialized)));
+}
@@ -0,0 +1,59 @@
+use std::sync::Mutex;
+
+use wapc::{errors, WapcHost};
+use wasm3_provider::Wasm3EngineProvider;
+
+// Prints two lines to its standard output, then replies with the number of bytes written
+const PRINTING_GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_request" (func $guest_request (param i32 i32)))
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (import "wapc" "__guest_error" (func $guest_error (param i32 i32)))
+  (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 1024) "hello from\nthe guest\n")
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    ;; iovec: buffer at 1024, 21 bytes long
+    (i32.store (i32.const 0) (i32.const 1024))
+    (i32.store (i32.const 4) (i32.const 21))
+    (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))
+    (call $guest_response (i32.const 8) (i32.const 4))
+    (i32.const 1)))
+"#;
+
+// Keeps the messages logged by the console of the guests
+struct Logger(Mutex<Vec<String>>);
+
+impl log::Log for Logger {
+  fn enabled(&self, _metadata: &log::Metadata<'_>) -> bool {
+    true
+  }
+
+  fn log(&self, record: &log::Record<'_>) {
+    if record.target().starts_with("wapc") {
+      self.0.lock().unwrap().push(record.args().to_string());
+    }
+  }
+
+  fn flush(&self) {}
+}
+
+static LOGGER: Logger = Logger(Mutex::new(Vec::new()));
+
+#[test]
+fn stdout_is_forwarded_to_the_host_log() -> Result<(), errors::Error> {
+  log::set_logger(&LOGGER).unwrap();
+  log::set_max_level(log::LevelFilter::Info);
+
+  let buf = wat::parse_str(PRINTING_GUEST).unwrap();
+  let engine = Wasm3EngineProvider::new(&buf);
+  let host = WapcHost::new(Box::new(engine), None)?;
+
+  let callresult = host.call("print", b"")?;
+  assert_eq!(callresult, 21_u32.to_le_bytes());
+
+  let messages = LOGGER.0.lock().unwrap();
+  assert!(messages.iter().any(|m| m.ends_with(": hello from")), "{messages:?}");
+  assert!(messages.iter().any(|m| m.ends_with(": the guest")), "{messages:?}");
+  Ok(())
+}
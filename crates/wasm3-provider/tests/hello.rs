@@ -6,7 +6,9 @@ use wapc::WapcHost;
 fn create_guest(path: &str) -> Result<WapcHost, Error> {
   let buf = read(path)?;
 
-  let engine = wasm3_provider::Wasm3EngineProvider::new(&buf);
+  let engine = wasm3_provider::Wasm3EngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .build()?;
 
   WapcHost::new(Box::new(engine), Some(Box::new(move |_a, _b, _c, _d, _e| Ok(vec![]))))
 }
@@ -40,3 +42,72 @@ fn runs_hello_zig() -> Result<(), Error> {
   assert_eq!(result, "Hello, this is a test!");
   Ok(())
 }
+
+#[test]
+fn runs_wapc_timeout() -> Result<(), Error> {
+  let buf = read("../../wasm/crates/wapc-guest-timeout/build/wapc_guest_timeout.wasm")?;
+  let engine = wasm3_provider::Wasm3EngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .call_deadline(std::time::Duration::from_secs(2))
+    .max_ops_per_call(1_000_000_000)
+    .build()?;
+  let guest = WapcHost::new(Box::new(engine), Some(Box::new(move |_a, _b, _c, _d, _e| Ok(vec![]))))?;
+
+  let callresult = guest.call("sleep", b"1")?;
+  let result = String::from_utf8_lossy(&callresult);
+  assert_eq!(result, "slept for 1 seconds");
+
+  let callresult = guest.call("sleep", b"10");
+  let err = callresult.expect_err("a timeout error was supposed to happen");
+  assert_eq!(
+    err.to_string(),
+    "Guest call failure: guest code interrupted, func execution deadline of 2s exceeded".to_string()
+  );
+
+  // the runtime has been replaced, the guest keeps working
+  let callresult = guest.call("sleep", b"1")?;
+  let result = String::from_utf8_lossy(&callresult);
+  assert_eq!(result, "slept for 1 seconds");
+  Ok(())
+}
+
+#[cfg(feature = "async")]
+async fn create_guest_async(path: &str) -> Result<wapc::WapcHostAsync, Error> {
+  let buf = read(path)?;
+
+  let engine = wasm3_provider::Wasm3EngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .build_async()?;
+  let host_callback: Box<wapc::HostCallbackAsync> = Box::new(|_id, bd, ns, op, payload| {
+    Box::pin(async move {
+      assert_eq!(bd, "binding");
+      assert_eq!(ns, "sample:namespace");
+      assert_eq!(op, "pong");
+      Ok(payload)
+    })
+  });
+
+  wapc::WapcHostAsync::new(Box::new(engine), Some(host_callback)).await
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn runs_hello_async() -> Result<(), Error> {
+  let guest = create_guest_async("../../wasm/crates/wasm-basic/build/wasm_basic.wasm").await?;
+  let payload = "this is a test";
+  let callresult = guest.call("ping", payload.as_bytes()).await?;
+  let result = String::from_utf8_lossy(&callresult);
+  assert_eq!(result, payload);
+  Ok(())
+}
+
+#[cfg(feature = "async")]
+#[tokio::test(flavor = "multi_thread")]
+async fn runs_hello_zig_async() -> Result<(), Error> {
+  let guest = create_guest_async("../../wasm/hello_zig.wasm").await?;
+
+  let callresult = guest.call("hello", b"this is a test").await?;
+  let result = String::from_utf8_lossy(&callresult);
+  assert_eq!(result, "Hello, this is a test!");
+  Ok(())
+}
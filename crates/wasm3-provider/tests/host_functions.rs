@@ -0,0 +1,56 @@
+use wapc::WapcHost;
+use wasm3_provider::errors::Error;
+use wasm3_provider::Wasm3EngineProviderBuilder;
+
+// Responds with the sum of the lengths of the operation and of the payload, computed by the
+// host
+const ADDING_GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_request" (func $guest_request (param i32 i32)))
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (import "wapc" "__guest_error" (func $guest_error (param i32 i32)))
+  (import "env" "add" (func $add (param i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param $op_len i32) (param $msg_len i32) (result i32)
+    (i32.store (i32.const 0) (call $add (local.get $op_len) (local.get $msg_len)))
+    (call $guest_response (i32.const 0) (i32.const 4))
+    (i32.const 1)))
+"#;
+
+#[test]
+fn guest_calls_custom_host_function() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+  let module_bytes = wat::parse_str(ADDING_GUEST)?;
+  let engine = Wasm3EngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .link_closure("env", "add", |_ctx, (a, b): (i32, i32)| Ok(a + b))
+    .build()?;
+  let host = WapcHost::new(Box::new(engine), None)?;
+
+  assert_eq!(host.call("op", b"12345")?, 7_i32.to_le_bytes());
+
+  // the function is linked into the runtime of the new module too
+  host.replace_module(&module_bytes)?;
+  assert_eq!(host.call("op", b"1")?, 3_i32.to_le_bytes());
+  Ok(())
+}
+
+#[test]
+fn wapc_namespace_is_reserved() {
+  let module_bytes = wat::parse_str(ADDING_GUEST).unwrap();
+  let result = Wasm3EngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .link_closure("wapc", "__host_call", |_ctx, (): ()| Ok(0_i32))
+    .build();
+  assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+}
+
+#[test]
+fn duplicate_host_function() {
+  let module_bytes = wat::parse_str(ADDING_GUEST).unwrap();
+  let result = Wasm3EngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .link_closure("env", "add", |_ctx, (a, b): (i32, i32)| Ok(a + b))
+    .link_closure("env", "add", |_ctx, (a, b): (i32, i32)| Ok(a - b))
+    .build();
+  assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+}
@@ -0,0 +1,56 @@
+use std::fs::read;
+use std::time::{Duration, Instant};
+
+use wapc::{errors, WapcHost};
+use wasm3_provider::{Wasm3EngineProvider, Wasm3EngineProviderBuilder};
+
+const MODULE: &str = "../../wasm/crates/wasm-calc-hash/module1/build/module1_hash.wasm";
+const ROUNDS: u32 = 10;
+
+// Average time spent initializing a host, the module is a few hundred KB
+fn average_init_time(create_engine: impl Fn() -> Wasm3EngineProvider) -> Result<Duration, errors::Error> {
+  let start = Instant::now();
+  for _ in 0..ROUNDS {
+    WapcHost::new(Box::new(create_engine()), Some(Box::new(|_, _, _, _, _| Ok(vec![]))))?;
+  }
+  Ok(start.elapsed() / ROUNDS)
+}
+
+#[test]
+fn init_time_with_and_without_sharing_the_module() -> Result<(), errors::Error> {
+  let module_bytes = read(MODULE)?;
+  let pre = Wasm3EngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .build_pre()?;
+
+  // each provider copies the module, then parses it when initialized
+  let copied = average_init_time(|| Wasm3EngineProvider::new(&module_bytes))?;
+  // the providers share the module of `pre`, they only parse it when initialized
+  let shared = average_init_time(|| pre.rehydrate())?;
+  println!("average init time: {copied:?} copying the module, {shared:?} sharing it");
+
+  assert!(!std::ptr::eq(
+    Wasm3EngineProvider::new(&module_bytes).module_bytes(),
+    module_bytes.as_slice()
+  ));
+  let provider = pre.rehydrate();
+  assert!(std::ptr::eq(provider.module_bytes(), pre.module_bytes()));
+  assert!(std::ptr::eq(provider.clone().module_bytes(), pre.module_bytes()));
+  Ok(())
+}
+
+#[test]
+fn shared_module_is_parsed_only_when_initialized() -> Result<(), errors::Error> {
+  let pre = Wasm3EngineProviderBuilder::new()
+    .module_bytes(b"not a wasm module")
+    .build_pre()?;
+
+  // creating and cloning the providers doesn't parse the module
+  let provider = pre.rehydrate();
+  let clone = provider.clone();
+  assert!(provider.memory_stats().is_none());
+
+  assert!(WapcHost::new(Box::new(provider), None).is_err());
+  assert!(WapcHost::new(Box::new(clone), None).is_err());
+  Ok(())
+}
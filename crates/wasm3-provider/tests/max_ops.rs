@@ -0,0 +1,110 @@
+use std::sync::Arc;
+
+use wapc::{ModuleState, WebAssemblyEngineProvider};
+use wasm3_provider::errors::Error;
+use wasm3_provider::Wasm3EngineProviderBuilder;
+
+// Loops `msg_len` times, forever when the payload is empty
+const LOOPING_GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_request" (func $guest_request (param i32 i32)))
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (import "wapc" "__guest_error" (func $guest_error (param i32 i32)))
+  (memory (export "memory") 1)
+  (func $step (param $i i32) (result i32)
+    (i32.add (local.get $i) (i32.const 1)))
+  (func (export "__guest_call") (param $op_len i32) (param $msg_len i32) (result i32)
+    (local $i i32)
+    (loop $forever
+      (br_if $forever (i32.eqz (local.get $msg_len))))
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $msg_len)))
+        (local.set $i (call $step (local.get $i)))
+        (br $next)))
+    (i32.const 1)))
+"#;
+
+fn init_looping(max_ops: u64) -> Result<impl WebAssemblyEngineProvider, Box<dyn std::error::Error + Send + Sync>> {
+  let module_bytes = wat::parse_str(LOOPING_GUEST)?;
+  let mut engine = Wasm3EngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .max_ops_per_call(max_ops)
+    .build()?;
+  engine.init(Arc::new(ModuleState::with_callback(None)))?;
+  Ok(engine)
+}
+
+#[test]
+fn infinite_loop_is_interrupted() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+  let mut engine = init_looping(10_000)?;
+
+  let err = engine.call(0, 0).unwrap_err();
+  let err = err.downcast_ref::<Error>().unwrap();
+  assert!(matches!(err, Error::OpsDeadlineExceeded(10_000)), "{:?}", err);
+  assert!(err
+    .to_string()
+    .starts_with("guest code interrupted, func execution deadline of"));
+
+  // the runtime is kept and the next call gets a fresh budget
+  assert_eq!(engine.call(0, 100)?, 1);
+  Ok(())
+}
+
+#[test]
+fn budget_applies_to_every_call() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+  // each iteration costs a loop iteration and a call
+  let mut engine = init_looping(1_000)?;
+
+  for _ in 0..10 {
+    assert_eq!(engine.call(0, 400)?, 1);
+  }
+  let err = engine.call(0, 600).unwrap_err();
+  assert!(matches!(
+    err.downcast_ref::<Error>(),
+    Some(Error::OpsDeadlineExceeded(1_000))
+  ));
+  Ok(())
+}
+
+#[test]
+fn zero_ops_is_rejected() {
+  let module_bytes = wat::parse_str(LOOPING_GUEST).unwrap();
+  let result = Wasm3EngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .max_ops_per_call(0)
+    .build();
+  assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+}
+
+#[test]
+fn call_deadline_requires_max_ops() {
+  let module_bytes = wat::parse_str(LOOPING_GUEST).unwrap();
+  let result = Wasm3EngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .call_deadline(std::time::Duration::from_millis(100))
+    .build();
+  assert!(matches!(result, Err(Error::UnboundedCallDeadline)));
+}
+
+#[test]
+fn abandoned_call_is_replaced() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+  let module_bytes = wat::parse_str(LOOPING_GUEST)?;
+  let mut engine = Wasm3EngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .call_deadline(std::time::Duration::from_millis(100))
+    .max_ops_per_call(100_000_000)
+    .build()?;
+  engine.init(Arc::new(ModuleState::with_callback(None)))?;
+
+  assert_eq!(engine.call(0, 100)?, 1);
+  let err = engine.call(0, 0).unwrap_err();
+  assert!(matches!(
+    err.downcast_ref::<Error>(),
+    Some(Error::CallDeadlineExceeded(_))
+  ));
+  // the next calls run on a new runtime and a new worker thread
+  assert_eq!(engine.call(0, 100)?, 1);
+  assert_eq!(engine.call(0, 100)?, 1);
+  Ok(())
+}
@@ -0,0 +1,73 @@
+use wapc::{errors, WapcHost};
+use wasm3_provider::Wasm3EngineProvider;
+
+// Invokes the waPC function selected by the operation name with pointers out of the bounds
+// of its single page of memory
+const BAD_POINTERS_GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_request" (func $guest_request (param i32 i32)))
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (import "wapc" "__guest_error" (func $guest_error (param i32 i32)))
+  (import "wapc" "__console_log" (func $console_log (param i32 i32)))
+  (import "wapc" "__host_call" (func $host_call (param i32 i32 i32 i32 i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param $op_len i32) (param $msg_len i32) (result i32)
+    (call $guest_request (i32.const 0) (i32.const 0x100))
+    (block $done
+      (br_if $done (i32.ne (i32.load8_u (i32.const 0)) (i32.const 0x72)))
+      ;; "request": the payload is written at the very end of the memory
+      (call $guest_request (i32.const 0) (i32.const 0xfffe))
+      (return (i32.const 1)))
+    (block $done
+      (br_if $done (i32.ne (i32.load8_u (i32.const 0)) (i32.const 0x6c)))
+      ;; "log": negative length
+      (call $console_log (i32.const 0x100) (i32.const -1))
+      (return (i32.const 1)))
+    (block $done
+      (br_if $done (i32.ne (i32.load8_u (i32.const 0)) (i32.const 0x68)))
+      ;; "host_call": the operation starts after the end of the memory
+      (drop (call $host_call
+        (i32.const 0) (i32.const 0)
+        (i32.const 0) (i32.const 0)
+        (i32.const 0x10000) (i32.const 4)
+        (i32.const 0x100) (local.get $msg_len)))
+      (return (i32.const 1)))
+    ;; anything else: the response overlaps the end of the memory
+    (call $guest_response (i32.const 0xfff0) (i32.const 0x20))
+    (i32.const 1)))
+"#;
+
+fn create_host() -> Result<WapcHost, errors::Error> {
+  let engine = Wasm3EngineProvider::new(&wat::parse_str(BAD_POINTERS_GUEST).unwrap());
+  WapcHost::new(Box::new(engine), Some(Box::new(|_, _, _, _, _| Ok(vec![]))))
+}
+
+fn assert_trap(host: &WapcHost, operation: &str, details: &str) {
+  match host.call(operation, b"payload") {
+    Err(errors::Error::GuestCallFailure(msg)) => assert!(msg.contains(details), "{msg}"),
+    result => panic!("the call should have trapped: {result:?}"),
+  }
+}
+
+#[test]
+fn out_of_bounds_pointers_trap() -> Result<(), errors::Error> {
+  let host = create_host()?;
+
+  assert_trap(
+    &host,
+    "request",
+    "__guest_request payload pointer/length pair (65534, 7) is out of the bounds of the guest memory of 65536 bytes",
+  );
+  assert_trap(&host, "log", "__console_log pointer/length pair (256, -1)");
+  assert_trap(
+    &host,
+    "host_call",
+    "__host_call operation pointer/length pair (65536, 4)",
+  );
+  assert_trap(
+    &host,
+    "guest_response",
+    "__guest_response pointer/length pair (65520, 32)",
+  );
+  Ok(())
+}
@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use wapc::{ModuleState, WebAssemblyEngineProvider};
+use wasm3_provider::errors::{Error, OUT_OF_MEMORY_PREFIX};
+use wasm3_provider::{Wasm3EngineProvider, Wasm3EngineProviderBuilder, DEFAULT_STACK_SIZE_BYTES};
+
+// Grows its memory one page at a time until it fails. Without payload the guest then traps,
+// otherwise it responds with the number of pages of its memory
+const GREEDY_GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_request" (func $guest_request (param i32 i32)))
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (import "wapc" "__guest_error" (func $guest_error (param i32 i32)))
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param $op_len i32) (param $msg_len i32) (result i32)
+    (block $full
+      (loop $grow
+        (br_if $full (i32.eq (memory.grow (i32.const 1)) (i32.const -1)))
+        (br $grow)))
+    (if (i32.eqz (local.get $msg_len))
+      (then unreachable))
+    (i32.store (i32.const 0) (memory.size))
+    (call $guest_response (i32.const 0) (i32.const 4))
+    (i32.const 1)))
+"#;
+
+const LARGE_GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_request" (func $guest_request (param i32 i32)))
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (import "wapc" "__guest_error" (func $guest_error (param i32 i32)))
+  (memory (export "memory") 8)
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (i32.const 1)))
+"#;
+
+fn init_greedy(
+  pages: u32,
+) -> Result<(Wasm3EngineProvider, Arc<ModuleState>), Box<dyn std::error::Error + Send + Sync>> {
+  let module_bytes = wat::parse_str(GREEDY_GUEST)?;
+  let mut engine = Wasm3EngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .memory_limit_pages(pages)
+    .build()?;
+  let state = Arc::new(ModuleState::with_callback(None));
+  engine.init(state.clone())?;
+  Ok((engine, state))
+}
+
+#[test]
+fn memory_cannot_grow_above_the_limit() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+  let (mut engine, state) = init_greedy(4)?;
+  assert_eq!(engine.call(0, 4)?, 1);
+  let pages = state.get_guest_response().unwrap();
+  assert_eq!(pages, 4_u32.to_le_bytes());
+
+  let stats = engine.memory_stats().unwrap();
+  assert_eq!(stats.memory_limit_bytes, Some(4 * 64 * 1024));
+  // the limit doesn't change the runtime stack
+  assert_eq!(stats.stack_size_bytes, DEFAULT_STACK_SIZE_BYTES);
+  Ok(())
+}
+
+#[test]
+fn trap_at_the_limit_is_reported_as_out_of_memory() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+  let (mut engine, _state) = init_greedy(2)?;
+
+  let err = engine.call(0, 0).unwrap_err();
+  let err = err.downcast_ref::<Error>().unwrap();
+  assert!(matches!(err, Error::GuestOutOfMemory(_)), "{:?}", err);
+  assert!(err.to_string().starts_with(OUT_OF_MEMORY_PREFIX));
+  Ok(())
+}
+
+#[test]
+fn initial_memory_above_the_limit() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+  let module_bytes = wat::parse_str(LARGE_GUEST)?;
+  let mut engine = Wasm3EngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .memory_limit_pages(4)
+    .build()?;
+
+  let err = engine.init(Arc::new(ModuleState::with_callback(None))).unwrap_err();
+  let err = err.downcast_ref::<Error>().unwrap();
+  assert!(matches!(
+    err,
+    Error::MemoryLimitExceeded {
+      initial_pages: 8,
+      limit_pages: 4
+    }
+  ));
+  Ok(())
+}
+
+#[test]
+fn invalid_limit() {
+  let module_bytes = wat::parse_str(LARGE_GUEST).unwrap();
+  let result = Wasm3EngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .memory_limit_pages(0)
+    .build();
+  assert!(matches!(result, Err(Error::InvalidMemoryLimit(0))));
+}
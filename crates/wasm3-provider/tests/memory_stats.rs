@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use wapc::{ModuleState, WebAssemblyEngineProvider};
+use wasm3_provider::{Wasm3EngineProvider, DEFAULT_STACK_SIZE_BYTES};
+
+const PAGE_SIZE: u64 = 64 * 1024;
+
+// Allocates a buffer of 16 pages on every call
+const GROWING_GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_request" (func $guest_request (param i32 i32)))
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (import "wapc" "__guest_error" (func $guest_error (param i32 i32)))
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (drop (memory.grow (i32.const 16)))
+    (i32.const 1)))
+"#;
+
+#[test]
+fn memory_size_grows_with_the_guest_allocations() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+  let module_bytes = wat::parse_str(GROWING_GUEST)?;
+  let mut engine = Wasm3EngineProvider::new(&module_bytes);
+  assert!(engine.memory_stats().is_none());
+
+  engine.init(Arc::new(ModuleState::with_callback(None)))?;
+  let before = engine.memory_stats().unwrap();
+  assert_eq!(before.stack_size_bytes, DEFAULT_STACK_SIZE_BYTES);
+  assert_eq!(before.memory_size_bytes, PAGE_SIZE);
+
+  engine.call(0, 0)?;
+  let after = engine.memory_stats().unwrap();
+  assert_eq!(after.memory_size_bytes, 17 * PAGE_SIZE);
+  assert_eq!(after.peak_memory_size_bytes, 17 * PAGE_SIZE);
+
+  let stats = engine.stats();
+  assert_eq!(stats["memory.size_bytes"], 17 * PAGE_SIZE);
+  assert_eq!(stats["memory.stack_size_bytes"], u64::from(DEFAULT_STACK_SIZE_BYTES));
+
+  // the peak includes the runtimes replaced by a hot swap
+  engine.replace(&module_bytes)?;
+  let replaced = engine.memory_stats().unwrap();
+  assert_eq!(replaced.memory_size_bytes, PAGE_SIZE);
+  assert_eq!(replaced.peak_memory_size_bytes, 17 * PAGE_SIZE);
+  Ok(())
+}
@@ -0,0 +1,44 @@
+use std::fs::read;
+
+use futures::future::try_join_all;
+use wapc::WapcHost;
+use wapc_codec::messagepack::{deserialize, serialize};
+use wapc_pool::HostPoolBuilder;
+use wasm3_provider::Wasm3EngineProviderBuilder;
+
+#[tokio::test]
+async fn pool_of_wasm3_hosts() -> Result<(), Box<dyn std::error::Error>> {
+  let buf = read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm")?;
+
+  let pre = Wasm3EngineProviderBuilder::new().module_bytes(&buf).build_pre()?;
+  let mut pool = HostPoolBuilder::new()
+    .name("wasm3-test")
+    .factory(move || WapcHost::new(Box::new(pre.rehydrate()), None).unwrap())
+    .min_threads(2)
+    .max_threads(4)
+    .build()?;
+
+  let results =
+    try_join_all((0..20).map(|num| pool.call("echo", serialize(format!("hello world: {}", num)).unwrap()))).await?;
+  for (i, bytes) in results.iter().enumerate() {
+    let result: String = deserialize(bytes)?;
+    assert_eq!(result, format!("hello world: {}", i));
+  }
+
+  pool.shutdown()?;
+  Ok(())
+}
+
+#[test]
+fn cloned_provider_is_uninitialized() -> Result<(), wapc::errors::Error> {
+  let buf = read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm")?;
+  let engine = Wasm3EngineProviderBuilder::new().module_bytes(&buf).build()?;
+
+  let host = WapcHost::new(Box::new(engine.clone()), None)?;
+  let other = WapcHost::new(Box::new(engine), None)?;
+  for host in [host, other] {
+    let result: String = deserialize(&host.call("echo", &serialize("hello").unwrap())?).unwrap();
+    assert_eq!(result, "hello");
+  }
+  Ok(())
+}
@@ -0,0 +1,63 @@
+use wapc::{errors, WapcHost};
+use wasm3_provider::errors::Error;
+use wasm3_provider::{Wasm3EngineProvider, Wasm3EngineProviderBuilder, Wasm3Options};
+
+// Recurses 100000 times, then returns the depth reached
+const RECURSIVE_GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_request" (func $guest_request (param i32 i32)))
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (import "wapc" "__guest_error" (func $guest_error (param i32 i32)))
+  (memory (export "memory") 1)
+  (func $depth (param $n i32) (result i32)
+    (if (result i32) (i32.eqz (local.get $n))
+      (then (i32.const 0))
+      (else (i32.add (i32.const 1) (call $depth (i32.sub (local.get $n) (i32.const 1)))))))
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (i32.store (i32.const 0) (call $depth (i32.const 100000)))
+    (call $guest_response (i32.const 0) (i32.const 4))
+    (i32.const 1)))
+"#;
+
+fn create_guest(options: Wasm3Options) -> Result<WapcHost, errors::Error> {
+  let buf = wat::parse_str(RECURSIVE_GUEST).unwrap();
+  let engine = Wasm3EngineProvider::new_with_options(&buf, options);
+
+  WapcHost::new(Box::new(engine), Some(Box::new(move |_a, _b, _c, _d, _e| Ok(vec![]))))
+}
+
+#[test]
+fn default_stack_is_too_small_for_deep_recursion() -> Result<(), errors::Error> {
+  let guest = create_guest(Wasm3Options::default())?;
+
+  let result = guest.call("recurse", b"");
+  assert!(matches!(result, Err(errors::Error::GuestCallFailure(_))));
+  Ok(())
+}
+
+#[test]
+fn larger_stack_allows_deep_recursion() -> Result<(), errors::Error> {
+  let guest = create_guest(Wasm3Options::default().stack_size_bytes(1024 * 1024 * 16))?;
+
+  let callresult = guest.call("recurse", b"")?;
+  assert_eq!(callresult, 100_000_u32.to_le_bytes());
+  Ok(())
+}
+
+#[test]
+fn absurd_stack_sizes_are_rejected() {
+  for stack_size_bytes in [0, 16, u32::MAX] {
+    let result = create_guest(Wasm3Options::default().stack_size_bytes(stack_size_bytes));
+    assert!(matches!(result, Err(errors::Error::InitFailed(_))));
+  }
+}
+
+#[test]
+fn builder_rejects_absurd_stack_sizes() {
+  let buf = wat::parse_str(RECURSIVE_GUEST).unwrap();
+  let result = Wasm3EngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .stack_size(16)
+    .build();
+  assert!(matches!(result, Err(Error::InvalidStackSize(16))));
+}
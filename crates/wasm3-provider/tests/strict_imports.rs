@@ -0,0 +1,78 @@
+use std::fs::read;
+use std::sync::Arc;
+
+use wapc::{errors, wapc_functions, ModuleState, WapcHost, WebAssemblyEngineProvider};
+use wasm3_provider::errors::Error;
+use wasm3_provider::{Wasm3EngineProvider, Wasm3EngineProviderBuilder, DEFAULT_REQUIRED_IMPORTS};
+
+// Doesn't import `__host_call` and imports `__console_log` with a wrong signature
+const STRIPPED_GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_request" (func $guest_request (param i32 i32)))
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (import "wapc" "__guest_error" (func $guest_error (param i32 i32)))
+  (import "wapc" "__console_log" (func $console_log (param i32)))
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (i32.const 1)))
+"#;
+
+fn required_imports() -> Vec<&'static str> {
+  let mut required = DEFAULT_REQUIRED_IMPORTS.to_vec();
+  required.push(wapc_functions::HOST_CALL);
+  required
+}
+
+fn create_engine(module_bytes: &[u8], strict_imports: bool) -> Result<Wasm3EngineProvider, Error> {
+  Wasm3EngineProviderBuilder::new()
+    .module_bytes(module_bytes)
+    .strict_imports(strict_imports)
+    .required_imports(&required_imports())
+    .build()
+}
+
+fn init(mut engine: Wasm3EngineProvider) -> Result<(), Error> {
+  engine
+    .init(Arc::new(ModuleState::with_callback(None)))
+    .map_err(|e| *e.downcast::<Error>().unwrap())
+}
+
+#[test]
+fn guest_with_host_call_passes() -> Result<(), errors::Error> {
+  let module_bytes = read("../../wasm/crates/wasm-basic/build/wasm_basic.wasm")?;
+  let engine = create_engine(&module_bytes, true)?;
+  let host = WapcHost::new(Box::new(engine), Some(Box::new(|_, _, _, _, _| Ok(vec![]))))?;
+
+  let result = host.call("ping", b"hello")?;
+  assert_eq!(result, b"hello");
+  Ok(())
+}
+
+#[test]
+fn stripped_guest_fails_in_strict_mode() -> Result<(), Error> {
+  let module_bytes = wat::parse_str(STRIPPED_GUEST).unwrap();
+  let result = init(create_engine(&module_bytes, true)?);
+
+  match result {
+    Err(Error::MissingRequiredImport(problems)) => assert_eq!(
+      problems,
+      [
+        "__host_call is not imported",
+        "__console_log is imported but cannot be linked"
+      ]
+    ),
+    _ => panic!("the module should have been rejected"),
+  }
+  Ok(())
+}
+
+#[test]
+fn lenient_mode_ignores_unlinked_imports() -> Result<(), Error> {
+  let module_bytes = wat::parse_str(STRIPPED_GUEST).unwrap();
+  init(Wasm3EngineProviderBuilder::new().module_bytes(&module_bytes).build()?)?;
+
+  // the required imports are checked in both modes
+  let result = init(create_engine(&module_bytes, false)?);
+  assert!(matches!(result, Err(Error::MissingRequiredImport(problems)) if problems.len() == 1));
+  Ok(())
+}
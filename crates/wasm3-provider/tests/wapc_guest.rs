@@ -1,8 +1,24 @@
 use std::fs::read;
 
+use serde::{Deserialize, Serialize};
 use wapc::{errors, WapcHost};
 use wapc_codec::messagepack::{deserialize, serialize};
 
+const WAPC_FUNCTION_NAME: &str = "serdes_example";
+
+//simple struct to pass to wasm module and calc hash inside
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+struct PersonSend {
+  first_name: String,
+}
+
+// recv struct
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+struct PersonHashedRecv {
+  first_name: String,
+  hash: u64,
+}
+
 #[test]
 fn runs_wapc_guest() -> Result<(), errors::Error> {
   let buf = read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm")?;
@@ -15,3 +31,105 @@ fn runs_wapc_guest() -> Result<(), errors::Error> {
   assert_eq!(result, "hello world");
   Ok(())
 }
+
+#[test]
+fn runs_wasm_calc_hash() -> Result<(), errors::Error> {
+  let module_bytes1 = read("../../wasm/crates/wasm-calc-hash/module1/build/module1_hash.wasm")?;
+  let module_bytes2 = read("../../wasm/crates/wasm-calc-hash/module2/build/module2_hash.wasm")?;
+  // test modules binaries not equal
+  assert_ne!(module_bytes1, module_bytes2);
+
+  let engine = wasm3_provider::Wasm3EngineProvider::new(&module_bytes1);
+  let host = WapcHost::new(
+    Box::new(engine),
+    Some(Box::new(move |_id, _bd, _ns, _op, _payload| Ok(vec![]))),
+  )?;
+
+  let name = "John Doe".to_string();
+
+  // supply person struct
+  let person = PersonSend {
+    first_name: name.clone(),
+  };
+  let serbytes: Vec<u8> = serialize(&person).unwrap();
+
+  let res = host.call(WAPC_FUNCTION_NAME, &serbytes)?;
+  let recv_struct: PersonHashedRecv = deserialize(&res).unwrap();
+
+  // hotswapping
+  host.replace_module(&module_bytes2)?;
+
+  let res2 = host.call(WAPC_FUNCTION_NAME, &serbytes)?;
+  let recv_struct2: PersonHashedRecv = deserialize(&res2).unwrap();
+
+  assert_ne!(recv_struct, recv_struct2);
+  assert_eq!(recv_struct.first_name, name);
+  assert_eq!(recv_struct2.first_name, name);
+
+  Ok(())
+}
+
+#[test]
+fn failed_replace_keeps_the_current_module() -> Result<(), errors::Error> {
+  let buf = read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm")?;
+
+  let engine = wasm3_provider::Wasm3EngineProvider::new(&buf);
+  let guest = WapcHost::new(Box::new(engine), Some(Box::new(move |_a, _b, _c, _d, _e| Ok(vec![]))))?;
+
+  let result = guest.replace_module(b"not a wasm module");
+  assert!(matches!(result, Err(errors::Error::ReplacementFailed(_))));
+
+  let callresult = guest.call("echo", &serialize("hello world").unwrap())?;
+  let result: String = deserialize(&callresult).unwrap();
+  assert_eq!(result, "hello world");
+  Ok(())
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn runs_wapc_guest_async() -> Result<(), errors::Error> {
+  let buf = read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm")?;
+
+  let engine = wasm3_provider::Wasm3EngineProviderAsync::new(&buf);
+  let host_callback: Box<wapc::HostCallbackAsync> =
+    Box::new(|_id, _bd, _ns, _op, _payload| Box::pin(async { Ok(vec![]) }));
+  let guest = wapc::WapcHostAsync::new(Box::new(engine), Some(host_callback)).await?;
+
+  let callresult = guest.call("echo", &serialize("hello world").unwrap()).await?;
+  let result: String = deserialize(&callresult).unwrap();
+  assert_eq!(result, "hello world");
+  Ok(())
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn runs_wasm_calc_hash_async() -> Result<(), errors::Error> {
+  let module_bytes1 = read("../../wasm/crates/wasm-calc-hash/module1/build/module1_hash.wasm")?;
+  let module_bytes2 = read("../../wasm/crates/wasm-calc-hash/module2/build/module2_hash.wasm")?;
+
+  let engine = wasm3_provider::Wasm3EngineProviderAsync::new(&module_bytes1);
+  let host_callback: Box<wapc::HostCallbackAsync> =
+    Box::new(|_id, _bd, _ns, _op, _payload| Box::pin(async { Ok(vec![]) }));
+  let host = wapc::WapcHostAsync::new(Box::new(engine), Some(host_callback)).await?;
+
+  let name = "John Doe".to_string();
+  let person = PersonSend {
+    first_name: name.clone(),
+  };
+  let serbytes: Vec<u8> = serialize(&person).unwrap();
+
+  let res = host.call(WAPC_FUNCTION_NAME, &serbytes).await?;
+  let recv_struct: PersonHashedRecv = deserialize(&res).unwrap();
+
+  // hotswapping
+  host.replace_module(&module_bytes2).await?;
+
+  let res2 = host.call(WAPC_FUNCTION_NAME, &serbytes).await?;
+  let recv_struct2: PersonHashedRecv = deserialize(&res2).unwrap();
+
+  assert_ne!(recv_struct, recv_struct2);
+  assert_eq!(recv_struct.first_name, name);
+  assert_eq!(recv_struct2.first_name, name);
+
+  Ok(())
+}
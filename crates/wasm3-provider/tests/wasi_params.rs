@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use wapc::{errors, ModuleState, WapcHost, WasiParams, WebAssemblyEngineProvider};
+use wasm3_provider::errors::Error;
+use wasm3_provider::Wasm3EngineProvider;
+
+// The same guest is used by the WASI tests of wasmtime-provider
+const ENVIRON_GUEST: &str = "../../wasm/wasi_environ.wat";
+
+// Returns the NUL separated entries sent back by the guest
+fn guest_entries(wasi_params: WasiParams, operation: &str) -> Result<Vec<String>, errors::Error> {
+  let module_bytes = wat::parse_file(ENVIRON_GUEST).unwrap();
+  let engine = Wasm3EngineProvider::new_with_wasi(&module_bytes, wasi_params);
+  let host = WapcHost::new(Box::new(engine), None)?;
+
+  let response = host.call(operation, b"")?;
+  Ok(
+    String::from_utf8(response)
+      .unwrap()
+      .split_terminator('\0')
+      .map(ToOwned::to_owned)
+      .collect(),
+  )
+}
+
+#[test]
+fn env_vars_are_exposed() -> Result<(), errors::Error> {
+  std::env::set_var("WAPC_TEST_NOT_INHERITED", "host");
+
+  let env = guest_entries(
+    WasiParams::default().env_vars(vec![("GREETING".to_owned(), "hello".to_owned())]),
+    "env",
+  )?;
+  assert_eq!(env, vec!["GREETING=hello".to_owned()]);
+
+  Ok(())
+}
+
+#[test]
+fn argv_is_exposed() -> Result<(), errors::Error> {
+  let args = guest_entries(
+    WasiParams::default().argv(vec!["guest".to_owned(), "--verbose".to_owned()]),
+    "args",
+  )?;
+  assert_eq!(args, vec!["guest".to_owned(), "--verbose".to_owned()]);
+
+  Ok(())
+}
+
+#[test]
+fn preopened_dirs_are_not_supported() {
+  let module_bytes = wat::parse_file(ENVIRON_GUEST).unwrap();
+  let mut engine = Wasm3EngineProvider::new_with_wasi(
+    &module_bytes,
+    WasiParams::default().preopened_dirs(vec![".".to_owned()]),
+  );
+
+  let err = engine.init(Arc::new(ModuleState::with_callback(None))).unwrap_err();
+  assert!(matches!(err.downcast_ref::<Error>(), Some(Error::WasiUnsupported(_))));
+}
@@ -0,0 +1,122 @@
+use anyhow::anyhow;
+use serde::Serialize;
+use wapc_codec::messagepack::{deserialize, serialize};
+use wasmtime::{ExternType, FuncType, Module, Val, ValType};
+
+use crate::errors::{Error, Result};
+use crate::linking::{self, LinkOptions};
+
+/// A function import satisfied by forwarding it to the host callback
+pub(crate) struct BridgedImport {
+  /// The namespace pattern matching the import module, used as waPC binding
+  pub(crate) binding: String,
+  /// The import module, used as waPC namespace
+  pub(crate) namespace: String,
+  /// The import name, used as waPC operation
+  pub(crate) operation: String,
+  pub(crate) ty: FuncType,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum BridgedValue {
+  I32(i32),
+  I64(i64),
+  F32(f32),
+  F64(f64),
+}
+
+// A pattern ending with `*` matches all the namespaces starting with the given prefix
+pub(crate) fn matches_namespace(pattern: &str, namespace: &str) -> bool {
+  pattern
+    .strip_suffix('*')
+    .map_or_else(|| pattern == namespace, |prefix| namespace.starts_with(prefix))
+}
+
+// Collect the function imports of `module`, and of the library modules, that have to be
+// forwarded to the host callback
+pub(crate) fn bridged_imports(module: &Module, link_options: &LinkOptions) -> Result<Vec<BridgedImport>> {
+  let mut bridged: Vec<BridgedImport> = Vec::new();
+  if link_options.bridged_namespaces.is_empty() {
+    return Ok(bridged);
+  }
+
+  let modules = std::iter::once(module).chain(link_options.modules.iter().map(|(_, library)| library));
+  for import in modules.flat_map(Module::imports) {
+    let namespace = import.module();
+    if linking::is_reserved(namespace) || link_options.modules.iter().any(|(name, _)| name == namespace) {
+      continue;
+    }
+    let Some(binding) = link_options
+      .bridged_namespaces
+      .iter()
+      .find(|pattern| matches_namespace(pattern, namespace))
+    else {
+      continue;
+    };
+    if bridged
+      .iter()
+      .any(|b| b.namespace == namespace && b.operation == import.name())
+    {
+      continue;
+    }
+
+    let func = format!("{}.{}", namespace, import.name());
+    let ExternType::Func(ty) = import.ty() else {
+      return Err(Error::LinkerFuncDef {
+        func,
+        err: "only function imports can be bridged".into(),
+      });
+    };
+    if !ty
+      .params()
+      .all(|param| matches!(param, ValType::I32 | ValType::I64 | ValType::F32 | ValType::F64))
+    {
+      return Err(Error::LinkerFuncDef {
+        func,
+        err: "bridged functions can only take i32, i64, f32 and f64 parameters".into(),
+      });
+    }
+    let results: Vec<ValType> = ty.results().collect();
+    if results.len() > 1 || results.first().is_some_and(|result| !matches!(result, ValType::I32)) {
+      return Err(Error::LinkerFuncDef {
+        func,
+        err: "bridged functions can only return nothing or an i32".into(),
+      });
+    }
+
+    bridged.push(BridgedImport {
+      binding: binding.clone(),
+      namespace: namespace.to_owned(),
+      operation: import.name().to_owned(),
+      ty,
+    });
+  }
+
+  Ok(bridged)
+}
+
+// Encode the arguments of a bridged function as a MessagePack array
+pub(crate) fn encode_params(params: &[Val]) -> anyhow::Result<Vec<u8>> {
+  let values = params
+    .iter()
+    .map(|param| match param {
+      Val::I32(v) => Ok(BridgedValue::I32(*v)),
+      Val::I64(v) => Ok(BridgedValue::I64(*v)),
+      Val::F32(bits) => Ok(BridgedValue::F32(f32::from_bits(*bits))),
+      Val::F64(bits) => Ok(BridgedValue::F64(f64::from_bits(*bits))),
+      _ => Err(anyhow!("unsupported parameter type: {:?}", param)),
+    })
+    .collect::<anyhow::Result<Vec<BridgedValue>>>()?;
+
+  serialize(values).map_err(|e| anyhow!("cannot encode the parameters: {}", e))
+}
+
+// Decode the host response as the result of a bridged function, if it has one
+pub(crate) fn decode_result(response: &[u8], results: &mut [Val]) -> anyhow::Result<()> {
+  if let Some(result) = results.first_mut() {
+    let value: i32 = deserialize(response).map_err(|e| anyhow!("cannot decode the host response: {}", e))?;
+    *result = Val::I32(value);
+  }
+  Ok(())
+}
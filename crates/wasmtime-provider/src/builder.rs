@@ -1,23 +1,62 @@
 use crate::errors::{Error, Result};
+use crate::linking::LinkOptions;
+use crate::memory_tuning::MemoryTuning;
+use crate::proposals::WasmProposals;
+use crate::target::CompileSource;
 use crate::{WasmtimeEngineProvider, WasmtimeEngineProviderPre};
 
 #[cfg(feature = "async")]
 use crate::{WasmtimeEngineProviderAsync, WasmtimeEngineProviderAsyncPre};
 
+use crate::WasmtimeComponentEngineProvider;
+
 /// Used to build [`WasmtimeEngineProvider`](crate::WasmtimeEngineProvider) instances.
 #[allow(missing_debug_implementations)]
 #[derive(Default)]
 pub struct WasmtimeEngineProviderBuilder<'a> {
   engine: Option<wasmtime::Engine>,
   module: Option<wasmtime::Module>,
+  name: Option<String>,
+  expected_sha256: Option<String>,
   module_bytes: Option<&'a [u8]>,
+  module_path: Option<std::path::PathBuf>,
+  precompiled_module: bool,
+  // the binary format of the text provided via `module_wat`, or the parsing error
+  module_wat: Option<std::result::Result<Vec<u8>, String>>,
+  component_bytes: Option<&'a [u8]>,
   #[cfg(feature = "cache")]
-  cache_enabled: bool,
-  #[cfg(feature = "cache")]
-  cache_path: Option<std::path::PathBuf>,
-  #[cfg(feature = "wasi")]
+  cache_options: Option<crate::CacheOptions>,
+  linked_modules: Vec<(String, &'a [u8])>,
+  bridged_namespaces: Vec<String>,
+  stub_wasi_imports: bool,
   wasi_params: Option<wapc::WasiParams>,
+  #[cfg(feature = "wasi")]
+  wasi_ctx_hook: Option<std::sync::Arc<crate::wasi::WasiCtxHook>>,
+  #[cfg(all(feature = "wasi", feature = "async"))]
+  wasi_ctx_hook_async: Option<std::sync::Arc<crate::wasi::WasiCtxHookAsync>>,
   epoch_deadlines: Option<crate::EpochDeadlines>,
+  epoch_timeouts: Option<(std::time::Duration, std::time::Duration)>,
+  epoch_ticker: Option<crate::EpochTickerHandle>,
+  exclude_host_calls_from_deadline: bool,
+  strategy: Option<wasmtime::Strategy>,
+  debug_info: bool,
+  compile_target: Option<String>,
+  wasm_proposals: WasmProposals,
+  memory_tuning: MemoryTuning,
+  on_memory_grow: Option<std::sync::Arc<crate::limits::MemoryGrowCallback>>,
+  reset_memory: bool,
+  max_payload_size: Option<usize>,
+  max_host_response: Option<usize>,
+  fuel_per_call: Option<u64>,
+  deterministic: bool,
+  reuse_host_call_buffer: bool,
+  count_host_functions: bool,
+  #[cfg(feature = "async")]
+  runtime_handle: Option<tokio::runtime::Handle>,
+  validate_on_build: bool,
+  call_timings: bool,
+  precompile_cache_dir: Option<std::path::PathBuf>,
+  force_recompile: bool,
 }
 
 #[allow(deprecated)]
@@ -28,6 +67,33 @@ impl<'a> WasmtimeEngineProviderBuilder<'a> {
     Default::default()
   }
 
+  /// Name the providers, to tell them apart inside of the logs and the traces
+  ///
+  /// The name is attached to every log and tracing event emitted by the providers. It defaults
+  /// to the first 12 characters of the hex encoded SHA-256 digest of the WebAssembly module,
+  /// or to the name found inside of the module when its bytes are not known.
+  #[must_use]
+  pub fn name(mut self, name: &str) -> Self {
+    self.name = Some(name.to_owned());
+    self
+  }
+
+  /// Require the WebAssembly module to match the hex encoded SHA-256 `digest`
+  ///
+  /// The digest of the bytes provided via
+  /// [`module_bytes`](WasmtimeEngineProviderBuilder::module_bytes) or read from
+  /// [`module_path`](WasmtimeEngineProviderBuilder::module_path) is computed before compiling
+  /// them, [`build_pre`](WasmtimeEngineProviderBuilder::build_pre) and
+  /// [`build_async_pre`](WasmtimeEngineProviderBuilder::build_async_pre) fail with
+  /// [`Error::ModuleDigestMismatch`] when it differs. The text provided via `module_wat` is
+  /// hashed once converted into its binary form. Hot swaps are covered by the
+  /// [`ExpectedModuleDigest`](crate::ExpectedModuleDigest) handle of the providers.
+  #[must_use]
+  pub fn expect_sha256(mut self, digest: &str) -> Self {
+    self.expected_sha256 = Some(digest.to_owned());
+    self
+  }
+
   /// Provide contents of the WebAssembly module
   #[must_use]
   pub fn module_bytes(mut self, module_bytes: &'a [u8]) -> Self {
@@ -35,6 +101,53 @@ impl<'a> WasmtimeEngineProviderBuilder<'a> {
     self
   }
 
+  /// Load the WebAssembly module from the file at `path`
+  ///
+  /// This saves reading the whole file into memory before handing it over to Wasmtime.
+  /// When [`precompiled_module`](WasmtimeEngineProviderBuilder::precompiled_module) is set,
+  /// the file is a module precompiled by Wasmtime and it's memory mapped instead of being read.
+  #[must_use]
+  pub fn module_path(mut self, path: &std::path::Path) -> Self {
+    self.module_path = Some(path.to_path_buf());
+    self
+  }
+
+  /// Treat the file provided via [`module_path`](WasmtimeEngineProviderBuilder::module_path)
+  /// as a module precompiled by Wasmtime, usually a `.cwasm` file created via
+  /// [`wasmtime::Module::serialize`] or [`wasmtime::Engine::precompile_module`]
+  ///
+  /// The precompiled module must have been created with the same Wasmtime version and
+  /// compatible engine settings, otherwise the `build*` methods fail.
+  ///
+  /// **Warning:** the native code of the precompiled module is loaded without being
+  /// validated, the file must come from a trusted source.
+  #[must_use]
+  pub fn precompiled_module(mut self, enabled: bool) -> Self {
+    self.precompiled_module = enabled;
+    self
+  }
+
+  /// Provide the WebAssembly module in its text format, handy to write small guests
+  ///
+  /// The text is converted to the binary format right away, the `build*` methods fail with
+  /// [`Error::Wat`] when it is not valid.
+  #[must_use]
+  pub fn module_wat(mut self, wat: &str) -> Self {
+    self.module_wat = Some(wat::parse_str(wat).map_err(|e| e.to_string()));
+    self
+  }
+
+  /// Provide contents of the WebAssembly component
+  ///
+  /// The component must target the `wapc` world defined inside of the `wit` directory
+  /// of this crate. Use [`build_component`](WasmtimeEngineProviderBuilder::build_component)
+  /// to create the engine provider.
+  #[must_use]
+  pub fn component_bytes(mut self, component_bytes: &'a [u8]) -> Self {
+    self.component_bytes = Some(component_bytes);
+    self
+  }
+
   /// Provide a preloaded [`wasmtime::Module`]
   ///
   /// **Warning:** the [`wasmtime::Engine`] used to load it must be provided via the
@@ -59,26 +172,158 @@ impl<'a> WasmtimeEngineProviderBuilder<'a> {
     self
   }
 
+  /// Link an additional WebAssembly module, whose exports can then be imported by the
+  /// main module under the `name` namespace
+  ///
+  /// The library modules are instantiated inside of the Store of each engine provider,
+  /// in the order they have been linked and right before the main module. Hence the state
+  /// of a library is never shared between engine providers. A library module can import the
+  /// waPC and WASI host functions, plus the exports of the modules linked before it.
+  ///
+  /// Linking a module under the name of another library, or of a namespace provided by
+  /// the host (like `wapc` or `wasi_snapshot_preview1`), causes the `build*` methods
+  /// to fail with [`Error::LinkModule`].
+  #[must_use]
+  pub fn link_module(mut self, name: &str, module_bytes: &'a [u8]) -> Self {
+    self.linked_modules.push((name.to_owned(), module_bytes));
+    self
+  }
+
+  /// Forward the function imports of the given namespaces to the host callback
+  ///
+  /// Each import matching one of the `namespaces` is satisfied by a host function that invokes
+  /// the [`wapc::HostCallback`] with the matching namespace pattern as binding, the import
+  /// module as namespace and the import name as operation. The payload is the list of
+  /// arguments encoded with MessagePack, while the response is decoded as the function result.
+  ///
+  /// A namespace ending with `*` matches all the import modules starting with the given prefix.
+  /// The bridged functions can take `i32`, `i64`, `f32` and `f64` parameters and return either
+  /// nothing or an `i32`. Namespaces provided by the host or by [linked modules](WasmtimeEngineProviderBuilder::link_module)
+  /// are never bridged.
+  #[must_use]
+  pub fn bridge_namespaces(mut self, namespaces: &[&str]) -> Self {
+    self
+      .bridged_namespaces
+      .extend(namespaces.iter().map(|namespace| (*namespace).to_owned()));
+    self
+  }
+
+  /// Satisfy the `wasi_snapshot_preview1` function imports of the guest with stubs
+  ///
+  /// This is not WASI support: it only allows guests that import WASI functions without
+  /// relying on them, like the Rust ones leaving the `fd_write` import of `println!` in, to be
+  /// loaded when the `wasi` feature is disabled. The guest sees no arguments nor environment
+  /// variables and gets non cryptographically secure random bytes. The `fd_*` stubs fail with
+  /// `EBADF`, which makes the output of `println!` disappear, `proc_exit` traps and the other
+  /// stubs fail with `ENOSYS`.
+  ///
+  /// Ignored when the `wasi` feature is enabled, the WASI functions are provided in that case.
+  #[must_use]
+  pub fn stub_wasi_imports(mut self, enabled: bool) -> Self {
+    self.stub_wasi_imports = enabled;
+    self
+  }
+
   /// WASI params
-  #[cfg(feature = "wasi")]
-  #[cfg_attr(docsrs, doc(cfg(feature = "wasi")))]
+  ///
+  /// **Warning:** when the `wasi` feature is disabled, providing WASI params causes
+  /// [`build_pre`](WasmtimeEngineProviderBuilder::build_pre) and the other `build*` methods
+  /// to fail with [`Error::WasiDisabled`].
   #[must_use]
   pub fn wasi_params(mut self, wasi: wapc::WasiParams) -> Self {
     self.wasi_params = Some(wasi);
     self
   }
 
-  /// Enable Wasmtime cache feature
+  /// Customize the WASI context of the guest beyond what [`wapc::WasiParams`] provides
   ///
-  /// **Warning:** this has no effect when a custom [`wasmtime::Engine`] is provided via
-  /// the [`WasmtimeEngineProviderBuilder::engine`] helper. In that case, it's up to the
-  /// user to provide a [`wasmtime::Engine`] instance with the cache values properly configured.
+  /// The hook is invoked each time a WASI context is created (when the provider is built,
+  /// cloned, rehydrated or initialized), after the WASI params have been applied. Errors
+  /// returned by the hook are reported as [`Error::WasiInitCtxError`].
+  ///
+  /// This hook is used by the synchronous providers. Use
+  /// [`with_wasi_ctx_async`](WasmtimeEngineProviderBuilder::with_wasi_ctx_async) to customize
+  /// the WASI context of the asynchronous ones.
+  #[cfg(feature = "wasi")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "wasi")))]
+  #[must_use]
+  pub fn with_wasi_ctx<F>(mut self, hook: F) -> Self
+  where
+    F: Fn(&mut wasi_common::sync::WasiCtxBuilder) -> anyhow::Result<()> + Send + Sync + 'static,
+  {
+    self.wasi_ctx_hook = Some(std::sync::Arc::new(hook));
+    self
+  }
+
+  /// Customize the WASI context of the guest run by the asynchronous providers,
+  /// see [`with_wasi_ctx`](WasmtimeEngineProviderBuilder::with_wasi_ctx)
+  #[cfg(all(feature = "wasi", feature = "async"))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "wasi", feature = "async"))))]
+  #[must_use]
+  pub fn with_wasi_ctx_async<F>(mut self, hook: F) -> Self
+  where
+    F: Fn(&mut wasi_common::tokio::WasiCtxBuilder) -> anyhow::Result<()> + Send + Sync + 'static,
+  {
+    self.wasi_ctx_hook_async = Some(std::sync::Arc::new(hook));
+    self
+  }
+
+  /// Enable Wasmtime cache feature, loading the given Wasmtime cache configuration file
+  /// or the default one when `path` is `None`
+  ///
+  /// This is a shorthand for [`enable_cache_with`](WasmtimeEngineProviderBuilder::enable_cache_with)
+  /// with [`CacheOptions::config_file`](crate::CacheOptions::config_file) set to `path`.
+  /// This cannot be combined with a custom [`wasmtime::Engine`] provided via
+  /// the [`WasmtimeEngineProviderBuilder::engine`] helper: it's up to the user to provide
+  /// a [`wasmtime::Engine`] instance with the cache values properly configured.
   #[cfg(feature = "cache")]
   #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
   #[must_use]
-  pub fn enable_cache(mut self, path: Option<&std::path::Path>) -> Self {
-    self.cache_enabled = true;
-    self.cache_path = path.map(|p| p.to_path_buf());
+  pub fn enable_cache(self, path: Option<&std::path::Path>) -> Self {
+    self.enable_cache_with(crate::CacheOptions {
+      config_file: path.map(std::path::Path::to_path_buf),
+      ..Default::default()
+    })
+  }
+
+  /// Enable Wasmtime cache feature, configuring it with the given [`CacheOptions`](crate::CacheOptions)
+  ///
+  /// Invalid options cause the `build*` methods to fail with [`Error::BuilderInvalidConfig`].
+  /// This cannot be combined with a custom [`wasmtime::Engine`] provided via
+  /// the [`WasmtimeEngineProviderBuilder::engine`] helper.
+  #[cfg(feature = "cache")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+  #[must_use]
+  pub fn enable_cache_with(mut self, options: crate::CacheOptions) -> Self {
+    self.cache_options = Some(options);
+    self
+  }
+
+  /// Store the compiled module inside of `dir`, and load it from there on the next builds
+  ///
+  /// The artifacts are named after the SHA-256 of the module bytes and of the engine settings
+  /// affecting the compiled code: changing the module, the builder options or the Wasmtime
+  /// version leads to a new compilation. An artifact that cannot be loaded, for example
+  /// because it's corrupted, is replaced by a fresh compilation.
+  ///
+  /// Unlike [`enable_cache`](WasmtimeEngineProviderBuilder::enable_cache), this doesn't depend
+  /// on the `cache` feature and the directory is never cleaned up.
+  ///
+  /// **Warning:** the artifacts contain native code which is loaded without being validated,
+  /// `dir` must be writable only by trusted parties. The module must be provided via
+  /// [`module_bytes`](WasmtimeEngineProviderBuilder::module_bytes).
+  #[must_use]
+  pub fn precompile_cache_dir(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+    self.precompile_cache_dir = Some(dir.into());
+    self
+  }
+
+  /// Compile the module even when a precompiled artifact is available inside of the
+  /// [`precompile_cache_dir`](WasmtimeEngineProviderBuilder::precompile_cache_dir), the artifact
+  /// is then replaced
+  #[must_use]
+  pub fn force_recompile(mut self, enabled: bool) -> Self {
+    self.force_recompile = enabled;
     self
   }
 
@@ -110,27 +355,766 @@ impl<'a> WasmtimeEngineProviderBuilder<'a> {
     self
   }
 
-  /// Create a [`WasmtimeEngineProviderPre`] instance. This instance can then
-  /// be reused as many time as wanted to quickly instantiate a [`WasmtimeEngineProvider`]
-  /// by using the [`WasmtimeEngineProviderPre::rehydrate`] method.
-  pub fn build_pre(&self) -> Result<WasmtimeEngineProviderPre> {
-    if self.module_bytes.is_some() && self.module.is_some() {
+  /// Enable epoch interruptions, with deadlines expressed as durations instead of ticks
+  ///
+  /// The durations are converted into ticks using the interval of the
+  /// [`epoch_ticker`](WasmtimeEngineProviderBuilder::epoch_ticker), which is required. They are
+  /// rounded up to a whole number of ticks, the guest can hence run up to one interval longer.
+  ///
+  /// This cannot be used together with
+  /// [`enable_epoch_interruptions`](WasmtimeEngineProviderBuilder::enable_epoch_interruptions).
+  #[must_use]
+  pub fn enable_epoch_timeouts(mut self, wapc_init: std::time::Duration, wapc_func: std::time::Duration) -> Self {
+    self.epoch_timeouts = Some((wapc_init, wapc_func));
+    self
+  }
+
+  /// Drive the epoch deadlines with a ticker shared by many engine providers
+  ///
+  /// The providers are created using the engine of the ticker, unless a custom
+  /// [`engine`](WasmtimeEngineProviderBuilder::engine) is provided, which must then be the
+  /// same. The engine must have been created with [`wasmtime::Config::epoch_interruption`]
+  /// enabled. The providers keep the ticker running, even after `ticker` has been dropped.
+  ///
+  /// The interval of the ticker is used by
+  /// [`enable_epoch_timeouts`](WasmtimeEngineProviderBuilder::enable_epoch_timeouts) to convert
+  /// durations into ticks.
+  #[must_use]
+  pub fn epoch_ticker(mut self, ticker: &crate::EpochTickerHandle) -> Self {
+    self.epoch_ticker = Some(ticker.clone());
+    self
+  }
+
+  // The engine the providers are created with instead of one configured by the builder, along
+  // with the option that provided it
+  fn preset_engine(&self) -> Option<(&wasmtime::Engine, &'static str)> {
+    match (&self.engine, &self.epoch_ticker) {
+      (Some(engine), _) => Some((engine, "a custom `engine`")),
+      (None, Some(ticker)) => Some((ticker.engine(), "an `epoch_ticker`")),
+      (None, None) => None,
+    }
+  }
+
+  // The deadlines set via `enable_epoch_interruptions`, or computed from the ones set via
+  // `enable_epoch_timeouts`
+  fn resolved_epoch_deadlines(&self) -> Option<crate::EpochDeadlines> {
+    match (self.epoch_timeouts, &self.epoch_ticker) {
+      (Some((wapc_init, wapc_func)), Some(ticker)) => Some(crate::EpochDeadlines {
+        wapc_init: ticker.ticks(wapc_init),
+        wapc_func: ticker.ticks(wapc_func),
+      }),
+      _ => self.epoch_deadlines,
+    }
+  }
+
+  /// Charge only the time spent running guest code against the `wapc_func_deadline` set via
+  /// [`enable_epoch_interruptions`](WasmtimeEngineProviderBuilder::enable_epoch_interruptions)
+  ///
+  /// The epoch ticks elapsed while the host callback is serving a `__host_call`, or a bridged
+  /// import, are not counted: a guest waiting on a slow database query is not interrupted,
+  /// while a guest spinning in a loop still is. The accounting is done at the granularity of
+  /// a tick, the guest can be granted up to one extra tick per host call.
+  ///
+  /// This requires epoch interruptions to be enabled. The store deadline callback is used to
+  /// count the ticks, it is invoked once per tick while the guest is running.
+  #[must_use]
+  pub fn exclude_host_calls_from_deadline(mut self, enabled: bool) -> Self {
+    self.exclude_host_calls_from_deadline = enabled;
+    self
+  }
+
+  /// Select the compiler used to translate the WebAssembly code into native code
+  ///
+  /// [`wasmtime::Strategy::Winch`] trades the quality of the generated code for a shorter
+  /// compilation time. It requires the `winch` feature of this crate and it's supported only
+  /// by some architectures.
+  ///
+  /// **Warning:** this cannot be used together with a custom [`wasmtime::Engine`], nor can
+  /// Winch be used together with the cache, which requires Cranelift. The `build*` methods
+  /// fail with [`Error::BuilderInvalidConfig`] otherwise.
+  #[must_use]
+  pub fn strategy(mut self, strategy: wasmtime::Strategy) -> Self {
+    self.strategy = Some(strategy);
+    self
+  }
+
+  /// Generate the native debug information of the guests and disable the optimizations
+  /// of the compiler, so that the guests can be debugged with their original source code
+  ///
+  /// The DWARF sections of the module, e.g. produced by `cargo build` without `--release`,
+  /// are translated to describe the generated native code and registered with the debugger.
+  /// The guest can then be debugged by attaching `lldb` or `gdb` to the host process:
+  ///
+  /// ```text
+  /// $ lldb -- target/debug/my-host
+  /// (lldb) settings set plugin.jit-loader.gdb.enable on
+  /// (lldb) breakpoint set --name my_guest_function
+  /// (lldb) run
+  /// ```
+  ///
+  /// The generated code is slower, this should be used only during development.
+  ///
+  /// **Warning:** this cannot be used together with a custom [`wasmtime::Engine`], the cache
+  /// or [`wasmtime::Strategy::Winch`]. The `build*` methods fail with
+  /// [`Error::BuilderInvalidConfig`] otherwise.
+  #[must_use]
+  pub fn debug_info(mut self, enable: bool) -> Self {
+    self.debug_info = enable;
+    self
+  }
+
+  /// Set the target triple the WebAssembly code is compiled for, e.g. `aarch64-unknown-linux-gnu`
+  ///
+  /// The providers can run only the code compiled for the host, or for a Pulley target sharing
+  /// its pointer width (this requires the `all-arch` feature). The `build*` methods fail with
+  /// [`Error::BuilderInvalidConfig`] otherwise. Use
+  /// [`WasmtimeEngineProviderPre::serialize_for_target`] to precompile modules for other hosts.
+  ///
+  /// **Warning:** this cannot be used together with a custom [`wasmtime::Engine`].
+  #[must_use]
+  pub fn compile_target(mut self, target: &str) -> Self {
+    self.compile_target = Some(target.to_owned());
+    self
+  }
+
+  /// Enable or disable the WebAssembly [SIMD proposal](https://github.com/webassembly/simd)
+  ///
+  /// Disabling it disables the relaxed SIMD proposal too, unless
+  /// [`wasm_relaxed_simd`](WasmtimeEngineProviderBuilder::wasm_relaxed_simd) is used.
+  /// Modules relying on a disabled proposal fail to load.
+  ///
+  /// **Warning:** the `wasm_*` methods cannot be used together with a custom
+  /// [`wasmtime::Engine`]. The `build*` methods fail with [`Error::BuilderInvalidConfig`] otherwise.
+  #[must_use]
+  pub fn wasm_simd(mut self, enable: bool) -> Self {
+    self.wasm_proposals.simd = Some(enable);
+    self
+  }
+
+  /// Enable or disable the WebAssembly [relaxed SIMD proposal](https://github.com/webassembly/relaxed-simd),
+  /// which requires SIMD to be enabled
+  #[must_use]
+  pub fn wasm_relaxed_simd(mut self, enable: bool) -> Self {
+    self.wasm_proposals.relaxed_simd = Some(enable);
+    self
+  }
+
+  /// Enable or disable the WebAssembly [bulk memory proposal](https://github.com/webassembly/bulk-memory-operations)
+  ///
+  /// Disabling it disables the reference types proposal too, and the threads proposal
+  /// unless [`wasm_threads`](WasmtimeEngineProviderBuilder::wasm_threads) is used.
+  #[must_use]
+  pub fn wasm_bulk_memory(mut self, enable: bool) -> Self {
+    self.wasm_proposals.bulk_memory = Some(enable);
+    self
+  }
+
+  /// Enable or disable the WebAssembly [multi memory proposal](https://github.com/webassembly/multi-memory)
+  ///
+  /// The proposal is enabled by default. It allows the guests to export a second memory named
+  /// `__wapc_memory`, dedicated to the waPC request and response buffers: the host functions
+  /// then read from and write to it instead of the main `memory`, hence large payloads don't
+  /// grow the heap of the guest.
+  #[must_use]
+  pub fn wasm_multi_memory(mut self, enable: bool) -> Self {
+    self.wasm_proposals.multi_memory = Some(enable);
+    self
+  }
+
+  /// Enable or disable the WebAssembly [threads proposal](https://github.com/webassembly/threads),
+  /// which gates shared memories and atomic instructions
+  ///
+  /// The guest can define its own shared memory, but the host doesn't provide one: a module
+  /// importing a shared memory fails to be instantiated, unless a
+  /// [linked module](WasmtimeEngineProviderBuilder::link_module) exports it.
+  /// Spawning threads ([wasi-threads](https://github.com/webassembly/wasi-threads)) is not supported.
+  #[must_use]
+  pub fn wasm_threads(mut self, enable: bool) -> Self {
+    self.wasm_proposals.threads = Some(enable);
+    self
+  }
+
+  /// Enable or disable the WebAssembly [memory64 proposal](https://github.com/webassembly/memory64),
+  /// which lets the guest use a linear memory indexed with 64-bit addresses
+  ///
+  /// The waPC protocol itself stays based on 32-bit pointers: the guest must keep the buffers
+  /// exchanged with the host inside of the first 4 GiB of its memory and pass their addresses
+  /// and lengths as `i32` values.
+  #[must_use]
+  pub fn wasm_memory64(mut self, enable: bool) -> Self {
+    self.wasm_proposals.memory64 = Some(enable);
+    self
+  }
+
+  /// Set the size of the virtual address space reserved for each linear memory of the guests
+  ///
+  /// On 64-bit hosts Wasmtime reserves 4 GiB per memory by default, which, together with the
+  /// guard region, lets the generated code skip almost all the bounds checks. A smaller
+  /// reservation lowers the virtual memory footprint of each instance, at the cost of explicit
+  /// bounds checks and of copying a memory when it grows beyond its reservation (unless
+  /// [`memory_may_move`](WasmtimeEngineProviderBuilder::memory_may_move) is disabled, in which
+  /// case the growth fails). A moved memory gets 2 GiB of extra address space, choose a
+  /// reservation covering the usual memory size of the guests to keep the footprint low.
+  ///
+  /// The size must be a multiple of the WebAssembly page size (64 KiB).
+  ///
+  /// **Warning:** the `memory_*` methods cannot be used together with a custom
+  /// [`wasmtime::Engine`]. The `build*` methods fail with [`Error::BuilderInvalidConfig`] otherwise.
+  #[must_use]
+  pub fn memory_reservation(mut self, bytes: u64) -> Self {
+    self.memory_tuning.reservation = Some(bytes);
+    self
+  }
+
+  /// Set the size of the unmapped region placed after each linear memory of the guests
+  ///
+  /// Accesses falling into the guard region trap without an explicit bounds check. The default
+  /// is 32 MiB on 64-bit hosts, a smaller guard region lowers the virtual memory footprint at
+  /// the cost of more bounds checks. The size must be a multiple of the WebAssembly page size
+  /// (64 KiB), it can be 0.
+  #[must_use]
+  pub fn memory_guard_size(mut self, bytes: u64) -> Self {
+    self.memory_tuning.guard_size = Some(bytes);
+    self
+  }
+
+  /// Choose whether the linear memories of the guests can be moved to another location of the
+  /// address space when they grow beyond their [reservation](WasmtimeEngineProviderBuilder::memory_reservation)
+  ///
+  /// When disabled, the generated code can rely on the base address of the memories never
+  /// changing, but the memories cannot grow beyond their reservation. This cannot be disabled
+  /// when the reservation is 0.
+  #[must_use]
+  pub fn memory_may_move(mut self, enable: bool) -> Self {
+    self.memory_tuning.may_move = Some(enable);
+    self
+  }
+
+  /// Observe the memory growths of the guest and decide whether to allow them
+  ///
+  /// The callback is invoked each time a memory of the guest is created or grown, with the
+  /// current and desired sizes and the id of the waPC module. Returning `false` denies the
+  /// growth: the guest sees it as an allocation failure (`memory.grow` returns `-1`) and a trap
+  /// raised right after is reported as [`Error::GuestOutOfMemory`]. Denying the creation of a
+  /// memory makes the instantiation fail.
+  ///
+  /// The callback is shared by the synchronous and the asynchronous providers. It is not
+  /// supported by [`build_component`](WasmtimeEngineProviderBuilder::build_component).
+  #[must_use]
+  pub fn on_memory_grow<F>(mut self, callback: F) -> Self
+  where
+    F: Fn(crate::GrowRequest) -> bool + Send + Sync + 'static,
+  {
+    self.on_memory_grow = Some(std::sync::Arc::new(callback));
+    self
+  }
+
+  /// Bring the guest back to the state it had right after its initialization before each call
+  ///
+  /// A [`MemorySnapshot`](crate::MemorySnapshot) is taken once the waPC initialization code
+  /// has run, then restored before each guest call. This is cheaper than instantiating the
+  /// module again, but only the exported memories and mutable globals are reset: host resources
+  /// like the WASI file descriptors are not.
+  #[must_use]
+  pub fn reset_memory_between_calls(mut self, enabled: bool) -> Self {
+    self.reset_memory = enabled;
+    self
+  }
+
+  /// Limit the size of the payloads handed by the guest to the host: guest responses,
+  /// guest errors and host call payloads
+  ///
+  /// A payload larger than `max_payload_size`, or larger than the memory of the guest,
+  /// is not read. The guest error is set to a message naming the offending length instead,
+  /// and the host call is reported as failed to the guest without invoking the host callback.
+  #[must_use]
+  pub fn max_payload_size(mut self, max_payload_size: usize) -> Self {
+    self.max_payload_size = Some(max_payload_size);
+    self
+  }
+
+  /// Limit the size of the responses handed by the host callback to the guest
+  ///
+  /// A larger response is discarded before being stored, the host call fails and the guest
+  /// can read a host error like `host response of 3000000 bytes exceeds limit 1048576`. This
+  /// protects the guest from trying to allocate a response that it cannot hold.
+  #[must_use]
+  pub fn max_host_response(mut self, max_host_response: usize) -> Self {
+    self.max_host_response = Some(max_host_response);
+    self
+  }
+
+  /// Meter the guest with fuel, granting it `fuel` units for its initialization and for
+  /// each call
+  ///
+  /// The guest traps with [`Error::GuestOutOfFuel`] when it runs out of fuel. The fuel consumed
+  /// by each call is reported by [`WasmtimeEngineProvider::last_call_cost`].
+  ///
+  /// **Warning:** a custom [`engine`](WasmtimeEngineProviderBuilder::engine) must have been
+  /// created with [`wasmtime::Config::consume_fuel`] enabled, otherwise the providers fail to initialize.
+  #[must_use]
+  pub fn fuel_per_call(mut self, fuel: u64) -> Self {
+    self.fuel_per_call = Some(fuel);
+    self
+  }
+
+  /// Configure Wasmtime for deterministic execution
+  ///
+  /// NaN values are canonicalized, the SIMD, relaxed SIMD and threads proposals are disabled
+  /// and the guest is metered with fuel instead of epoch interruptions. Unless
+  /// [`fuel_per_call`](WasmtimeEngineProviderBuilder::fuel_per_call) is used, the guest is
+  /// granted an unlimited amount of fuel.
+  ///
+  /// The same module called with the same inputs then returns the same outputs and consumes
+  /// the same amount of fuel, across runs and platforms, as long as the host callback is
+  /// deterministic too. The options breaking this guarantee are rejected with
+  /// [`Error::BuilderInvalidConfig`] when building the provider: a custom
+  /// [`engine`](WasmtimeEngineProviderBuilder::engine), a compiled
+  /// [`module`](WasmtimeEngineProviderBuilder::module), epoch interruptions, the proposals
+  /// listed above, a compilation strategy other than Cranelift, WASI and the WASI stubs.
+  #[must_use]
+  pub fn deterministic(mut self) -> Self {
+    self.deterministic = true;
+    self
+  }
+
+  // The fuel granted to the guest, `deterministic` implies fuel metering
+  fn effective_fuel_per_call(&self) -> Option<u64> {
+    self.fuel_per_call.or_else(|| self.deterministic.then_some(u64::MAX))
+  }
+
+  /// Count the invocations of each waPC host function made by the guest during a call
+  ///
+  /// This helps spotting chatty guests, like the ones performing many small host calls to
+  /// serve a single request. The counters are read via
+  /// [`WasmtimeEngineProvider::host_fn_counters`]. Counting is disabled by default, to keep
+  /// the host functions free of any bookkeeping.
+  #[must_use]
+  pub fn count_host_functions(mut self, enabled: bool) -> Self {
+    self.count_host_functions = enabled;
+    self
+  }
+
+  /// Reuse the same buffer to hand the payloads of the host calls to the host callback,
+  /// instead of allocating a new one on each call
+  ///
+  /// The payload still has to be copied out of the guest memory, but the allocation is
+  /// saved. This pays off when the guest sends large payloads to a host callback that only
+  /// inspects or forwards them. The buffer keeps the size of the largest payload seen so far,
+  /// unless [`max_payload_size`](WasmtimeEngineProviderBuilder::max_payload_size) bounds it.
+  ///
+  /// This is available only for the synchronous providers: the asynchronous host callback
+  /// takes ownership of the payload.
+  #[must_use]
+  pub fn reuse_host_call_buffer(mut self, enabled: bool) -> Self {
+    self.reuse_host_call_buffer = enabled;
+    self
+  }
+
+  /// Provide the Tokio runtime used by the asynchronous providers when they have to run
+  /// code outside of an async context, like when an initialized provider is cloned
+  ///
+  /// By default the runtime of the current thread is used, see [`tokio::runtime::Handle::current`].
+  /// Providing a handle allows the providers to be built and cloned on threads that are not
+  /// managed by a Tokio runtime, e.g. while bootstrapping a service.
+  #[cfg(feature = "async")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+  #[must_use]
+  pub fn runtime_handle(mut self, handle: tokio::runtime::Handle) -> Self {
+    self.runtime_handle = Some(handle);
+    self
+  }
+
+  /// Validate the module while building the provider, see [`validate`](WasmtimeEngineProviderBuilder::validate)
+  ///
+  /// When enabled, the build fails with [`Error::InvalidModule`] if the module is not a valid
+  /// waPC guest, instead of failing later during the initialization or the guest calls.
+  #[must_use]
+  pub fn validate_on_build(mut self, enabled: bool) -> Self {
+    self.validate_on_build = enabled;
+    self
+  }
+
+  /// Measure the time spent by each guest call inside of WebAssembly and inside of the
+  /// host functions, together with the number of waPC host calls performed
+  ///
+  /// The timings are exposed by [`WasmtimeEngineProvider::last_call_timings`] and
+  /// [`WasmtimeEngineProvider::call_timings_handle`]. When not enabled, no overhead is added
+  /// to the guest calls.
+  #[must_use]
+  pub fn enable_call_timings(mut self) -> Self {
+    self.call_timings = true;
+    self
+  }
+
+  // Create the configuration used when the user didn't provide a custom `wasmtime::Engine`
+  fn wasmtime_config(&self) -> Result<wasmtime::Config> {
+    let mut config = wasmtime::Config::default();
+    if self.resolved_epoch_deadlines().is_some() {
+      config.epoch_interruption(true);
+    }
+    if self.effective_fuel_per_call().is_some() {
+      config.consume_fuel(true);
+    }
+    if self.deterministic {
+      config.cranelift_nan_canonicalization(true);
+      config.wasm_simd(false);
+      config.wasm_relaxed_simd(false);
+      config.wasm_threads(false);
+    }
+    if let Some(strategy) = self.strategy {
+      config.strategy(strategy);
+    }
+    if self.debug_info {
+      config.debug_info(true);
+      config.cranelift_opt_level(wasmtime::OptLevel::None);
+    }
+    if let Some(target) = &self.compile_target {
+      config.target(target)?;
+    }
+    self.wasm_proposals.apply(&mut config);
+    self.memory_tuning.apply(&mut config);
+
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "cache")] {
+              if let Some(options) = &self.cache_options {
+                config.strategy(wasmtime::Strategy::Cranelift);
+                options.apply(&mut config)?;
+            }
+        }
+    }
+
+    Ok(config)
+  }
+
+  fn link_options(&self, engine: &wasmtime::Engine) -> Result<LinkOptions> {
+    let modules = self
+      .linked_modules
+      .iter()
+      .map(|(name, module_bytes)| {
+        wasmtime::Module::new(engine, module_bytes)
+          .map(|module| (name.clone(), module))
+          .map_err(|e| Error::LinkModule {
+            name: name.clone(),
+            err: e.to_string(),
+          })
+      })
+      .collect::<Result<_>>()?;
+
+    Ok(LinkOptions {
+      modules,
+      bridged_namespaces: self.bridged_namespaces.clone(),
+      stub_wasi_imports: self.stub_wasi_imports,
+    })
+  }
+
+  /// Check the module is a waPC guest whose imports can be satisfied by the host
+  ///
+  /// The module is compiled, but not instantiated. All the problems found are listed by
+  /// the returned [`ValidationReport`](crate::ValidationReport): missing waPC exports,
+  /// imports not provided by the host and waPC imports with an unexpected signature.
+  /// An error is returned only when the builder configuration is invalid or the module
+  /// cannot be compiled.
+  pub fn validate(&self) -> Result<crate::ValidationReport> {
+    self.validate_config()?;
+
+    let engine = match self.preset_engine() {
+      Some((e, _)) => e.clone(),
+      None => wasmtime::Engine::new(&self.wasmtime_config()?)?,
+    };
+    let module = self.load_module(&engine)?;
+    let link_options = self.link_options(&engine)?;
+
+    Ok(crate::validation::validate(&module, &link_options))
+  }
+
+  // Compile the module provided by the user, going through the precompiled modules
+  // directory when one has been provided
+  fn load_module(&self, engine: &wasmtime::Engine) -> Result<wasmtime::Module> {
+    if let Some(path) = &self.module_path {
+      return crate::precompiled::load_file(engine, path, self.precompiled_module);
+    }
+    match (self.module_source()?, &self.precompile_cache_dir) {
+      (Some(module_bytes), Some(dir)) => {
+        crate::precompiled::load_or_compile(engine, module_bytes, dir, self.force_recompile)
+      }
+      (Some(module_bytes), None) => Ok(wasmtime::Module::new(engine, module_bytes)?),
+      (None, _) => Ok(self.module.as_ref().unwrap().clone()),
+    }
+  }
+
+  // The binary contents of the module provided by the user, either as bytes or as text
+  fn module_source(&self) -> Result<Option<&[u8]>> {
+    match &self.module_wat {
+      Some(Ok(module_bytes)) => Ok(Some(module_bytes)),
+      Some(Err(e)) => Err(Error::Wat(e.clone())),
+      None => Ok(self.module_bytes),
+    }
+  }
+
+  // Compare the digest of the module with the one expected by the user, if any. The file
+  // provided via `module_path` is streamed once more to compute its digest
+  fn verify_digest(&self, module_hash: Option<&str>) -> Result<()> {
+    let Some(expected) = &self.expected_sha256 else {
+      return Ok(());
+    };
+    let expected = crate::digest::parse_sha256(expected)?;
+    let actual = match (module_hash, &self.module_path) {
+      (Some(hash), _) => hash.to_owned(),
+      (None, Some(path)) => crate::precompiled::file_hash(path).map_err(|e| Error::ModulePath {
+        path: path.display().to_string(),
+        err: e.to_string(),
+      })?,
+      (None, None) => {
+        return Err(Error::BuilderInvalidConfig(
+          "`expect_sha256` requires the bytes of the module, a compiled `module` cannot be verified".to_owned(),
+        ))
+      }
+    };
+    crate::digest::check(&expected, actual)
+  }
+
+  // Reject the modules that are not valid waPC guests, when requested by the user
+  fn check_module(&self, module: &wasmtime::Module, link_options: &LinkOptions) -> Result<()> {
+    if !self.validate_on_build {
+      return Ok(());
+    }
+    let report = crate::validation::validate(module, link_options);
+    if report.is_valid() {
+      Ok(())
+    } else {
+      Err(Error::InvalidModule(report))
+    }
+  }
+
+  // Ensure the options provided by the user are consistent
+  fn validate_config(&self) -> Result<()> {
+    if self.component_bytes.is_some() {
       return Err(Error::BuilderInvalidConfig(
-        "`module_bytes` and `module` cannot be provided at the same time".to_owned(),
+        "`component_bytes` can only be used with `build_component`".to_owned(),
       ));
     }
-    if self.module_bytes.is_none() && self.module.is_none() {
+    let module_sources = [
+      self.module_bytes.is_some(),
+      self.module.is_some(),
+      self.module_path.is_some(),
+      self.module_wat.is_some(),
+    ];
+    match module_sources.iter().filter(|provided| **provided).count() {
+      0 => {
+        return Err(Error::BuilderInvalidConfig(
+          "Neither `module_bytes`, `module`, `module_path` nor `module_wat` have been provided".to_owned(),
+        ))
+      }
+      1 => {}
+      _ => {
+        return Err(Error::BuilderInvalidConfig(
+          "only one of `module_bytes`, `module`, `module_path` and `module_wat` can be provided".to_owned(),
+        ))
+      }
+    }
+    if self.precompiled_module && self.module_path.is_none() {
       return Err(Error::BuilderInvalidConfig(
-        "Neither `module_bytes` nor `module` have been provided".to_owned(),
+        "`precompiled_module` requires the module to be provided via `module_path`".to_owned(),
       ));
     }
+    #[cfg(not(feature = "wasi"))]
+    if self.wasi_params.is_some() {
+      return Err(Error::WasiDisabled);
+    }
+    #[cfg(feature = "cache")]
+    if let Some((_, preset)) = self.preset_engine().filter(|_| self.cache_options.is_some()) {
+      return Err(Error::BuilderInvalidConfig(format!(
+        "the cache cannot be used together with {preset}"
+      )));
+    }
+    if self.precompile_cache_dir.is_some() && self.module_bytes.is_none() {
+      return Err(Error::BuilderInvalidConfig(
+        "`precompile_cache_dir` requires the module to be provided via `module_bytes`".to_owned(),
+      ));
+    }
+    if self.exclude_host_calls_from_deadline && self.resolved_epoch_deadlines().is_none() {
+      return Err(Error::BuilderInvalidConfig(
+        "`exclude_host_calls_from_deadline` requires epoch interruptions to be enabled".to_owned(),
+      ));
+    }
+    self.validate_epoch_ticker()?;
+    self.validate_deterministic()?;
+    self.validate_compiler()?;
+
+    Ok(())
+  }
+
+  fn validate_epoch_ticker(&self) -> Result<()> {
+    if self.epoch_timeouts.is_some() {
+      if self.epoch_ticker.is_none() {
+        return Err(Error::BuilderInvalidConfig(
+          "`enable_epoch_timeouts` requires an `epoch_ticker`".to_owned(),
+        ));
+      }
+      if self.epoch_deadlines.is_some() {
+        return Err(Error::BuilderInvalidConfig(
+          "`enable_epoch_timeouts` cannot be used together with `enable_epoch_interruptions`".to_owned(),
+        ));
+      }
+    }
+    if let (Some(ticker), Some(engine)) = (&self.epoch_ticker, &self.engine) {
+      if !wasmtime::Engine::same(ticker.engine(), engine) {
+        return Err(Error::BuilderInvalidConfig(
+          "the `epoch_ticker` must increment the epoch of the custom `engine`".to_owned(),
+        ));
+      }
+    }
+
+    Ok(())
+  }
+
+  // Reject the options that would void the guarantees of `deterministic`
+  fn validate_deterministic(&self) -> Result<()> {
+    if !self.deterministic {
+      return Ok(());
+    }
+    let conflict = if let Some((_, preset)) = self.preset_engine() {
+      Some(preset)
+    } else if self.module.is_some() {
+      // the module has been compiled by an engine that may not be deterministic
+      Some("a compiled `module`")
+    } else if self.resolved_epoch_deadlines().is_some() {
+      Some("`enable_epoch_interruptions`")
+    } else if self.wasm_proposals.simd == Some(true) {
+      Some("`wasm_simd(true)`")
+    } else if self.wasm_proposals.relaxed_simd == Some(true) {
+      Some("`wasm_relaxed_simd(true)`")
+    } else if self.wasm_proposals.threads == Some(true) {
+      Some("`wasm_threads(true)`")
+    } else if matches!(self.strategy, Some(s) if !matches!(s, wasmtime::Strategy::Auto | wasmtime::Strategy::Cranelift))
+    {
+      Some("a compilation strategy other than Cranelift")
+    } else if self.wasi_params.is_some() {
+      Some("`wasi_params`")
+    } else if self.stub_wasi_imports {
+      Some("`stub_wasi_imports`")
+    } else {
+      None
+    };
+
+    conflict.map_or(Ok(()), |option| {
+      Err(Error::BuilderInvalidConfig(format!(
+        "`deterministic` cannot be used together with {option}"
+      )))
+    })
+  }
+
+  // The names of the options set on the builder that only apply to the module providers
+  fn module_only_options(&self) -> Vec<&'static str> {
+    let options = [
+      #[cfg(feature = "wasi")]
+      (self.wasi_ctx_hook.is_some(), "with_wasi_ctx"),
+      #[cfg(all(feature = "wasi", feature = "async"))]
+      (self.wasi_ctx_hook_async.is_some(), "with_wasi_ctx_async"),
+      #[cfg(feature = "async")]
+      (self.runtime_handle.is_some(), "runtime_handle"),
+      (!self.linked_modules.is_empty(), "link_module"),
+      (!self.bridged_namespaces.is_empty(), "bridge_namespaces"),
+      (self.stub_wasi_imports, "stub_wasi_imports"),
+      (self.validate_on_build, "validate_on_build"),
+      (self.call_timings, "enable_call_timings"),
+      (self.force_recompile, "force_recompile"),
+      (self.on_memory_grow.is_some(), "on_memory_grow"),
+      (self.reset_memory, "reset_memory_between_calls"),
+      (self.max_payload_size.is_some(), "max_payload_size"),
+      (self.fuel_per_call.is_some(), "fuel_per_call"),
+      (self.deterministic, "deterministic"),
+      (self.epoch_timeouts.is_some(), "enable_epoch_timeouts"),
+      (self.epoch_ticker.is_some(), "epoch_ticker"),
+      (self.count_host_functions, "count_host_functions"),
+      (self.expected_sha256.is_some(), "expect_sha256"),
+      (self.max_host_response.is_some(), "max_host_response"),
+      (
+        self.exclude_host_calls_from_deadline,
+        "exclude_host_calls_from_deadline",
+      ),
+      (self.precompile_cache_dir.is_some(), "precompile_cache_dir"),
+      (self.reuse_host_call_buffer, "reuse_host_call_buffer"),
+    ];
+    options
+      .into_iter()
+      .filter_map(|(set, name)| set.then_some(name))
+      .collect()
+  }
+
+  // Ensure the compiler options can be honored
+  fn validate_compiler(&self) -> Result<()> {
+    if let Some((_, preset)) = self.preset_engine().filter(|_| self.strategy.is_some()) {
+      return Err(Error::BuilderInvalidConfig(format!(
+        "`strategy` cannot be used together with {preset}"
+      )));
+    }
+    if let Some((_, preset)) = self.preset_engine().filter(|_| self.wasm_proposals.is_set()) {
+      return Err(Error::BuilderInvalidConfig(format!(
+        "the `wasm_*` proposal toggles cannot be used together with {preset}"
+      )));
+    }
+    self.wasm_proposals.validate()?;
+    if let Some((_, preset)) = self.preset_engine().filter(|_| self.memory_tuning.is_set()) {
+      return Err(Error::BuilderInvalidConfig(format!(
+        "the `memory_*` settings cannot be used together with {preset}"
+      )));
+    }
+    self.memory_tuning.validate()?;
+    if self.debug_info {
+      if let Some((_, preset)) = self.preset_engine() {
+        return Err(Error::BuilderInvalidConfig(format!(
+          "`debug_info` cannot be used together with {preset}"
+        )));
+      }
+      if matches!(self.strategy, Some(wasmtime::Strategy::Winch)) {
+        return Err(Error::BuilderInvalidConfig(
+          "`debug_info` cannot be used together with the Winch compilation strategy".to_owned(),
+        ));
+      }
+      #[cfg(feature = "cache")]
+      if self.cache_options.is_some() {
+        return Err(Error::BuilderInvalidConfig(
+          "`debug_info` cannot be used together with the cache".to_owned(),
+        ));
+      }
+    }
+    if let Some(target) = &self.compile_target {
+      if let Some((_, preset)) = self.preset_engine() {
+        return Err(Error::BuilderInvalidConfig(format!(
+          "`compile_target` cannot be used together with {preset}"
+        )));
+      }
+      crate::target::check_runnable(target)?;
+    }
+    #[cfg(feature = "cache")]
+    if let Some(strategy) = self.strategy.filter(|s| {
+      self.cache_options.is_some() && !matches!(s, wasmtime::Strategy::Auto | wasmtime::Strategy::Cranelift)
+    }) {
+      return Err(Error::BuilderInvalidConfig(format!(
+        "the cache requires the Cranelift compilation strategy, {strategy:?} has been selected"
+      )));
+    }
+
+    Ok(())
+  }
+
+  /// Create a [`WasmtimeEngineProviderPre`] instance. This instance can then
+  /// be reused as many time as wanted to quickly instantiate a [`WasmtimeEngineProvider`]
+  /// by using the [`WasmtimeEngineProviderPre::rehydrate`] method.
+  pub fn build_pre(&self) -> Result<WasmtimeEngineProviderPre> {
+    self.validate_config()?;
+    let module_hash = self.module_source()?.map(crate::precompiled::module_hash);
+    self.verify_digest(module_hash.as_deref())?;
 
-    let pre = match &self.engine {
-      Some(e) => {
-        let module = self.module_bytes.as_ref().map_or_else(
-          || Ok(self.module.as_ref().unwrap().clone()),
-          |module_bytes| wasmtime::Module::new(e, module_bytes),
-        )?;
+    let mut compile_source = None;
+
+    let pre = match self.preset_engine() {
+      Some((e, _)) => {
+        let module = self.load_module(e)?;
+        let link_options = self.link_options(e)?;
+        self.check_module(&module, &link_options)?;
 
         // note: we have to call `.clone()` because `e` is behind
         // a shared reference and `Engine` does not implement `Copy`.
@@ -140,50 +1124,54 @@ impl<'a> WasmtimeEngineProviderBuilder<'a> {
         // See https://docs.rs/wasmtime/latest/wasmtime/struct.Engine.html#engines-and-clone
         cfg_if::cfg_if! {
             if #[cfg(feature = "wasi")] {
-                WasmtimeEngineProviderPre::new(e.clone(), module, self.wasi_params.clone(), self.epoch_deadlines)
+                WasmtimeEngineProviderPre::new(e.clone(), module, link_options, self.wasi_params.clone(), self.resolved_epoch_deadlines())
             } else {
-                WasmtimeEngineProviderPre::new(e.clone(), module, self.epoch_deadlines)
+                WasmtimeEngineProviderPre::new(e.clone(), module, link_options, self.resolved_epoch_deadlines())
             }
         }
       }
       None => {
-        let mut config = wasmtime::Config::default();
-        if self.epoch_deadlines.is_some() {
-          config.epoch_interruption(true);
-        }
-
-        cfg_if::cfg_if! {
-            if #[cfg(feature = "cache")] {
-                  if self.cache_enabled {
-                    config.strategy(wasmtime::Strategy::Cranelift);
-                    if let Some(cache) = &self.cache_path {
-                      config.cache_config_load(cache)?;
-                    } else if let Err(e) = config.cache_config_load_default() {
-                      log::warn!("Wasmtime cache configuration not found ({}). Repeated loads will speed up significantly with a cache configuration. See https://docs.wasmtime.dev/cli-cache.html for more information.",e);
-                    }
-                }
-            }
-        }
-
+        let config = self.wasmtime_config()?;
         let engine = wasmtime::Engine::new(&config)?;
+        compile_source = self.module_source()?.map(|module_bytes| CompileSource {
+          config,
+          module_bytes: module_bytes.into(),
+        });
 
-        let module = self.module_bytes.as_ref().map_or_else(
-          || Ok(self.module.as_ref().unwrap().clone()),
-          |module_bytes| wasmtime::Module::new(&engine, module_bytes),
-        )?;
+        let module = self.load_module(&engine)?;
+        let link_options = self.link_options(&engine)?;
+        self.check_module(&module, &link_options)?;
 
         cfg_if::cfg_if! {
             if #[cfg(feature = "wasi")] {
-                WasmtimeEngineProviderPre::new(engine, module, self.wasi_params.clone(), self.epoch_deadlines)
+                WasmtimeEngineProviderPre::new(engine, module, link_options, self.wasi_params.clone(), self.resolved_epoch_deadlines())
             } else {
-                WasmtimeEngineProviderPre::new(engine, module, self.epoch_deadlines)
+                WasmtimeEngineProviderPre::new(engine, module, link_options, self.resolved_epoch_deadlines())
 
             }
         }
       }
     }?;
 
-    Ok(pre)
+    #[cfg(feature = "wasi")]
+    let pre = pre.with_wasi_ctx_hook(self.wasi_ctx_hook.clone());
+
+    Ok(
+      pre
+        .with_call_timings(self.call_timings)
+        .with_memory_grow_callback(self.on_memory_grow.clone())
+        .with_memory_reset(self.reset_memory)
+        .with_max_payload_size(self.max_payload_size)
+        .with_max_host_response(self.max_host_response)
+        .with_fuel_per_call(self.effective_fuel_per_call())
+        .with_host_fn_counters(self.count_host_functions)
+        .with_epoch_ticker(self.epoch_ticker.clone())
+        .with_host_calls_excluded_from_deadline(self.exclude_host_calls_from_deadline)
+        .with_host_call_buffer_reuse(self.reuse_host_call_buffer)
+        .with_compile_source(compile_source.map(std::sync::Arc::new))
+        .with_module_hash(module_hash)
+        .with_name(self.name.as_deref()),
+    )
   }
 
   /// Create a `WasmtimeEngineProvider` instance
@@ -201,23 +1189,20 @@ impl<'a> WasmtimeEngineProviderBuilder<'a> {
   #[cfg(feature = "async")]
   #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
   pub fn build_async_pre(&self) -> Result<WasmtimeEngineProviderAsyncPre> {
-    if self.module_bytes.is_some() && self.module.is_some() {
+    self.validate_config()?;
+    let module_hash = self.module_source()?.map(crate::precompiled::module_hash);
+    self.verify_digest(module_hash.as_deref())?;
+    if self.reuse_host_call_buffer {
       return Err(Error::BuilderInvalidConfig(
-        "`module_bytes` and `module` cannot be provided at the same time".to_owned(),
-      ));
-    }
-    if self.module_bytes.is_none() && self.module.is_none() {
-      return Err(Error::BuilderInvalidConfig(
-        "Neither `module_bytes` nor `module` have been provided".to_owned(),
+        "`reuse_host_call_buffer` cannot be used to build an async provider".to_owned(),
       ));
     }
 
-    let pre = match &self.engine {
-      Some(e) => {
-        let module = self.module_bytes.as_ref().map_or_else(
-          || Ok(self.module.as_ref().unwrap().clone()),
-          |module_bytes| wasmtime::Module::new(e, module_bytes),
-        )?;
+    let pre = match self.preset_engine() {
+      Some((e, _)) => {
+        let module = self.load_module(e)?;
+        let link_options = self.link_options(e)?;
+        self.check_module(&module, &link_options)?;
 
         // note: we have to call `.clone()` because `e` is behind
         // a shared reference and `Engine` does not implement `Copy`.
@@ -227,51 +1212,50 @@ impl<'a> WasmtimeEngineProviderBuilder<'a> {
         // See https://docs.rs/wasmtime/latest/wasmtime/struct.Engine.html#engines-and-clone
         cfg_if::cfg_if! {
             if #[cfg(feature = "wasi")] {
-                WasmtimeEngineProviderAsyncPre::new(e.clone(), module, self.wasi_params.clone(), self.epoch_deadlines)
+                WasmtimeEngineProviderAsyncPre::new(e.clone(), module, link_options, self.wasi_params.clone(), self.resolved_epoch_deadlines())
             } else {
-                WasmtimeEngineProviderAsyncPre::new(e.clone(), module, self.epoch_deadlines)
+                WasmtimeEngineProviderAsyncPre::new(e.clone(), module, link_options, self.resolved_epoch_deadlines())
             }
         }
       }
       None => {
-        let mut config = wasmtime::Config::default();
+        let mut config = self.wasmtime_config()?;
         config.async_support(true);
 
-        if self.epoch_deadlines.is_some() {
-          config.epoch_interruption(true);
-        }
-
-        cfg_if::cfg_if! {
-            if #[cfg(feature = "cache")] {
-                  if self.cache_enabled {
-                    config.strategy(wasmtime::Strategy::Cranelift);
-                    if let Some(cache) = &self.cache_path {
-                      config.cache_config_load(cache)?;
-                    } else if let Err(e) = config.cache_config_load_default() {
-                      log::warn!("Wasmtime cache configuration not found ({}). Repeated loads will speed up significantly with a cache configuration. See https://docs.wasmtime.dev/cli-cache.html for more information.",e);
-                    }
-                }
-            }
-        }
-
         let engine = wasmtime::Engine::new(&config)?;
 
-        let module = self.module_bytes.as_ref().map_or_else(
-          || Ok(self.module.as_ref().unwrap().clone()),
-          |module_bytes| wasmtime::Module::new(&engine, module_bytes),
-        )?;
+        let module = self.load_module(&engine)?;
+        let link_options = self.link_options(&engine)?;
+        self.check_module(&module, &link_options)?;
 
         cfg_if::cfg_if! {
             if #[cfg(feature = "wasi")] {
-                WasmtimeEngineProviderAsyncPre::new(engine, module, self.wasi_params.clone(), self.epoch_deadlines)
+                WasmtimeEngineProviderAsyncPre::new(engine, module, link_options, self.wasi_params.clone(), self.resolved_epoch_deadlines())
             } else {
-                WasmtimeEngineProviderAsyncPre::new(engine, module, self.epoch_deadlines)
+                WasmtimeEngineProviderAsyncPre::new(engine, module, link_options, self.resolved_epoch_deadlines())
             }
         }
       }
     }?;
 
-    Ok(pre)
+    #[cfg(feature = "wasi")]
+    let pre = pre.with_wasi_ctx_hook(self.wasi_ctx_hook_async.clone());
+
+    Ok(
+      pre
+        .with_call_timings(self.call_timings)
+        .with_memory_grow_callback(self.on_memory_grow.clone())
+        .with_memory_reset(self.reset_memory)
+        .with_max_payload_size(self.max_payload_size)
+        .with_max_host_response(self.max_host_response)
+        .with_fuel_per_call(self.effective_fuel_per_call())
+        .with_host_fn_counters(self.count_host_functions)
+        .with_epoch_ticker(self.epoch_ticker.clone())
+        .with_host_calls_excluded_from_deadline(self.exclude_host_calls_from_deadline)
+        .with_runtime_handle(self.runtime_handle.clone())
+        .with_module_hash(module_hash)
+        .with_name(self.name.as_deref()),
+    )
   }
 
   /// Create a `WasmtimeEngineProviderAsync` instance
@@ -281,4 +1265,59 @@ impl<'a> WasmtimeEngineProviderBuilder<'a> {
     let pre = self.build_async_pre()?;
     pre.rehydrate()
   }
+
+  /// Create a [`WasmtimeComponentEngineProvider`] instance from the contents provided via
+  /// [`component_bytes`](WasmtimeEngineProviderBuilder::component_bytes)
+  pub fn build_component(&self) -> Result<WasmtimeComponentEngineProvider> {
+    if self.module_bytes.is_some() || self.module.is_some() || self.module_path.is_some() {
+      return Err(Error::BuilderInvalidConfig(
+        "`module_bytes`, `module` and `module_path` cannot be used to build a component".to_owned(),
+      ));
+    }
+    if self.module_wat.is_some() {
+      return Err(Error::BuilderInvalidConfig(
+        "`module_wat` cannot be used to build a component".to_owned(),
+      ));
+    }
+    let component_bytes = self
+      .component_bytes
+      .ok_or_else(|| Error::BuilderInvalidConfig("`component_bytes` has not been provided".to_owned()))?;
+    #[cfg(not(feature = "wasi"))]
+    if self.wasi_params.is_some() {
+      return Err(Error::WasiDisabled);
+    }
+    if let Some(option) = self.module_only_options().first() {
+      return Err(Error::BuilderInvalidConfig(format!(
+        "`{}` cannot be used to build a component",
+        option
+      )));
+    }
+    #[cfg(feature = "cache")]
+    if self.cache_options.is_some() && self.engine.is_some() {
+      return Err(Error::BuilderInvalidConfig(
+        "the cache cannot be used together with a custom `engine`".to_owned(),
+      ));
+    }
+    self.validate_compiler()?;
+
+    let engine = match &self.engine {
+      Some(e) => e.clone(),
+      None => wasmtime::Engine::new(&self.wasmtime_config()?)?,
+    };
+    let component = wasmtime::component::Component::new(&engine, component_bytes)?;
+    let name = self.name.clone().unwrap_or_else(|| {
+      let mut hash = crate::precompiled::module_hash(component_bytes);
+      hash.truncate(12);
+      hash
+    });
+
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "wasi")] {
+            let provider = WasmtimeComponentEngineProvider::new(engine, component, self.wasi_params.clone(), self.resolved_epoch_deadlines())?;
+        } else {
+            let provider = WasmtimeComponentEngineProvider::new(engine, component, self.resolved_epoch_deadlines())?;
+        }
+    }
+    Ok(provider.with_name(name))
+  }
 }
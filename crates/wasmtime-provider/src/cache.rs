@@ -0,0 +1,108 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::errors::{Error, Result};
+
+static CONFIG_FILE_COUNT: AtomicU64 = AtomicU64::new(0);
+
+/// Fine-grained configuration of the Wasmtime compilation cache
+///
+/// Refer to [`WasmtimeEngineProviderBuilder::enable_cache_with`](crate::WasmtimeEngineProviderBuilder::enable_cache_with)
+/// to use it. Options left to `None` keep the Wasmtime defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheOptions {
+  /// Wasmtime cache configuration file to load instead of the other options. When every option
+  /// is `None`, the default Wasmtime configuration file is loaded if it exists
+  pub config_file: Option<PathBuf>,
+  /// Directory where the compiled artifacts are stored. Must be an absolute path
+  pub directory: Option<PathBuf>,
+  /// Soft limit of the total size of the cache. Once exceeded, the oldest entries are removed
+  /// during the next cleanup
+  pub max_size_bytes: Option<u64>,
+  /// How often the cache is cleaned up. Must be at least one second
+  pub cleanup_interval: Option<Duration>,
+}
+
+impl CacheOptions {
+  // Reject values that Wasmtime would silently replace or fail to parse
+  fn validate(&self) -> Result<()> {
+    if self.config_file.is_some()
+      && (self.directory.is_some() || self.max_size_bytes.is_some() || self.cleanup_interval.is_some())
+    {
+      return Err(Error::BuilderInvalidConfig(
+        "cache `config_file` cannot be combined with the other cache options".to_owned(),
+      ));
+    }
+    if let Some(directory) = &self.directory {
+      if !directory.is_absolute() {
+        return Err(Error::BuilderInvalidConfig(format!(
+          "cache directory must be an absolute path, got {}",
+          directory.display()
+        )));
+      }
+    }
+    if self.max_size_bytes == Some(0) {
+      return Err(Error::BuilderInvalidConfig(
+        "cache `max_size_bytes` must be greater than zero".to_owned(),
+      ));
+    }
+    if self.cleanup_interval.is_some_and(|interval| interval.as_secs() == 0) {
+      return Err(Error::BuilderInvalidConfig(
+        "cache `cleanup_interval` must be at least one second".to_owned(),
+      ));
+    }
+
+    Ok(())
+  }
+
+  // Render the options using the format of the Wasmtime cache configuration file.
+  // See https://docs.wasmtime.dev/cli-cache.html
+  fn to_toml(&self) -> String {
+    let mut toml = "[cache]\nenabled = true\n".to_owned();
+    if let Some(directory) = &self.directory {
+      let directory = directory.to_string_lossy().replace('\\', "\\\\").replace('"', "\\\"");
+      toml.push_str(&format!("directory = \"{directory}\"\n"));
+    }
+    if let Some(max_size_bytes) = self.max_size_bytes {
+      toml.push_str(&format!("files-total-size-soft-limit = \"{max_size_bytes}\"\n"));
+    }
+    if let Some(cleanup_interval) = self.cleanup_interval {
+      toml.push_str(&format!("cleanup-interval = \"{}s\"\n", cleanup_interval.as_secs()));
+    }
+    toml
+  }
+
+  // Wasmtime only reads the cache configuration from a file, hence the options are written
+  // to a temporary file that is removed once it has been loaded
+  pub(crate) fn apply(&self, config: &mut wasmtime::Config) -> Result<()> {
+    self.validate()?;
+
+    if let Some(config_file) = &self.config_file {
+      config.cache_config_load(config_file)?;
+      return Ok(());
+    }
+    if *self == Self::default() {
+      if let Err(e) = config.cache_config_load_default() {
+        log::warn!("Wasmtime cache configuration not found ({}). Repeated loads will speed up significantly with a cache configuration. See https://docs.wasmtime.dev/cli-cache.html for more information.",e);
+      }
+      return Ok(());
+    }
+
+    let path = std::env::temp_dir().join(format!(
+      "wasmtime-provider-cache-{}-{}.toml",
+      std::process::id(),
+      CONFIG_FILE_COUNT.fetch_add(1, Ordering::SeqCst)
+    ));
+    std::fs::write(&path, self.to_toml())
+      .map_err(|e| Error::BuilderInvalidConfig(format!("cannot write cache configuration: {e}")))?;
+
+    let result = config
+      .cache_config_load(&path)
+      .map(|_| ())
+      .map_err(|e| Error::BuilderInvalidConfig(format!("invalid cache configuration: {e}")));
+    let _ = std::fs::remove_file(&path);
+
+    result
+  }
+}
@@ -1,10 +1,17 @@
 use anyhow::anyhow;
 use wapc::{wapc_functions, HOST_NAMESPACE};
-use wasmtime::{AsContext, AsContextMut, Caller, Linker, Memory, StoreContext};
+use wasmtime::{AsContext, AsContextMut, Caller, Extern, Linker, Memory, Module};
 
+use crate::bridge::{self, BridgedImport};
 use crate::errors::{Error, Result};
+use crate::instrument;
+use crate::linking::LinkOptions;
+use crate::payloads::{read_guest_payload, read_guest_payload_into};
 use crate::store::WapcStore;
 
+/// Name of the memory dedicated to the waPC buffers, when exported by the guest
+const WAPC_MEMORY_EXPORT: &str = "__wapc_memory";
+
 pub(crate) fn add_to_linker(linker: &mut Linker<WapcStore>) -> Result<()> {
   register_guest_request_func(linker)?;
   register_console_log_func(linker)?;
@@ -19,12 +26,82 @@ pub(crate) fn add_to_linker(linker: &mut Linker<WapcStore>) -> Result<()> {
   Ok(())
 }
 
+// Forward the imports of the bridged namespaces to the host callback
+pub(crate) fn add_bridge_to_linker(
+  linker: &mut Linker<WapcStore>,
+  module: &Module,
+  link_options: &LinkOptions,
+) -> Result<()> {
+  let imports = bridge::bridged_imports(module, link_options)?;
+  if imports.is_empty() {
+    return Ok(());
+  }
+
+  // a hot swapped module redefines the functions bridged for the previous one
+  linker.allow_shadowing(true);
+  let result = imports
+    .into_iter()
+    .try_for_each(|import| register_bridged_func(linker, import));
+  linker.allow_shadowing(false);
+
+  result
+}
+
+fn register_bridged_func(linker: &mut Linker<WapcStore>, import: BridgedImport) -> Result<()> {
+  let BridgedImport {
+    binding,
+    namespace,
+    operation,
+    ty,
+  } = import;
+  let func = format!("{}.{}", namespace, operation);
+
+  linker
+    .func_new(
+      &namespace.clone(),
+      &operation.clone(),
+      ty,
+      move |mut caller: Caller<'_, WapcStore>, params, results| {
+        let host = caller
+          .data()
+          .host
+          .as_ref()
+          .ok_or_else(|| anyhow!("host should have been set during the init"))?
+          .clone();
+
+        let payload = bridge::encode_params(params)?;
+        let max_host_response = caller.data().max_host_response;
+        let span = instrument::host_call_span(
+          &caller.data().provider_name,
+          host.id(),
+          &binding,
+          &namespace,
+          &operation,
+          payload.len(),
+        );
+        let result = span.run(|| {
+          host
+            .do_host_call_with_limit(&binding, &namespace, &operation, &payload, max_host_response)
+            .unwrap_or(0)
+        });
+        skip_host_call_ticks(&mut caller);
+        if result == 0 {
+          return Err(anyhow!(host.get_host_error().unwrap_or_default()));
+        }
+        bridge::decode_result(&host.get_host_response().unwrap_or_default(), results)
+      },
+    )
+    .map_err(|e| Error::LinkerFuncDef { func, err: e.into() })?;
+  Ok(())
+}
+
 fn register_guest_request_func(linker: &mut Linker<WapcStore>) -> Result<()> {
   linker
     .func_wrap(
       HOST_NAMESPACE,
       wapc_functions::GUEST_REQUEST_FN,
       |mut caller: Caller<'_, WapcStore>, op_ptr: i32, ptr: i32| {
+        count_host_fn(&mut caller, wapc_functions::GUEST_REQUEST_FN);
         let host = caller
           .data()
           .host
@@ -41,7 +118,7 @@ fn register_guest_request_func(linker: &mut Linker<WapcStore>) -> Result<()> {
     )
     .map_err(|e| Error::LinkerFuncDef {
       func: format!("{}.{}", HOST_NAMESPACE, wapc_functions::GUEST_REQUEST_FN),
-      err: e.to_string(),
+      err: e.into(),
     })?;
   Ok(())
 }
@@ -52,24 +129,27 @@ fn register_console_log_func(linker: &mut Linker<WapcStore>) -> Result<()> {
       HOST_NAMESPACE,
       wapc_functions::HOST_CONSOLE_LOG,
       |mut caller: Caller<'_, WapcStore>, ptr: i32, len: i32| {
+        count_host_fn(&mut caller, wapc_functions::HOST_CONSOLE_LOG);
         let memory = get_caller_memory(&mut caller)?;
         let host = caller
           .data()
           .host
           .as_ref()
           .ok_or_else(|| anyhow!("host should have been set during the init"))?;
-        let vec = get_vec_from_memory(caller.as_context(), memory, ptr, len);
+        let vec = read_guest_payload(caller.as_context(), memory, ptr, len, None, "console_log message")
+          .map_err(|e| anyhow!(e))?;
 
         let msg = std::str::from_utf8(&vec)
           .map_err(|e| anyhow!(format!("console_log: cannot convert message to UTF8: {:?}", e)))?;
 
+        instrument::console_log(&caller.data().provider_name, host.id(), vec.len());
         host.do_console_log(msg);
         Ok(())
       },
     )
     .map_err(|e| Error::LinkerFuncDef {
       func: format!("{}.{}", HOST_NAMESPACE, wapc_functions::HOST_CONSOLE_LOG),
-      err: e.to_string(),
+      err: e.into(),
     })?;
   Ok(())
 }
@@ -88,32 +168,65 @@ fn register_host_call_func(linker: &mut Linker<WapcStore>) -> Result<()> {
        op_len: i32,
        ptr: i32,
        len: i32| {
+        count_host_fn(&mut caller, wapc_functions::HOST_CALL);
+        if let Some(collector) = caller.data_mut().call_timings.as_mut() {
+          collector.record_host_call();
+        }
+
         let memory = get_caller_memory(&mut caller)?;
 
+        // the buffer is only kept by the store when its reuse has been enabled
+        let reuse_buffer = caller.data().host_call_buffer.is_some();
+        let mut payload = caller.data_mut().host_call_buffer.take().unwrap_or_default();
+
         let host = caller
           .data()
           .host
           .as_ref()
           .ok_or_else(|| anyhow!("host should have been set during the init"))?;
 
-        let vec = get_vec_from_memory(caller.as_context(), memory, ptr, len);
-        let bd_vec = get_vec_from_memory(caller.as_context(), memory, bd_ptr, bd_len);
+        let max_payload_size = caller.data().max_payload_size;
+        if let Err(e) = read_guest_payload_into(
+          caller.as_context(),
+          memory,
+          ptr,
+          len,
+          max_payload_size,
+          "host call payload",
+          &mut payload,
+        ) {
+          host.set_guest_error(e);
+          if reuse_buffer {
+            caller.data_mut().host_call_buffer = Some(payload);
+          }
+          return Ok(0);
+        }
+        let bd_vec = read_guest_payload(caller.as_context(), memory, bd_ptr, bd_len, None, "host call binding")
+          .map_err(|e| anyhow!(e))?;
         let bd = std::str::from_utf8(&bd_vec)
           .map_err(|e| anyhow!(format!("host_call: cannot convert bd to UTF8: {:?}", e)))?;
-        let ns_vec = get_vec_from_memory(caller.as_context(), memory, ns_ptr, ns_len);
+        let ns_vec = read_guest_payload(caller.as_context(), memory, ns_ptr, ns_len, None, "host call namespace")
+          .map_err(|e| anyhow!(e))?;
         let ns = std::str::from_utf8(&ns_vec)
           .map_err(|e| anyhow!(format!("host_call: cannot convert ns to UTF8: {:?}", e)))?;
-        let op_vec = get_vec_from_memory(caller.as_context(), memory, op_ptr, op_len);
+        let op_vec = read_guest_payload(caller.as_context(), memory, op_ptr, op_len, None, "host call operation")
+          .map_err(|e| anyhow!(e))?;
         let op = std::str::from_utf8(&op_vec)
           .map_err(|e| anyhow!(format!("host_call: cannot convert op to UTF8: {:?}", e)))?;
 
-        let result = host.do_host_call(bd, ns, op, &vec);
-        Ok(result.unwrap_or(0))
+        let max_host_response = caller.data().max_host_response;
+        let span = instrument::host_call_span(&caller.data().provider_name, host.id(), bd, ns, op, payload.len());
+        let result = span.run(|| host.do_host_call_with_limit(bd, ns, op, &payload, max_host_response).unwrap_or(0));
+        skip_host_call_ticks(&mut caller);
+        if reuse_buffer {
+          caller.data_mut().host_call_buffer = Some(payload);
+        }
+        Ok(result)
       },
     )
     .map_err(|e| Error::LinkerFuncDef {
       func: format!("{}.{}", HOST_NAMESPACE, wapc_functions::HOST_CALL),
-      err: e.to_string(),
+      err: e.into(),
     })?;
   Ok(())
 }
@@ -124,6 +237,7 @@ fn register_host_response_func(linker: &mut Linker<WapcStore>) -> Result<()> {
       HOST_NAMESPACE,
       wapc_functions::HOST_RESPONSE_FN,
       |mut caller: Caller<'_, WapcStore>, ptr: i32| {
+        count_host_fn(&mut caller, wapc_functions::HOST_RESPONSE_FN);
         let memory = get_caller_memory(&mut caller)?;
         let host = caller
           .data()
@@ -139,7 +253,7 @@ fn register_host_response_func(linker: &mut Linker<WapcStore>) -> Result<()> {
     )
     .map_err(|e| Error::LinkerFuncDef {
       func: format!("{}.{}", HOST_NAMESPACE, wapc_functions::HOST_RESPONSE_FN),
-      err: e.to_string(),
+      err: e.into(),
     })?;
   Ok(())
 }
@@ -149,7 +263,8 @@ fn register_host_response_len_func(linker: &mut Linker<WapcStore>) -> Result<()>
     .func_wrap(
       HOST_NAMESPACE,
       wapc_functions::HOST_RESPONSE_LEN_FN,
-      |caller: Caller<'_, WapcStore>| {
+      |mut caller: Caller<'_, WapcStore>| {
+        count_host_fn(&mut caller, wapc_functions::HOST_RESPONSE_LEN_FN);
         let host = caller
           .data()
           .host
@@ -162,7 +277,7 @@ fn register_host_response_len_func(linker: &mut Linker<WapcStore>) -> Result<()>
     )
     .map_err(|e| Error::LinkerFuncDef {
       func: format!("{}.{}", HOST_NAMESPACE, wapc_functions::HOST_RESPONSE_LEN_FN),
-      err: e.to_string(),
+      err: e.into(),
     })?;
   Ok(())
 }
@@ -173,6 +288,7 @@ fn register_guest_response_func(linker: &mut Linker<WapcStore>) -> Result<()> {
       HOST_NAMESPACE,
       wapc_functions::GUEST_RESPONSE_FN,
       |mut caller: Caller<'_, WapcStore>, ptr: i32, len: i32| {
+        count_host_fn(&mut caller, wapc_functions::GUEST_RESPONSE_FN);
         let memory = get_caller_memory(&mut caller)?;
 
         let host = caller
@@ -181,14 +297,27 @@ fn register_guest_response_func(linker: &mut Linker<WapcStore>) -> Result<()> {
           .as_ref()
           .ok_or_else(|| anyhow!("host should have been set during the init"))?;
 
-        let vec = get_vec_from_memory(caller.as_context(), memory, ptr, len);
-        host.set_guest_response(vec);
+        let max_payload_size = caller.data().max_payload_size;
+        match read_guest_payload(
+          caller.as_context(),
+          memory,
+          ptr,
+          len,
+          max_payload_size,
+          "guest response",
+        ) {
+          Ok(vec) => {
+            instrument::guest_response(&caller.data().provider_name, host.id(), vec.len());
+            host.set_guest_response(vec);
+          }
+          Err(e) => host.set_guest_error(e),
+        }
         Ok(())
       },
     )
     .map_err(|e| Error::LinkerFuncDef {
       func: format!("{}.{}", HOST_NAMESPACE, wapc_functions::GUEST_RESPONSE_FN),
-      err: e.to_string(),
+      err: e.into(),
     })?;
   Ok(())
 }
@@ -199,6 +328,7 @@ fn register_guest_error_func(linker: &mut Linker<WapcStore>) -> Result<()> {
       HOST_NAMESPACE,
       wapc_functions::GUEST_ERROR_FN,
       |mut caller: Caller<'_, WapcStore>, ptr: i32, len: i32| {
+        count_host_fn(&mut caller, wapc_functions::GUEST_ERROR_FN);
         let memory = get_caller_memory(&mut caller)?;
         let host = caller
           .data()
@@ -206,16 +336,21 @@ fn register_guest_error_func(linker: &mut Linker<WapcStore>) -> Result<()> {
           .as_ref()
           .ok_or_else(|| anyhow!("host should have been set during the init"))?;
 
-        let vec = get_vec_from_memory(caller.as_context(), memory, ptr, len);
-        let guest_err_msg = String::from_utf8(vec)
-          .map_err(|e| anyhow!(format!("guest_error_func: cannot convert message to UTF8: {:?}", e)))?;
+        let max_payload_size = caller.data().max_payload_size;
+        let guest_err_msg =
+          match read_guest_payload(caller.as_context(), memory, ptr, len, max_payload_size, "guest error") {
+            Ok(vec) => String::from_utf8(vec)
+              .map_err(|e| anyhow!(format!("guest_error_func: cannot convert message to UTF8: {:?}", e)))?,
+            Err(e) => e,
+          };
+        instrument::guest_error(&caller.data().provider_name, host.id(), guest_err_msg.len());
         host.set_guest_error(guest_err_msg);
         Ok(())
       },
     )
     .map_err(|e| Error::LinkerFuncDef {
       func: format!("{}.{}", HOST_NAMESPACE, wapc_functions::GUEST_ERROR_FN),
-      err: e.to_string(),
+      err: e.into(),
     })?;
   Ok(())
 }
@@ -226,6 +361,7 @@ fn register_host_error_func(linker: &mut Linker<WapcStore>) -> Result<()> {
       HOST_NAMESPACE,
       wapc_functions::HOST_ERROR_FN,
       |mut caller: Caller<'_, WapcStore>, ptr: i32| {
+        count_host_fn(&mut caller, wapc_functions::HOST_ERROR_FN);
         let memory = get_caller_memory(&mut caller)?;
         let host = caller
           .data()
@@ -241,7 +377,7 @@ fn register_host_error_func(linker: &mut Linker<WapcStore>) -> Result<()> {
     )
     .map_err(|e| Error::LinkerFuncDef {
       func: format!("{}.{}", HOST_NAMESPACE, wapc_functions::HOST_ERROR_FN),
-      err: e.to_string(),
+      err: e.into(),
     })?;
   Ok(())
 }
@@ -251,7 +387,8 @@ fn register_host_error_len_func(linker: &mut Linker<WapcStore>) -> Result<()> {
     .func_wrap(
       HOST_NAMESPACE,
       wapc_functions::HOST_ERROR_LEN_FN,
-      |caller: Caller<'_, WapcStore>| {
+      |mut caller: Caller<'_, WapcStore>| {
+        count_host_fn(&mut caller, wapc_functions::HOST_ERROR_LEN_FN);
         let host = caller
           .data()
           .host
@@ -264,12 +401,26 @@ fn register_host_error_len_func(linker: &mut Linker<WapcStore>) -> Result<()> {
     )
     .map_err(|e| Error::LinkerFuncDef {
       func: format!("{}.{}", HOST_NAMESPACE, wapc_functions::HOST_ERROR_LEN_FN),
-      err: e.to_string(),
+      err: e.into(),
     })?;
   Ok(())
 }
 
-fn get_caller_memory<T>(caller: &mut Caller<T>) -> anyhow::Result<Memory> {
+// When host calls are excluded from the deadline of the guest, set the deadline again one tick
+// beyond the current epoch: the ticks elapsed inside of the host callback are not charged
+fn skip_host_call_ticks(caller: &mut Caller<'_, WapcStore>) {
+  if caller.data().guest_ticks.is_counting() {
+    caller.as_context_mut().set_epoch_deadline(1);
+  }
+}
+
+// The waPC buffers live inside of the dedicated `__wapc_memory` when the guest exports one
+// (multi-memory proposal), the pointers given by the guest are then relative to it. Otherwise
+// they live inside of the main `memory`.
+pub(crate) fn get_caller_memory<T>(caller: &mut Caller<'_, T>) -> anyhow::Result<Memory> {
+  if let Some(Extern::Memory(memory)) = caller.get_export(WAPC_MEMORY_EXPORT) {
+    return Ok(memory);
+  }
   let memory_export = caller
     .get_export("memory")
     .ok_or_else(|| anyhow!("Cannot find 'mem' export"))?;
@@ -278,13 +429,41 @@ fn get_caller_memory<T>(caller: &mut Caller<T>) -> anyhow::Result<Memory> {
     .ok_or_else(|| anyhow!("'mem' export cannot be converted into a Memory instance"))
 }
 
-fn get_vec_from_memory<'a, T: 'a>(store: impl Into<StoreContext<'a, T>>, mem: Memory, ptr: i32, len: i32) -> Vec<u8> {
-  let data = mem.data(store);
-  data[ptr as usize..(ptr + len) as usize].to_vec()
+// Count the invocations of the waPC host functions, when enabled
+fn count_host_fn(caller: &mut Caller<'_, WapcStore>, name: &'static str) {
+  if let Some(counters) = caller.data_mut().host_fn_counters.as_mut() {
+    *counters.entry(name).or_default() += 1;
+  }
 }
 
+// The waPC pointers are unsigned 32-bit addresses, even inside of a 64-bit memory
 fn write_bytes_to_memory(store: impl AsContextMut, memory: Memory, ptr: i32, slice: &[u8]) -> anyhow::Result<()> {
   memory
-    .write(store, ptr as usize, slice)
+    .write(store, ptr as u32 as usize, slice)
     .map_err(|e| anyhow!(e.to_string()))
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn duplicate_definition_keeps_the_source_error() {
+    let engine = wasmtime::Engine::default();
+    let mut linker: Linker<WapcStore> = Linker::new(&engine);
+    add_to_linker(&mut linker).unwrap();
+
+    let err = add_to_linker(&mut linker).unwrap_err();
+    let Error::LinkerFuncDef { func, .. } = &err else {
+      panic!("unexpected error: {err}");
+    };
+    assert_eq!(
+      func,
+      &format!("{}.{}", HOST_NAMESPACE, wapc_functions::GUEST_REQUEST_FN)
+    );
+
+    let source = std::error::Error::source(&err).unwrap();
+    assert!(source.to_string().contains("defined twice"), "{source}");
+    assert!(err.to_string().ends_with(&source.to_string()), "{err}");
+  }
+}
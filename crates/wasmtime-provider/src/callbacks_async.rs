@@ -1,8 +1,13 @@
 use anyhow::anyhow;
 use wapc::{wapc_functions, HOST_NAMESPACE};
-use wasmtime::{AsContext, AsContextMut, Caller, Linker, Memory, StoreContext};
+use wasmtime::{AsContext, AsContextMut, Caller, Linker, Memory, Module};
 
+use crate::bridge::{self, BridgedImport};
+use crate::callbacks::get_caller_memory;
 use crate::errors::{Error, Result};
+use crate::instrument;
+use crate::linking::LinkOptions;
+use crate::payloads::read_guest_payload;
 use crate::store_async::WapcStoreAsync;
 
 pub(crate) fn add_to_linker(linker: &mut Linker<WapcStoreAsync>) -> Result<()> {
@@ -19,12 +24,84 @@ pub(crate) fn add_to_linker(linker: &mut Linker<WapcStoreAsync>) -> Result<()> {
   Ok(())
 }
 
+// Forward the imports of the bridged namespaces to the host callback
+pub(crate) fn add_bridge_to_linker(
+  linker: &mut Linker<WapcStoreAsync>,
+  module: &Module,
+  link_options: &LinkOptions,
+) -> Result<()> {
+  let imports = bridge::bridged_imports(module, link_options)?;
+  if imports.is_empty() {
+    return Ok(());
+  }
+
+  // a hot swapped module redefines the functions bridged for the previous one
+  linker.allow_shadowing(true);
+  let result = imports
+    .into_iter()
+    .try_for_each(|import| register_bridged_func(linker, import));
+  linker.allow_shadowing(false);
+
+  result
+}
+
+fn register_bridged_func(linker: &mut Linker<WapcStoreAsync>, import: BridgedImport) -> Result<()> {
+  let BridgedImport {
+    binding,
+    namespace,
+    operation,
+    ty,
+  } = import;
+  let func = format!("{}.{}", namespace, operation);
+
+  linker
+    .func_new_async(
+      &namespace.clone(),
+      &operation.clone(),
+      ty,
+      move |mut caller: Caller<'_, WapcStoreAsync>, params, results| {
+        let binding = binding.clone();
+        let namespace = namespace.clone();
+        let operation = operation.clone();
+        Box::new(async move {
+          let host = caller
+            .data()
+            .host
+            .as_ref()
+            .ok_or_else(|| anyhow!("host should have been set during the init"))?
+            .clone();
+
+          let payload = bridge::encode_params(params)?;
+          let max_host_response = caller.data().max_host_response;
+          let span = instrument::host_call_span(
+            &caller.data().provider_name,
+            host.id(),
+            &binding,
+            &namespace,
+            &operation,
+            payload.len(),
+          );
+          let call = host.do_host_call_with_limit(binding, namespace, operation, payload, max_host_response);
+          let result = span.run_async(async { call.await.unwrap_or(0) }).await;
+          skip_host_call_ticks(&mut caller);
+          if result == 0 {
+            return Err(anyhow!(host.get_host_error().await.unwrap_or_default()));
+          }
+          bridge::decode_result(&host.get_host_response().await.unwrap_or_default(), results)
+        })
+      },
+    )
+    .map_err(|e| Error::LinkerFuncDef { func, err: e.into() })?;
+  Ok(())
+}
+
 fn register_guest_request_func(linker: &mut Linker<WapcStoreAsync>) -> Result<()> {
   linker
     .func_wrap_async(
       HOST_NAMESPACE,
       wapc_functions::GUEST_REQUEST_FN,
       |mut caller: Caller<'_, WapcStoreAsync>, (op_ptr, ptr): (i32, i32)| {
+        count_host_fn(&mut caller, wapc_functions::GUEST_REQUEST_FN);
         Box::new(async move {
           let host = caller
             .data()
@@ -43,7 +120,7 @@ fn register_guest_request_func(linker: &mut Linker<WapcStoreAsync>) -> Result<()
     )
     .map_err(|e| Error::LinkerFuncDef {
       func: format!("{}.{}", HOST_NAMESPACE, wapc_functions::GUEST_REQUEST_FN),
-      err: e.to_string(),
+      err: e.into(),
     })?;
   Ok(())
 }
@@ -54,6 +131,7 @@ fn register_console_log_func(linker: &mut Linker<WapcStoreAsync>) -> Result<()>
       HOST_NAMESPACE,
       wapc_functions::HOST_CONSOLE_LOG,
       |mut caller: Caller<'_, WapcStoreAsync>, (ptr, len): (i32, i32)| {
+        count_host_fn(&mut caller, wapc_functions::HOST_CONSOLE_LOG);
         Box::new(async move {
           let memory = get_caller_memory(&mut caller)?;
           let host = caller
@@ -61,11 +139,13 @@ fn register_console_log_func(linker: &mut Linker<WapcStoreAsync>) -> Result<()>
             .host
             .as_ref()
             .ok_or_else(|| anyhow!("host should have been set during the init"))?;
-          let vec = get_vec_from_memory(caller.as_context(), memory, ptr, len);
+          let vec = read_guest_payload(caller.as_context(), memory, ptr, len, None, "console_log message")
+            .map_err(|e| anyhow!(e))?;
 
           let msg = std::str::from_utf8(&vec)
             .map_err(|e| anyhow!(format!("console_log: cannot convert message to UTF8: {:?}", e)))?;
 
+          instrument::console_log(&caller.data().provider_name, host.id(), vec.len());
           host.do_console_log(msg);
           Ok(())
         })
@@ -73,7 +153,7 @@ fn register_console_log_func(linker: &mut Linker<WapcStoreAsync>) -> Result<()>
     )
     .map_err(|e| Error::LinkerFuncDef {
       func: format!("{}.{}", HOST_NAMESPACE, wapc_functions::HOST_CONSOLE_LOG),
-      err: e.to_string(),
+      err: e.into(),
     })?;
   Ok(())
 }
@@ -85,7 +165,12 @@ fn register_host_call_func(linker: &mut Linker<WapcStoreAsync>) -> Result<()> {
       wapc_functions::HOST_CALL,
       |mut caller: Caller<'_, WapcStoreAsync>,
        (bd_ptr, bd_len, ns_ptr, ns_len, op_ptr, op_len, ptr, len): (i32, i32, i32, i32, i32, i32, i32, i32)| {
+        count_host_fn(&mut caller, wapc_functions::HOST_CALL);
         Box::new(async move {
+          if let Some(collector) = caller.data_mut().call_timings.as_mut() {
+            collector.record_host_call();
+          }
+
           let memory = get_caller_memory(&mut caller)?;
 
           let host = caller
@@ -94,28 +179,49 @@ fn register_host_call_func(linker: &mut Linker<WapcStoreAsync>) -> Result<()> {
             .as_ref()
             .ok_or_else(|| anyhow!("host should have been set during the init"))?;
 
-          let vec = get_vec_from_memory(caller.as_context(), memory, ptr, len);
-          let bd_vec = get_vec_from_memory(caller.as_context(), memory, bd_ptr, bd_len);
+          let max_payload_size = caller.data().max_payload_size;
+          let vec = match read_guest_payload(
+            caller.as_context(),
+            memory,
+            ptr,
+            len,
+            max_payload_size,
+            "host call payload",
+          ) {
+            Ok(vec) => vec,
+            Err(e) => {
+              host.set_guest_error(e).await;
+              return Ok(0);
+            }
+          };
+          let bd_vec = read_guest_payload(caller.as_context(), memory, bd_ptr, bd_len, None, "host call binding")
+            .map_err(|e| anyhow!(e))?;
           let bd = std::str::from_utf8(&bd_vec)
             .map_err(|e| anyhow!(format!("host_call: cannot convert bd to UTF8: {:?}", e)))?
             .to_owned();
-          let ns_vec = get_vec_from_memory(caller.as_context(), memory, ns_ptr, ns_len);
+          let ns_vec = read_guest_payload(caller.as_context(), memory, ns_ptr, ns_len, None, "host call namespace")
+            .map_err(|e| anyhow!(e))?;
           let ns = std::str::from_utf8(&ns_vec)
             .map_err(|e| anyhow!(format!("host_call: cannot convert ns to UTF8: {:?}", e)))?
             .to_owned();
-          let op_vec = get_vec_from_memory(caller.as_context(), memory, op_ptr, op_len);
+          let op_vec = read_guest_payload(caller.as_context(), memory, op_ptr, op_len, None, "host call operation")
+            .map_err(|e| anyhow!(e))?;
           let op = std::str::from_utf8(&op_vec)
             .map_err(|e| anyhow!(format!("host_call: cannot convert op to UTF8: {:?}", e)))?
             .to_owned();
 
-          let result = host.do_host_call(bd, ns, op, vec).await;
-          Ok(result.unwrap_or(0))
+          let max_host_response = caller.data().max_host_response;
+          let span = instrument::host_call_span(&caller.data().provider_name, host.id(), &bd, &ns, &op, vec.len());
+          let call = host.do_host_call_with_limit(bd, ns, op, vec, max_host_response);
+          let result = span.run_async(async { call.await.unwrap_or(0) }).await;
+          skip_host_call_ticks(&mut caller);
+          Ok(result)
         })
       },
     )
     .map_err(|e| Error::LinkerFuncDef {
       func: format!("{}.{}", HOST_NAMESPACE, wapc_functions::HOST_CALL),
-      err: e.to_string(),
+      err: e.into(),
     })?;
   Ok(())
 }
@@ -126,6 +232,7 @@ fn register_host_response_func(linker: &mut Linker<WapcStoreAsync>) -> Result<()
       HOST_NAMESPACE,
       wapc_functions::HOST_RESPONSE_FN,
       |mut caller: Caller<'_, WapcStoreAsync>, (ptr,): (i32,)| {
+        count_host_fn(&mut caller, wapc_functions::HOST_RESPONSE_FN);
         Box::new(async move {
           let memory = get_caller_memory(&mut caller)?;
           let host = caller
@@ -143,7 +250,7 @@ fn register_host_response_func(linker: &mut Linker<WapcStoreAsync>) -> Result<()
     )
     .map_err(|e| Error::LinkerFuncDef {
       func: format!("{}.{}", HOST_NAMESPACE, wapc_functions::HOST_RESPONSE_FN),
-      err: e.to_string(),
+      err: e.into(),
     })?;
   Ok(())
 }
@@ -153,7 +260,8 @@ fn register_host_response_len_func(linker: &mut Linker<WapcStoreAsync>) -> Resul
     .func_wrap_async(
       HOST_NAMESPACE,
       wapc_functions::HOST_RESPONSE_LEN_FN,
-      |caller: Caller<'_, WapcStoreAsync>, ()| {
+      |mut caller: Caller<'_, WapcStoreAsync>, ()| {
+        count_host_fn(&mut caller, wapc_functions::HOST_RESPONSE_LEN_FN);
         Box::new(async move {
           let host = caller
             .data()
@@ -168,7 +276,7 @@ fn register_host_response_len_func(linker: &mut Linker<WapcStoreAsync>) -> Resul
     )
     .map_err(|e| Error::LinkerFuncDef {
       func: format!("{}.{}", HOST_NAMESPACE, wapc_functions::HOST_RESPONSE_LEN_FN),
-      err: e.to_string(),
+      err: e.into(),
     })?;
   Ok(())
 }
@@ -179,6 +287,7 @@ fn register_guest_response_func(linker: &mut Linker<WapcStoreAsync>) -> Result<(
       HOST_NAMESPACE,
       wapc_functions::GUEST_RESPONSE_FN,
       |mut caller: Caller<'_, WapcStoreAsync>, (ptr, len): (i32, i32)| {
+        count_host_fn(&mut caller, wapc_functions::GUEST_RESPONSE_FN);
         Box::new(async move {
           let memory = get_caller_memory(&mut caller)?;
 
@@ -188,15 +297,28 @@ fn register_guest_response_func(linker: &mut Linker<WapcStoreAsync>) -> Result<(
             .as_ref()
             .ok_or_else(|| anyhow!("host should have been set during the init"))?;
 
-          let vec = get_vec_from_memory(caller.as_context(), memory, ptr, len);
-          host.set_guest_response(vec).await;
+          let max_payload_size = caller.data().max_payload_size;
+          match read_guest_payload(
+            caller.as_context(),
+            memory,
+            ptr,
+            len,
+            max_payload_size,
+            "guest response",
+          ) {
+            Ok(vec) => {
+              instrument::guest_response(&caller.data().provider_name, host.id(), vec.len());
+              host.set_guest_response(vec).await;
+            }
+            Err(e) => host.set_guest_error(e).await,
+          }
           Ok(())
         })
       },
     )
     .map_err(|e| Error::LinkerFuncDef {
       func: format!("{}.{}", HOST_NAMESPACE, wapc_functions::GUEST_RESPONSE_FN),
-      err: e.to_string(),
+      err: e.into(),
     })?;
   Ok(())
 }
@@ -207,6 +329,7 @@ fn register_guest_error_func(linker: &mut Linker<WapcStoreAsync>) -> Result<()>
       HOST_NAMESPACE,
       wapc_functions::GUEST_ERROR_FN,
       |mut caller: Caller<'_, WapcStoreAsync>, (ptr, len): (i32, i32)| {
+        count_host_fn(&mut caller, wapc_functions::GUEST_ERROR_FN);
         Box::new(async move {
           let memory = get_caller_memory(&mut caller)?;
           let host = caller
@@ -215,9 +338,14 @@ fn register_guest_error_func(linker: &mut Linker<WapcStoreAsync>) -> Result<()>
             .as_ref()
             .ok_or_else(|| anyhow!("host should have been set during the init"))?;
 
-          let vec = get_vec_from_memory(caller.as_context(), memory, ptr, len);
-          let guest_err_msg = String::from_utf8(vec)
-            .map_err(|e| anyhow!(format!("guest_error_func: cannot convert message to UTF8: {:?}", e)))?;
+          let max_payload_size = caller.data().max_payload_size;
+          let guest_err_msg =
+            match read_guest_payload(caller.as_context(), memory, ptr, len, max_payload_size, "guest error") {
+              Ok(vec) => String::from_utf8(vec)
+                .map_err(|e| anyhow!(format!("guest_error_func: cannot convert message to UTF8: {:?}", e)))?,
+              Err(e) => e,
+            };
+          instrument::guest_error(&caller.data().provider_name, host.id(), guest_err_msg.len());
           host.set_guest_error(guest_err_msg).await;
           Ok(())
         })
@@ -225,7 +353,7 @@ fn register_guest_error_func(linker: &mut Linker<WapcStoreAsync>) -> Result<()>
     )
     .map_err(|e| Error::LinkerFuncDef {
       func: format!("{}.{}", HOST_NAMESPACE, wapc_functions::GUEST_ERROR_FN),
-      err: e.to_string(),
+      err: e.into(),
     })?;
   Ok(())
 }
@@ -236,6 +364,7 @@ fn register_host_error_func(linker: &mut Linker<WapcStoreAsync>) -> Result<()> {
       HOST_NAMESPACE,
       wapc_functions::HOST_ERROR_FN,
       |mut caller: Caller<'_, WapcStoreAsync>, (ptr,): (i32,)| {
+        count_host_fn(&mut caller, wapc_functions::HOST_ERROR_FN);
         Box::new(async move {
           let memory = get_caller_memory(&mut caller)?;
           let host = caller
@@ -253,7 +382,7 @@ fn register_host_error_func(linker: &mut Linker<WapcStoreAsync>) -> Result<()> {
     )
     .map_err(|e| Error::LinkerFuncDef {
       func: format!("{}.{}", HOST_NAMESPACE, wapc_functions::HOST_ERROR_FN),
-      err: e.to_string(),
+      err: e.into(),
     })?;
   Ok(())
 }
@@ -263,7 +392,8 @@ fn register_host_error_len_func(linker: &mut Linker<WapcStoreAsync>) -> Result<(
     .func_wrap_async(
       HOST_NAMESPACE,
       wapc_functions::HOST_ERROR_LEN_FN,
-      |caller: Caller<'_, WapcStoreAsync>, ()| {
+      |mut caller: Caller<'_, WapcStoreAsync>, ()| {
+        count_host_fn(&mut caller, wapc_functions::HOST_ERROR_LEN_FN);
         Box::new(async move {
           let host = caller
             .data()
@@ -278,27 +408,29 @@ fn register_host_error_len_func(linker: &mut Linker<WapcStoreAsync>) -> Result<(
     )
     .map_err(|e| Error::LinkerFuncDef {
       func: format!("{}.{}", HOST_NAMESPACE, wapc_functions::HOST_ERROR_LEN_FN),
-      err: e.to_string(),
+      err: e.into(),
     })?;
   Ok(())
 }
 
-fn get_caller_memory<T>(caller: &mut Caller<T>) -> anyhow::Result<Memory> {
-  let memory_export = caller
-    .get_export("memory")
-    .ok_or_else(|| anyhow!("Cannot find 'mem' export"))?;
-  memory_export
-    .into_memory()
-    .ok_or_else(|| anyhow!("'mem' export cannot be converted into a Memory instance"))
+// When host calls are excluded from the deadline of the guest, set the deadline again one tick
+// beyond the current epoch: the ticks elapsed inside of the host callback are not charged
+fn skip_host_call_ticks(caller: &mut Caller<'_, WapcStoreAsync>) {
+  if caller.data().guest_ticks.is_counting() {
+    caller.as_context_mut().set_epoch_deadline(1);
+  }
 }
 
-fn get_vec_from_memory<'a, T: 'a>(store: impl Into<StoreContext<'a, T>>, mem: Memory, ptr: i32, len: i32) -> Vec<u8> {
-  let data = mem.data(store);
-  data[ptr as usize..(ptr + len) as usize].to_vec()
+// Count the invocations of the waPC host functions, when enabled
+fn count_host_fn(caller: &mut Caller<'_, WapcStoreAsync>, name: &'static str) {
+  if let Some(counters) = caller.data_mut().host_fn_counters.as_mut() {
+    *counters.entry(name).or_default() += 1;
+  }
 }
 
+// The waPC pointers are unsigned 32-bit addresses, even inside of a 64-bit memory
 fn write_bytes_to_memory(store: impl AsContextMut, memory: Memory, ptr: i32, slice: &[u8]) -> anyhow::Result<()> {
   memory
-    .write(store, ptr as usize, slice)
+    .write(store, ptr as u32 as usize, slice)
     .map_err(|e| anyhow!(e.to_string()))
 }
@@ -0,0 +1,269 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use log::{error, info};
+#[cfg(feature = "wasi")]
+use wapc::WasiParams;
+use wapc::{ModuleState, WebAssemblyEngineProvider};
+use wasmtime::component::{Component, Linker, TypedFunc};
+use wasmtime::{Engine, Store, StoreContextMut};
+
+use crate::errors::{Error, Result};
+use crate::store_component::WapcComponentStore;
+use crate::traps;
+use crate::EpochDeadlines;
+
+/// Name of the WIT interface that provides the waPC host functions to the guest
+const HOST_INTERFACE: &str = "wapc:guest/host@0.1.0";
+/// Name of the function exported by waPC guest components
+const GUEST_CALL: &str = "guest-call";
+
+type GuestCallFn = TypedFunc<(String, Vec<u8>), (std::result::Result<Vec<u8>, String>,)>;
+
+struct ComponentInner {
+  guest_call_fn: GuestCallFn,
+  host: Arc<ModuleState>,
+}
+
+/// A waPC engine provider that runs WebAssembly components with the Wasmtime runtime
+///
+/// The component must target the `wapc` world defined inside of the `wit` directory
+/// of this crate: it exports a `guest-call` function and imports the `host-call` and
+/// `console-log` functions. The provider adapts these to the waPC protocol, hence
+/// it can be used with [`wapc::WapcHost`] like any other engine provider.
+///
+/// Refer to [`WasmtimeEngineProviderBuilder::build_component`](crate::WasmtimeEngineProviderBuilder::build_component)
+/// to create an instance of this struct.
+#[allow(missing_debug_implementations)]
+pub struct WasmtimeComponentEngineProvider {
+  component: Component,
+  name: String,
+  #[cfg(feature = "wasi")]
+  wasi_params: WasiParams,
+  inner: Option<ComponentInner>,
+  engine: Engine,
+  linker: Linker<WapcComponentStore>,
+  store: Store<WapcComponentStore>,
+  epoch_deadlines: Option<EpochDeadlines>,
+}
+
+impl WasmtimeComponentEngineProvider {
+  #[cfg(feature = "wasi")]
+  pub(crate) fn new(
+    engine: Engine,
+    component: Component,
+    wasi: Option<WasiParams>,
+    epoch_deadlines: Option<EpochDeadlines>,
+  ) -> Result<Self> {
+    let mut linker: Linker<WapcComponentStore> = Linker::new(&engine);
+
+    let wasi_params = wasi.unwrap_or_default();
+    wasmtime_wasi::add_to_linker_sync(&mut linker)?;
+
+    // register all the waPC host functions
+    add_to_linker(&mut linker)?;
+
+    let store = Store::new(&engine, WapcComponentStore::new(&wasi_params, None)?);
+
+    Ok(Self {
+      component,
+      name: String::new(),
+      wasi_params,
+      inner: None,
+      engine,
+      linker,
+      store,
+      epoch_deadlines,
+    })
+  }
+
+  #[cfg(not(feature = "wasi"))]
+  pub(crate) fn new(engine: Engine, component: Component, epoch_deadlines: Option<EpochDeadlines>) -> Result<Self> {
+    let mut linker: Linker<WapcComponentStore> = Linker::new(&engine);
+
+    // register all the waPC host functions
+    add_to_linker(&mut linker)?;
+
+    let store = Store::new(&engine, WapcComponentStore::new(None));
+
+    Ok(Self {
+      component,
+      name: String::new(),
+      inner: None,
+      engine,
+      linker,
+      store,
+      epoch_deadlines,
+    })
+  }
+
+  pub(crate) fn with_name(mut self, name: String) -> Self {
+    self.name = name;
+    self
+  }
+
+  /// Name of the provider, refer to
+  /// [`WasmtimeEngineProviderBuilder::name`](crate::WasmtimeEngineProviderBuilder::name)
+  #[must_use]
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  // Instantiate the component inside of a brand new store bound to `host`
+  fn instantiate(&mut self, host: Arc<ModuleState>) -> Result<()> {
+    #[cfg(feature = "wasi")]
+    let wapc_store = WapcComponentStore::new(&self.wasi_params, Some(host.clone()))?;
+    #[cfg(not(feature = "wasi"))]
+    let wapc_store = WapcComponentStore::new(Some(host.clone()));
+
+    self.store = Store::new(&self.engine, wapc_store);
+
+    if let Some(deadlines) = &self.epoch_deadlines {
+      // instantiating a component can run the start functions of its core modules
+      self.store.set_epoch_deadline(deadlines.wapc_init);
+    }
+
+    let instance = self.linker.instantiate(&mut self.store, &self.component).map_err(|e| {
+      if matches!(e.downcast_ref::<wasmtime::Trap>(), Some(wasmtime::Trap::Interrupt)) {
+        Error::InitializationFailedTimeout("component instantiation".to_owned())
+      } else {
+        Error::InitializationFailed(e.to_string())
+      }
+    })?;
+
+    let guest_call_fn = instance
+      .get_typed_func(&mut self.store, GUEST_CALL)
+      .map_err(|_| Error::ComponentGuestCallNotFound)?;
+
+    self.inner = Some(ComponentInner { guest_call_fn, host });
+
+    Ok(())
+  }
+}
+
+impl WebAssemblyEngineProvider for WasmtimeComponentEngineProvider {
+  fn init(
+    &mut self,
+    host: Arc<ModuleState>,
+  ) -> std::result::Result<(), Box<(dyn std::error::Error + Send + Sync + 'static)>> {
+    Ok(self.instantiate(host)?)
+  }
+
+  fn call(
+    &mut self,
+    _op_length: i32,
+    _msg_length: i32,
+  ) -> std::result::Result<i32, Box<(dyn std::error::Error + Send + Sync + 'static)>> {
+    if let Some(deadlines) = &self.epoch_deadlines {
+      // the deadline counter must be set before invoking the wasm function
+      self.store.set_epoch_deadline(deadlines.wapc_func);
+    }
+
+    let engine_inner = self.inner.as_ref().unwrap();
+    let invocation = engine_inner
+      .host
+      .get_guest_request()
+      .ok_or("no guest request has been set by the waPC host")?;
+
+    let call = engine_inner
+      .guest_call_fn
+      .call(&mut self.store, (invocation.operation, invocation.msg))
+      .and_then(|(result,)| {
+        engine_inner.guest_call_fn.post_return(&mut self.store)?;
+        Ok(result)
+      });
+
+    match call {
+      Ok(Ok(response)) => {
+        engine_inner.host.set_guest_response(response);
+        Ok(1)
+      }
+      Ok(Err(guest_error)) => {
+        engine_inner.host.set_guest_error(guest_error);
+        Ok(0)
+      }
+      Err(err) => {
+        error!("[{}] Failure invoking guest component handler: {:?}", self.name, err);
+        let mut guest_error = err.to_string();
+        // the store of the component doesn't keep track of the failed memory growths
+        if let Some(trap_error) = traps::classify(&err, false) {
+          guest_error = trap_error.to_string();
+        } else if let Some(trap) = err.downcast_ref::<wasmtime::Trap>() {
+          if matches!(trap, wasmtime::Trap::Interrupt) {
+            let ticks = self.epoch_deadlines.map(|deadlines| deadlines.wapc_func);
+            guest_error = Error::FuncDeadlineExceeded(ticks.unwrap_or_default()).to_string();
+          }
+        }
+        engine_inner.host.set_guest_error(guest_error);
+        Ok(0)
+      }
+    }
+  }
+
+  fn replace(
+    &mut self,
+    component: &[u8],
+  ) -> std::result::Result<(), Box<(dyn std::error::Error + Send + Sync + 'static)>> {
+    info!(
+      "[{}] HOT SWAP - Replacing existing WebAssembly component with new buffer, {} bytes",
+      self.name,
+      component.len()
+    );
+
+    self.component = Component::new(&self.engine, component)?;
+    if let Some(inner) = self.inner.take() {
+      self.instantiate(inner.host)?;
+    }
+
+    Ok(())
+  }
+}
+
+// Register the functions of the `host` interface defined by the waPC world
+fn add_to_linker(linker: &mut Linker<WapcComponentStore>) -> Result<()> {
+  let mut host_interface = linker.instance(HOST_INTERFACE)?;
+
+  host_interface
+    .func_wrap(
+      "host-call",
+      |store: StoreContextMut<'_, WapcComponentStore>,
+       (binding, namespace, operation, payload): (String, String, String, Vec<u8>)| {
+        let host = store
+          .data()
+          .host
+          .as_ref()
+          .ok_or_else(|| anyhow!("host should have been set during the init"))?;
+
+        let result = match host.do_host_call(&binding, &namespace, &operation, &payload) {
+          Ok(1) => Ok(host.get_host_response().unwrap_or_default()),
+          _ => Err(host.get_host_error().unwrap_or_default()),
+        };
+        Ok((result,))
+      },
+    )
+    .map_err(|e| Error::LinkerFuncDef {
+      func: format!("{HOST_INTERFACE}#host-call"),
+      err: e.into(),
+    })?;
+
+  host_interface
+    .func_wrap(
+      "console-log",
+      |store: StoreContextMut<'_, WapcComponentStore>, (msg,): (String,)| {
+        let host = store
+          .data()
+          .host
+          .as_ref()
+          .ok_or_else(|| anyhow!("host should have been set during the init"))?;
+
+        host.do_console_log(&msg);
+        Ok(())
+      },
+    )
+    .map_err(|e| Error::LinkerFuncDef {
+      func: format!("{HOST_INTERFACE}#console-log"),
+      err: e.into(),
+    })?;
+
+  Ok(())
+}
@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+
+/// Handle used to override the deadline of the waPC guest functions
+///
+/// The handle can be cloned and kept by the host after the engine provider has been moved into
+/// a [`wapc::WapcHost`]. While set, the override replaces the `wapc_func_deadline` provided via
+/// [`enable_epoch_interruptions`](crate::WasmtimeEngineProviderBuilder::enable_epoch_interruptions).
+/// It has no effect when epoch interruptions are not enabled.
+#[derive(Clone, Debug, Default)]
+pub struct FuncDeadlineOverride(Arc<RwLock<Option<u64>>>);
+
+impl FuncDeadlineOverride {
+  /// Grant `ticks` epoch ticks to the guest functions invoked from now on
+  pub fn set(&self, ticks: u64) {
+    *self.0.write() = Some(ticks);
+  }
+
+  /// Remove the override, the deadline provided at build time applies again
+  pub fn clear(&self) {
+    *self.0.write() = None;
+  }
+
+  /// Returns the override currently set, if any
+  #[must_use]
+  pub fn get(&self) -> Option<u64> {
+    *self.0.read()
+  }
+
+  /// Grant `ticks` epoch ticks to the guest functions invoked until the returned guard is dropped
+  #[must_use]
+  pub fn scoped(&self, ticks: u64) -> FuncDeadlineGuard<'_> {
+    let previous = self.get();
+    self.set(ticks);
+    FuncDeadlineGuard { handle: self, previous }
+  }
+}
+
+/// Restores the previous deadline override when dropped, see [`FuncDeadlineOverride::scoped`]
+#[derive(Debug)]
+pub struct FuncDeadlineGuard<'a> {
+  handle: &'a FuncDeadlineOverride,
+  previous: Option<u64>,
+}
+
+impl Drop for FuncDeadlineGuard<'_> {
+  fn drop(&mut self) {
+    *self.handle.0.write() = self.previous;
+  }
+}
+
+// Epoch ticks left to the guest function being invoked, when the time spent inside of the host
+// calls is not charged to its deadline
+//
+// Wasmtime doesn't expose the current epoch, hence the store deadline is moved forward one tick
+// at a time while the guest is running. After a host call, the deadline is set again one tick
+// beyond the current epoch: the ticks elapsed inside of the host callback are skipped.
+#[derive(Debug, Default)]
+pub(crate) struct GuestTicks {
+  remaining: Option<u64>,
+  elapsed: u64,
+}
+
+impl GuestTicks {
+  // Start counting the `ticks` granted to a guest function, returns the deadline to set on the store
+  pub(crate) fn start(&mut self, ticks: u64) -> u64 {
+    self.remaining = Some(ticks);
+    self.elapsed = 0;
+    ticks.min(1)
+  }
+
+  // Stop counting, returns the number of ticks elapsed while running guest code
+  pub(crate) fn stop(&mut self) -> Option<u64> {
+    self.remaining.take().map(|_| self.elapsed)
+  }
+
+  // `true` while the ticks of a guest function are being counted
+  pub(crate) fn is_counting(&self) -> bool {
+    self.remaining.is_some()
+  }
+
+  // Invoked when the store deadline is reached: either move it one tick forward or interrupt the guest
+  pub(crate) fn on_deadline(&mut self) -> anyhow::Result<wasmtime::UpdateDeadline> {
+    self.elapsed += 1;
+    match self.remaining.as_mut() {
+      Some(remaining) if *remaining > 1 => {
+        *remaining -= 1;
+        Ok(wasmtime::UpdateDeadline::Continue(1))
+      }
+      _ => Err(wasmtime::Trap::Interrupt.into()),
+    }
+  }
+}
@@ -0,0 +1,62 @@
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+
+use crate::errors::{Error, Result};
+
+/// Handle holding the SHA-256 digest the modules given to `replace` must match
+///
+/// The handle can be cloned and kept by the host after the engine provider has been moved into
+/// a [`wapc::WapcHost`]: set the digest of the next module, then hot swap it. While a digest is
+/// set, the modules whose bytes don't match it are rejected with
+/// [`Error::ModuleDigestMismatch`] before being compiled. The digest is computed over the bytes
+/// given to `replace`, hence over the serialized artifact for the precompiled modules.
+#[derive(Clone, Debug, Default)]
+pub struct ExpectedModuleDigest(Arc<RwLock<Option<String>>>);
+
+impl ExpectedModuleDigest {
+  /// Require the next modules to match the hex encoded SHA-256 `digest`
+  pub fn set(&self, digest: &str) -> Result<()> {
+    *self.0.write() = Some(parse_sha256(digest)?);
+    Ok(())
+  }
+
+  /// Remove the requirement, any module is accepted again
+  pub fn clear(&self) {
+    *self.0.write() = None;
+  }
+
+  /// Returns the digest currently required, if any
+  #[must_use]
+  pub fn get(&self) -> Option<String> {
+    self.0.read().clone()
+  }
+
+  // Ensure `module_bytes` match the digest, when one is set
+  pub(crate) fn verify(&self, module_bytes: &[u8]) -> Result<()> {
+    self.0.read().as_deref().map_or(Ok(()), |expected| {
+      check(expected, crate::precompiled::module_hash(module_bytes))
+    })
+  }
+}
+
+// Validate a hex encoded SHA-256 digest, returns its lowercase form
+pub(crate) fn parse_sha256(digest: &str) -> Result<String> {
+  if digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit()) {
+    Ok(digest.to_ascii_lowercase())
+  } else {
+    Err(Error::InvalidSha256(digest.to_owned()))
+  }
+}
+
+// Compare the lowercase `expected` digest with the `actual` one
+pub(crate) fn check(expected: &str, actual: String) -> Result<()> {
+  if expected == actual {
+    Ok(())
+  } else {
+    Err(Error::ModuleDigestMismatch {
+      expected: expected.to_owned(),
+      actual,
+    })
+  }
+}
@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+
+/// Handle used to override the environment variables seen by a WASI guest, call by call
+///
+/// The handle can be cloned and kept by the host after the engine provider has been moved into
+/// a [`wapc::WapcHost`]. The overrides take precedence over the variables provided via
+/// [`wapc::WasiParams`] and apply from the next guest call on.
+///
+/// The WASI context of the guest cannot be changed in place: when the overrides changed since
+/// the previous call, a new context is created before the call. The instance and the files
+/// opened by the guest are preserved, but the preopened directories are opened again.
+#[derive(Clone, Debug, Default)]
+pub struct WasiEnvOverrides(Arc<RwLock<Overrides>>);
+
+#[derive(Debug, Default)]
+struct Overrides {
+  vars: Vec<(String, String)>,
+  // bumped at each change, `0` means no override has ever been set
+  generation: u64,
+}
+
+impl WasiEnvOverrides {
+  /// Set the value of the environment variable `key`
+  pub fn set(&self, key: &str, value: &str) {
+    let mut overrides = self.0.write();
+    match overrides.vars.iter_mut().find(|(k, _)| k == key) {
+      Some((_, v)) => value.clone_into(v),
+      None => overrides.vars.push((key.to_owned(), value.to_owned())),
+    }
+    overrides.generation += 1;
+  }
+
+  /// Remove the override of the environment variable `key`
+  pub fn remove(&self, key: &str) {
+    let mut overrides = self.0.write();
+    overrides.vars.retain(|(k, _)| k != key);
+    overrides.generation += 1;
+  }
+
+  /// Remove all the overrides, the guest sees the environment provided at build time again
+  pub fn clear(&self) {
+    let mut overrides = self.0.write();
+    overrides.vars.clear();
+    overrides.generation += 1;
+  }
+
+  /// Returns the override of the environment variable `key`, if any
+  #[must_use]
+  pub fn get(&self, key: &str) -> Option<String> {
+    self
+      .0
+      .read()
+      .vars
+      .iter()
+      .find_map(|(k, v)| (k == key).then(|| v.clone()))
+  }
+
+  // Returns the overrides together with their generation when they changed since `generation`
+  pub(crate) fn changed_since(&self, generation: u64) -> Option<(u64, Vec<(String, String)>)> {
+    let overrides = self.0.read();
+    (overrides.generation != generation).then(|| (overrides.generation, overrides.vars.clone()))
+  }
+}
+
+// The WASI params of the guest, with the environment variables replaced by the overrides
+pub(crate) fn apply_overrides(wasi_params: &wapc::WasiParams, vars: Vec<(String, String)>) -> wapc::WasiParams {
+  let mut wasi_params = wasi_params.clone();
+  for (key, value) in vars {
+    match wasi_params.env_vars.iter_mut().find(|(k, _)| *k == key) {
+      Some((_, v)) => *v = value,
+      None => wasi_params.env_vars.push((key, value)),
+    }
+  }
+  wasi_params
+}
@@ -0,0 +1,114 @@
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use wasmtime::Engine;
+
+use crate::errors::{Error, Result};
+
+/// Increments the epoch of a [`wasmtime::Engine`] at a fixed interval, from a single thread
+///
+/// All the engine providers built from the same engine can share one ticker, instead of each
+/// embedder spawning its own thread. Refer to
+/// [`WasmtimeEngineProviderBuilder::epoch_ticker`](crate::WasmtimeEngineProviderBuilder::epoch_ticker).
+#[derive(Debug, Clone, Copy)]
+pub struct EpochTicker;
+
+impl EpochTicker {
+  /// Spawn a thread incrementing the epoch of `engine` every `interval`
+  ///
+  /// The thread runs until the returned handle, all its clones and the engine providers built
+  /// with it are dropped.
+  ///
+  /// # Errors
+  ///
+  /// Returns [`Error::ZeroEpochTickInterval`] when `interval` is zero and
+  /// [`Error::EpochTickerSpawn`] when the thread cannot be spawned.
+  pub fn spawn(engine: &Engine, interval: Duration) -> Result<EpochTickerHandle> {
+    if interval.is_zero() {
+      return Err(Error::ZeroEpochTickInterval);
+    }
+
+    let (stop, stopped) = mpsc::channel::<()>();
+    let ticked_engine = engine.clone();
+    let thread = std::thread::Builder::new()
+      .name("wapc-epoch-ticker".to_owned())
+      .spawn(move || {
+        // the channel is disconnected once the last handle is dropped
+        while stopped.recv_timeout(interval) == Err(RecvTimeoutError::Timeout) {
+          ticked_engine.increment_epoch();
+        }
+      })
+      .map_err(|e| Error::EpochTickerSpawn(e.to_string()))?;
+
+    Ok(EpochTickerHandle(Arc::new(TickerInner {
+      engine: engine.clone(),
+      interval,
+      stop: Some(stop),
+      thread: Some(thread),
+    })))
+  }
+}
+
+/// Reference counted handle to the thread spawned by [`EpochTicker::spawn`]
+#[derive(Clone)]
+pub struct EpochTickerHandle(Arc<TickerInner>);
+
+impl EpochTickerHandle {
+  /// The engine whose epoch is incremented
+  #[must_use]
+  pub fn engine(&self) -> &Engine {
+    &self.0.engine
+  }
+
+  /// The time elapsed between two ticks
+  #[must_use]
+  pub fn interval(&self) -> Duration {
+    self.0.interval
+  }
+
+  // The number of ticks covering `duration`, rounded up and at least one
+  pub(crate) fn ticks(&self, duration: Duration) -> u64 {
+    let ticks = duration.as_nanos().div_ceil(self.0.interval.as_nanos()).max(1);
+    u64::try_from(ticks).unwrap_or(u64::MAX)
+  }
+}
+
+impl std::fmt::Debug for EpochTickerHandle {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("EpochTickerHandle")
+      .field("interval", &self.0.interval)
+      .finish_non_exhaustive()
+  }
+}
+
+struct TickerInner {
+  engine: Engine,
+  interval: Duration,
+  stop: Option<Sender<()>>,
+  thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for TickerInner {
+  fn drop(&mut self) {
+    drop(self.stop.take());
+    if let Some(thread) = self.thread.take() {
+      let _ = thread.join();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn durations_are_rounded_up_to_whole_ticks() {
+    let handle = EpochTicker::spawn(&Engine::default(), Duration::from_millis(10)).unwrap();
+    assert_eq!(handle.ticks(Duration::ZERO), 1);
+    assert_eq!(handle.ticks(Duration::from_millis(10)), 1);
+    assert_eq!(handle.ticks(Duration::from_millis(11)), 2);
+    assert_eq!(handle.ticks(Duration::from_secs(1)), 100);
+  }
+}
@@ -5,6 +5,15 @@
 /// to hold errors
 pub(crate) type Result<T> = std::result::Result<T, Error>;
 
+/// Prefix of the guest error reported when the guest ran out of memory
+pub const OUT_OF_MEMORY_PREFIX: &str = "wapc:oom:";
+/// Prefix of the guest error reported when the guest ran out of fuel
+pub const OUT_OF_FUEL_PREFIX: &str = "wapc:out_of_fuel:";
+/// Prefix of the guest error reported when the guest exhausted its stack
+pub const STACK_OVERFLOW_PREFIX: &str = "wapc:stack_overflow:";
+/// Prefix of the guest error reported when the guest executed an `unreachable` instruction
+pub const UNREACHABLE_PREFIX: &str = "wapc:unreachable:";
+
 /// This crate's Error type
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -16,10 +25,37 @@ pub enum Error {
   #[error("Initialization failed: {0} init interrupted, execution deadline exceeded")]
   InitializationFailedTimeout(String),
 
+  /// The guest function has been interrupted because it exceeded its epoch deadline,
+  /// expressed in number of epoch ticks
+  #[error("guest code interrupted, func execution deadline of {0} epoch ticks exceeded")]
+  FuncDeadlineExceeded(u64),
+
   /// The guest call function was not exported by the guest.
   #[error("Guest call function (__guest_call) not exported by wasm module.")]
   GuestCallNotFound,
 
+  /// The `guest-call` function was not exported by the guest component.
+  #[error("Guest call function (guest-call) not exported by wasm component.")]
+  ComponentGuestCallNotFound,
+
+  /// The guest trapped after failing to grow its memory, or attempted an allocation that is too large.
+  /// Starts with [`OUT_OF_MEMORY_PREFIX`]
+  #[error("{OUT_OF_MEMORY_PREFIX} {0}")]
+  GuestOutOfMemory(String),
+
+  /// The guest consumed all the fuel it was granted. Starts with [`OUT_OF_FUEL_PREFIX`]
+  #[error("{OUT_OF_FUEL_PREFIX} {0}")]
+  GuestOutOfFuel(String),
+
+  /// The guest exhausted its stack. Starts with [`STACK_OVERFLOW_PREFIX`]
+  #[error("{STACK_OVERFLOW_PREFIX} {0}")]
+  GuestStackOverflow(String),
+
+  /// The guest executed an `unreachable` instruction, usually because of a panic.
+  /// Starts with [`UNREACHABLE_PREFIX`]
+  #[error("{UNREACHABLE_PREFIX} {0}")]
+  GuestUnreachable(String),
+
   /// Error originating when wasi feature is disabled, but the user provides wasi related params
   #[error("WASI related parameter provided, but wasi feature is disabled")]
   WasiDisabled,
@@ -33,10 +69,86 @@ pub enum Error {
   LinkerFuncDef {
     /// wasm function that was being defined
     func: String,
+    /// error reported, its own sources are available via [`std::error::Error::source`]
+    #[source]
+    err: Box<dyn std::error::Error + Send + Sync>,
+  },
+
+  /// Error caused when a library module cannot be linked
+  #[error("Cannot link module '{name}': {err}")]
+  LinkModule {
+    /// name under which the module was linked
+    name: String,
     /// error reported
     err: String,
   },
 
+  /// Error caused when the module cannot be compiled for the requested target, usually
+  /// because the triple is invalid or its support has not been enabled in the compiler
+  #[error("Cannot compile for target '{target}': {err}")]
+  CompileTarget {
+    /// target triple requested
+    target: String,
+    /// error reported
+    err: String,
+  },
+
+  /// Error caused when the module cannot be loaded from the file provided via
+  /// [`crate::WasmtimeEngineProviderBuilder::module_path`]
+  #[error("Cannot load the module from '{path}': {err}")]
+  ModulePath {
+    /// path of the module
+    path: String,
+    /// error reported
+    err: String,
+  },
+
+  /// Error caused when the text provided via [`crate::WasmtimeEngineProviderBuilder::module_wat`]
+  /// is not a valid WebAssembly module
+  #[error("Cannot parse the WebAssembly text format: {0}")]
+  Wat(String),
+
+  /// Error caused when a compiled module cannot be stored inside of the directory provided via
+  /// [`crate::WasmtimeEngineProviderBuilder::precompile_cache_dir`]
+  #[error("Cannot store the precompiled module into '{path}': {err}")]
+  PrecompileCache {
+    /// path of the precompiled module
+    path: String,
+    /// error reported
+    err: String,
+  },
+
+  /// Error caused by a module whose SHA-256 digest differs from the expected one, refer to
+  /// [`crate::WasmtimeEngineProviderBuilder::expect_sha256`] and [`crate::ExpectedModuleDigest`]
+  #[error("WebAssembly module digest mismatch: expected SHA-256 {expected}, got {actual}")]
+  ModuleDigestMismatch {
+    /// hex encoded digest expected
+    expected: String,
+    /// hex encoded digest of the module
+    actual: String,
+  },
+
+  /// Error caused by a SHA-256 digest that is not made of 64 hexadecimal characters
+  #[error("Invalid SHA-256 digest '{0}', 64 hexadecimal characters are expected")]
+  InvalidSha256(String),
+
+  /// Error caused when a [`crate::MemorySnapshot`] cannot be restored
+  #[error("Cannot restore the memory snapshot: {0}")]
+  Snapshot(String),
+
+  /// Error caused by a zero interval given to [`crate::EpochTicker::spawn`]
+  #[error("The epoch tick interval cannot be zero")]
+  ZeroEpochTickInterval,
+
+  /// Error caused when the thread of a [`crate::EpochTicker`] cannot be spawned
+  #[error("Cannot spawn the epoch ticker thread: {0}")]
+  EpochTickerSpawn(String),
+
+  /// Error caused by a module that is not a valid waPC guest, refer to
+  /// [`crate::WasmtimeEngineProviderBuilder::validate_on_build`]
+  #[error("Invalid waPC module: {0}")]
+  InvalidModule(crate::ValidationReport),
+
   /// Error caused by an invalid configuration of the [`crate::WasmtimeEngineProviderBuilder`]
   #[error("Invalid WasmtimeEngineProviderBuilder configuration: {0}")]
   BuilderInvalidConfig(String),
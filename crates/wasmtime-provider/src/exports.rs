@@ -0,0 +1,37 @@
+use wasmtime::{ExternType, Module};
+
+/// The kind of an item exported by a WebAssembly module
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExternKind {
+  /// A function
+  Func,
+  /// A global
+  Global,
+  /// A table
+  Table,
+  /// A linear memory
+  Memory,
+}
+
+impl From<&ExternType> for ExternKind {
+  fn from(ty: &ExternType) -> Self {
+    match ty {
+      ExternType::Func(_) => ExternKind::Func,
+      ExternType::Global(_) => ExternKind::Global,
+      ExternType::Table(_) => ExternKind::Table,
+      ExternType::Memory(_) => ExternKind::Memory,
+    }
+  }
+}
+
+// The exports are read from the compiled module, no instantiation is required
+pub(crate) fn module_exports(module: &Module) -> Vec<(String, ExternKind)> {
+  module
+    .exports()
+    .map(|export| (export.name().to_owned(), ExternKind::from(&export.ty())))
+    .collect()
+}
+
+pub(crate) fn module_has_export(module: &Module, name: &str) -> bool {
+  module.get_export(name).is_some()
+}
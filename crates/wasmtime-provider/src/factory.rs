@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use wapc::{HostCallback, ModuleState, WapcHost};
+
+use crate::errors::Result;
+use crate::{WasmtimeEngineProvider, WasmtimeEngineProviderPre};
+
+/// Creates fully initialized providers, or waPC hosts, from a single compiled module
+///
+/// The module is compiled and linked once, each provider gets its own `Store` and instance.
+/// Creating one takes tens of microseconds, which makes a fresh [`WapcHost`] per request viable
+/// for guests that don't need to keep any state between calls.
+///
+/// The factory is cheap to clone and can be shared between threads.
+#[allow(missing_debug_implementations)]
+#[derive(Clone)]
+pub struct WasmtimeEngineProviderFactory {
+  pre: WasmtimeEngineProviderPre,
+  host_callback: Option<Arc<HostCallback>>,
+}
+
+impl WasmtimeEngineProviderFactory {
+  /// Create a new factory. The `host_callback`, if any, is shared by all the hosts
+  /// created via [`host`](WasmtimeEngineProviderFactory::host).
+  ///
+  /// Refer to [`WasmtimeEngineProviderBuilder::build_pre`](crate::WasmtimeEngineProviderBuilder::build_pre)
+  /// to create `pre`.
+  #[must_use]
+  pub fn new(pre: WasmtimeEngineProviderPre, host_callback: Option<Arc<HostCallback>>) -> Self {
+    Self { pre, host_callback }
+  }
+
+  /// Create a [`WasmtimeEngineProvider`] that is already bound to `host` and fully initialized,
+  /// see [`WasmtimeEngineProviderPre::rehydrate_with_host`]
+  pub fn provider(&self, host: Arc<ModuleState>) -> Result<WasmtimeEngineProvider> {
+    self.pre.rehydrate_with_host(host)
+  }
+
+  /// Create a new [`WapcHost`] backed by a fully initialized provider
+  pub fn host(&self) -> std::result::Result<WapcHost, wapc::errors::Error> {
+    let host_callback = self.host_callback.clone().map(|callback| {
+      let callback: Box<HostCallback> = Box::new(move |id, binding, namespace, operation, payload| {
+        callback(id, binding, namespace, operation, payload)
+      });
+      callback
+    });
+    let state = Arc::new(ModuleState::with_callback(host_callback));
+    let provider = self.provider(state.clone())?;
+
+    WapcHost::new_with_state(Box::new(provider), state)
+  }
+}
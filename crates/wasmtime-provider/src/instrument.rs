@@ -0,0 +1,79 @@
+// Tracing spans and events describing the waPC conversation between the guest and the host.
+// Only the sizes of the payloads are recorded, never their contents.
+
+#[cfg(feature = "async")]
+use std::future::Future;
+
+#[cfg(all(feature = "tracing", feature = "async"))]
+use tracing::Instrument;
+
+// The span of a host call, which does nothing without the `tracing` feature
+pub(crate) struct HostCallSpan(#[cfg(feature = "tracing")] tracing::Span);
+
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn host_call_span(
+  provider: &str,
+  module_id: u64,
+  binding: &str,
+  namespace: &str,
+  operation: &str,
+  len: usize,
+) -> HostCallSpan {
+  HostCallSpan(
+    #[cfg(feature = "tracing")]
+    tracing::debug_span!(
+      "wapc.host_call",
+      provider = %provider,
+      module_id,
+      bd = %binding,
+      ns = %namespace,
+      op = %operation,
+      payload_len = len,
+      success = tracing::field::Empty,
+    ),
+  )
+}
+
+impl HostCallSpan {
+  // Run the host call inside of the span, then record its outcome
+  pub(crate) fn run(self, call: impl FnOnce() -> i32) -> i32 {
+    #[cfg(feature = "tracing")]
+    {
+      let result = self.0.in_scope(call);
+      self.0.record("success", result == 1);
+      result
+    }
+    #[cfg(not(feature = "tracing"))]
+    call()
+  }
+
+  #[cfg(feature = "async")]
+  pub(crate) async fn run_async(self, call: impl Future<Output = i32> + Send) -> i32 {
+    #[cfg(feature = "tracing")]
+    {
+      let result = call.instrument(self.0.clone()).await;
+      self.0.record("success", result == 1);
+      result
+    }
+    #[cfg(not(feature = "tracing"))]
+    call.await
+  }
+}
+
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn guest_response(provider: &str, module_id: u64, len: usize) {
+  #[cfg(feature = "tracing")]
+  tracing::debug!(name: "wapc.guest_response", provider = %provider, module_id, len);
+}
+
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn guest_error(provider: &str, module_id: u64, len: usize) {
+  #[cfg(feature = "tracing")]
+  tracing::debug!(name: "wapc.guest_error", provider = %provider, module_id, len);
+}
+
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn console_log(provider: &str, module_id: u64, len: usize) {
+  #[cfg(feature = "tracing")]
+  tracing::debug!(name: "wapc.console_log", provider = %provider, module_id, len);
+}
@@ -89,6 +89,12 @@ mod wasi;
 mod provider;
 pub use provider::{WasmtimeEngineProvider, WasmtimeEngineProviderPre};
 
+mod factory;
+pub use factory::WasmtimeEngineProviderFactory;
+
+mod pre;
+pub use pre::EnginePre;
+
 #[cfg(feature = "async")]
 mod provider_async;
 #[cfg(feature = "async")]
@@ -96,17 +102,85 @@ mod provider_async;
 pub use provider_async::{WasmtimeEngineProviderAsync, WasmtimeEngineProviderAsyncPre};
 
 mod store;
+pub use store::WapcStore;
+
+mod component;
+pub use component::WasmtimeComponentEngineProvider;
+
+mod store_component;
 
 #[cfg(feature = "async")]
 mod store_async;
+#[cfg(feature = "async")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+pub use store_async::WapcStoreAsync;
 
 pub mod errors;
 
+mod exports;
+pub use exports::ExternKind;
+
+mod linking;
+
+mod bridge;
+
+mod wasi_stub;
+
+mod deadlines;
+pub use deadlines::{FuncDeadlineGuard, FuncDeadlineOverride};
+
+mod digest;
+pub use digest::ExpectedModuleDigest;
+
+mod epoch_ticker;
+pub use epoch_ticker::{EpochTicker, EpochTickerHandle};
+
+mod timings;
+pub use timings::{CallCost, CallTimings, CallTimingsHandle};
+
+mod limits;
+pub use limits::GrowRequest;
+
+mod traps;
+
+mod payloads;
+
+mod instrument;
+
+mod validation;
+pub use validation::{ValidationProblem, ValidationReport};
+
+mod target;
+
+mod precompiled;
+
+mod proposals;
+
+mod memory_tuning;
+
+mod snapshot;
+pub use snapshot::MemorySnapshot;
+
+#[cfg(feature = "wasi")]
+mod env;
+#[cfg(feature = "wasi")]
+#[cfg_attr(docsrs, doc(cfg(feature = "wasi")))]
+pub use env::WasiEnvOverrides;
+
 mod builder;
 pub use builder::WasmtimeEngineProviderBuilder;
 
-// export wasmtime and wasmtime_wasi, so that consumers of this crate can use
+#[cfg(feature = "cache")]
+mod cache;
+#[cfg(feature = "cache")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+pub use cache::CacheOptions;
+
+// export wasmtime, wasmtime_wasi and wasi_common, so that consumers of this crate can use
 // the very same version
+#[cfg(feature = "wasi")]
+#[cfg_attr(docsrs, doc(cfg(feature = "wasi")))]
+pub use wasi_common;
 pub use wasmtime;
 #[cfg(feature = "wasi")]
 #[cfg_attr(docsrs, doc(cfg(feature = "wasi")))]
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use log::debug;
+use wasmtime::ResourceLimiter;
+
+/// A request of the guest to grow one of its memories, given to the callback registered via
+/// [`WasmtimeEngineProviderBuilder::on_memory_grow`](crate::WasmtimeEngineProviderBuilder::on_memory_grow)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GrowRequest {
+  /// Current size of the memory, in bytes. This is `0` when the memory is created
+  pub current: usize,
+  /// Size of the memory after the growth, in bytes
+  pub desired: usize,
+  /// Maximum size of the memory declared by the module, if any
+  pub maximum: Option<usize>,
+  /// Id of the waPC module, the same returned by [`wapc::WapcHost::id`]
+  pub module_id: u64,
+}
+
+pub(crate) type MemoryGrowCallback = dyn Fn(GrowRequest) -> bool + Send + Sync;
+
+// Resource limiter installed inside of every store. Memory growths are allowed unless the
+// user callback denies them. The limiter keeps track of the memory growths that failed while
+// running guest code: a trap raised right after a failed growth is reported as the guest
+// running out of memory
+#[derive(Default)]
+pub(crate) struct WapcResourceLimiter {
+  module_id: u64,
+  provider_name: Arc<str>,
+  on_memory_grow: Option<Arc<MemoryGrowCallback>>,
+  memory_growth_failed: bool,
+}
+
+impl WapcResourceLimiter {
+  pub(crate) fn new(module_id: u64, provider_name: Arc<str>, on_memory_grow: Option<Arc<MemoryGrowCallback>>) -> Self {
+    Self {
+      module_id,
+      provider_name,
+      on_memory_grow,
+      memory_growth_failed: false,
+    }
+  }
+
+  // Returns whether a memory growth failed since the last invocation
+  pub(crate) fn take_memory_growth_failed(&mut self) -> bool {
+    std::mem::take(&mut self.memory_growth_failed)
+  }
+}
+
+impl ResourceLimiter for WapcResourceLimiter {
+  fn memory_growing(&mut self, current: usize, desired: usize, maximum: Option<usize>) -> anyhow::Result<bool> {
+    let Some(on_memory_grow) = &self.on_memory_grow else {
+      return Ok(true);
+    };
+
+    let allowed = on_memory_grow(GrowRequest {
+      current,
+      desired,
+      maximum,
+      module_id: self.module_id,
+    });
+    if !allowed {
+      // the guest sees `memory.grow` returning -1, like any other allocation failure
+      debug!(
+        "[{}] guest memory growth from {} to {} bytes denied",
+        self.provider_name, current, desired
+      );
+      self.memory_growth_failed = true;
+    }
+    Ok(allowed)
+  }
+
+  fn memory_grow_failed(&mut self, error: anyhow::Error) -> anyhow::Result<()> {
+    debug!("[{}] guest memory growth failed: {:?}", self.provider_name, error);
+    self.memory_growth_failed = true;
+    Ok(())
+  }
+
+  fn table_growing(&mut self, _current: usize, _desired: usize, _maximum: Option<usize>) -> anyhow::Result<bool> {
+    Ok(true)
+  }
+}
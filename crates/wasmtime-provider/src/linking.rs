@@ -0,0 +1,110 @@
+use wapc::HOST_NAMESPACE;
+use wasmtime::{AsContextMut, InstancePre, Linker, Module};
+
+use crate::errors::{Error, Result};
+
+/// Namespaces already provided by the host, they cannot be used by library modules
+#[cfg(feature = "wasi")]
+const RESERVED_NAMESPACES: &[&str] = &[HOST_NAMESPACE, "wasi_snapshot_preview1", "wasi_unstable"];
+#[cfg(not(feature = "wasi"))]
+const RESERVED_NAMESPACES: &[&str] = &[HOST_NAMESPACE];
+
+/// Resolution of the guest imports that are not provided by the waPC and WASI host functions
+#[derive(Clone, Default)]
+pub(crate) struct LinkOptions {
+  /// Library modules, in instantiation order
+  pub(crate) modules: Vec<(String, Module)>,
+  /// Namespace patterns whose function imports are forwarded to the host callback
+  pub(crate) bridged_namespaces: Vec<String>,
+  /// Whether the WASI imports are satisfied by stubs, when the `wasi` feature is disabled
+  pub(crate) stub_wasi_imports: bool,
+}
+
+// The library modules are instantiated inside of each store, hence the `InstancePre`
+// can be computed ahead of time only when no library module has been linked
+pub(crate) fn instance_pre<T: 'static>(
+  linker: &Linker<T>,
+  module: &Module,
+  link_options: &LinkOptions,
+) -> Result<Option<InstancePre<T>>> {
+  if link_options.modules.is_empty() {
+    return Ok(Some(linker.instantiate_pre(module)?));
+  }
+
+  check_linked_modules(module, &link_options.modules)?;
+  Ok(None)
+}
+
+// Whether `namespace` is provided by the host
+pub(crate) fn is_reserved(namespace: &str) -> bool {
+  RESERVED_NAMESPACES.contains(&namespace)
+}
+
+// Ensure the library modules don't clash with each other or with the host namespaces,
+// and that the imports of `module` coming from a library are exported by it.
+// Type checking happens later, when the modules are instantiated.
+fn check_linked_modules(module: &Module, linked_modules: &[(String, Module)]) -> Result<()> {
+  for (i, (name, _)) in linked_modules.iter().enumerate() {
+    if is_reserved(name) {
+      return Err(Error::LinkModule {
+        name: name.clone(),
+        err: "the name is reserved by the host".to_owned(),
+      });
+    }
+    if linked_modules[..i].iter().any(|(other, _)| other == name) {
+      return Err(Error::LinkModule {
+        name: name.clone(),
+        err: "another module has been linked with the same name".to_owned(),
+      });
+    }
+  }
+
+  for import in module.imports() {
+    if let Some((name, library)) = linked_modules.iter().find(|(name, _)| name == import.module()) {
+      if library.get_export(import.name()).is_none() {
+        return Err(Error::LinkModule {
+          name: name.clone(),
+          err: format!("`{}` is imported but not exported by the module", import.name()),
+        });
+      }
+    }
+  }
+
+  Ok(())
+}
+
+// Instantiate the library modules inside of `store` and define their exports into a copy
+// of `linker`. The returned linker must be used only with `store`, which owns the instances.
+pub(crate) fn store_linker<T: 'static>(
+  linker: &Linker<T>,
+  mut store: impl AsContextMut<Data = T>,
+  link_options: &LinkOptions,
+) -> Result<Linker<T>> {
+  let mut linker = linker.clone();
+  for (name, module) in &link_options.modules {
+    linker.module(&mut store, name, module).map_err(|e| Error::LinkModule {
+      name: name.clone(),
+      err: e.to_string(),
+    })?;
+  }
+  Ok(linker)
+}
+
+#[cfg(feature = "async")]
+pub(crate) async fn store_linker_async<T: Send + 'static>(
+  linker: &Linker<T>,
+  mut store: impl AsContextMut<Data = T> + Send,
+  link_options: &LinkOptions,
+) -> Result<Linker<T>> {
+  let mut linker = linker.clone();
+  for (name, module) in &link_options.modules {
+    linker
+      .module_async(&mut store, name, module)
+      .await
+      .map_err(|e| Error::LinkModule {
+        name: name.clone(),
+        err: e.to_string(),
+      })?;
+  }
+  Ok(linker)
+}
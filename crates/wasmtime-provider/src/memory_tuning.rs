@@ -0,0 +1,63 @@
+use crate::errors::{Error, Result};
+
+/// Size of a WebAssembly page
+const WASM_PAGE_SIZE: u64 = 64 * 1024;
+
+/// The virtual memory settings of the linear memories explicitly set by the user, `None`
+/// keeps the Wasmtime default
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MemoryTuning {
+  pub(crate) reservation: Option<u64>,
+  pub(crate) guard_size: Option<u64>,
+  pub(crate) may_move: Option<bool>,
+}
+
+impl MemoryTuning {
+  pub(crate) fn is_set(&self) -> bool {
+    self.reservation.is_some() || self.guard_size.is_some() || self.may_move.is_some()
+  }
+
+  // Reject the settings that cannot be honored by the host, or that would prevent
+  // any memory from being created
+  pub(crate) fn validate(&self) -> Result<()> {
+    for (option, bytes) in [
+      ("memory_reservation", self.reservation),
+      ("memory_guard_size", self.guard_size),
+    ] {
+      let Some(bytes) = bytes else {
+        continue;
+      };
+      if bytes % WASM_PAGE_SIZE != 0 {
+        return Err(Error::BuilderInvalidConfig(format!(
+          "`{}` must be a multiple of the WebAssembly page size ({} bytes), {} bytes requested",
+          option, WASM_PAGE_SIZE, bytes
+        )));
+      }
+      if usize::try_from(bytes).is_err() {
+        return Err(Error::BuilderInvalidConfig(format!(
+          "`{}` of {} bytes exceeds the address space of the host",
+          option, bytes
+        )));
+      }
+    }
+    if self.reservation == Some(0) && self.may_move == Some(false) {
+      return Err(Error::BuilderInvalidConfig(
+        "`memory_may_move` cannot be disabled when `memory_reservation` is 0, the memories could not grow".to_owned(),
+      ));
+    }
+
+    Ok(())
+  }
+
+  pub(crate) fn apply(&self, config: &mut wasmtime::Config) {
+    if let Some(reservation) = self.reservation {
+      config.memory_reservation(reservation);
+    }
+    if let Some(guard_size) = self.guard_size {
+      config.memory_guard_size(guard_size);
+    }
+    if let Some(may_move) = self.may_move {
+      config.memory_may_move(may_move);
+    }
+  }
+}
@@ -0,0 +1,57 @@
+use wasmtime::{Memory, StoreContext};
+
+// Copy the `len` bytes found at `ptr` inside of the guest memory. `what` names the payload
+// inside of the error returned when the guest asks for a length larger than its own memory,
+// larger than `max_payload_size` or out of the bounds of its memory.
+pub(crate) fn read_guest_payload<'a, T: 'a>(
+  store: impl Into<StoreContext<'a, T>>,
+  memory: Memory,
+  ptr: i32,
+  len: i32,
+  max_payload_size: Option<usize>,
+  what: &str,
+) -> Result<Vec<u8>, String> {
+  let mut payload = Vec::new();
+  read_guest_payload_into(store, memory, ptr, len, max_payload_size, what, &mut payload)?;
+  Ok(payload)
+}
+
+// Same as `read_guest_payload`, the bytes replace the contents of `buffer`. Its allocation is
+// reused when it's large enough to hold the payload.
+pub(crate) fn read_guest_payload_into<'a, T: 'a>(
+  store: impl Into<StoreContext<'a, T>>,
+  memory: Memory,
+  ptr: i32,
+  len: i32,
+  max_payload_size: Option<usize>,
+  what: &str,
+  buffer: &mut Vec<u8>,
+) -> Result<(), String> {
+  let data = memory.data(store);
+  // the guest pointers and lengths are unsigned, and 32-bit wide even when the guest uses a
+  // 64-bit memory: they never truncate the memory size
+  let ptr = ptr as u32 as usize;
+  let len = len as u32 as usize;
+
+  if len > data.len() {
+    return Err(format!(
+      "{} of {} bytes exceeds the guest memory size of {} bytes",
+      what,
+      len,
+      data.len()
+    ));
+  }
+  if let Some(max) = max_payload_size.filter(|max| len > *max) {
+    return Err(format!(
+      "{} of {} bytes exceeds the maximum payload size of {} bytes",
+      what, len, max
+    ));
+  }
+  let payload = ptr
+    .checked_add(len)
+    .and_then(|end| data.get(ptr..end))
+    .ok_or_else(|| format!("{} of {} bytes at {} is out of the guest memory bounds", what, len, ptr))?;
+  buffer.clear();
+  buffer.extend_from_slice(payload);
+  Ok(())
+}
@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use crate::errors::Result;
+use crate::{ExternKind, WasmtimeEngineProvider, WasmtimeEngineProviderPre};
+
+#[cfg(feature = "async")]
+use crate::{WasmtimeEngineProviderAsync, WasmtimeEngineProviderAsyncPre};
+
+/// Interface shared by [`WasmtimeEngineProviderPre`] and
+/// [`WasmtimeEngineProviderAsyncPre`](crate::WasmtimeEngineProviderAsyncPre)
+///
+/// Allows code managing the lifecycle of modules, like pools or multi-tenant routers,
+/// to be written once for both flavours of providers:
+///
+/// ```
+/// use wasmtime_provider::EnginePre;
+///
+/// fn providers<P: EnginePre>(pre: &P, count: usize) -> Result<Vec<P::Provider>, wasmtime_provider::errors::Error> {
+///   (0..count).map(|_| pre.rehydrate()).collect()
+/// }
+/// ```
+pub trait EnginePre {
+  /// The provider created by [`rehydrate`](EnginePre::rehydrate)
+  type Provider;
+
+  /// Hex encoded SHA-256 digest of the WebAssembly module, when known
+  fn module_hash(&self) -> Option<&str>;
+
+  /// List the items exported by the WebAssembly module, together with their kind
+  fn exports(&self) -> Vec<(String, ExternKind)>;
+
+  /// Create a new provider ready to be consumed
+  fn rehydrate(&self) -> Result<Self::Provider>;
+}
+
+impl EnginePre for WasmtimeEngineProviderPre {
+  type Provider = WasmtimeEngineProvider;
+
+  fn module_hash(&self) -> Option<&str> {
+    WasmtimeEngineProviderPre::module_hash(self)
+  }
+
+  fn exports(&self) -> Vec<(String, ExternKind)> {
+    WasmtimeEngineProviderPre::exports(self)
+  }
+
+  fn rehydrate(&self) -> Result<Self::Provider> {
+    WasmtimeEngineProviderPre::rehydrate(self)
+  }
+}
+
+#[cfg(feature = "async")]
+impl EnginePre for WasmtimeEngineProviderAsyncPre {
+  type Provider = WasmtimeEngineProviderAsync;
+
+  fn module_hash(&self) -> Option<&str> {
+    WasmtimeEngineProviderAsyncPre::module_hash(self)
+  }
+
+  fn exports(&self) -> Vec<(String, ExternKind)> {
+    WasmtimeEngineProviderAsyncPre::exports(self)
+  }
+
+  fn rehydrate(&self) -> Result<Self::Provider> {
+    WasmtimeEngineProviderAsyncPre::rehydrate(self)
+  }
+}
+
+// Name given to the providers when the user didn't pick one: the beginning of the module
+// hash, otherwise the name found inside of the module
+pub(crate) fn default_name(module: &wasmtime::Module, module_hash: Option<&str>) -> Arc<str> {
+  module_hash
+    .map(|hash| &hash[..hash.len().min(12)])
+    .or_else(|| module.name())
+    .unwrap_or("wapc-guest")
+    .into()
+}
@@ -0,0 +1,127 @@
+use std::fmt::Write;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use log::{debug, warn};
+use sha2::{Digest, Sha256};
+use wasmtime::{Engine, Module};
+
+use crate::errors::{Error, Result};
+
+static TMP_FILE_COUNT: AtomicU64 = AtomicU64::new(0);
+
+// Feeds the values hashed via `std::hash::Hash` into a SHA-256 digest, unlike the std hashers
+// the result is stable across processes and Rust releases
+struct Sha256Hasher(Sha256);
+
+impl Hasher for Sha256Hasher {
+  fn write(&mut self, bytes: &[u8]) {
+    self.0.update(bytes);
+  }
+
+  fn finish(&self) -> u64 {
+    unreachable!("only the SHA-256 digest is used")
+  }
+}
+
+// Path of the artifact of `module_bytes` compiled by `engine`: the SHA-256 of the module bytes
+// and of the engine settings affecting the compiled code
+fn artifact_path(dir: &Path, engine: &Engine, module_bytes: &[u8]) -> PathBuf {
+  let mut hasher = Sha256Hasher(Sha256::new());
+  hasher.write(module_bytes);
+  engine.precompile_compatibility_hash().hash(&mut hasher);
+  let name = to_hex(&hasher.0.finalize());
+  dir.join(format!("{name}.cwasm"))
+}
+
+// Hex encoded SHA-256 digest of the module bytes
+pub(crate) fn module_hash(module_bytes: &[u8]) -> String {
+  to_hex(&Sha256::digest(module_bytes))
+}
+
+// Hex encoded SHA-256 digest of the module stored at `path`, the file is streamed into the digest
+pub(crate) fn file_hash(path: &Path) -> std::io::Result<String> {
+  let mut hasher = Sha256::new();
+  std::io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+  Ok(to_hex(&hasher.finalize()))
+}
+
+fn to_hex(digest: &[u8]) -> String {
+  digest.iter().fold(String::with_capacity(digest.len() * 2), |mut hex, b| {
+    let _ = write!(hex, "{:02x}", b);
+    hex
+  })
+}
+
+// Load the module compiled from `module_bytes` out of `dir`. When missing, unreadable or when
+// `force_recompile` is set, the module is compiled and its artifact is written to `dir`
+#[allow(unsafe_code)]
+pub(crate) fn load_or_compile(
+  engine: &Engine,
+  module_bytes: &[u8],
+  dir: &Path,
+  force_recompile: bool,
+) -> Result<Module> {
+  let path = artifact_path(dir, engine, module_bytes);
+
+  if !force_recompile && path.is_file() {
+    // SAFETY: the artifacts are only written by this function, the directory must be writable
+    // only by trusted parties, as documented by `precompile_cache_dir`
+    match unsafe { Module::deserialize_file(engine, &path) } {
+      Ok(module) => {
+        debug!("loaded precompiled module {}", path.display());
+        return Ok(module);
+      }
+      Err(e) => warn!(
+        "cannot load precompiled module {}, compiling it again: {}",
+        path.display(),
+        e
+      ),
+    }
+  }
+
+  let module = Module::new(engine, module_bytes)?;
+  store_artifact(&module, &path)?;
+  Ok(module)
+}
+
+// Load the module stored at `path`. The precompiled modules are memory mapped, while the
+// WebAssembly ones are compiled
+#[allow(unsafe_code)]
+pub(crate) fn load_file(engine: &Engine, path: &Path, precompiled: bool) -> Result<Module> {
+  let module = if precompiled {
+    // SAFETY: the user vouched for the origin of the file, as documented by `precompiled_module`
+    unsafe { Module::deserialize_file(engine, path) }
+  } else {
+    Module::from_file(engine, path)
+  };
+  module.map_err(|e| Error::ModulePath {
+    path: path.display().to_string(),
+    err: format!("{:#}", e),
+  })
+}
+
+// Write the artifact next to its final path, then rename it: concurrent readers never see
+// a partially written file
+fn store_artifact(module: &Module, path: &Path) -> Result<()> {
+  let cache_error = |err: String| Error::PrecompileCache {
+    path: path.display().to_string(),
+    err,
+  };
+
+  let artifact = module.serialize().map_err(|e| cache_error(e.to_string()))?;
+  if let Some(dir) = path.parent() {
+    std::fs::create_dir_all(dir).map_err(|e| cache_error(e.to_string()))?;
+  }
+  let tmp_path = path.with_extension(format!(
+    "cwasm.{}-{}.tmp",
+    std::process::id(),
+    TMP_FILE_COUNT.fetch_add(1, Ordering::Relaxed)
+  ));
+  std::fs::write(&tmp_path, artifact).map_err(|e| cache_error(e.to_string()))?;
+  std::fs::rename(&tmp_path, path).map_err(|e| {
+    let _ = std::fs::remove_file(&tmp_path);
+    cache_error(e.to_string())
+  })
+}
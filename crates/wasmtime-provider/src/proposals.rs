@@ -0,0 +1,72 @@
+use crate::errors::{Error, Result};
+
+/// The WebAssembly proposals explicitly enabled or disabled by the user, `None` keeps
+/// the Wasmtime default
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WasmProposals {
+  pub(crate) simd: Option<bool>,
+  pub(crate) relaxed_simd: Option<bool>,
+  pub(crate) bulk_memory: Option<bool>,
+  pub(crate) multi_memory: Option<bool>,
+  pub(crate) threads: Option<bool>,
+  pub(crate) memory64: Option<bool>,
+}
+
+impl WasmProposals {
+  pub(crate) fn is_set(&self) -> bool {
+    self.simd.is_some()
+      || self.relaxed_simd.is_some()
+      || self.bulk_memory.is_some()
+      || self.multi_memory.is_some()
+      || self.threads.is_some()
+      || self.memory64.is_some()
+  }
+
+  // Reject combinations that Wasmtime refuses when creating the engine
+  pub(crate) fn validate(&self) -> Result<()> {
+    if self.simd == Some(false) && self.relaxed_simd == Some(true) {
+      return Err(Error::BuilderInvalidConfig(
+        "`wasm_relaxed_simd` cannot be enabled when `wasm_simd` is disabled".to_owned(),
+      ));
+    }
+    if self.bulk_memory == Some(false) && self.threads == Some(true) {
+      return Err(Error::BuilderInvalidConfig(
+        "`wasm_threads` cannot be enabled when `wasm_bulk_memory` is disabled".to_owned(),
+      ));
+    }
+
+    Ok(())
+  }
+
+  // The proposals depending on a disabled one are disabled too, unless the user
+  // asked otherwise
+  pub(crate) fn apply(&self, config: &mut wasmtime::Config) {
+    if let Some(simd) = self.simd {
+      config.wasm_simd(simd);
+      if !simd && self.relaxed_simd.is_none() {
+        config.wasm_relaxed_simd(false);
+      }
+    }
+    if let Some(relaxed_simd) = self.relaxed_simd {
+      config.wasm_relaxed_simd(relaxed_simd);
+    }
+    if let Some(bulk_memory) = self.bulk_memory {
+      config.wasm_bulk_memory(bulk_memory);
+      if !bulk_memory {
+        config.wasm_reference_types(false);
+        if self.threads.is_none() {
+          config.wasm_threads(false);
+        }
+      }
+    }
+    if let Some(multi_memory) = self.multi_memory {
+      config.wasm_multi_memory(multi_memory);
+    }
+    if let Some(threads) = self.threads {
+      config.wasm_threads(threads);
+    }
+    if let Some(memory64) = self.memory64 {
+      config.wasm_memory64(memory64);
+    }
+  }
+}
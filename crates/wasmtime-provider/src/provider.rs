@@ -1,7 +1,10 @@
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 
 use log::{error, info};
 use parking_lot::RwLock;
+#[cfg(feature = "tracing")]
 use tracing::trace;
 #[cfg(feature = "wasi")]
 use wapc::WasiParams;
@@ -10,8 +13,28 @@ use wasmtime::{AsContextMut, Engine, Instance, InstancePre, Linker, Module, Stor
 
 use crate::callbacks;
 use crate::errors::{Error, Result};
+use crate::exports::{self, ExternKind};
+use crate::limits::{MemoryGrowCallback, WapcResourceLimiter};
+use crate::linking::{self, LinkOptions};
 use crate::store::WapcStore;
-use crate::EpochDeadlines;
+use crate::target::CompileSource;
+use crate::timings::CallTimingsCollector;
+use crate::traps;
+#[cfg(feature = "wasi")]
+use crate::wasi::WasiCtxHook;
+use crate::wasi_stub;
+#[cfg(feature = "wasi")]
+use crate::WasiEnvOverrides;
+use crate::{
+  CallCost,
+  CallTimings,
+  CallTimingsHandle,
+  EpochDeadlines,
+  EpochTickerHandle,
+  ExpectedModuleDigest,
+  FuncDeadlineOverride,
+  MemorySnapshot,
+};
 
 struct EngineInner {
   instance: Arc<RwLock<Instance>>,
@@ -28,19 +51,39 @@ struct EngineInner {
 #[derive(Clone)]
 pub struct WasmtimeEngineProviderPre {
   module: Module,
+  name: Arc<str>,
+  module_hash: Option<String>,
   #[cfg(feature = "wasi")]
   wasi_params: WasiParams,
+  #[cfg(feature = "wasi")]
+  wasi_ctx_hook: Option<Arc<WasiCtxHook>>,
   engine: Engine,
-  linker: Linker<WapcStore>,
-  instance_pre: InstancePre<WapcStore>,
+  linker: Arc<Linker<WapcStore>>,
+  instance_pre: Option<InstancePre<WapcStore>>,
+  link_options: LinkOptions,
   epoch_deadlines: Option<EpochDeadlines>,
+  call_timings: bool,
+  on_memory_grow: Option<Arc<MemoryGrowCallback>>,
+  reset_memory: bool,
+  max_payload_size: Option<usize>,
+  max_host_response: Option<usize>,
+  fuel_per_call: Option<u64>,
+  epoch_ticker: Option<EpochTickerHandle>,
+  exclude_host_calls_from_deadline: bool,
+  reuse_host_call_buffer: bool,
+  count_host_fns: bool,
+  compile_source: Option<Arc<CompileSource>>,
 }
 
+// `module_hash`, `exports` and `rehydrate` are also provided by `EnginePre`, they are kept
+// here so that callers don't need the trait in scope
+#[allow(clippy::same_name_method)]
 impl WasmtimeEngineProviderPre {
   #[cfg(feature = "wasi")]
   pub(crate) fn new(
     engine: Engine,
     module: Module,
+    link_options: LinkOptions,
     wasi: Option<WasiParams>,
     epoch_deadlines: Option<EpochDeadlines>,
   ) -> Result<Self> {
@@ -51,61 +94,269 @@ impl WasmtimeEngineProviderPre {
 
     // register all the waPC host functions
     callbacks::add_to_linker(&mut linker)?;
+    callbacks::add_bridge_to_linker(&mut linker, &module, &link_options)?;
+    wasi_stub::add_to_linker(&mut linker, &module, &link_options)?;
 
-    let instance_pre = linker.instantiate_pre(&module)?;
+    let instance_pre = linking::instance_pre(&linker, &module, &link_options)?;
 
     Ok(Self {
+      name: crate::pre::default_name(&module, None),
       module,
+      module_hash: None,
       wasi_params,
+      wasi_ctx_hook: None,
       engine,
-      linker,
+      linker: Arc::new(linker),
       instance_pre,
+      link_options,
       epoch_deadlines,
+      call_timings: false,
+      on_memory_grow: None,
+      reset_memory: false,
+      max_payload_size: None,
+      max_host_response: None,
+      fuel_per_call: None,
+      epoch_ticker: None,
+      exclude_host_calls_from_deadline: false,
+      reuse_host_call_buffer: false,
+      count_host_fns: false,
+      compile_source: None,
     })
   }
 
   #[cfg(not(feature = "wasi"))]
-  pub(crate) fn new(engine: Engine, module: Module, epoch_deadlines: Option<EpochDeadlines>) -> Result<Self> {
+  pub(crate) fn new(
+    engine: Engine,
+    module: Module,
+    link_options: LinkOptions,
+    epoch_deadlines: Option<EpochDeadlines>,
+  ) -> Result<Self> {
     let mut linker: Linker<WapcStore> = Linker::new(&engine);
 
     // register all the waPC host functions
     callbacks::add_to_linker(&mut linker)?;
+    callbacks::add_bridge_to_linker(&mut linker, &module, &link_options)?;
+    wasi_stub::add_to_linker(&mut linker, &module, &link_options)?;
 
-    let instance_pre = linker.instantiate_pre(&module)?;
+    let instance_pre = linking::instance_pre(&linker, &module, &link_options)?;
 
     Ok(Self {
+      name: crate::pre::default_name(&module, None),
       module,
+      module_hash: None,
       engine,
-      linker,
+      linker: Arc::new(linker),
       instance_pre,
+      link_options,
       epoch_deadlines,
+      call_timings: false,
+      on_memory_grow: None,
+      reset_memory: false,
+      max_payload_size: None,
+      max_host_response: None,
+      fuel_per_call: None,
+      epoch_ticker: None,
+      exclude_host_calls_from_deadline: false,
+      reuse_host_call_buffer: false,
+      count_host_fns: false,
+      compile_source: None,
     })
   }
 
+  pub(crate) fn with_call_timings(mut self, enabled: bool) -> Self {
+    self.call_timings = enabled;
+    self
+  }
+
+  pub(crate) fn with_memory_grow_callback(mut self, callback: Option<Arc<MemoryGrowCallback>>) -> Self {
+    self.on_memory_grow = callback;
+    self
+  }
+
+  pub(crate) fn with_memory_reset(mut self, enabled: bool) -> Self {
+    self.reset_memory = enabled;
+    self
+  }
+
+  pub(crate) fn with_max_payload_size(mut self, max_payload_size: Option<usize>) -> Self {
+    self.max_payload_size = max_payload_size;
+    self
+  }
+
+  pub(crate) fn with_max_host_response(mut self, max_host_response: Option<usize>) -> Self {
+    self.max_host_response = max_host_response;
+    self
+  }
+
+  pub(crate) fn with_fuel_per_call(mut self, fuel: Option<u64>) -> Self {
+    self.fuel_per_call = fuel;
+    self
+  }
+
+  pub(crate) fn with_host_fn_counters(mut self, enabled: bool) -> Self {
+    self.count_host_fns = enabled;
+    self
+  }
+
+  pub(crate) fn with_epoch_ticker(mut self, ticker: Option<EpochTickerHandle>) -> Self {
+    self.epoch_ticker = ticker;
+    self
+  }
+
+  pub(crate) fn with_host_calls_excluded_from_deadline(mut self, enabled: bool) -> Self {
+    self.exclude_host_calls_from_deadline = enabled;
+    self
+  }
+
+  pub(crate) fn with_host_call_buffer_reuse(mut self, enabled: bool) -> Self {
+    self.reuse_host_call_buffer = enabled;
+    self
+  }
+
+  #[cfg(feature = "wasi")]
+  pub(crate) fn with_wasi_ctx_hook(mut self, hook: Option<Arc<WasiCtxHook>>) -> Self {
+    self.wasi_ctx_hook = hook;
+    self
+  }
+
+  pub(crate) fn with_compile_source(mut self, source: Option<Arc<CompileSource>>) -> Self {
+    self.compile_source = source;
+    self
+  }
+
+  pub(crate) fn with_module_hash(mut self, module_hash: Option<String>) -> Self {
+    self.module_hash = module_hash;
+    self
+  }
+
+  // Must be invoked after `with_module_hash`, the default name is derived from the hash
+  pub(crate) fn with_name(mut self, name: Option<&str>) -> Self {
+    self.name = name.map_or_else(
+      || crate::pre::default_name(&self.module, self.module_hash.as_deref()),
+      Arc::from,
+    );
+    self
+  }
+
+  /// Name of the providers created by this instance, refer to
+  /// [`WasmtimeEngineProviderBuilder::name`](crate::WasmtimeEngineProviderBuilder::name)
+  #[must_use]
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  /// Hex encoded SHA-256 digest of the WebAssembly module, computed from the bytes given
+  /// to the builder. `None` when the module was provided as a compiled [`wasmtime::Module`]
+  /// or via [`module_path`](crate::WasmtimeEngineProviderBuilder::module_path).
+  #[must_use]
+  pub fn module_hash(&self) -> Option<&str> {
+    self.module_hash.as_deref()
+  }
+
+  /// List the items exported by the WebAssembly module, together with their kind
+  ///
+  /// The exports are read from the compiled module, hence this can be used to
+  /// verify a module before paying the instantiation cost.
+  #[must_use]
+  pub fn exports(&self) -> Vec<(String, ExternKind)> {
+    exports::module_exports(&self.module)
+  }
+
+  /// Returns `true` when the WebAssembly module exports an item named `name`
+  #[must_use]
+  pub fn has_export(&self, name: &str) -> bool {
+    exports::module_has_export(&self.module, name)
+  }
+
+  /// Compile the module for the `target` triple, e.g. `aarch64-unknown-linux-gnu`, and return
+  /// the serialized artifact. The compilation settings of this instance are preserved.
+  ///
+  /// The artifact can be loaded on the target host via [`wasmtime::Module::deserialize`],
+  /// then given to [`WasmtimeEngineProviderBuilder::module`](crate::WasmtimeEngineProviderBuilder::module).
+  /// Targets other than the host require the `all-arch` feature, otherwise
+  /// [`Error::CompileTarget`] is returned.
+  ///
+  /// This is available only when the builder created the engine and the module has been
+  /// provided via [`WasmtimeEngineProviderBuilder::module_bytes`](crate::WasmtimeEngineProviderBuilder::module_bytes).
+  pub fn serialize_for_target(&self, target: &str) -> Result<Vec<u8>> {
+    let source = self.compile_source.as_ref().ok_or_else(|| Error::CompileTarget {
+      target: target.to_owned(),
+      err: "the module has not been compiled from `module_bytes` by an engine created by the builder".to_owned(),
+    })?;
+    source.serialize_for_target(target)
+  }
+
   /// Create an instance of [`WasmtimeEngineProvider`] ready to be consumed
   ///
-  /// Note: from micro-benchmarking, this method is 10 microseconds faster than
-  /// `WasmtimeEngineProvider::clone`.
+  /// Note: turning the result into an initialized [`wapc::WapcHost`] costs about as much as
+  /// `WasmtimeEngineProvider::clone` (50-100 microseconds for a small module in a release build,
+  /// see `tests/rehydrate_cost.rs`), use
+  /// [`rehydrate_with_host`](WasmtimeEngineProviderPre::rehydrate_with_host) to save the second `Store`.
   pub fn rehydrate(&self) -> Result<WasmtimeEngineProvider> {
+    self.unbound(None)
+  }
+
+  /// Create an instance of [`WasmtimeEngineProvider`] that is already bound to `host`
+  /// and fully initialized.
+  ///
+  /// Unlike [`rehydrate`](WasmtimeEngineProviderPre::rehydrate), the `Store` (and its WASI context)
+  /// is created only once. The subsequent [`WebAssemblyEngineProvider::init`] invoked by
+  /// [`wapc::WapcHost::new_with_state`] with the same `host` is a no-op.
+  ///
+  /// Note: from `tests/rehydrate_cost.rs`, this saves 5-10 microseconds per initialized host
+  /// over [`rehydrate`](WasmtimeEngineProviderPre::rehydrate) in a release build.
+  pub fn rehydrate_with_host(&self, host: Arc<ModuleState>) -> Result<WasmtimeEngineProvider> {
+    let mut provider = self.unbound(Some(host.clone()))?;
+    provider.instantiate(host)?;
+
+    Ok(provider)
+  }
+
+  // Create a provider whose store is bound to `host`, without instantiating the module
+  fn unbound(&self, host: Option<Arc<ModuleState>>) -> Result<WasmtimeEngineProvider> {
     let engine = self.engine.clone();
 
     #[cfg(feature = "wasi")]
-    let wapc_store = WapcStore::new(&self.wasi_params, None)?;
+    let wapc_store = WapcStore::new(&self.wasi_params, self.wasi_ctx_hook.as_deref(), host)?;
     #[cfg(not(feature = "wasi"))]
-    let wapc_store = WapcStore::new(None);
+    let wapc_store = WapcStore::new(host);
 
     let store = Store::new(&engine, wapc_store);
 
     Ok(WasmtimeEngineProvider {
       module: self.module.clone(),
+      name: self.name.clone(),
       inner: None,
       engine,
       epoch_deadlines: self.epoch_deadlines,
+      func_deadline_override: FuncDeadlineOverride::default(),
+      expected_digest: ExpectedModuleDigest::default(),
+      call_timings: self.call_timings.then(CallTimingsHandle::default),
+      on_memory_grow: self.on_memory_grow.clone(),
+      reset_memory: self.reset_memory,
+      max_payload_size: self.max_payload_size,
+      max_host_response: self.max_host_response,
+      fuel_per_call: self.fuel_per_call,
+      epoch_ticker: self.epoch_ticker.clone(),
+      exclude_host_calls_from_deadline: self.exclude_host_calls_from_deadline,
+      reuse_host_call_buffer: self.reuse_host_call_buffer,
+      count_host_fns: self.count_host_fns,
+      reset_snapshot: None,
+      last_call_cost: None,
+      #[cfg(feature = "wasi")]
+      last_exit_code: None,
+      #[cfg(feature = "wasi")]
+      env_overrides: WasiEnvOverrides::default(),
+      #[cfg(feature = "wasi")]
+      env_generation: 0,
       linker: self.linker.clone(),
       instance_pre: self.instance_pre.clone(),
+      link_options: self.link_options.clone(),
       store,
       #[cfg(feature = "wasi")]
       wasi_params: self.wasi_params.clone(),
+      #[cfg(feature = "wasi")]
+      wasi_ctx_hook: self.wasi_ctx_hook.clone(),
     })
   }
 }
@@ -114,55 +365,52 @@ impl WasmtimeEngineProviderPre {
 #[allow(missing_debug_implementations)]
 pub struct WasmtimeEngineProvider {
   module: Module,
+  name: Arc<str>,
   #[cfg(feature = "wasi")]
   wasi_params: WasiParams,
+  #[cfg(feature = "wasi")]
+  wasi_ctx_hook: Option<Arc<WasiCtxHook>>,
   inner: Option<EngineInner>,
   engine: Engine,
-  linker: Linker<WapcStore>,
+  // shared with the `Pre` and the clones, copied by `replace` before being changed
+  linker: Arc<Linker<WapcStore>>,
   store: Store<WapcStore>,
-  instance_pre: InstancePre<WapcStore>,
+  instance_pre: Option<InstancePre<WapcStore>>,
+  link_options: LinkOptions,
   epoch_deadlines: Option<EpochDeadlines>,
+  func_deadline_override: FuncDeadlineOverride,
+  expected_digest: ExpectedModuleDigest,
+  call_timings: Option<CallTimingsHandle>,
+  on_memory_grow: Option<Arc<MemoryGrowCallback>>,
+  reset_memory: bool,
+  max_payload_size: Option<usize>,
+  max_host_response: Option<usize>,
+  fuel_per_call: Option<u64>,
+  // keeps the shared epoch ticker running as long as the provider is alive
+  epoch_ticker: Option<EpochTickerHandle>,
+  exclude_host_calls_from_deadline: bool,
+  reuse_host_call_buffer: bool,
+  count_host_fns: bool,
+  // state of the guest right after its initialization, restored before each call
+  reset_snapshot: Option<MemorySnapshot>,
+  // resources used by the most recent guest call
+  last_call_cost: Option<CallCost>,
+  #[cfg(feature = "wasi")]
+  last_exit_code: Option<i32>,
+  #[cfg(feature = "wasi")]
+  env_overrides: WasiEnvOverrides,
+  // generation of the env overrides applied to the current WASI context
+  #[cfg(feature = "wasi")]
+  env_generation: u64,
 }
 
 impl Clone for WasmtimeEngineProvider {
   fn clone(&self) -> Self {
-    let engine = self.engine.clone();
-
-    #[cfg(feature = "wasi")]
-    let wapc_store = WapcStore::new(&self.wasi_params, None).unwrap();
-    #[cfg(not(feature = "wasi"))]
-    let wapc_store = WapcStore::new(None);
-
-    let store = Store::new(&engine, wapc_store);
-
-    match &self.inner {
-      Some(state) => {
-        let mut new = Self {
-          module: self.module.clone(),
-          inner: None,
-          engine,
-          epoch_deadlines: self.epoch_deadlines,
-          linker: self.linker.clone(),
-          instance_pre: self.instance_pre.clone(),
-          store,
-          #[cfg(feature = "wasi")]
-          wasi_params: self.wasi_params.clone(),
-        };
-        new.init(state.host.clone()).unwrap();
-        new
-      }
-      None => Self {
-        module: self.module.clone(),
-        inner: None,
-        engine,
-        epoch_deadlines: self.epoch_deadlines,
-        linker: self.linker.clone(),
-        instance_pre: self.instance_pre.clone(),
-        store,
-        #[cfg(feature = "wasi")]
-        wasi_params: self.wasi_params.clone(),
-      },
+    let mut new = self.pre().rehydrate().unwrap();
+    if let Some(state) = &self.inner {
+      new.init(state.host.clone()).unwrap();
     }
+    new
   }
 }
 
@@ -171,25 +419,15 @@ impl WebAssemblyEngineProvider for WasmtimeEngineProvider {
     &mut self,
     host: Arc<ModuleState>,
   ) -> std::result::Result<(), Box<(dyn std::error::Error + Send + Sync + 'static)>> {
-    // create the proper store, now we have a value for `host`
-    #[cfg(feature = "wasi")]
-    let wapc_store = WapcStore::new(&self.wasi_params, Some(host.clone()))?;
-    #[cfg(not(feature = "wasi"))]
-    let wapc_store = WapcStore::new(Some(host.clone()));
-
-    self.store = Store::new(&self.engine, wapc_store);
+    // nothing to do, this provider has been created via `rehydrate_with_host`
+    if self.inner.as_ref().is_some_and(|inner| Arc::ptr_eq(&inner.host, &host)) {
+      return Ok(());
+    }
 
-    let instance = self.instance_pre.instantiate(&mut self.store)?;
+    // create the proper store, now we have a value for `host`
+    self.store = self.new_store(&host)?;
 
-    let instance_ref = Arc::new(RwLock::new(instance));
-    let gc = guest_call_fn(&mut self.store, &instance_ref)?;
-    self.inner = Some(EngineInner {
-      instance: instance_ref,
-      guest_call_fn: gc,
-      host,
-    });
-    self.initialize()?;
-    Ok(())
+    Ok(self.instantiate(host)?)
   }
 
   fn call(
@@ -197,26 +435,80 @@ impl WebAssemblyEngineProvider for WasmtimeEngineProvider {
     op_length: i32,
     msg_length: i32,
   ) -> std::result::Result<i32, Box<(dyn std::error::Error + Send + Sync + 'static)>> {
-    if let Some(deadlines) = &self.epoch_deadlines {
+    #[cfg(feature = "wasi")]
+    self.apply_env_overrides()?;
+
+    if let Some(snapshot) = &self.reset_snapshot {
+      let instance = *self.inner.as_ref().unwrap().instance.read();
+      if snapshot.outgrown(&mut self.store, &instance) {
+        // restoring would keep the grown memory, which the guest would grow again at each call
+        self.reset()?;
+      } else {
+        snapshot.restore(&mut self.store, &instance)?;
+      }
+    }
+
+    let func_deadline = self
+      .epoch_deadlines
+      .map(|deadlines| self.func_deadline_override.get().unwrap_or(deadlines.wapc_func));
+    if let Some(ticks) = func_deadline {
+      let ticks = if self.exclude_host_calls_from_deadline {
+        self.store.data_mut().guest_ticks.start(ticks)
+      } else {
+        ticks
+      };
       // the deadline counter must be set before invoking the wasm function
-      self.store.set_epoch_deadline(deadlines.wapc_func);
+      self.store.set_epoch_deadline(ticks);
     }
 
+    if let Some(collector) = self.store.data_mut().call_timings.as_mut() {
+      collector.take();
+    }
+    if let Some(counters) = self.store.data_mut().host_fn_counters.as_mut() {
+      counters.clear();
+    }
+    self.store.data_mut().limiter.take_memory_growth_failed();
+    if let Some(fuel) = self.fuel_per_call {
+      self.store.set_fuel(fuel)?;
+    }
+    let fuel_before = self.store.get_fuel().ok();
+    let started = Instant::now();
+
     let engine_inner = self.inner.as_ref().unwrap();
     let call = engine_inner
       .guest_call_fn
       .call(&mut self.store, (op_length, msg_length));
+    let epochs_elapsed = self.store.data_mut().guest_ticks.stop();
+    self.last_call_cost = Some(CallCost {
+      fuel_consumed: fuel_before
+        .zip(self.store.get_fuel().ok())
+        .map(|(before, after)| before.saturating_sub(after)),
+      epochs_elapsed,
+      duration: started.elapsed(),
+    });
+
+    if let (Some(handle), Some(collector)) = (&self.call_timings, self.store.data_mut().call_timings.as_mut()) {
+      handle.publish(collector.take());
+    }
 
     match call {
       Ok(result) => Ok(result),
       Err(err) => {
-        error!("Failure invoking guest module handler: {:?}", err);
+        error!("[{}] Failure invoking guest module handler: {:?}", self.name, err);
+        let memory_growth_failed = self.store.data_mut().limiter.take_memory_growth_failed();
         let mut guest_error = err.to_string();
-        if let Some(trap) = err.downcast_ref::<wasmtime::Trap>() {
+        if let Some(trap_error) = traps::classify(&err, memory_growth_failed) {
+          guest_error = trap_error.to_string();
+        } else if let Some(trap) = err.downcast_ref::<wasmtime::Trap>() {
           if matches!(trap, wasmtime::Trap::Interrupt) {
-            "guest code interrupted, execution deadline exceeded".clone_into(&mut guest_error);
+            guest_error = Error::FuncDeadlineExceeded(func_deadline.unwrap_or_default()).to_string();
           }
         }
+        #[cfg(feature = "wasi")]
+        if let Some(exit_err) = err.downcast_ref::<wasi_common::I32Exit>() {
+          self.last_exit_code = Some(exit_err.0);
+          guest_error = format!("guest exited with code {}", exit_err.0);
+        }
         engine_inner.host.set_guest_error(guest_error);
         Ok(0)
       }
@@ -228,32 +520,404 @@ impl WebAssemblyEngineProvider for WasmtimeEngineProvider {
     module: &[u8],
   ) -> std::result::Result<(), Box<(dyn std::error::Error + Send + Sync + 'static)>> {
     info!(
-      "HOT SWAP - Replacing existing WebAssembly module with new buffer, {} bytes",
+      "[{}] HOT SWAP - Replacing existing WebAssembly module with new buffer, {} bytes",
+      self.name,
       module.len()
     );
+    self.expected_digest.verify(module)?;
 
     let module = Module::new(&self.engine, module)?;
-    self.module = module;
-    self.instance_pre = self.linker.instantiate_pre(&self.module)?;
-    let new_instance = self.instance_pre.instantiate(&mut self.store)?;
-    if let Some(inner) = self.inner.as_mut() {
-      *inner.instance.write() = new_instance;
-      let gc = guest_call_fn(&mut self.store, &inner.instance)?;
-      inner.guest_call_fn = gc;
-    }
+    Ok(self.swap_module(module)?)
+  }
+
+  #[allow(unsafe_code)]
+  fn replace_precompiled(
+    &mut self,
+    module: &[u8],
+  ) -> std::result::Result<(), Box<(dyn std::error::Error + Send + Sync + 'static)>> {
+    info!(
+      "[{}] HOT SWAP - Replacing existing WebAssembly module with precompiled buffer, {} bytes",
+      self.name,
+      module.len()
+    );
+    self.expected_digest.verify(module)?;
 
-    Ok(self.initialize()?)
+    // SAFETY: the user vouched for the origin of the module, as documented by
+    // `WapcHost::replace_module_precompiled`. Modules created by an incompatible engine
+    // are rejected by wasmtime.
+    let module = unsafe { Module::deserialize(&self.engine, module) }?;
+    Ok(self.swap_module(module)?)
+  }
+
+  fn stats(&self) -> HashMap<String, u64> {
+    let mut stats = self.last_call_cost.as_ref().map(CallCost::stats).unwrap_or_default();
+    stats.extend(
+      self
+        .host_fn_counters()
+        .into_iter()
+        .map(|(name, count)| (format!("host_fn.{name}"), count)),
+    );
+    stats
   }
 }
 
 impl WasmtimeEngineProvider {
+  // The configuration of the provider, used to create its clones
+  fn pre(&self) -> WasmtimeEngineProviderPre {
+    WasmtimeEngineProviderPre {
+      module: self.module.clone(),
+      name: self.name.clone(),
+      module_hash: None,
+      #[cfg(feature = "wasi")]
+      wasi_params: self.wasi_params.clone(),
+      #[cfg(feature = "wasi")]
+      wasi_ctx_hook: self.wasi_ctx_hook.clone(),
+      engine: self.engine.clone(),
+      linker: self.linker.clone(),
+      instance_pre: self.instance_pre.clone(),
+      link_options: self.link_options.clone(),
+      epoch_deadlines: self.epoch_deadlines,
+      call_timings: self.call_timings.is_some(),
+      on_memory_grow: self.on_memory_grow.clone(),
+      reset_memory: self.reset_memory,
+      max_payload_size: self.max_payload_size,
+      max_host_response: self.max_host_response,
+      fuel_per_call: self.fuel_per_call,
+      epoch_ticker: self.epoch_ticker.clone(),
+      exclude_host_calls_from_deadline: self.exclude_host_calls_from_deadline,
+      reuse_host_call_buffer: self.reuse_host_call_buffer,
+      count_host_fns: self.count_host_fns,
+      compile_source: None,
+    }
+  }
+
+  // Instantiate and initialize `module` inside of a new store, which replaces the current one.
+  // The previous store, together with the instances and the WASI context it holds, is dropped
+  // once the swap succeeded. The provider is left untouched when the swap fails.
+  fn swap_module(&mut self, module: Module) -> Result<()> {
+    let mut linker = self.linker.clone();
+    callbacks::add_bridge_to_linker(Arc::make_mut(&mut linker), &module, &self.link_options)?;
+    wasi_stub::add_to_linker(Arc::make_mut(&mut linker), &module, &self.link_options)?;
+    let instance_pre = linking::instance_pre(&linker, &module, &self.link_options)?;
+
+    let Some(host) = self.inner.as_ref().map(|inner| inner.host.clone()) else {
+      // the module is instantiated when the provider is initialized
+      self.module = module;
+      self.linker = linker;
+      self.instance_pre = instance_pre;
+      return Ok(());
+    };
+    let store = self.new_store(&host)?;
+
+    let previous = (
+      std::mem::replace(&mut self.module, module),
+      std::mem::replace(&mut self.linker, linker),
+      std::mem::replace(&mut self.instance_pre, instance_pre),
+      std::mem::replace(&mut self.store, store),
+      self.inner.take(),
+      self.reset_snapshot.take(),
+    );
+    #[cfg(feature = "wasi")]
+    let env_generation = self.env_generation;
+
+    if let Err(e) = self.instantiate(host) {
+      (
+        self.module,
+        self.linker,
+        self.instance_pre,
+        self.store,
+        self.inner,
+        self.reset_snapshot,
+      ) = previous;
+      #[cfg(feature = "wasi")]
+      {
+        self.env_generation = env_generation;
+      }
+      return Err(e);
+    }
+    Ok(())
+  }
+
+  // Instantiate and initialize the module again inside of a new store, which replaces the current
+  // one along with the memory of its instance
+  fn reset(&mut self) -> Result<()> {
+    let Some(host) = self.inner.as_ref().map(|inner| inner.host.clone()) else {
+      return Ok(());
+    };
+
+    self.store = self.new_store(&host)?;
+    self.instantiate(host)
+  }
+
+  // Create a store bound to `host`, holding a new WASI context
+  fn new_store(&self, host: &Arc<ModuleState>) -> Result<Store<WapcStore>> {
+    #[cfg(feature = "wasi")]
+    let wapc_store = WapcStore::new(&self.wasi_params, self.wasi_ctx_hook.as_deref(), Some(host.clone()))?;
+    #[cfg(not(feature = "wasi"))]
+    let wapc_store = WapcStore::new(Some(host.clone()));
+
+    Ok(Store::new(&self.engine, wapc_store))
+  }
+
+  /// Change the number of epoch ticks granted to the waPC guest functions
+  ///
+  /// This has no effect unless epoch interruptions have been enabled via
+  /// [`enable_epoch_interruptions`](crate::WasmtimeEngineProviderBuilder::enable_epoch_interruptions).
+  pub fn set_func_deadline(&mut self, ticks: u64) {
+    if let Some(deadlines) = self.epoch_deadlines.as_mut() {
+      deadlines.wapc_func = ticks;
+    }
+  }
+
+  /// Returns a handle that can override the deadline of the waPC guest functions
+  /// after this provider has been moved into a host
+  #[must_use]
+  pub fn func_deadline_override(&self) -> FuncDeadlineOverride {
+    self.func_deadline_override.clone()
+  }
+
+  /// Returns a handle holding the digest the modules given to `replace` must match, it can be
+  /// used after this provider has been moved into a host
+  #[must_use]
+  pub fn expected_module_digest(&self) -> ExpectedModuleDigest {
+    self.expected_digest.clone()
+  }
+
+  /// Returns a handle that can override the environment variables of the WASI guest, call by call,
+  /// after this provider has been moved into a host
+  #[cfg(feature = "wasi")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "wasi")))]
+  #[must_use]
+  pub fn wasi_env_overrides(&self) -> WasiEnvOverrides {
+    self.env_overrides.clone()
+  }
+
+  /// Set the environment variable `key` seen by the WASI guest from the next call on
+  ///
+  /// Refer to [`WasiEnvOverrides`] for the cost of changing the environment between calls.
+  #[cfg(feature = "wasi")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "wasi")))]
+  pub fn set_env(&self, key: &str, value: &str) {
+    self.env_overrides.set(key, value);
+  }
+
+  /// Name of the provider, refer to
+  /// [`WasmtimeEngineProviderBuilder::name`](crate::WasmtimeEngineProviderBuilder::name)
+  #[must_use]
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  /// Returns how many times the guest invoked each waPC host function, like `__host_call`
+  /// or `__console_log`, during the most recent call
+  ///
+  /// The counters are reset at the beginning of every call. They are empty unless enabled via
+  /// [`WasmtimeEngineProviderBuilder::count_host_functions`](crate::WasmtimeEngineProviderBuilder::count_host_functions).
+  /// The provider `stats()` report them too, with the `host_fn.` prefix, which can be read via
+  /// [`wapc::WapcHost::stats`] after the provider has been moved into the host.
+  #[must_use]
+  pub fn host_fn_counters(&self) -> HashMap<&'static str, u64> {
+    self.store.data().host_fn_counters.clone().unwrap_or_default()
+  }
+
+  /// Returns the resources used by the most recent guest call, `None` if no call has been made yet
+  ///
+  /// The same values are reported by the provider `stats()`, with the `last_call.duration_ns`,
+  /// `last_call.fuel_consumed` and `last_call.epochs_elapsed` keys, which can be read via
+  /// [`wapc::WapcHost::stats`] after the provider has been moved into the host.
+  #[must_use]
+  pub fn last_call_cost(&self) -> Option<CallCost> {
+    self.last_call_cost
+  }
+
+  /// Returns the timings of the most recent guest call
+  ///
+  /// This is `None` unless call timings have been enabled via
+  /// [`enable_call_timings`](crate::WasmtimeEngineProviderBuilder::enable_call_timings).
+  #[must_use]
+  pub fn last_call_timings(&self) -> Option<CallTimings> {
+    self.call_timings.as_ref().and_then(CallTimingsHandle::last)
+  }
+
+  /// Returns a handle giving access to the call timings after this provider has been moved
+  /// into a host. This is `None` unless call timings have been enabled.
+  #[must_use]
+  pub fn call_timings_handle(&self) -> Option<CallTimingsHandle> {
+    self.call_timings.clone()
+  }
+
+  /// Returns the exit code of the most recent WASI `proc_exit` invocation performed by the guest,
+  /// either during the initialization or during a call
+  #[cfg(feature = "wasi")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "wasi")))]
+  #[must_use]
+  pub fn last_exit_code(&self) -> Option<i32> {
+    self.last_exit_code
+  }
+
+  /// List the items exported by the WebAssembly module currently loaded, together with their kind
+  #[must_use]
+  pub fn exports(&self) -> Vec<(String, ExternKind)> {
+    exports::module_exports(&self.module)
+  }
+
+  /// Returns `true` when the WebAssembly module exports an item named `name`
+  #[must_use]
+  pub fn has_export(&self, name: &str) -> bool {
+    exports::module_has_export(&self.module, name)
+  }
+
+  /// Copy the exported memories and mutable globals of the guest
+  ///
+  /// Returns `None` when the provider has not been initialized yet. Refer to [`MemorySnapshot`]
+  /// for the state that is captured.
+  pub fn snapshot(&mut self) -> Option<MemorySnapshot> {
+    let instance = *self.inner.as_ref()?.instance.read();
+    Some(MemorySnapshot::capture(&mut self.store, &instance))
+  }
+
+  /// Bring the exported memories and mutable globals of the guest back to the state captured
+  /// by `snapshot`. This is cheaper than instantiating the module again.
+  ///
+  /// **Note:** host resources, like the WASI file descriptors opened by the guest, are not reset.
+  pub fn restore(&mut self, snapshot: &MemorySnapshot) -> Result<()> {
+    let instance = *self
+      .inner
+      .as_ref()
+      .ok_or_else(|| Error::Snapshot("the provider has not been initialized".to_owned()))?
+      .instance
+      .read();
+    snapshot.restore(&mut self.store, &instance)
+  }
+
+  /// Give direct access to the Wasmtime store and to the instance of the guest module
+  ///
+  /// Returns `None` when the provider has not been initialized yet.
+  ///
+  /// **Warning:** this is an escape hatch for advanced use cases, like invoking exports that
+  /// are not part of the waPC protocol. The store is shared with the waPC calls, hence it's up
+  /// to the caller to not break them, for example by changing the epoch deadline or by
+  /// leaving the guest memory in an inconsistent state.
+  pub fn with_store<R>(&mut self, f: impl FnOnce(&mut Store<WapcStore>, &Instance) -> R) -> Option<R> {
+    let instance = *self.inner.as_ref()?.instance.read();
+    Some(f(&mut self.store, &instance))
+  }
+
+  /// Mutable access to the WASI context of the guest, for example to give it access to more
+  /// resources after its initialization
+  ///
+  /// Returns `None` when the provider has not been initialized yet: the initialization creates
+  /// a new context, hence changes made before it would be lost. For the same reason, the changes
+  /// are lost when the provider is initialized again, for example by binding it to another host.
+  #[cfg(feature = "wasi")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "wasi")))]
+  pub fn wasi_ctx_mut(&mut self) -> Option<&mut wasi_common::WasiCtx> {
+    self.inner.as_ref()?;
+    Some(self.store.data_mut().wasi_ctx_mut())
+  }
+
+  /// Give the guest access to the `host_path` directory, preopened at `guest_path`, while
+  /// the guest is running
+  ///
+  /// The guest sees the new directory the next time it looks up its preopened directories,
+  /// refer to [`wasi_ctx_mut`](WasmtimeEngineProvider::wasi_ctx_mut) for how long it stays available.
+  #[cfg(feature = "wasi")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "wasi")))]
+  pub fn push_preopened_dir(&mut self, host_path: impl AsRef<std::path::Path>, guest_path: &str) -> Result<()> {
+    let host_path = host_path.as_ref();
+    let ctx = self
+      .wasi_ctx_mut()
+      .ok_or_else(|| Error::WasiInitCtxError("the provider has not been initialized".to_owned()))?;
+    crate::wasi::push_preopened_dir(ctx, host_path, guest_path).map_err(|e| {
+      Error::WasiInitCtxError(format!(
+        "Cannot preopen {} as {}: {}",
+        host_path.display(),
+        guest_path,
+        e
+      ))
+    })
+  }
+
+  // Instantiate the module inside of the current store, then run the waPC initialization code
+  fn instantiate(&mut self, host: Arc<ModuleState>) -> Result<()> {
+    // the store is new, the env overrides must be applied again
+    #[cfg(feature = "wasi")]
+    {
+      self.env_generation = 0;
+    }
+
+    self.store.data_mut().provider_name = self.name.clone();
+    self.store.data_mut().limiter = WapcResourceLimiter::new(host.id(), self.name.clone(), self.on_memory_grow.clone());
+    self.store.limiter(|store| &mut store.limiter);
+    self.store.data_mut().max_payload_size = self.max_payload_size;
+    self.store.data_mut().max_host_response = self.max_host_response;
+    self.store.data_mut().host_fn_counters = self.count_host_fns.then(HashMap::new);
+    if let Some(fuel) = self.fuel_per_call {
+      self.store.set_fuel(fuel)?;
+    }
+    if self.exclude_host_calls_from_deadline && self.epoch_deadlines.is_some() {
+      self
+        .store
+        .epoch_deadline_callback(|mut store| store.data_mut().guest_ticks.on_deadline());
+    }
+    self.store.data_mut().host_call_buffer = self.reuse_host_call_buffer.then(Vec::new);
+
+    if self.call_timings.is_some() {
+      self.store.data_mut().call_timings = Some(CallTimingsCollector::default());
+      self.store.call_hook(|mut store, hook| {
+        if let Some(collector) = store.data_mut().call_timings.as_mut() {
+          collector.update(hook);
+        }
+        Ok(())
+      });
+    }
+
+    let instance = self.new_instance()?;
+
+    let instance_ref = Arc::new(RwLock::new(instance));
+    let gc = guest_call_fn(&mut self.store, &instance_ref)?;
+    self.inner = Some(EngineInner {
+      instance: instance_ref,
+      guest_call_fn: gc,
+      host,
+    });
+    self.initialize()
+  }
+
+  // Rebuild the WASI context when the env overrides changed since the previous call
+  #[cfg(feature = "wasi")]
+  fn apply_env_overrides(&mut self) -> Result<()> {
+    if let Some((generation, vars)) = self.env_overrides.changed_since(self.env_generation) {
+      let wasi_params = crate::env::apply_overrides(&self.wasi_params, vars);
+      self
+        .store
+        .data_mut()
+        .rebuild_wasi_ctx(&wasi_params, self.wasi_ctx_hook.as_deref())?;
+      self.env_generation = generation;
+    }
+    Ok(())
+  }
+
+  // Create a new instance of the module inside of the current store. The library
+  // modules, if any, are instantiated first inside of the same store
+  fn new_instance(&mut self) -> Result<Instance> {
+    if let Some(instance_pre) = &self.instance_pre {
+      return Ok(instance_pre.instantiate(&mut self.store)?);
+    }
+
+    let linker = linking::store_linker(&self.linker, &mut self.store, &self.link_options)?;
+    Ok(linker.instantiate(&mut self.store, &self.module)?)
+  }
+
   fn initialize(&mut self) -> Result<()> {
     for starter in wapc_functions::REQUIRED_STARTS.iter() {
-      trace!(function = starter, "calling init function");
+      #[cfg(feature = "tracing")]
+      trace!(provider = %self.name, function = starter, "calling init function");
       if let Some(deadlines) = &self.epoch_deadlines {
         // the deadline counter must be set before invoking the wasm function
         self.store.set_epoch_deadline(deadlines.wapc_init);
       }
+      self.store.data_mut().limiter.take_memory_growth_failed();
 
       let engine_inner = self.inner.as_ref().unwrap();
       if engine_inner
@@ -269,7 +933,11 @@ impl WasmtimeEngineProvider {
         let starter_func: TypedFunc<(), ()> = engine_inner.instance.read().get_typed_func(&mut self.store, starter)?;
 
         if let Err(err) = starter_func.call(&mut self.store, ()) {
-          trace!(function = starter, ?err, "handling error returned by init function");
+          #[cfg(feature = "tracing")]
+          trace!(provider = %self.name, function = starter, ?err, "handling error returned by init function");
+          if let Some(trap_error) = traps::classify(&err, self.store.data_mut().limiter.take_memory_growth_failed()) {
+            return Err(trap_error);
+          }
           if let Some(trap) = err.downcast_ref::<wasmtime::Trap>() {
             if matches!(trap, wasmtime::Trap::Interrupt) {
               return Err(Error::InitializationFailedTimeout((*starter).to_owned()));
@@ -286,10 +954,15 @@ impl WasmtimeEngineProvider {
           // will fail.
           #[cfg(feature = "wasi")]
           if let Some(exit_err) = err.downcast_ref::<wasi_common::I32Exit>() {
+            self.last_exit_code = Some(exit_err.0);
             if exit_err.0 != 0 {
-              return Err(Error::InitializationFailed(err.to_string()));
+              return Err(Error::InitializationFailed(format!(
+                "guest exited with code {}",
+                exit_err.0
+              )));
             }
-            trace!("ignoring successful exit trap generated by WASI");
+            #[cfg(feature = "tracing")]
+            trace!(provider = %self.name, "ignoring successful exit trap generated by WASI");
             continue;
           }
 
@@ -297,6 +970,9 @@ impl WasmtimeEngineProvider {
         };
       }
     }
+    if self.reset_memory {
+      self.reset_snapshot = self.snapshot();
+    }
     Ok(())
   }
 }
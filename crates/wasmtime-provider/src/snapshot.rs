@@ -0,0 +1,92 @@
+use wasmtime::{AsContextMut, Extern, Instance, Mutability, Val};
+
+use crate::errors::{Error, Result};
+
+/// A copy of the exported linear memories and mutable globals of a guest
+///
+/// Created by [`WasmtimeEngineProvider::snapshot`](crate::WasmtimeEngineProvider::snapshot) and
+/// given back to [`WasmtimeEngineProvider::restore`](crate::WasmtimeEngineProvider::restore).
+/// Only the state owned by the WebAssembly instance is captured: non exported globals, tables
+/// and host resources like the WASI file descriptors are not part of the snapshot.
+#[derive(Debug, Clone)]
+pub struct MemorySnapshot {
+  memories: Vec<(String, Vec<u8>)>,
+  globals: Vec<(String, Val)>,
+}
+
+impl MemorySnapshot {
+  pub(crate) fn capture(mut store: impl AsContextMut, instance: &Instance) -> Self {
+    let mut memories = Vec::new();
+    let mut globals = Vec::new();
+
+    let exports: Vec<(String, Extern)> = instance
+      .exports(&mut store)
+      .map(|export| (export.name().to_owned(), export.into_extern()))
+      .collect();
+    for (name, export) in exports {
+      match export {
+        Extern::Memory(memory) => memories.push((name, memory.data(&store).to_vec())),
+        Extern::Global(global) if global.ty(&store).mutability() == Mutability::Var => {
+          let value = global.get(&mut store);
+          // references cannot outlive the store they belong to
+          if matches!(
+            value,
+            Val::I32(_) | Val::I64(_) | Val::F32(_) | Val::F64(_) | Val::V128(_)
+          ) {
+            globals.push((name, value));
+          }
+        }
+        _ => {}
+      }
+    }
+
+    Self { memories, globals }
+  }
+
+  // Copy the snapshot back into the instance. Memory pages added after the snapshot
+  // was taken cannot be removed, they are zeroed instead
+  pub(crate) fn restore(&self, mut store: impl AsContextMut, instance: &Instance) -> Result<()> {
+    for (name, data) in &self.memories {
+      let memory = instance
+        .get_memory(&mut store, name)
+        .ok_or_else(|| Error::Snapshot(format!("memory `{name}` is not exported")))?;
+      let current = memory.data_mut(&mut store);
+      if current.len() < data.len() {
+        return Err(Error::Snapshot(format!(
+          "memory `{name}` is smaller than the snapshot ({} < {} bytes)",
+          current.len(),
+          data.len()
+        )));
+      }
+      current[..data.len()].copy_from_slice(data);
+      current[data.len()..].fill(0);
+    }
+
+    for (name, value) in &self.globals {
+      let global = instance
+        .get_global(&mut store, name)
+        .ok_or_else(|| Error::Snapshot(format!("global `{name}` is not exported")))?;
+      global
+        .set(&mut store, *value)
+        .map_err(|e| Error::Snapshot(format!("cannot restore global `{name}`: {e}")))?;
+    }
+
+    Ok(())
+  }
+
+  // Whether a memory of the instance grew past its size in the snapshot: the pages added since
+  // cannot be released by `restore`
+  pub(crate) fn outgrown(&self, mut store: impl AsContextMut, instance: &Instance) -> bool {
+    self.memories.iter().any(|(name, data)| {
+      instance
+        .get_memory(&mut store, name)
+        .is_some_and(|memory| memory.data_size(&store) > data.len())
+    })
+  }
+
+  /// Total size of the memories held by the snapshot, in bytes
+  #[must_use]
+  pub fn memory_size(&self) -> usize {
+    self.memories.iter().map(|(_, data)| data.len()).sum()
+  }
+}
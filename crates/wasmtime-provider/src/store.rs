@@ -1,26 +1,127 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use wapc::ModuleState;
 
-pub(crate) struct WapcStore {
+use crate::deadlines::GuestTicks;
+use crate::limits::WapcResourceLimiter;
+use crate::timings::CallTimingsCollector;
+
+/// The data held by the Wasmtime `Store` of the guest
+///
+/// It can be accessed via [`WasmtimeEngineProvider::with_store`](crate::WasmtimeEngineProvider::with_store).
+#[allow(missing_debug_implementations)]
+pub struct WapcStore {
   #[cfg(feature = "wasi")]
   pub(crate) wasi_ctx: wasi_common::WasiCtx,
+  pub(crate) call_timings: Option<CallTimingsCollector>,
+  pub(crate) limiter: WapcResourceLimiter,
+  pub(crate) max_payload_size: Option<usize>,
+  pub(crate) max_host_response: Option<usize>,
+  pub(crate) guest_ticks: GuestTicks,
+  // name of the provider owning the store, attached to the traces of the host functions
+  pub(crate) provider_name: Arc<str>,
+  // invocations of each waPC host function during the current call, when counting is enabled
+  pub(crate) host_fn_counters: Option<HashMap<&'static str, u64>>,
+  // buffer holding the payload of the host calls, kept between the calls when its reuse is enabled
+  pub(crate) host_call_buffer: Option<Vec<u8>>,
   pub(crate) host: Option<Arc<ModuleState>>,
 }
 
 impl WapcStore {
   #[cfg(feature = "wasi")]
-  pub(crate) fn new(wasi_params: &wapc::WasiParams, host: Option<Arc<ModuleState>>) -> crate::errors::Result<Self> {
-    let preopened_dirs = crate::wasi::compute_preopen_dirs(&wasi_params.preopened_dirs, &wasi_params.map_dirs)
-      .map_err(|e| crate::errors::Error::WasiInitCtxError(format!("Cannot compute preopened dirs: {:?}", e)))?;
-    let wasi_ctx = crate::wasi::init_ctx(preopened_dirs.as_slice(), &wasi_params.argv, &wasi_params.env_vars)
-      .map_err(|e| crate::errors::Error::WasiInitCtxError(e.to_string()))?;
+  pub(crate) fn new(
+    wasi_params: &wapc::WasiParams,
+    wasi_ctx_hook: Option<&crate::wasi::WasiCtxHook>,
+    host: Option<Arc<ModuleState>>,
+  ) -> crate::errors::Result<Self> {
+    let wasi_ctx = build_wasi_ctx(wasi_params, wasi_ctx_hook)?;
 
-    Ok(Self { wasi_ctx, host })
+    Ok(Self {
+      wasi_ctx,
+      call_timings: None,
+      limiter: WapcResourceLimiter::default(),
+      max_payload_size: None,
+      max_host_response: None,
+      guest_ticks: GuestTicks::default(),
+      provider_name: Arc::default(),
+      host_fn_counters: None,
+      host_call_buffer: None,
+      host,
+    })
   }
 
   #[cfg(not(feature = "wasi"))]
   pub(crate) fn new(host: Option<Arc<ModuleState>>) -> Self {
-    Self { host }
+    Self {
+      call_timings: None,
+      limiter: WapcResourceLimiter::default(),
+      max_payload_size: None,
+      max_host_response: None,
+      guest_ticks: GuestTicks::default(),
+      provider_name: Arc::default(),
+      host_fn_counters: None,
+      host_call_buffer: None,
+      host,
+    }
+  }
+
+  // Replace the WASI context with a new one created from `wasi_params`, the files opened
+  // by the guest are moved into the new context
+  #[cfg(feature = "wasi")]
+  pub(crate) fn rebuild_wasi_ctx(
+    &mut self,
+    wasi_params: &wapc::WasiParams,
+    wasi_ctx_hook: Option<&crate::wasi::WasiCtxHook>,
+  ) -> crate::errors::Result<()> {
+    let mut wasi_ctx = build_wasi_ctx(wasi_params, wasi_ctx_hook)?;
+    match (wasi_ctx.table_mut(), self.wasi_ctx.table_mut()) {
+      (Some(table), Some(previous_table)) => std::mem::swap(table, previous_table),
+      _ => {
+        return Err(crate::errors::Error::WasiInitCtxError(
+          "the WASI context is shared, it cannot be rebuilt".to_owned(),
+        ))
+      }
+    }
+    self.wasi_ctx = wasi_ctx;
+    Ok(())
+  }
+
+  /// The waPC host the guest is bound to, `None` before the provider is initialized
+  #[must_use]
+  pub fn host(&self) -> Option<&Arc<ModuleState>> {
+    self.host.as_ref()
+  }
+
+  /// The WASI context of the guest
+  #[cfg(feature = "wasi")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "wasi")))]
+  #[must_use]
+  pub fn wasi_ctx(&self) -> &wasi_common::WasiCtx {
+    &self.wasi_ctx
   }
+
+  /// Mutable access to the WASI context of the guest
+  #[cfg(feature = "wasi")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "wasi")))]
+  pub fn wasi_ctx_mut(&mut self) -> &mut wasi_common::WasiCtx {
+    &mut self.wasi_ctx
+  }
+}
+
+#[cfg(feature = "wasi")]
+fn build_wasi_ctx(
+  wasi_params: &wapc::WasiParams,
+  wasi_ctx_hook: Option<&crate::wasi::WasiCtxHook>,
+) -> crate::errors::Result<wasi_common::WasiCtx> {
+  let preopened_dirs = crate::wasi::compute_preopen_dirs(&wasi_params.preopened_dirs, &wasi_params.map_dirs)
+    .map_err(|e| crate::errors::Error::WasiInitCtxError(format!("Cannot compute preopened dirs: {:?}", e)))?;
+  crate::wasi::init_ctx(
+    preopened_dirs.as_slice(),
+    &crate::wasi::compute_args(wasi_params),
+    &crate::wasi::compute_env(wasi_params),
+    &wasi_params.stdio,
+    wasi_ctx_hook,
+  )
+  .map_err(|e| crate::errors::Error::WasiInitCtxError(e.to_string()))
 }
@@ -0,0 +1,41 @@
+use std::sync::Arc;
+
+use wapc::ModuleState;
+
+pub(crate) struct WapcComponentStore {
+  #[cfg(feature = "wasi")]
+  pub(crate) wasi_ctx: wasmtime_wasi::WasiCtx,
+  #[cfg(feature = "wasi")]
+  pub(crate) table: wasmtime_wasi::ResourceTable,
+  pub(crate) host: Option<Arc<ModuleState>>,
+}
+
+impl WapcComponentStore {
+  #[cfg(feature = "wasi")]
+  pub(crate) fn new(wasi_params: &wapc::WasiParams, host: Option<Arc<ModuleState>>) -> crate::errors::Result<Self> {
+    let wasi_ctx = crate::wasi::init_component_ctx(wasi_params)
+      .map_err(|e| crate::errors::Error::WasiInitCtxError(e.to_string()))?;
+
+    Ok(Self {
+      wasi_ctx,
+      table: wasmtime_wasi::ResourceTable::new(),
+      host,
+    })
+  }
+
+  #[cfg(not(feature = "wasi"))]
+  pub(crate) fn new(host: Option<Arc<ModuleState>>) -> Self {
+    Self { host }
+  }
+}
+
+#[cfg(feature = "wasi")]
+impl wasmtime_wasi::WasiView for WapcComponentStore {
+  fn table(&mut self) -> &mut wasmtime_wasi::ResourceTable {
+    &mut self.table
+  }
+
+  fn ctx(&mut self) -> &mut wasmtime_wasi::WasiCtx {
+    &mut self.wasi_ctx
+  }
+}
@@ -0,0 +1,50 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use target_lexicon::{Architecture, Triple};
+
+use crate::errors::{Error, Result};
+
+/// What is needed to compile the module again, for a different target
+pub(crate) struct CompileSource {
+  pub(crate) config: wasmtime::Config,
+  pub(crate) module_bytes: Arc<[u8]>,
+}
+
+impl CompileSource {
+  // Compile the module for `target` using a compilation-only engine, the returned
+  // artifact can be loaded with `wasmtime::Module::deserialize` on the target host
+  pub(crate) fn serialize_for_target(&self, target: &str) -> Result<Vec<u8>> {
+    let mut config = self.config.clone();
+    config.target(target).map_err(|e| compile_target_error(target, &e))?;
+    let engine = wasmtime::Engine::new(&config).map_err(|e| compile_target_error(target, &e))?;
+
+    Ok(engine.precompile_module(&self.module_bytes)?)
+  }
+}
+
+// Ensure the code compiled for `target` can be executed by this host. Like wasmtime, the
+// Pulley targets are accepted when they share the pointer width and endianness of the host
+pub(crate) fn check_runnable(target: &str) -> Result<()> {
+  let triple = Triple::from_str(target).map_err(|e| compile_target_error(target, &e))?;
+  let host = Triple::host();
+  let pulley = matches!(
+    triple.architecture,
+    Architecture::Pulley32 | Architecture::Pulley64 | Architecture::Pulley32be | Architecture::Pulley64be
+  ) && triple.pointer_width() == host.pointer_width()
+    && triple.endianness() == host.endianness();
+
+  if triple != host && !pulley {
+    return Err(Error::BuilderInvalidConfig(format!(
+      "the code compiled for `{target}` cannot run on this host (`{host}`), use `WasmtimeEngineProviderPre::serialize_for_target` to precompile modules for other targets"
+    )));
+  }
+  Ok(())
+}
+
+fn compile_target_error(target: &str, err: &impl std::fmt::Display) -> Error {
+  Error::CompileTarget {
+    target: target.to_owned(),
+    err: err.to_string(),
+  }
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::RwLock;
+use wasmtime::CallHook;
+
+/// Time spent by a guest call, see
+/// [`WasmtimeEngineProviderBuilder::enable_call_timings`](crate::WasmtimeEngineProviderBuilder::enable_call_timings)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CallTimings {
+  /// Time spent executing WebAssembly code
+  pub wasm_time: Duration,
+  /// Time spent inside of the host functions invoked by the guest
+  pub host_time: Duration,
+  /// Number of waPC host calls performed by the guest
+  pub host_call_count: u64,
+}
+
+/// Resources used by a guest call, see
+/// [`WasmtimeEngineProvider::last_call_cost`](crate::WasmtimeEngineProvider::last_call_cost)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CallCost {
+  /// Fuel consumed by the guest, `None` unless fuel consumption is enabled, refer to
+  /// [`WasmtimeEngineProviderBuilder::fuel_per_call`](crate::WasmtimeEngineProviderBuilder::fuel_per_call)
+  pub fuel_consumed: Option<u64>,
+  /// Epoch ticks elapsed while running guest code. Wasmtime doesn't expose the current epoch,
+  /// hence this is `None` unless the provider counts the ticks itself, refer to
+  /// [`WasmtimeEngineProviderBuilder::exclude_host_calls_from_deadline`](crate::WasmtimeEngineProviderBuilder::exclude_host_calls_from_deadline)
+  pub epochs_elapsed: Option<u64>,
+  /// Duration of the call, host calls included
+  pub duration: Duration,
+}
+
+impl CallCost {
+  // Entries of the provider `stats()` map
+  pub(crate) fn stats(&self) -> HashMap<String, u64> {
+    let mut stats = HashMap::from([(
+      "last_call.duration_ns".to_owned(),
+      u64::try_from(self.duration.as_nanos()).unwrap_or(u64::MAX),
+    )]);
+    if let Some(fuel) = self.fuel_consumed {
+      stats.insert("last_call.fuel_consumed".to_owned(), fuel);
+    }
+    if let Some(ticks) = self.epochs_elapsed {
+      stats.insert("last_call.epochs_elapsed".to_owned(), ticks);
+    }
+    stats
+  }
+}
+
+/// Handle giving access to the timings of the most recent guest call
+///
+/// The handle can be cloned and kept by the host after the engine provider has been moved into
+/// a [`wapc::WapcHost`].
+#[derive(Clone, Debug, Default)]
+pub struct CallTimingsHandle(Arc<RwLock<Option<CallTimings>>>);
+
+impl CallTimingsHandle {
+  /// Returns the timings of the most recent guest call, `None` if no call has been made yet
+  #[must_use]
+  pub fn last(&self) -> Option<CallTimings> {
+    *self.0.read()
+  }
+
+  pub(crate) fn publish(&self, timings: CallTimings) {
+    *self.0.write() = Some(timings);
+  }
+}
+
+// Collects the timings of the ongoing guest call. Lives inside of the Store data,
+// it's updated by the Store call hook
+#[derive(Default)]
+pub(crate) struct CallTimingsCollector {
+  timings: CallTimings,
+  last_transition: Option<Instant>,
+  wasm_depth: u32,
+}
+
+impl CallTimingsCollector {
+  pub(crate) fn update(&mut self, hook: CallHook) {
+    let now = Instant::now();
+    let elapsed = self.last_transition.map(|last| now - last).unwrap_or_default();
+
+    match hook {
+      CallHook::CallingWasm => {
+        self.timings.host_time += elapsed;
+        self.wasm_depth += 1;
+      }
+      CallHook::ReturningFromHost => self.timings.host_time += elapsed,
+      CallHook::CallingHost => self.timings.wasm_time += elapsed,
+      CallHook::ReturningFromWasm => {
+        self.timings.wasm_time += elapsed;
+        self.wasm_depth = self.wasm_depth.saturating_sub(1);
+      }
+    }
+
+    // time spent by the embedder outside of any wasm call is not accounted
+    self.last_transition = (self.wasm_depth > 0).then_some(now);
+  }
+
+  pub(crate) fn record_host_call(&mut self) {
+    self.timings.host_call_count += 1;
+  }
+
+  pub(crate) fn take(&mut self) -> CallTimings {
+    std::mem::take(self).timings
+  }
+}
@@ -0,0 +1,21 @@
+use wasmtime::Trap;
+
+use crate::errors::Error;
+
+// Map the traps the waPC host can act upon to their dedicated error variant. `memory_growth_failed`
+// tells whether a memory growth failed while running the guest code that raised `err`.
+// Returns `None` for all the other errors.
+pub(crate) fn classify(err: &anyhow::Error, memory_growth_failed: bool) -> Option<Error> {
+  let trap = err.downcast_ref::<Trap>()?;
+  let msg = trap.to_string();
+
+  match trap {
+    Trap::OutOfFuel => Some(Error::GuestOutOfFuel(msg)),
+    Trap::StackOverflow => Some(Error::GuestStackOverflow(msg)),
+    Trap::AllocationTooLarge => Some(Error::GuestOutOfMemory(msg)),
+    // guests usually abort, hence trap, when they cannot allocate memory
+    _ if memory_growth_failed => Some(Error::GuestOutOfMemory(msg)),
+    Trap::UnreachableCodeReached => Some(Error::GuestUnreachable(msg)),
+    _ => None,
+  }
+}
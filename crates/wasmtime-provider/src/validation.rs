@@ -0,0 +1,217 @@
+use std::fmt;
+
+use wapc::{wapc_functions, HOST_NAMESPACE};
+use wasmtime::{ExternType, FuncType, Module};
+
+use crate::linking::{self, LinkOptions};
+use crate::wasi_stub;
+
+/// Name of the memory the waPC host functions read from and write to
+const MEMORY_EXPORT: &str = "memory";
+
+/// Signature expected for `__guest_call`
+const GUEST_CALL_SIGNATURE: &str = "(i32, i32) -> i32";
+
+/// The waPC host functions, together with their signature
+const WAPC_IMPORTS: &[(&str, &str)] = &[
+  (wapc_functions::GUEST_REQUEST_FN, "(i32, i32) -> ()"),
+  (wapc_functions::HOST_CONSOLE_LOG, "(i32, i32) -> ()"),
+  (
+    wapc_functions::HOST_CALL,
+    "(i32, i32, i32, i32, i32, i32, i32, i32) -> i32",
+  ),
+  (wapc_functions::HOST_RESPONSE_FN, "(i32) -> ()"),
+  (wapc_functions::HOST_RESPONSE_LEN_FN, "() -> i32"),
+  (wapc_functions::GUEST_RESPONSE_FN, "(i32, i32) -> ()"),
+  (wapc_functions::GUEST_ERROR_FN, "(i32, i32) -> ()"),
+  (wapc_functions::HOST_ERROR_FN, "(i32) -> ()"),
+  (wapc_functions::HOST_ERROR_LEN_FN, "() -> i32"),
+];
+
+/// The outcome of the validation of a WebAssembly module as a waPC guest
+///
+/// Refer to [`WasmtimeEngineProviderBuilder::validate`](crate::WasmtimeEngineProviderBuilder::validate).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+  /// The waPC host functions imported by the module
+  pub wapc_imports: Vec<String>,
+  /// All the problems found, empty when the module is a valid waPC guest
+  pub problems: Vec<ValidationProblem>,
+}
+
+impl ValidationReport {
+  /// Returns `true` when no problem has been found
+  #[must_use]
+  pub fn is_valid(&self) -> bool {
+    self.problems.is_empty()
+  }
+}
+
+impl fmt::Display for ValidationReport {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if self.problems.is_empty() {
+      return write!(f, "the module is a valid waPC guest");
+    }
+    for (i, problem) in self.problems.iter().enumerate() {
+      if i > 0 {
+        write!(f, "; ")?;
+      }
+      write!(f, "{}", problem)?;
+    }
+    Ok(())
+  }
+}
+
+/// A problem preventing a WebAssembly module from being used as a waPC guest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationProblem {
+  /// An export required by the waPC host is missing, or is not of the expected kind
+  MissingExport {
+    /// name of the export
+    name: String,
+    /// kind of the export
+    kind: crate::ExternKind,
+  },
+  /// An export required by the waPC host has an unexpected signature
+  ExportSignatureMismatch {
+    /// name of the export
+    name: String,
+    /// signature expected by the host
+    expected: String,
+    /// signature of the export
+    found: String,
+  },
+  /// An import is not provided by the host, by WASI, by a bridged namespace or by a linked module
+  UnknownImport {
+    /// module of the import
+    module: String,
+    /// name of the import
+    name: String,
+  },
+  /// An import provided by the host has an unexpected signature
+  ImportSignatureMismatch {
+    /// module of the import
+    module: String,
+    /// name of the import
+    name: String,
+    /// signature provided by the host
+    expected: String,
+    /// signature of the import
+    found: String,
+  },
+}
+
+impl fmt::Display for ValidationProblem {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ValidationProblem::MissingExport { name, kind } => {
+        let kind = match kind {
+          crate::ExternKind::Func => "function",
+          crate::ExternKind::Global => "global",
+          crate::ExternKind::Table => "table",
+          crate::ExternKind::Memory => "memory",
+        };
+        write!(f, "the module does not export the `{}` {}", name, kind)
+      }
+      ValidationProblem::ExportSignatureMismatch { name, expected, found } => write!(
+        f,
+        "the exported function `{}` has signature {}, expected {}",
+        name, found, expected
+      ),
+      ValidationProblem::UnknownImport { module, name } => {
+        write!(f, "the import `{}.{}` is not provided by the host", module, name)
+      }
+      ValidationProblem::ImportSignatureMismatch {
+        module,
+        name,
+        expected,
+        found,
+      } => write!(
+        f,
+        "the imported function `{}.{}` has signature {}, expected {}",
+        module, name, found, expected
+      ),
+    }
+  }
+}
+
+// Check `module` is a waPC guest whose imports can be satisfied with `link_options`
+pub(crate) fn validate(module: &Module, link_options: &LinkOptions) -> ValidationReport {
+  let mut report = ValidationReport::default();
+
+  match module.get_export(wapc_functions::GUEST_CALL) {
+    Some(ExternType::Func(ty)) => {
+      let found = signature(&ty);
+      if found != GUEST_CALL_SIGNATURE {
+        report.problems.push(ValidationProblem::ExportSignatureMismatch {
+          name: wapc_functions::GUEST_CALL.to_owned(),
+          expected: GUEST_CALL_SIGNATURE.to_owned(),
+          found,
+        });
+      }
+    }
+    _ => report.problems.push(ValidationProblem::MissingExport {
+      name: wapc_functions::GUEST_CALL.to_owned(),
+      kind: crate::ExternKind::Func,
+    }),
+  }
+  if !matches!(module.get_export(MEMORY_EXPORT), Some(ExternType::Memory(_))) {
+    report.problems.push(ValidationProblem::MissingExport {
+      name: MEMORY_EXPORT.to_owned(),
+      kind: crate::ExternKind::Memory,
+    });
+  }
+
+  for import in module.imports() {
+    let (namespace, name) = (import.module(), import.name());
+    let unknown = || ValidationProblem::UnknownImport {
+      module: namespace.to_owned(),
+      name: name.to_owned(),
+    };
+
+    if namespace == HOST_NAMESPACE {
+      let expected = WAPC_IMPORTS.iter().find(|(func, _)| *func == name);
+      match (expected, import.ty()) {
+        (Some((_, expected)), ExternType::Func(ty)) => {
+          let found = signature(&ty);
+          if found != *expected {
+            report.problems.push(ValidationProblem::ImportSignatureMismatch {
+              module: namespace.to_owned(),
+              name: name.to_owned(),
+              expected: (*expected).to_owned(),
+              found,
+            });
+          }
+          report.wapc_imports.push(name.to_owned());
+        }
+        _ => report.problems.push(unknown()),
+      }
+    } else if linking::is_reserved(namespace) || (link_options.stub_wasi_imports && namespace == wasi_stub::NAMESPACE) {
+      // the WASI functions are checked when the module is instantiated
+    } else if let Some((_, library)) = link_options.modules.iter().find(|(library, _)| library == namespace) {
+      if library.get_export(name).is_none() {
+        report.problems.push(unknown());
+      }
+    } else if !link_options
+      .bridged_namespaces
+      .iter()
+      .any(|pattern| crate::bridge::matches_namespace(pattern, namespace))
+    {
+      report.problems.push(unknown());
+    }
+  }
+
+  report
+}
+
+// Render the signature of a function, e.g. `(i32, i32) -> i32`
+fn signature(ty: &FuncType) -> String {
+  let params: Vec<String> = ty.params().map(|p| p.to_string()).collect();
+  let results: Vec<String> = ty.results().map(|r| r.to_string()).collect();
+  let results = if results.len() == 1 {
+    results[0].clone()
+  } else {
+    format!("({})", results.join(", "))
+  };
+  format!("({}) -> {}", params.join(", "), results)
+}
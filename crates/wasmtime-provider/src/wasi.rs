@@ -3,16 +3,42 @@ use std::ffi::OsStr;
 use std::path::{Component, Path};
 
 use cap_std::{ambient_authority, fs::Dir};
+use wapc::StdioPolicy;
+use wasi_common::pipe::{ReadPipe, WritePipe};
 use wasi_common::WasiCtx;
 
+// Hook customizing the WASI context of the synchronous providers
+pub(crate) type WasiCtxHook = dyn Fn(&mut wasi_common::sync::WasiCtxBuilder) -> anyhow::Result<()> + Send + Sync;
+
+// Hook customizing the WASI context of the asynchronous providers
+#[cfg(feature = "async")]
+pub(crate) type WasiCtxHookAsync = dyn Fn(&mut wasi_common::tokio::WasiCtxBuilder) -> anyhow::Result<()> + Send + Sync;
+
 pub(crate) fn init_ctx(
   preopen_dirs: &[(String, Dir)],
   argv: &[String],
   env: &[(String, String)],
+  stdio: &StdioPolicy,
+  hook: Option<&WasiCtxHook>,
 ) -> Result<WasiCtx, Box<dyn Error + Send + Sync>> {
   let mut ctx_builder = wasi_common::sync::WasiCtxBuilder::new();
 
-  ctx_builder.inherit_stdio();
+  match stdio {
+    StdioPolicy::Inherit => {
+      ctx_builder.inherit_stdio();
+    }
+    StdioPolicy::Null => {
+      ctx_builder.stdin(Box::new(ReadPipe::new(std::io::empty())));
+      ctx_builder.stdout(Box::new(WritePipe::new(std::io::sink())));
+      ctx_builder.stderr(Box::new(WritePipe::new(std::io::sink())));
+    }
+    StdioPolicy::Capture(output) => {
+      ctx_builder.stdin(Box::new(ReadPipe::new(std::io::empty())));
+      ctx_builder.stdout(Box::new(WritePipe::new(output.clone())));
+      ctx_builder.stderr(Box::new(WritePipe::new(output.clone())));
+    }
+  }
+
   ctx_builder.args(argv)?;
   ctx_builder.envs(env)?;
 
@@ -20,6 +46,11 @@ pub(crate) fn init_ctx(
     ctx_builder.preopened_dir(file.try_clone()?, name)?;
   }
 
+  // applied last, so that the hook can override the standard settings
+  if let Some(hook) = hook {
+    hook(&mut ctx_builder)?;
+  }
+
   Ok(ctx_builder.build())
 }
 
@@ -28,10 +59,27 @@ pub(crate) fn init_ctx_async(
   preopen_dirs: &[(String, Dir)],
   argv: &[String],
   env: &[(String, String)],
+  stdio: &StdioPolicy,
+  hook: Option<&WasiCtxHookAsync>,
 ) -> Result<WasiCtx, Box<dyn Error + Send + Sync>> {
   let mut ctx_builder = wasi_common::tokio::WasiCtxBuilder::new();
 
-  ctx_builder.inherit_stdio();
+  match stdio {
+    StdioPolicy::Inherit => {
+      ctx_builder.inherit_stdio();
+    }
+    StdioPolicy::Null => {
+      ctx_builder.stdin(Box::new(ReadPipe::new(std::io::empty())));
+      ctx_builder.stdout(Box::new(WritePipe::new(std::io::sink())));
+      ctx_builder.stderr(Box::new(WritePipe::new(std::io::sink())));
+    }
+    StdioPolicy::Capture(output) => {
+      ctx_builder.stdin(Box::new(ReadPipe::new(std::io::empty())));
+      ctx_builder.stdout(Box::new(WritePipe::new(output.clone())));
+      ctx_builder.stderr(Box::new(WritePipe::new(output.clone())));
+    }
+  }
+
   ctx_builder.args(argv)?;
   ctx_builder.envs(env)?;
 
@@ -39,9 +87,77 @@ pub(crate) fn init_ctx_async(
     ctx_builder.preopened_dir(file.try_clone()?, name)?;
   }
 
+  // applied last, so that the hook can override the standard settings
+  if let Some(hook) = hook {
+    hook(&mut ctx_builder)?;
+  }
+
+  Ok(ctx_builder.build())
+}
+
+pub(crate) fn init_component_ctx(
+  wasi_params: &wapc::WasiParams,
+) -> Result<wasmtime_wasi::WasiCtx, Box<dyn Error + Send + Sync>> {
+  use wasmtime_wasi::{DirPerms, FilePerms};
+
+  let mut ctx_builder = wasmtime_wasi::WasiCtxBuilder::new();
+
+  match &wasi_params.stdio {
+    StdioPolicy::Inherit => {
+      ctx_builder.inherit_stdio();
+    }
+    StdioPolicy::Null => {
+      ctx_builder.stdout(wasmtime_wasi::pipe::SinkOutputStream);
+      ctx_builder.stderr(wasmtime_wasi::pipe::SinkOutputStream);
+    }
+    StdioPolicy::Capture(_) => return Err("capturing the stdio is not supported by WebAssembly components".into()),
+  }
+  ctx_builder.args(&compute_args(wasi_params));
+  ctx_builder.envs(&compute_env(wasi_params));
+
+  for dir in &wasi_params.preopened_dirs {
+    ctx_builder.preopened_dir(dir, dir, DirPerms::all(), FilePerms::all())?;
+  }
+
+  for (guest, host) in &wasi_params.map_dirs {
+    ctx_builder.preopened_dir(host, guest, DirPerms::all(), FilePerms::all())?;
+  }
+
   Ok(ctx_builder.build())
 }
 
+// Combine the environment of the host process, when inherited, with the variables explicitly
+// provided. The latter take precedence over the inherited ones
+pub(crate) fn compute_env(wasi_params: &wapc::WasiParams) -> Vec<(String, String)> {
+  let mut env: Vec<(String, String)> = Vec::new();
+
+  if wasi_params.inherit_env {
+    env.extend(std::env::vars_os().filter_map(|(key, value)| {
+      let key = key.into_string().ok()?;
+      let value = value.into_string().ok()?;
+      let allowed = wasi_params
+        .env_allowlist
+        .as_ref()
+        .is_none_or(|allowlist| allowlist.contains(&key));
+      let overridden = wasi_params.env_vars.iter().any(|(explicit, _)| *explicit == key);
+      (allowed && !overridden).then_some((key, value))
+    }));
+  }
+  env.extend(wasi_params.env_vars.iter().cloned());
+
+  env
+}
+
+// The command line arguments of the host process are used only when no argument has been
+// explicitly provided
+pub(crate) fn compute_args(wasi_params: &wapc::WasiParams) -> Vec<String> {
+  if wasi_params.inherit_args && wasi_params.argv.is_empty() {
+    std::env::args_os().filter_map(|arg| arg.into_string().ok()).collect()
+  } else {
+    wasi_params.argv.clone()
+  }
+}
+
 pub(crate) fn compute_preopen_dirs(
   dirs: &[String],
   map_dirs: &[(String, String)],
@@ -60,6 +176,26 @@ pub(crate) fn compute_preopen_dirs(
   Ok(preopen_dirs)
 }
 
+// Open the `host_path` directory and make it available to the guest at `guest_path`, on a
+// WASI context of a synchronous provider that is already in use
+pub(crate) fn push_preopened_dir(ctx: &WasiCtx, host_path: &Path, guest_path: &str) -> Result<(), Box<dyn Error>> {
+  let dir = Dir::open_ambient_dir(host_path, ambient_authority())?;
+  ctx.push_preopened_dir(Box::new(wasi_common::sync::dir::Dir::from_cap_std(dir)), guest_path)?;
+  Ok(())
+}
+
+// Same as `push_preopened_dir`, for the WASI context of an asynchronous provider
+#[cfg(feature = "async")]
+pub(crate) fn push_preopened_dir_async(
+  ctx: &WasiCtx,
+  host_path: &Path,
+  guest_path: &str,
+) -> Result<(), Box<dyn Error>> {
+  let dir = Dir::open_ambient_dir(host_path, ambient_authority())?;
+  ctx.push_preopened_dir(Box::new(wasi_common::tokio::Dir::from_cap_std(dir)), guest_path)?;
+  Ok(())
+}
+
 #[allow(dead_code)]
 pub(crate) fn compute_argv(module: &Path, module_args: &[String]) -> Vec<String> {
   // Add argv[0], which is the program name. Only include the base name of the
@@ -0,0 +1,99 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use anyhow::anyhow;
+use wasmtime::{Caller, ExternType, Linker, Module, Val};
+
+use crate::errors::{Error, Result};
+use crate::linking::LinkOptions;
+
+/// Namespace of the WASI functions replaced by stubs
+pub(crate) const NAMESPACE: &str = "wasi_snapshot_preview1";
+
+// WASI errno values
+const ERRNO_SUCCESS: i32 = 0;
+const ERRNO_BADF: i32 = 8;
+const ERRNO_NOSYS: i32 = 52;
+
+// Define stubs for the WASI functions imported by the guest, when requested by the user.
+// They only allow the module to be instantiated and to run code that doesn't rely on WASI:
+// the guest sees no arguments and no environment variables, `random_get` succeeds because the
+// Rust standard library needs it to create a `HashMap`, the `fd_*` functions fail with `EBADF`,
+// which the Rust standard library ignores when printing, `proc_exit` traps and the other ones
+// fail with `ENOSYS`. When the `wasi` feature is enabled, the real functions are linked instead.
+pub(crate) fn add_to_linker<T: 'static>(
+  linker: &mut Linker<T>,
+  module: &Module,
+  link_options: &LinkOptions,
+) -> Result<()> {
+  if !link_options.stub_wasi_imports || cfg!(feature = "wasi") {
+    return Ok(());
+  }
+
+  // a hot swapped module redefines the stubs of the previous one
+  linker.allow_shadowing(true);
+  let modules = std::iter::once(module).chain(link_options.modules.iter().map(|(_, library)| library));
+  let result = modules
+    .flat_map(Module::imports)
+    .filter(|import| import.module() == NAMESPACE)
+    .try_for_each(|import| {
+      let ExternType::Func(ty) = import.ty() else {
+        return Ok(());
+      };
+      let name = import.name().to_owned();
+      linker
+        .func_new(NAMESPACE, import.name(), ty, move |mut caller, params, results| {
+          let errno = stub(&mut caller, &name, params)?;
+          // all the preview1 functions, except `proc_exit`, return an errno
+          if let Some(result) = results.first_mut() {
+            *result = Val::I32(errno);
+          }
+          Ok(())
+        })
+        .map(|_| ())
+        .map_err(|e| Error::LinkerFuncDef {
+          func: format!("{}.{}", NAMESPACE, import.name()),
+          err: e.into(),
+        })
+    });
+  linker.allow_shadowing(false);
+
+  result
+}
+
+fn stub<T>(caller: &mut Caller<'_, T>, name: &str, params: &[Val]) -> anyhow::Result<i32> {
+  let param = |i: usize| params.get(i).and_then(Val::i32).unwrap_or_default();
+  match name {
+    "proc_exit" => Err(anyhow!(
+      "proc_exit({}) invoked, WASI is not available to the guest",
+      param(0)
+    )),
+    "args_sizes_get" | "environ_sizes_get" => {
+      write_guest(caller, param(0), &0u32.to_le_bytes())?;
+      write_guest(caller, param(1), &0u32.to_le_bytes())?;
+      Ok(ERRNO_SUCCESS)
+    }
+    "args_get" | "environ_get" => Ok(ERRNO_SUCCESS),
+    "random_get" => {
+      let mut buf = vec![0u8; param(1) as u32 as usize];
+      // not cryptographically secure, the guest is only meant to seed its hash maps
+      for chunk in buf.chunks_mut(8) {
+        let random = RandomState::new().build_hasher().finish().to_le_bytes();
+        chunk.copy_from_slice(&random[..chunk.len()]);
+      }
+      write_guest(caller, param(0), &buf)?;
+      Ok(ERRNO_SUCCESS)
+    }
+    _ if name.starts_with("fd_") => Ok(ERRNO_BADF),
+    _ => Ok(ERRNO_NOSYS),
+  }
+}
+
+fn write_guest<T>(caller: &mut Caller<'_, T>, ptr: i32, bytes: &[u8]) -> anyhow::Result<()> {
+  let memory = caller
+    .get_export("memory")
+    .and_then(|export| export.into_memory())
+    .ok_or_else(|| anyhow!("the guest does not export its memory"))?;
+  memory.write(caller, ptr as u32 as usize, bytes)?;
+  Ok(())
+}
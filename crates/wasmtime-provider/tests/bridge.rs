@@ -0,0 +1,102 @@
+use wapc::errors::Error;
+use wapc::WapcHost;
+use wapc_codec::messagepack::{deserialize, serialize};
+
+#[cfg(feature = "async")]
+use wapc::WapcHostAsync;
+
+const GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (import "ext" "rand" (func $rand (param i32) (result i32)))
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (i32.store (i32.const 0) (call $rand (i32.const 6)))
+    (call $guest_response (i32.const 0) (i32.const 4))
+    (i32.const 1)))
+"#;
+
+fn rand_callback(
+  _id: u64,
+  bd: &str,
+  ns: &str,
+  op: &str,
+  payload: &[u8],
+) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+  assert_eq!(bd, "ext*");
+  assert_eq!(ns, "ext");
+  assert_eq!(op, "rand");
+  let args: Vec<i32> = deserialize(payload)?;
+  assert_eq!(args, vec![6]);
+
+  // chosen by fair dice roll
+  Ok(serialize(4)?)
+}
+
+#[test]
+fn bridges_imports_to_host_callback() -> Result<(), Error> {
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(GUEST.as_bytes())
+    .bridge_namespaces(&["ext*"])
+    .build()?;
+  let guest = WapcHost::new(Box::new(engine), Some(Box::new(rand_callback)))?;
+
+  let result = guest.call("rand", b"")?;
+  assert_eq!(result, 4_i32.to_le_bytes());
+
+  Ok(())
+}
+
+#[test]
+fn bridged_import_traps_on_host_error() -> Result<(), Error> {
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(GUEST.as_bytes())
+    .bridge_namespaces(&["ext"])
+    .build()?;
+  let guest = WapcHost::new(
+    Box::new(engine),
+    Some(Box::new(|_, _, _, _, _| Err("no entropy left".into()))),
+  )?;
+
+  let result = guest.call("rand", b"");
+  assert!(matches!(result, Err(Error::GuestCallFailure(_))));
+
+  Ok(())
+}
+
+#[test]
+fn bridge_rejects_unsupported_signature() {
+  let module = r#"
+  (module
+    (import "ext" "now" (func (result i64)))
+    (memory (export "memory") 1)
+    (func (export "__guest_call") (param i32 i32) (result i32) (i32.const 1)))
+  "#;
+
+  let result = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(module.as_bytes())
+    .bridge_namespaces(&["ext"])
+    .build_pre();
+  assert!(matches!(
+    result,
+    Err(wasmtime_provider::errors::Error::LinkerFuncDef { func, .. }) if func == "ext.now"
+  ));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn bridges_imports_to_host_callback_async() -> Result<(), Error> {
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(GUEST.as_bytes())
+    .bridge_namespaces(&["ext*"])
+    .build_async()?;
+
+  let host_callback: Box<wapc::HostCallbackAsync> =
+    Box::new(move |id, bd, ns, op, payload| Box::pin(async move { rand_callback(id, &bd, &ns, &op, &payload) }));
+  let guest = WapcHostAsync::new(Box::new(engine), Some(host_callback)).await?;
+
+  let result = guest.call("rand", b"").await?;
+  assert_eq!(result, 4_i32.to_le_bytes());
+
+  Ok(())
+}
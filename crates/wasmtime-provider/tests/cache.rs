@@ -0,0 +1,156 @@
+#![cfg(feature = "cache")]
+
+use std::fs::read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use wasmtime_provider::errors::Error;
+use wasmtime_provider::{CacheOptions, WasmtimeEngineProviderBuilder};
+
+const MODULE_PATH: &str = "../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm";
+
+fn cache_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("wasmtime-provider-{}-{}", name, std::process::id()));
+  let _ = std::fs::remove_dir_all(&dir);
+  dir
+}
+
+fn count_files(dir: &Path) -> usize {
+  std::fs::read_dir(dir).map_or(0, |entries| {
+    entries
+      .flatten()
+      .map(|entry| {
+        let path = entry.path();
+        if path.is_dir() {
+          count_files(&path)
+        } else {
+          1
+        }
+      })
+      .sum()
+  })
+}
+
+#[test]
+fn cache_is_written_to_configured_directory() {
+  let buf = read(MODULE_PATH).unwrap();
+  let dir = cache_dir("cache-directory");
+
+  WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .enable_cache_with(CacheOptions {
+      directory: Some(dir.clone()),
+      max_size_bytes: Some(64 * 1024 * 1024),
+      cleanup_interval: Some(Duration::from_secs(3600)),
+      ..Default::default()
+    })
+    .build()
+    .expect("Cannot create WebAssemblyEngineProvider");
+
+  assert!(count_files(&dir) > 0, "no cache file written under {}", dir.display());
+
+  let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn cache_rejects_relative_directory() {
+  let buf = read(MODULE_PATH).unwrap();
+
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .enable_cache_with(CacheOptions {
+      directory: Some(PathBuf::from("relative/cache")),
+      ..Default::default()
+    })
+    .build();
+  assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+}
+
+#[test]
+fn cache_rejects_invalid_limits() {
+  let buf = read(MODULE_PATH).unwrap();
+
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .enable_cache_with(CacheOptions {
+      max_size_bytes: Some(0),
+      ..Default::default()
+    })
+    .build();
+  assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .enable_cache_with(CacheOptions {
+      cleanup_interval: Some(Duration::from_millis(10)),
+      ..Default::default()
+    })
+    .build();
+  assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+}
+
+#[test]
+fn cache_options_cannot_be_used_with_custom_engine() {
+  let buf = read(MODULE_PATH).unwrap();
+
+  let result = WasmtimeEngineProviderBuilder::new()
+    .engine(wasmtime_provider::wasmtime::Engine::default())
+    .module_bytes(&buf)
+    .enable_cache_with(CacheOptions::default())
+    .build();
+  assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+}
+
+#[test]
+fn enable_cache_cannot_be_used_with_custom_engine() {
+  let buf = read(MODULE_PATH).unwrap();
+
+  let result = WasmtimeEngineProviderBuilder::new()
+    .engine(wasmtime_provider::wasmtime::Engine::default())
+    .module_bytes(&buf)
+    .enable_cache(None)
+    .build();
+  assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+}
+
+#[test]
+fn cache_config_file_is_loaded() {
+  let buf = read(MODULE_PATH).unwrap();
+  let dir = cache_dir("cache-config-file");
+  std::fs::create_dir_all(&dir).unwrap();
+  let config_file = dir.join("config.toml");
+  std::fs::write(
+    &config_file,
+    format!("[cache]\nenabled = true\ndirectory = {:?}\n", dir.join("artifacts")),
+  )
+  .unwrap();
+
+  WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .enable_cache(Some(&config_file))
+    .build()
+    .expect("Cannot create WebAssemblyEngineProvider");
+
+  assert!(
+    count_files(&dir.join("artifacts")) > 0,
+    "no cache file written under {}",
+    dir.display()
+  );
+
+  let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn cache_config_file_cannot_be_combined_with_other_options() {
+  let buf = read(MODULE_PATH).unwrap();
+
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .enable_cache_with(CacheOptions {
+      config_file: Some(PathBuf::from("/etc/wasmtime/cache.toml")),
+      max_size_bytes: Some(1024),
+      ..Default::default()
+    })
+    .build();
+  assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+}
@@ -1,10 +1,12 @@
 use std::fs::read;
+use std::sync::Arc;
 
-use wapc::{errors, WapcHost};
+use wapc::{errors, ModuleState, WapcHost};
 use wapc_codec::messagepack::{deserialize, serialize};
+use wasmtime_provider::ExternKind;
 
 #[cfg(feature = "async")]
-use wapc::WapcHostAsync;
+use wapc::{ModuleStateAsync, WapcHostAsync};
 
 #[test]
 fn runs_wapc_guest() -> Result<(), errors::Error> {
@@ -26,6 +28,23 @@ fn runs_wapc_guest() -> Result<(), errors::Error> {
   Ok(())
 }
 
+#[test]
+fn runs_wapc_guest_rehydrate_with_host() -> Result<(), errors::Error> {
+  let buf = read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm")?;
+
+  let pre = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .build_pre()?;
+  let state = Arc::new(ModuleState::with_callback(None));
+  let engine = pre.rehydrate_with_host(state.clone())?;
+  let guest = WapcHost::new_with_state(Box::new(engine), state)?;
+
+  let callresult = guest.call("echo", &serialize("hello world").unwrap())?;
+  let result: String = deserialize(&callresult).unwrap();
+  assert_eq!(result, "hello world");
+  Ok(())
+}
+
 #[cfg(feature = "async")]
 async fn host_callback_async(
   _id: u64,
@@ -58,3 +77,68 @@ async fn runs_wapc_guest_async() -> Result<(), errors::Error> {
   assert_eq!(result, "hello world");
   Ok(())
 }
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn runs_wapc_guest_async_rehydrate_with_host() -> Result<(), errors::Error> {
+  let buf = read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm")?;
+
+  let pre = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .build_async_pre()?;
+  let state = Arc::new(ModuleStateAsync::with_callback(None));
+  let engine = pre.rehydrate_with_host(state.clone()).await?;
+  let host = WapcHostAsync::new_with_state(Box::new(engine), state).await?;
+
+  let callresult = host.call("echo", &serialize("hello world").unwrap()).await?;
+  let result: String = deserialize(&callresult).unwrap();
+  assert_eq!(result, "hello world");
+  Ok(())
+}
+
+#[test]
+fn wapc_guest_exports() -> Result<(), errors::Error> {
+  let buf = read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm")?;
+
+  let pre = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .build_pre()?;
+  let exports = pre.exports();
+  assert!(exports.contains(&("__guest_call".to_owned(), ExternKind::Func)));
+  assert!(exports.iter().any(|(_, kind)| *kind == ExternKind::Memory));
+  assert!(pre.has_export("__guest_call"));
+
+  let engine = pre.rehydrate()?;
+  assert_eq!(engine.exports(), exports);
+
+  Ok(())
+}
+
+#[test]
+fn non_wapc_module_is_detected_before_instantiation() -> Result<(), errors::Error> {
+  let pre = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(br#"(module (func (export "hello")))"#)
+    .build_pre()?;
+
+  assert_eq!(pre.exports(), vec![("hello".to_owned(), ExternKind::Func)]);
+  assert!(!pre.has_export("__guest_call"));
+
+  Ok(())
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn wapc_guest_async_exports() -> Result<(), errors::Error> {
+  let buf = read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm")?;
+
+  let pre = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .build_async_pre()?;
+  assert!(pre.has_export("__guest_call"));
+  assert!(!pre.has_export("__wapc_abi_version"));
+
+  let engine = pre.rehydrate()?;
+  assert_eq!(engine.exports(), pre.exports());
+
+  Ok(())
+}
@@ -0,0 +1,92 @@
+use std::fs::read;
+
+use serde::{Deserialize, Serialize};
+use wapc::{errors, WapcHost};
+use wapc_codec::messagepack::serialize;
+use wasmtime_provider::WasmtimeEngineProviderBuilder;
+
+#[cfg(feature = "async")]
+use wapc::WapcHostAsync;
+
+const ECHO_MODULE: &str = "../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm";
+const HASH_MODULE: &str = "../../wasm/crates/wasm-calc-hash/module1/build/module1_hash.wasm";
+const FUEL: u64 = 10_000_000_000;
+
+#[derive(Deserialize, Serialize)]
+struct PersonSend {
+  first_name: String,
+}
+
+fn host(path: &str) -> Result<WapcHost, errors::Error> {
+  let module_bytes = read(path)?;
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .fuel_per_call(FUEL)
+    .build()?;
+  WapcHost::new(Box::new(engine), Some(Box::new(|_, _, _, _, _| Ok(vec![]))))
+}
+
+#[test]
+fn heavier_guest_consumes_more_fuel() -> Result<(), errors::Error> {
+  let echo = host(ECHO_MODULE)?;
+  assert!(echo.stats().is_empty());
+  echo.call("echo", &serialize("hello world").unwrap())?;
+  let echo_stats = echo.stats();
+
+  let hash = host(HASH_MODULE)?;
+  let person = PersonSend {
+    first_name: "Florian".to_owned(),
+  };
+  hash.call("serdes_example", &serialize(&person).unwrap())?;
+  let hash_stats = hash.stats();
+
+  let echo_fuel = echo_stats["last_call.fuel_consumed"];
+  let hash_fuel = hash_stats["last_call.fuel_consumed"];
+  assert!(echo_fuel > 0);
+  assert!(hash_fuel > echo_fuel, "{hash_fuel} <= {echo_fuel}");
+  assert!(hash_stats.contains_key("last_call.duration_ns"));
+  // epoch ticks are only counted when host calls are excluded from the deadline
+  assert!(!hash_stats.contains_key("last_call.epochs_elapsed"));
+  Ok(())
+}
+
+#[test]
+fn fuel_is_refilled_before_each_call() -> Result<(), errors::Error> {
+  let echo = host(ECHO_MODULE)?;
+  let payload = serialize("hello world").unwrap();
+  echo.call("echo", &payload)?;
+  let first = echo.stats()["last_call.fuel_consumed"];
+  echo.call("echo", &payload)?;
+  assert_eq!(echo.stats()["last_call.fuel_consumed"], first);
+  Ok(())
+}
+
+#[test]
+fn fuel_per_call_exhausted() -> Result<(), errors::Error> {
+  let module_bytes = read(HASH_MODULE)?;
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .fuel_per_call(1_000)
+    .build()?;
+  // the initialization of the module is bound by the same budget
+  let err = WapcHost::new(Box::new(engine), None).err().unwrap();
+  assert!(err.to_string().contains("out_of_fuel"), "{err}");
+  Ok(())
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn async_provider_reports_call_cost() -> Result<(), errors::Error> {
+  let module_bytes = read(ECHO_MODULE)?;
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .fuel_per_call(FUEL)
+    .build_async()?;
+  let host = WapcHostAsync::new(Box::new(engine), None).await?;
+  host.call("echo", &serialize("hello world").unwrap()).await?;
+
+  let stats = host.stats().await;
+  assert!(stats["last_call.fuel_consumed"] > 0);
+  assert!(stats.contains_key("last_call.duration_ns"));
+  Ok(())
+}
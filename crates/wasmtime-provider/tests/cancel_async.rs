@@ -0,0 +1,64 @@
+#![cfg(feature = "async")]
+
+use std::time::Duration;
+
+use wapc::errors::Error;
+use wapc::WapcHostAsync;
+
+// counts the calls, then forwards the operation to the host before responding with the counter
+const GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_request" (func $guest_request (param i32 i32)))
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (import "wapc" "__host_call" (func $host_call (param i32 i32 i32 i32 i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (global $calls (mut i32) (i32.const 0))
+  (func (export "__guest_call") (param $op_len i32) (param $msg_len i32) (result i32)
+    (call $guest_request (i32.const 0) (i32.const 256))
+    (global.set $calls (i32.add (global.get $calls) (i32.const 1)))
+    (drop (call $host_call
+      (i32.const 0) (i32.const 0)
+      (i32.const 0) (i32.const 0)
+      (i32.const 0) (local.get $op_len)
+      (i32.const 256) (local.get $msg_len)))
+    (i32.store (i32.const 512) (global.get $calls))
+    (call $guest_response (i32.const 512) (i32.const 4))
+    (i32.const 1)))
+"#;
+
+async fn slow_host_callback(op: String) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+  if op == "slow" {
+    tokio::time::sleep(Duration::from_secs(60)).await;
+  }
+  Ok(vec![])
+}
+
+async fn create_guest() -> Result<WapcHostAsync, Error> {
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(GUEST.as_bytes())
+    .build_async()?;
+  let host_callback: Box<wapc::HostCallbackAsync> =
+    Box::new(move |_id, _bd, _ns, op, _payload| Box::pin(slow_host_callback(op)));
+
+  WapcHostAsync::new(Box::new(engine), Some(host_callback)).await
+}
+
+fn calls(response: &[u8]) -> i32 {
+  i32::from_le_bytes(response.try_into().unwrap())
+}
+
+#[tokio::test]
+async fn call_after_cancellation_resets_the_guest() -> Result<(), Error> {
+  let guest = create_guest().await?;
+
+  assert_eq!(calls(&guest.call("fast", b"").await?), 1);
+  assert_eq!(calls(&guest.call("fast", b"").await?), 2);
+
+  let cancelled = tokio::time::timeout(Duration::from_millis(100), guest.call("slow", b"")).await;
+  assert!(cancelled.is_err(), "the call should have timed out");
+
+  // the partially executed call is discarded together with the guest state
+  assert_eq!(calls(&guest.call("fast", b"").await?), 1);
+  assert_eq!(calls(&guest.call("fast", b"").await?), 2);
+  Ok(())
+}
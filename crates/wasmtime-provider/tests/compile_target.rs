@@ -0,0 +1,108 @@
+use std::fs::read;
+
+use wapc::{errors, WapcHost};
+use wapc_codec::messagepack::{deserialize, serialize};
+use wasmtime_provider::errors::Error;
+use wasmtime_provider::WasmtimeEngineProviderBuilder;
+
+// A target that is never the host running the tests
+fn foreign_target() -> &'static str {
+  if cfg!(target_arch = "aarch64") {
+    "x86_64-unknown-linux-gnu"
+  } else {
+    "aarch64-unknown-linux-gnu"
+  }
+}
+
+#[test]
+fn serialize_for_host_target() -> Result<(), errors::Error> {
+  let buf = read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm")?;
+
+  let pre = WasmtimeEngineProviderBuilder::new().module_bytes(&buf).build_pre()?;
+  let artifact = pre.serialize_for_target(&target_lexicon::Triple::host().to_string())?;
+
+  let engine = wasmtime::Engine::default();
+  let module = unsafe { wasmtime::Module::deserialize(&engine, artifact) }.map_err(Error::from)?;
+  let provider = WasmtimeEngineProviderBuilder::new()
+    .engine(engine)
+    .module(module)
+    .build()?;
+  let guest = WapcHost::new(Box::new(provider), None)?;
+
+  let callresult = guest.call("echo", &serialize("hello world").unwrap())?;
+  let result: String = deserialize(&callresult).unwrap();
+  assert_eq!(result, "hello world");
+  Ok(())
+}
+
+#[cfg(feature = "all-arch")]
+#[test]
+fn serialize_for_foreign_target() -> Result<(), errors::Error> {
+  let buf = read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm")?;
+
+  let pre = WasmtimeEngineProviderBuilder::new().module_bytes(&buf).build_pre()?;
+  let artifact = pre.serialize_for_target(foreign_target())?;
+  assert!(!artifact.is_empty());
+
+  // the artifact cannot be loaded by the host
+  let engine = wasmtime::Engine::default();
+  assert!(unsafe { wasmtime::Module::deserialize(&engine, artifact) }.is_err());
+  Ok(())
+}
+
+#[cfg(not(feature = "all-arch"))]
+#[test]
+fn foreign_target_not_enabled() -> Result<(), errors::Error> {
+  let buf = read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm")?;
+
+  let pre = WasmtimeEngineProviderBuilder::new().module_bytes(&buf).build_pre()?;
+  let result = pre.serialize_for_target(foreign_target());
+  assert!(matches!(result, Err(Error::CompileTarget { target, .. }) if target == foreign_target()));
+  Ok(())
+}
+
+#[test]
+fn invalid_target_triple() -> Result<(), errors::Error> {
+  let buf = read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm")?;
+
+  let pre = WasmtimeEngineProviderBuilder::new().module_bytes(&buf).build_pre()?;
+  let result = pre.serialize_for_target("not-a-triple");
+  assert!(matches!(result, Err(Error::CompileTarget { .. })));
+  Ok(())
+}
+
+#[test]
+fn serialize_requires_module_bytes() -> Result<(), errors::Error> {
+  let engine = wasmtime::Engine::default();
+  let module = wasmtime::Module::new(&engine, r#"(module (func (export "hello")))"#).map_err(Error::from)?;
+
+  let pre = WasmtimeEngineProviderBuilder::new()
+    .engine(engine)
+    .module(module)
+    .build_pre()?;
+  let result = pre.serialize_for_target(foreign_target());
+  assert!(matches!(result, Err(Error::CompileTarget { .. })));
+  Ok(())
+}
+
+#[test]
+fn runnable_provider_requires_host_target() {
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(br#"(module (func (export "hello")))"#)
+    .compile_target(foreign_target())
+    .build_pre();
+  match result {
+    Err(Error::BuilderInvalidConfig(msg)) => assert!(msg.contains(foreign_target())),
+    Err(e) => panic!("unexpected error: {e}"),
+    Ok(_) => panic!("a provider has been built for a foreign target"),
+  }
+}
+
+#[test]
+fn host_compile_target() {
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(br#"(module (func (export "hello")))"#)
+    .compile_target(&target_lexicon::Triple::host().to_string())
+    .build_pre();
+  assert!(result.is_ok());
+}
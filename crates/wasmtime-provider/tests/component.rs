@@ -0,0 +1,139 @@
+use std::fs::read;
+
+#[cfg(feature = "wasi")]
+use wapc::errors::Error;
+#[cfg(feature = "wasi")]
+use wapc::WapcHost;
+
+#[cfg(feature = "wasi")]
+const PAYLOAD: &str = "this is a test";
+// the Rust guest targets `wasm32-wasip2`, it imports WASI preview 2
+const COMPONENT_PATH: &str = "../../wasm/crates/wapc-guest-component/build/wapc_guest_component.wasm";
+
+#[cfg(feature = "wasi")]
+fn create_guest(callback: Box<wapc::HostCallback>) -> Result<WapcHost, Error> {
+  let buf = read(COMPONENT_PATH)?;
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .component_bytes(&buf)
+    .build_component()
+    .expect("Cannot create WebAssemblyEngineProvider");
+  WapcHost::new(Box::new(engine), Some(callback))
+}
+
+#[cfg(feature = "wasi")]
+#[test]
+fn runs_component_echo() -> Result<(), Error> {
+  let guest = create_guest(Box::new(|_, _, _, _, _| Ok(vec![])))?;
+
+  let result = guest.call("echo", PAYLOAD.as_bytes())?;
+  assert_eq!(result, PAYLOAD.as_bytes());
+
+  Ok(())
+}
+
+#[cfg(feature = "wasi")]
+#[test]
+fn runs_component_host_call() -> Result<(), Error> {
+  let guest = create_guest(Box::new(|_id, bd, ns, op, payload| {
+    assert_eq!(bd, "binding");
+    assert_eq!(ns, "sample:namespace");
+    assert_eq!(op, "pong");
+    assert_eq!(payload, PAYLOAD.as_bytes());
+
+    Ok(b"pong".to_vec())
+  }))?;
+
+  let result = guest.call("ping", PAYLOAD.as_bytes())?;
+  assert_eq!(result, b"pong");
+
+  Ok(())
+}
+
+#[cfg(feature = "wasi")]
+#[test]
+fn component_host_call_error_is_returned_to_guest() -> Result<(), Error> {
+  let guest = create_guest(Box::new(|_, _, _, _, _| Err("host failure".into())))?;
+
+  let result = guest.call("ping", PAYLOAD.as_bytes());
+  match result {
+    Err(Error::GuestCallFailure(msg)) => assert!(msg.contains("host failure")),
+    other => panic!("unexpected result: {:?}", other),
+  }
+
+  Ok(())
+}
+
+#[cfg(feature = "wasi")]
+#[test]
+fn component_replace() -> Result<(), Error> {
+  let guest = create_guest(Box::new(|_, _, _, _, _| Ok(vec![])))?;
+
+  guest.replace_module(&read(COMPONENT_PATH)?)?;
+  let result = guest.call("echo", PAYLOAD.as_bytes())?;
+  assert_eq!(result, PAYLOAD.as_bytes());
+
+  Ok(())
+}
+
+#[test]
+fn build_component_rejects_module_bytes() {
+  let buf = read(COMPONENT_PATH).unwrap();
+  let result = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .component_bytes(&buf)
+    .module_bytes(&buf)
+    .build_component();
+  assert!(matches!(
+    result,
+    Err(wasmtime_provider::errors::Error::BuilderInvalidConfig(_))
+  ));
+}
+
+#[test]
+fn build_component_rejects_module_only_options() {
+  let buf = read(COMPONENT_PATH).unwrap();
+  let result = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .component_bytes(&buf)
+    .fuel_per_call(1000)
+    .build_component();
+  match result {
+    Err(wasmtime_provider::errors::Error::BuilderInvalidConfig(e)) => assert!(e.contains("fuel_per_call"), "{}", e),
+    Err(e) => panic!("unexpected error: {}", e),
+    Ok(_) => panic!("expected the component to be rejected"),
+  }
+}
+
+#[test]
+fn build_component_rejects_linking_options() {
+  let buf = read(COMPONENT_PATH).unwrap();
+  let linked = read("../../wasm/crates/wasm-basic/build/wasm_basic.wasm").unwrap();
+  let builders = [
+    (
+      wasmtime_provider::WasmtimeEngineProviderBuilder::new().link_module("helper", &linked),
+      "link_module",
+    ),
+    (
+      wasmtime_provider::WasmtimeEngineProviderBuilder::new().bridge_namespaces(&["math"]),
+      "bridge_namespaces",
+    ),
+    (
+      wasmtime_provider::WasmtimeEngineProviderBuilder::new().stub_wasi_imports(true),
+      "stub_wasi_imports",
+    ),
+    (
+      wasmtime_provider::WasmtimeEngineProviderBuilder::new().validate_on_build(true),
+      "validate_on_build",
+    ),
+    (
+      wasmtime_provider::WasmtimeEngineProviderBuilder::new().enable_call_timings(),
+      "enable_call_timings",
+    ),
+  ];
+
+  for (builder, option) in builders {
+    match builder.component_bytes(&buf).build_component() {
+      Err(wasmtime_provider::errors::Error::BuilderInvalidConfig(e)) => assert!(e.contains(option), "{}", e),
+      Err(e) => panic!("unexpected error: {}", e),
+      Ok(_) => panic!("expected the component to be rejected with `{}`", option),
+    }
+  }
+}
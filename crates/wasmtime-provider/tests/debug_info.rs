@@ -0,0 +1,44 @@
+use std::fs::read;
+
+use wapc::{errors, WapcHost};
+use wapc_codec::messagepack::{deserialize, serialize};
+use wasmtime_provider::errors::Error;
+use wasmtime_provider::WasmtimeEngineProviderBuilder;
+
+#[test]
+fn runs_guest_with_dwarf() -> Result<(), errors::Error> {
+  // the guest has been built with its DWARF sections
+  let buf = read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm")?;
+
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .debug_info(true)
+    .build()?;
+  let guest = WapcHost::new(Box::new(engine), None)?;
+
+  let callresult = guest.call("echo", &serialize("hello world").unwrap())?;
+  let result: String = deserialize(&callresult).unwrap();
+  assert_eq!(result, "hello world");
+  Ok(())
+}
+
+#[test]
+fn debug_info_cannot_be_used_with_custom_engine() {
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(br#"(module (func (export "hello")))"#)
+    .engine(wasmtime::Engine::default())
+    .debug_info(true)
+    .build_pre();
+  assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+}
+
+#[cfg(feature = "cache")]
+#[test]
+fn debug_info_cannot_be_used_with_cache() {
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(br#"(module (func (export "hello")))"#)
+    .enable_cache(None)
+    .debug_info(true)
+    .build_pre();
+  assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+}
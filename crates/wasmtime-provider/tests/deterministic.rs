@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+use wapc::{errors, WapcHost};
+use wapc_codec::messagepack::serialize;
+use wasmtime_provider::errors::Error;
+use wasmtime_provider::WasmtimeEngineProviderBuilder;
+
+const HASH_MODULE: &str = "../../wasm/crates/wasm-calc-hash/module1/build/module1_hash.wasm";
+
+// Returns the bits of the NaN produced by 0.0 / 0.0, which depend on the CPU unless NaN
+// values are canonicalized
+const NAN_GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (f32.store (i32.const 0) (f32.div (f32.const 0) (f32.const 0)))
+    (call $guest_response (i32.const 0) (i32.const 4))
+    (i32.const 1)))
+"#;
+
+#[derive(Deserialize, Serialize)]
+struct PersonSend {
+  first_name: String,
+}
+
+// Returns the response and the fuel consumed by the call
+fn run_hash_guest(module_bytes: &[u8], payload: &[u8]) -> Result<(Vec<u8>, u64), errors::Error> {
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(module_bytes)
+    .deterministic()
+    .build()?;
+  let host = WapcHost::new(Box::new(engine), Some(Box::new(|_, _, _, _, _| Ok(vec![]))))?;
+  let response = host.call("serdes_example", payload)?;
+  Ok((response, host.stats()["last_call.fuel_consumed"]))
+}
+
+#[test]
+fn same_inputs_lead_to_same_outputs_and_fuel() -> Result<(), errors::Error> {
+  let module_bytes = std::fs::read(HASH_MODULE)?;
+  let payload = serialize(PersonSend {
+    first_name: "Florian".to_owned(),
+  })
+  .unwrap();
+
+  let (first_response, first_fuel) = run_hash_guest(&module_bytes, &payload)?;
+  let (second_response, second_fuel) = run_hash_guest(&module_bytes, &payload)?;
+  assert_eq!(first_response, second_response);
+  assert_eq!(first_fuel, second_fuel);
+  assert!(first_fuel > 0);
+  Ok(())
+}
+
+#[test]
+fn nan_values_are_canonicalized() -> Result<(), errors::Error> {
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(NAN_GUEST.as_bytes())
+    .deterministic()
+    .build()?;
+  let host = WapcHost::new(Box::new(engine), None)?;
+
+  let bits = u32::from_le_bytes(host.call("nan", b"")?.try_into().unwrap());
+  assert_eq!(bits, 0x7fc0_0000);
+  Ok(())
+}
+
+#[test]
+fn fuel_per_call_still_applies() -> Result<(), errors::Error> {
+  let module_bytes = std::fs::read(HASH_MODULE)?;
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .deterministic()
+    .fuel_per_call(1_000)
+    .build()?;
+  assert!(WapcHost::new(Box::new(engine), None).is_err());
+  Ok(())
+}
+
+#[test]
+fn options_breaking_determinism_are_rejected() {
+  let builders = [
+    WasmtimeEngineProviderBuilder::new().wasm_simd(true),
+    WasmtimeEngineProviderBuilder::new().wasm_relaxed_simd(true),
+    WasmtimeEngineProviderBuilder::new().wasm_threads(true),
+    WasmtimeEngineProviderBuilder::new().enable_epoch_interruptions(10, 10),
+    WasmtimeEngineProviderBuilder::new().engine(wasmtime::Engine::default()),
+    WasmtimeEngineProviderBuilder::new().strategy(wasmtime::Strategy::Winch),
+    WasmtimeEngineProviderBuilder::new().stub_wasi_imports(true),
+  ];
+  for builder in builders {
+    let result = builder.module_bytes(NAN_GUEST.as_bytes()).deterministic().build_pre();
+    assert!(
+      matches!(&result, Err(Error::BuilderInvalidConfig(msg)) if msg.starts_with("`deterministic` cannot be used")),
+      "{:?}",
+      result.err()
+    );
+  }
+}
+
+#[test]
+fn compiled_module_is_rejected() {
+  let module = wasmtime::Module::new(&wasmtime::Engine::default(), NAN_GUEST).unwrap();
+  let result = WasmtimeEngineProviderBuilder::new().module(module).deterministic().build_pre();
+  assert!(
+    matches!(&result, Err(Error::BuilderInvalidConfig(msg)) if msg == "`deterministic` cannot be used together with a compiled `module`"),
+    "{:?}",
+    result.err()
+  );
+}
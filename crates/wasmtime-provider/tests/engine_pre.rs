@@ -0,0 +1,72 @@
+use std::fs::read;
+
+use wapc::{errors, WapcHost};
+use wasmtime_provider::{EnginePre, WasmtimeEngineProviderBuilder};
+
+const MODULE: &str = "../../wasm/crates/wasm-basic/build/wasm_basic.wasm";
+
+// Written once for both the sync and the async pre types
+fn providers<P: EnginePre>(pre: &P, count: usize) -> Result<Vec<P::Provider>, wasmtime_provider::errors::Error> {
+  assert!(pre.exports().iter().any(|(name, _)| name == "__guest_call"));
+  (0..count).map(|_| pre.rehydrate()).collect()
+}
+
+#[test]
+fn module_hash_of_module_bytes() -> Result<(), errors::Error> {
+  let module_bytes = read(MODULE)?;
+  let pre = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .build_pre()?;
+
+  let hash = EnginePre::module_hash(&pre).unwrap();
+  assert_eq!(hash.len(), 64);
+  assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+
+  // the same bytes lead to the same hash, the compiled module has none
+  let other = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .build_pre()?;
+  assert_eq!(other.module_hash(), Some(hash));
+  let engine = wasmtime::Engine::default();
+  let module = wasmtime::Module::new(&engine, &module_bytes).map_err(wasmtime_provider::errors::Error::from)?;
+  let from_module = WasmtimeEngineProviderBuilder::new()
+    .engine(engine)
+    .module(module)
+    .build_pre()?;
+  assert_eq!(from_module.module_hash(), None);
+
+  Ok(())
+}
+
+#[test]
+fn rehydrate_many_providers() -> Result<(), errors::Error> {
+  let module_bytes = read(MODULE)?;
+  let pre = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .build_pre()?;
+
+  for provider in providers(&pre, 3)? {
+    let host = WapcHost::new(Box::new(provider), Some(Box::new(|_, _, _, _, _| Ok(vec![]))))?;
+    assert_eq!(host.call("ping", b"hello")?, b"hello");
+  }
+  Ok(())
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn rehydrate_many_async_providers() -> Result<(), errors::Error> {
+  use wapc::WapcHostAsync;
+
+  let module_bytes = read(MODULE)?;
+  let pre = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .build_async_pre()?;
+  assert!(EnginePre::module_hash(&pre).is_some());
+
+  for provider in providers(&pre, 3)? {
+    let host_callback: Box<wapc::HostCallbackAsync> = Box::new(|_, _, _, _, _| Box::pin(async { Ok(vec![]) }));
+    let host = WapcHostAsync::new(Box::new(provider), Some(host_callback)).await?;
+    assert_eq!(host.call("ping", b"hello").await?, b"hello");
+  }
+  Ok(())
+}
@@ -0,0 +1,97 @@
+use std::time::{Duration, Instant};
+
+use wapc::{errors, WapcHost};
+use wasmtime_provider::errors::Error;
+use wasmtime_provider::{EpochTicker, WasmtimeEngineProviderBuilder};
+
+const ENDLESS_LOOP_GUEST: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (loop $forever (br $forever))
+    (i32.const 1)))
+"#;
+
+fn epoch_engine() -> wasmtime::Engine {
+  let mut config = wasmtime::Config::default();
+  config.epoch_interruption(true);
+  wasmtime::Engine::new(&config).unwrap()
+}
+
+#[test]
+fn providers_share_one_ticker() -> Result<(), errors::Error> {
+  let ticker = EpochTicker::spawn(&epoch_engine(), Duration::from_millis(10))?;
+  let hosts = (0..3)
+    .map(|_| {
+      let engine = WasmtimeEngineProviderBuilder::new()
+        .module_bytes(ENDLESS_LOOP_GUEST.as_bytes())
+        .epoch_ticker(&ticker)
+        .enable_epoch_timeouts(Duration::from_secs(1), Duration::from_millis(100))
+        .build()?;
+      WapcHost::new(Box::new(engine), None)
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+  // the providers keep the ticker running
+  drop(ticker);
+
+  for host in hosts {
+    let start = Instant::now();
+    let result = host.call("loop", b"");
+    assert!(
+      matches!(&result, Err(errors::Error::GuestCallFailure(msg)) if msg.contains("deadline of 10 epoch ticks exceeded")),
+      "{result:?}"
+    );
+    assert!(start.elapsed() < Duration::from_secs(5));
+  }
+  Ok(())
+}
+
+#[test]
+fn ticker_must_drive_the_custom_engine() {
+  let ticker = EpochTicker::spawn(&epoch_engine(), Duration::from_millis(10)).unwrap();
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(ENDLESS_LOOP_GUEST.as_bytes())
+    .engine(epoch_engine())
+    .epoch_ticker(&ticker)
+    .build_pre();
+  assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+}
+
+#[test]
+fn epoch_timeouts_require_a_ticker() {
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(ENDLESS_LOOP_GUEST.as_bytes())
+    .enable_epoch_timeouts(Duration::from_secs(1), Duration::from_secs(1))
+    .build_pre();
+  assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+}
+
+#[test]
+fn interval_cannot_be_zero() {
+  let result = EpochTicker::spawn(&epoch_engine(), Duration::ZERO);
+  assert!(matches!(result, Err(Error::ZeroEpochTickInterval)));
+}
+
+#[test]
+fn ticker_engine_is_not_reported_as_a_custom_engine() {
+  let ticker = EpochTicker::spawn(&epoch_engine(), Duration::from_millis(10)).unwrap();
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(ENDLESS_LOOP_GUEST.as_bytes())
+    .epoch_ticker(&ticker)
+    .strategy(wasmtime::Strategy::Cranelift)
+    .build_pre();
+  match result {
+    Err(Error::BuilderInvalidConfig(e)) => assert!(e.contains("`epoch_ticker`") && !e.contains("custom"), "{e}"),
+    other => panic!("unexpected result: {:?}", other.err()),
+  }
+}
+
+#[test]
+fn component_rejects_epoch_timeouts() {
+  let component = std::fs::read("../../wasm/crates/wapc-guest-component/build/wapc_guest_component.wasm").unwrap();
+  let result = WasmtimeEngineProviderBuilder::new()
+    .component_bytes(&component)
+    .enable_epoch_timeouts(Duration::from_secs(1), Duration::from_secs(1))
+    .build_component();
+  assert!(matches!(result, Err(Error::BuilderInvalidConfig(e)) if e.contains("enable_epoch_timeouts")));
+}
@@ -0,0 +1,69 @@
+use std::fs::read;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use wapc::errors;
+use wapc_codec::messagepack::{deserialize, serialize};
+use wasmtime_provider::{WasmtimeEngineProviderBuilder, WasmtimeEngineProviderFactory};
+
+fn create_factory() -> Result<WasmtimeEngineProviderFactory, errors::Error> {
+  let buf = read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm")?;
+  let pre = WasmtimeEngineProviderBuilder::new().module_bytes(&buf).build_pre()?;
+
+  Ok(WasmtimeEngineProviderFactory::new(
+    pre,
+    Some(Arc::new(|_, _, _, _, _| {
+      panic!("host callback should never be called by wapc_guest_test::echo");
+    })),
+  ))
+}
+
+#[test]
+fn host_per_call() -> Result<(), errors::Error> {
+  let factory = create_factory()?;
+
+  let handles: Vec<_> = (0..4)
+    .map(|thread| {
+      let factory = factory.clone();
+      std::thread::spawn(move || {
+        for num in 0..10 {
+          let msg = format!("hello world: {} {}", thread, num);
+          let host = factory.host().unwrap();
+          let result: String = deserialize(&host.call("echo", &serialize(&msg).unwrap()).unwrap()).unwrap();
+          assert_eq!(result, msg);
+        }
+      })
+    })
+    .collect();
+  for handle in handles {
+    handle.join().unwrap();
+  }
+  Ok(())
+}
+
+// Naive benchmark test to make sure creating a host per call stays cheap.
+#[test]
+fn benchmark_host_creation() -> Result<(), errors::Error> {
+  let factory = create_factory()?;
+  // generous budget, this test is usually executed by debug builds
+  let budget = Duration::from_millis(2);
+  let num_hosts: u32 = 200;
+
+  // prime the factory
+  drop(factory.host()?);
+
+  let now = Instant::now();
+  for _ in 0..num_hosts {
+    drop(factory.host()?);
+  }
+  let average = now.elapsed() / num_hosts;
+
+  println!("Creating a host took {}μs on average", average.as_micros());
+  assert!(
+    average < budget,
+    "creating a host took {}μs, expected less than {}μs",
+    average.as_micros(),
+    budget.as_micros()
+  );
+  Ok(())
+}
@@ -180,6 +180,53 @@ fn runs_wasi_basic() -> Result<(), Error> {
   Ok(())
 }
 
+#[cfg(feature = "wasi")]
+fn create_wasi_basic_guest(stdio: wapc::StdioPolicy) -> Result<WapcHost, Error> {
+  let buf = read("../../wasm/crates/wasi-basic/build/wasi_basic.wasm")?;
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .wasi_params(wapc::WasiParams::default().stdio(stdio))
+    .build()?;
+  WapcHost::new(Box::new(engine), Some(Box::new(host_callback_basic)))
+}
+
+#[test]
+#[cfg(feature = "wasi")]
+fn runs_wasi_basic_with_null_stdio() -> Result<(), Error> {
+  // The guest writes straight to the file descriptors of the process, hence the check
+  // is done by running this test inside of a child process
+  if std::env::var_os("WAPC_NULL_STDIO_CHILD").is_some() {
+    let guest = create_wasi_basic_guest(wapc::StdioPolicy::Null)?;
+    let callresult = guest.call("ping", PAYLOAD.as_bytes())?;
+    assert_eq!(String::from_utf8_lossy(&callresult), PAYLOAD);
+    return Ok(());
+  }
+
+  let output = std::process::Command::new(std::env::current_exe()?)
+    .args(["runs_wasi_basic_with_null_stdio", "--exact", "--nocapture"])
+    .env("WAPC_NULL_STDIO_CHILD", "1")
+    .output()?;
+  assert!(output.status.success());
+  assert!(!String::from_utf8_lossy(&output.stdout).contains("IN_WASI"));
+  Ok(())
+}
+
+#[test]
+#[cfg(feature = "wasi")]
+fn runs_wasi_basic_with_captured_stdio() -> Result<(), Error> {
+  let captured = wapc::CapturedOutput::new();
+  let guest = create_wasi_basic_guest(wapc::StdioPolicy::Capture(captured.clone()))?;
+
+  let callresult = guest.call("ping", PAYLOAD.as_bytes())?;
+  assert_eq!(String::from_utf8_lossy(&callresult), PAYLOAD);
+  assert_eq!(
+    String::from_utf8(captured.take()).unwrap(),
+    format!("IN_WASI: Received request for `ping` operation with payload : {PAYLOAD}\n")
+  );
+  assert!(captured.contents().is_empty());
+  Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread")]
 #[cfg(all(feature = "wasi", feature = "async"))]
 async fn runs_wasi_basic_async() -> Result<(), Error> {
@@ -293,7 +340,7 @@ fn runs_wapc_timeout() -> Result<(), Error> {
   let err = callresult.expect_err("a timeout error was supposed to happen");
   assert_eq!(
     err.to_string(),
-    "Guest call failure: guest code interrupted, execution deadline exceeded".to_string()
+    "Guest call failure: guest code interrupted, func execution deadline of 2 epoch ticks exceeded".to_string()
   );
   Ok(())
 }
@@ -334,7 +381,111 @@ async fn runs_wapc_timeout_async() -> Result<(), Error> {
   let err = callresult.expect_err("a timeout error was supposed to happen");
   assert_eq!(
     err.to_string(),
-    "Guest call failure: guest code interrupted, execution deadline exceeded".to_string()
+    "Guest call failure: guest code interrupted, func execution deadline of 2 epoch ticks exceeded".to_string()
   );
   Ok(())
 }
+
+#[test]
+#[cfg(feature = "wasi")]
+fn runs_wapc_timeout_with_func_deadline_override() -> Result<(), Error> {
+  let path = "../../wasm/crates/wapc-guest-timeout/build/wapc_guest_timeout.wasm";
+  let module_bytes = read(path)?;
+
+  let mut engine_conf = wasmtime::Config::default();
+  engine_conf.epoch_interruption(true);
+  let engine = wasmtime::Engine::new(&engine_conf).expect("cannot create wasmtime engine");
+
+  let wapc_engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .engine(engine.clone())
+    .enable_epoch_interruptions(100, 1)
+    .build()
+    .expect("Cannot create WebAssemblyEngineProvider");
+  let deadline_override = wapc_engine.func_deadline_override();
+  let guest = WapcHost::new(
+    Box::new(wapc_engine),
+    Some(Box::new(move |_a, _b, _c, _d, _e| Ok(vec![]))),
+  )?;
+
+  std::thread::spawn(move || {
+    // Starting timer thread
+    let interval = std::time::Duration::from_secs(1);
+    loop {
+      std::thread::sleep(interval);
+      engine.increment_epoch();
+    }
+  });
+
+  guest
+    .call("sleep", b"2")
+    .expect_err("a timeout error was supposed to happen");
+
+  {
+    let _guard = deadline_override.scoped(5);
+    let callresult = guest.call("sleep", b"2")?;
+    let result = String::from_utf8_lossy(&callresult);
+    assert_eq!(result, "slept for 2 seconds");
+  }
+
+  guest
+    .call("sleep", b"2")
+    .expect_err("a timeout error was supposed to happen once the override is dropped");
+  Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[cfg(all(feature = "wasi", feature = "async"))]
+async fn runs_wapc_timeout_with_func_deadline_override_async() -> Result<(), Error> {
+  let path = "../../wasm/crates/wapc-guest-timeout/build/wapc_guest_timeout.wasm";
+  let module_bytes = read(path)?;
+
+  let mut engine_conf = wasmtime::Config::default();
+  engine_conf.epoch_interruption(true);
+  engine_conf.async_support(true);
+  let engine = wasmtime::Engine::new(&engine_conf).expect("cannot create wasmtime engine");
+
+  let wapc_engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .engine(engine.clone())
+    .enable_epoch_interruptions(100, 1)
+    .build_async()
+    .expect("Cannot create WebAssemblyEngineProviderAsync");
+  let deadline_override = wapc_engine.func_deadline_override();
+  let guest = WapcHostAsync::new(Box::new(wapc_engine), None).await?;
+
+  tokio::spawn(async move {
+    // Starting timer thread
+    let interval = std::time::Duration::from_secs(1);
+    loop {
+      tokio::time::sleep(interval).await;
+      engine.increment_epoch();
+    }
+  });
+
+  guest
+    .call("sleep", b"2")
+    .await
+    .expect_err("a timeout error was supposed to happen");
+
+  deadline_override.set(5);
+  let callresult = guest.call("sleep", b"2").await?;
+  let result = String::from_utf8_lossy(&callresult);
+  assert_eq!(result, "slept for 2 seconds");
+  deadline_override.clear();
+
+  Ok(())
+}
+
+#[test]
+#[cfg(not(feature = "wasi"))]
+fn wasi_params_without_wasi_feature() -> Result<(), Error> {
+  let module_bytes = read("../../wasm/crates/wasm-basic/build/wasm_basic.wasm")?;
+
+  let result = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .wasi_params(wapc::WasiParams::default())
+    .build();
+  assert!(matches!(result, Err(wasmtime_provider::errors::Error::WasiDisabled)));
+  Ok(())
+}
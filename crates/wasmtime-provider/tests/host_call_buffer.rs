@@ -0,0 +1,90 @@
+use std::fs::read;
+use std::sync::{Arc, Mutex};
+
+use wapc::{errors, WapcHost};
+use wasmtime_provider::WasmtimeEngineProviderBuilder;
+
+// The guest forwards the payload of the `ping` operation to the host
+const GUEST: &str = "../../wasm/crates/wasm-basic/build/wasm_basic.wasm";
+
+#[test]
+fn host_call_payloads_with_reused_buffer() -> Result<(), errors::Error> {
+  let module_bytes = read(GUEST)?;
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .reuse_host_call_buffer(true)
+    .build()?;
+
+  let received = Arc::new(Mutex::new(Vec::new()));
+  let callback_received = received.clone();
+  let host = WapcHost::new(
+    Box::new(engine),
+    Some(Box::new(move |_, _, _, _, payload| {
+      callback_received.lock().unwrap().push(payload.to_vec());
+      Ok(vec![])
+    })),
+  )?;
+
+  // the payloads shrink and grow, the buffer must not leak the bytes of the previous calls
+  let payloads = [
+    vec![1u8; 1024 * 1024],
+    vec![2u8; 16],
+    vec![],
+    vec![3u8; 2 * 1024 * 1024],
+  ];
+  for payload in &payloads {
+    assert_eq!(&host.call("ping", payload)?, payload);
+  }
+  assert_eq!(*received.lock().unwrap(), payloads);
+
+  Ok(())
+}
+
+#[test]
+fn large_host_call_payloads_round_trip() -> Result<(), errors::Error> {
+  let module_bytes = read(GUEST)?;
+  let payload: Vec<u8> = (0..4 * 1024 * 1024).map(|i| b'a' + (i % 26) as u8).collect();
+
+  for reuse_host_call_buffer in [false, true] {
+    let engine = WasmtimeEngineProviderBuilder::new()
+      .module_bytes(&module_bytes)
+      .reuse_host_call_buffer(reuse_host_call_buffer)
+      .build()?;
+
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let callback_received = received.clone();
+    let expected = payload.clone();
+    let host = WapcHost::new(
+      Box::new(engine),
+      Some(Box::new(move |_, _, _, _, payload| {
+        callback_received.lock().unwrap().push(payload.len());
+        assert_eq!(payload, expected.as_slice());
+        Ok(vec![])
+      })),
+    )?;
+
+    for _ in 0..3 {
+      let result = host.call("ping", &payload)?;
+      assert_eq!(result.len(), payload.len());
+      assert_eq!(result, payload);
+    }
+    assert_eq!(*received.lock().unwrap(), vec![payload.len(); 3]);
+  }
+
+  Ok(())
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn reused_buffer_is_rejected_by_async_providers() {
+  let module_bytes = read(GUEST).unwrap();
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .reuse_host_call_buffer(true)
+    .build_async_pre();
+
+  assert!(matches!(
+    result,
+    Err(wasmtime_provider::errors::Error::BuilderInvalidConfig(_))
+  ));
+}
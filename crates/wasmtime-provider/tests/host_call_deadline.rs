@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use wapc::{errors, WapcHost};
+use wasmtime_provider::WasmtimeEngineProviderBuilder;
+
+#[cfg(feature = "async")]
+use wapc::WapcHostAsync;
+
+// performs a slow host call, then runs `body` which must check the epoch deadline
+fn guest(body: &str) -> String {
+  format!(
+    r#"
+(module
+  (import "wapc" "__host_call" (func $host_call (param i32 i32 i32 i32 i32 i32 i32 i32) (result i32)))
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (local $i i32)
+    (drop (call $host_call
+      (i32.const 0) (i32.const 2) (i32.const 0) (i32.const 2) (i32.const 0) (i32.const 2)
+      (i32.const 0) (i32.const 0)))
+    {}
+    (call $guest_response (i32.const 0) (i32.const 0))
+    (i32.const 1)))
+"#,
+    body
+  )
+}
+
+// a short loop, the epoch deadline is checked at each iteration
+const SHORT_LOOP: &str = "(loop $short
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br_if $short (i32.lt_u (local.get $i) (i32.const 1000))))";
+const ENDLESS_LOOP: &str = "(loop $forever (br $forever))";
+
+const HOST_CALL_DURATION: Duration = Duration::from_millis(200);
+const FUNC_DEADLINE: u64 = 5;
+
+// an engine whose epoch is incremented every 10 milliseconds
+fn ticking_engine(mut config: wasmtime::Config) -> wasmtime::Engine {
+  config.epoch_interruption(true);
+  let engine = wasmtime::Engine::new(&config).unwrap();
+
+  let ticker = engine.clone();
+  std::thread::spawn(move || loop {
+    std::thread::sleep(Duration::from_millis(10));
+    ticker.increment_epoch();
+  });
+  engine
+}
+
+fn call(body: &str, exclude_host_calls: bool) -> Result<Vec<u8>, errors::Error> {
+  let wat = guest(body);
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .engine(ticking_engine(wasmtime::Config::new()))
+    .module_bytes(wat.as_bytes())
+    .enable_epoch_interruptions(100, FUNC_DEADLINE)
+    .exclude_host_calls_from_deadline(exclude_host_calls)
+    .build()?;
+  let host = WapcHost::new(
+    Box::new(engine),
+    Some(Box::new(|_, _, _, _, _| {
+      std::thread::sleep(HOST_CALL_DURATION);
+      Ok(vec![])
+    })),
+  )?;
+  host.call("query", b"")
+}
+
+fn assert_deadline_exceeded(result: Result<Vec<u8>, errors::Error>) {
+  match result {
+    Err(errors::Error::GuestCallFailure(msg)) => assert_eq!(
+      msg,
+      "guest code interrupted, func execution deadline of 5 epoch ticks exceeded"
+    ),
+    res => panic!("the guest call should have been interrupted, got {:?}", res),
+  }
+}
+
+#[test]
+fn slow_host_call_exceeds_deadline_by_default() {
+  assert_deadline_exceeded(call(SHORT_LOOP, false));
+}
+
+#[test]
+fn slow_host_call_excluded_from_deadline() -> Result<(), errors::Error> {
+  call(SHORT_LOOP, true)?;
+  Ok(())
+}
+
+#[test]
+fn guest_code_still_bound_by_deadline() {
+  assert_deadline_exceeded(call(ENDLESS_LOOP, true));
+}
+
+#[test]
+fn exclusion_requires_epoch_interruptions() {
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(guest(SHORT_LOOP).as_bytes())
+    .exclude_host_calls_from_deadline(true)
+    .build();
+  assert!(matches!(
+    result,
+    Err(wasmtime_provider::errors::Error::BuilderInvalidConfig(_))
+  ));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn slow_async_host_call_excluded_from_deadline() -> Result<(), errors::Error> {
+  let wat = guest(SHORT_LOOP);
+  let mut config = wasmtime::Config::new();
+  config.async_support(true);
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .engine(ticking_engine(config))
+    .module_bytes(wat.as_bytes())
+    .enable_epoch_interruptions(100, FUNC_DEADLINE)
+    .exclude_host_calls_from_deadline(true)
+    .build_async()?;
+  let host_callback: Box<wapc::HostCallbackAsync> = Box::new(|_, _, _, _, _| {
+    Box::pin(async {
+      tokio::time::sleep(HOST_CALL_DURATION).await;
+      Ok(vec![])
+    })
+  });
+  let host = WapcHostAsync::new(Box::new(engine), Some(host_callback)).await?;
+
+  host.call("query", b"").await?;
+  Ok(())
+}
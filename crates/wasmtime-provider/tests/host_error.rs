@@ -0,0 +1,86 @@
+use wapc::errors::Error;
+use wapc::WapcHost;
+
+#[cfg(feature = "async")]
+use wapc::WapcHostAsync;
+
+// performs a host call, then fails with the host error when the call fails
+const GUEST: &str = r#"
+(module
+  (import "wapc" "__host_call" (func $host_call (param i32 i32 i32 i32 i32 i32 i32 i32) (result i32)))
+  (import "wapc" "__host_error_len" (func $host_error_len (result i32)))
+  (import "wapc" "__host_error" (func $host_error (param i32)))
+  (import "wapc" "__guest_error" (func $guest_error (param i32 i32)))
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "dbsqlquery")
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (if (i32.eqz (call $host_call
+          (i32.const 0) (i32.const 2)
+          (i32.const 2) (i32.const 3)
+          (i32.const 5) (i32.const 5)
+          (i32.const 0) (i32.const 0)))
+      (then
+        (call $host_error (i32.const 1024))
+        (call $guest_error (i32.const 1024) (call $host_error_len))
+        (return (i32.const 0))))
+    (call $guest_response (i32.const 0) (i32.const 0))
+    (i32.const 1)))
+"#;
+
+fn create_guest() -> Result<WapcHost, Error> {
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(GUEST.as_bytes())
+    .build()?;
+  WapcHost::new(
+    Box::new(engine),
+    Some(Box::new(|_, _, _, _, _| Err("connection refused".into()))),
+  )
+}
+
+#[test]
+fn host_error_includes_call_context() -> Result<(), Error> {
+  let guest = create_guest()?;
+  guest.set_host_error_context(true);
+
+  match guest.call("query", b"") {
+    Err(Error::GuestCallFailure(msg)) => assert_eq!(msg, "db/sql/query: connection refused"),
+    res => panic!("the guest call should have failed, got {:?}", res),
+  }
+  Ok(())
+}
+
+#[test]
+fn host_error_context_is_disabled_by_default() -> Result<(), Error> {
+  let guest = create_guest()?;
+
+  match guest.call("query", b"") {
+    Err(Error::GuestCallFailure(msg)) => assert_eq!(msg, "connection refused"),
+    res => panic!("the guest call should have failed, got {:?}", res),
+  }
+  Ok(())
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn host_error_includes_call_context_async() -> Result<(), Error> {
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(GUEST.as_bytes())
+    .build_async()?;
+  let host_callback: Box<wapc::HostCallbackAsync> =
+    Box::new(|_, _, _, _, _| Box::pin(async { Err("connection refused".into()) }));
+  let guest = WapcHostAsync::new(Box::new(engine), Some(host_callback)).await?;
+  guest.set_host_error_context(true);
+
+  match guest.call("query", b"").await {
+    Err(Error::GuestCallFailure(msg)) => assert_eq!(msg, "db/sql/query: connection refused"),
+    res => panic!("the guest call should have failed, got {:?}", res),
+  }
+
+  guest.set_host_error_context(false);
+  match guest.call("query", b"").await {
+    Err(Error::GuestCallFailure(msg)) => assert_eq!(msg, "connection refused"),
+    res => panic!("the guest call should have failed, got {:?}", res),
+  }
+  Ok(())
+}
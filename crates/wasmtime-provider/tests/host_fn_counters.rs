@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use wapc::{errors, WapcHost};
+use wapc_codec::messagepack::serialize;
+use wasmtime_provider::WasmtimeEngineProviderBuilder;
+
+#[cfg(feature = "async")]
+use wapc::WapcHostAsync;
+
+const HASH_MODULE: &str = "../../wasm/crates/wasm-calc-hash/module1/build/module1_hash.wasm";
+
+#[derive(Deserialize, Serialize)]
+struct PersonSend {
+  first_name: String,
+}
+
+fn payload() -> Vec<u8> {
+  serialize(PersonSend {
+    first_name: "Florian".to_owned(),
+  })
+  .unwrap()
+}
+
+#[test]
+fn counts_host_functions_per_call() -> Result<(), errors::Error> {
+  let module_bytes = std::fs::read(HASH_MODULE)?;
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .count_host_functions(true)
+    .build()?;
+  let host = WapcHost::new(Box::new(engine), Some(Box::new(|_, _, _, _, _| Ok(vec![]))))?;
+
+  // the counters are reset at the beginning of every call
+  for _ in 0..2 {
+    host.call("serdes_example", &payload())?;
+    let stats = host.stats();
+    assert_eq!(stats.get("host_fn.__host_call"), Some(&1));
+    assert_eq!(stats.get("host_fn.__guest_response"), Some(&1));
+    assert_eq!(stats.get("host_fn.__guest_request"), Some(&1));
+    assert_eq!(stats.get("host_fn.__guest_error"), None);
+  }
+  Ok(())
+}
+
+#[test]
+fn counters_are_disabled_by_default() -> Result<(), errors::Error> {
+  let module_bytes = std::fs::read(HASH_MODULE)?;
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .build()?;
+  let host = WapcHost::new(Box::new(engine), Some(Box::new(|_, _, _, _, _| Ok(vec![]))))?;
+
+  host.call("serdes_example", &payload())?;
+  assert!(host.stats().keys().all(|key| !key.starts_with("host_fn.")));
+  Ok(())
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn counts_host_functions_per_async_call() -> Result<(), errors::Error> {
+  let module_bytes = std::fs::read(HASH_MODULE)?;
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .count_host_functions(true)
+    .build_async()?;
+  let host_callback: Box<wapc::HostCallbackAsync> = Box::new(|_, _, _, _, _| Box::pin(async { Ok(vec![]) }));
+  let host = WapcHostAsync::new(Box::new(engine), Some(host_callback)).await?;
+
+  host.call("serdes_example", &payload()).await?;
+  let stats = host.stats().await;
+  assert_eq!(stats.get("host_fn.__host_call"), Some(&1));
+  assert_eq!(stats.get("host_fn.__guest_response"), Some(&1));
+  Ok(())
+}
@@ -0,0 +1,83 @@
+use wapc::errors::Error;
+use wapc::WapcHost;
+
+#[cfg(feature = "async")]
+use wapc::WapcHostAsync;
+
+// performs a host call, then fails with the host error when the call fails
+const GUEST: &str = r#"
+(module
+  (import "wapc" "__host_call" (func $host_call (param i32 i32 i32 i32 i32 i32 i32 i32) (result i32)))
+  (import "wapc" "__host_error_len" (func $host_error_len (result i32)))
+  (import "wapc" "__host_error" (func $host_error (param i32)))
+  (import "wapc" "__guest_error" (func $guest_error (param i32 i32)))
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "kvstoreget")
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (if (i32.eqz (call $host_call
+          (i32.const 0) (i32.const 2)
+          (i32.const 2) (i32.const 5)
+          (i32.const 7) (i32.const 3)
+          (i32.const 0) (i32.const 0)))
+      (then
+        (call $host_error (i32.const 1024))
+        (call $guest_error (i32.const 1024) (call $host_error_len))
+        (return (i32.const 0))))
+    (call $guest_response (i32.const 0) (i32.const 0))
+    (i32.const 1)))
+"#;
+
+const LIMIT: usize = 1024;
+
+fn create_guest(response_size: usize) -> Result<WapcHost, Error> {
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(GUEST.as_bytes())
+    .max_host_response(LIMIT)
+    .build()?;
+  WapcHost::new(
+    Box::new(engine),
+    Some(Box::new(move |_, _, _, _, _| Ok(vec![0; response_size]))),
+  )
+}
+
+#[test]
+fn oversized_host_response_is_a_host_error() -> Result<(), Error> {
+  let guest = create_guest(LIMIT + 1)?;
+
+  match guest.call("get", b"") {
+    Err(Error::GuestCallFailure(msg)) => {
+      assert_eq!(msg, "host response of 1025 bytes exceeds limit 1024")
+    }
+    res => panic!("the guest call should have failed, got {:?}", res),
+  }
+  Ok(())
+}
+
+#[test]
+fn host_response_within_limit() -> Result<(), Error> {
+  let guest = create_guest(LIMIT)?;
+
+  guest.call("get", b"")?;
+  Ok(())
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn oversized_host_response_is_a_host_error_async() -> Result<(), Error> {
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(GUEST.as_bytes())
+    .max_host_response(LIMIT)
+    .build_async()?;
+  let host_callback: Box<wapc::HostCallbackAsync> =
+    Box::new(|_, _, _, _, _| Box::pin(async { Ok(vec![0; 64 * 1024 * 1024]) }));
+  let guest = WapcHostAsync::new(Box::new(engine), Some(host_callback)).await?;
+
+  match guest.call("get", b"").await {
+    Err(Error::GuestCallFailure(msg)) => {
+      assert_eq!(msg, "host response of 67108864 bytes exceeds limit 1024");
+    }
+    res => panic!("the guest call should have failed, got {:?}", res),
+  }
+  Ok(())
+}
@@ -0,0 +1,88 @@
+use wapc::errors::Error;
+use wapc::WapcHost;
+
+#[cfg(feature = "async")]
+use wapc::WapcHostAsync;
+
+const LIBRARY: &str = r#"
+(module
+  (func (export "add") (param i32 i32) (result i32)
+    (i32.add (local.get 0) (local.get 1))))
+"#;
+
+const GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (import "math" "add" (func $add (param i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (i32.store (i32.const 0) (call $add (i32.const 40) (i32.const 2)))
+    (call $guest_response (i32.const 0) (i32.const 4))
+    (i32.const 1)))
+"#;
+
+#[test]
+fn runs_guest_importing_linked_module() -> Result<(), Error> {
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(GUEST.as_bytes())
+    .link_module("math", LIBRARY.as_bytes())
+    .build()?;
+  let guest = WapcHost::new(Box::new(engine), None)?;
+
+  let result = guest.call("add", b"")?;
+  assert_eq!(result, 42_i32.to_le_bytes());
+
+  Ok(())
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn runs_guest_importing_linked_module_async() -> Result<(), Error> {
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(GUEST.as_bytes())
+    .link_module("math", LIBRARY.as_bytes())
+    .build_async()?;
+  let guest = WapcHostAsync::new(Box::new(engine), None).await?;
+
+  let result = guest.call("add", b"").await?;
+  assert_eq!(result, 42_i32.to_le_bytes());
+
+  Ok(())
+}
+
+#[test]
+fn link_module_rejects_reserved_name() {
+  let result = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(GUEST.as_bytes())
+    .link_module("wapc", LIBRARY.as_bytes())
+    .build_pre();
+  assert!(matches!(
+    result,
+    Err(wasmtime_provider::errors::Error::LinkModule { name, .. }) if name == "wapc"
+  ));
+}
+
+#[test]
+fn link_module_rejects_duplicated_name() {
+  let result = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(GUEST.as_bytes())
+    .link_module("math", LIBRARY.as_bytes())
+    .link_module("math", LIBRARY.as_bytes())
+    .build_pre();
+  assert!(matches!(
+    result,
+    Err(wasmtime_provider::errors::Error::LinkModule { name, .. }) if name == "math"
+  ));
+}
+
+#[test]
+fn link_module_reports_missing_export() {
+  let result = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(GUEST.as_bytes())
+    .link_module("math", br#"(module (func (export "sub")))"#)
+    .build_pre();
+  assert!(matches!(
+    result,
+    Err(wasmtime_provider::errors::Error::LinkModule { name, .. }) if name == "math"
+  ));
+}
@@ -0,0 +1,73 @@
+use wapc::{errors, WapcHost};
+use wasmtime_provider::errors::Error;
+use wasmtime_provider::WasmtimeEngineProviderBuilder;
+
+#[cfg(feature = "async")]
+use wapc::WapcHostAsync;
+
+// Forwards its payload to the host, then replaces the first byte of the host response using
+// 64-bit addressing. The waPC functions are still given 32-bit pointers.
+const MEMORY64_GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_request" (func $guest_request (param i32 i32)))
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (import "wapc" "__host_call" (func $host_call (param i32 i32 i32 i32 i32 i32 i32 i32) (result i32)))
+  (import "wapc" "__host_response_len" (func $host_response_len (result i32)))
+  (import "wapc" "__host_response" (func $host_response (param i32)))
+  (memory (export "memory") i64 2)
+  (func (export "__guest_call") (param $op_len i32) (param $msg_len i32) (result i32)
+    (local $len i32)
+    (call $guest_request (i32.const 0) (i32.const 0x10000))
+    (drop (call $host_call
+      (i32.const 0) (i32.const 0)
+      (i32.const 0) (i32.const 0)
+      (i32.const 0) (local.get $op_len)
+      (i32.const 0x10000) (local.get $msg_len)))
+    (local.set $len (call $host_response_len))
+    (call $host_response (i32.const 0x18000))
+    (i64.store8 (i64.extend_i32_u (i32.const 0x18000)) (i64.const 0x21))
+    (call $guest_response (i32.const 0x18000) (local.get $len))
+    (i32.const 1)))
+"#;
+
+#[test]
+fn calls_a_memory64_guest() -> Result<(), errors::Error> {
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(MEMORY64_GUEST.as_bytes())
+    .wasm_memory64(true)
+    .build()?;
+  let host = WapcHost::new(
+    Box::new(engine),
+    Some(Box::new(|_, _, _, operation, payload| {
+      assert_eq!(operation, "upper");
+      Ok(payload.to_ascii_uppercase())
+    })),
+  )?;
+
+  assert_eq!(host.call("upper", b"hello world")?, b"!ELLO WORLD");
+  Ok(())
+}
+
+#[test]
+fn memory64_guest_fails_to_load_when_memory64_is_disabled() {
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(MEMORY64_GUEST.as_bytes())
+    .wasm_memory64(false)
+    .build_pre();
+  assert!(matches!(result, Err(Error::Generic(_))));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn calls_a_memory64_guest_async() -> Result<(), errors::Error> {
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(MEMORY64_GUEST.as_bytes())
+    .wasm_memory64(true)
+    .build_async()?;
+  let host_callback: Box<wapc::HostCallbackAsync> =
+    Box::new(|_, _, _, _, payload| Box::pin(async move { Ok(payload.to_ascii_uppercase()) }));
+  let host = WapcHostAsync::new(Box::new(engine), Some(host_callback)).await?;
+
+  assert_eq!(host.call("upper", b"hello world").await?, b"!ELLO WORLD");
+  Ok(())
+}
@@ -0,0 +1,77 @@
+use std::sync::{Arc, Mutex};
+
+use wapc::{errors, WapcHost};
+use wasmtime_provider::errors::OUT_OF_MEMORY_PREFIX;
+use wasmtime_provider::{GrowRequest, WasmtimeEngineProviderBuilder};
+
+#[cfg(feature = "async")]
+use wapc::WapcHostAsync;
+
+const MIB: usize = 1024 * 1024;
+
+// grows the memory by 1 MiB at each call, aborts when the growth fails
+const GROWING_GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (if (i32.eq (memory.grow (i32.const 16)) (i32.const -1))
+      (then unreachable))
+    (call $guest_response (i32.const 0) (i32.const 0))
+    (i32.const 1)))
+"#;
+
+fn builder(requests: Arc<Mutex<Vec<GrowRequest>>>) -> WasmtimeEngineProviderBuilder<'static> {
+  WasmtimeEngineProviderBuilder::new()
+    .module_bytes(GROWING_GUEST.as_bytes())
+    .on_memory_grow(move |request| {
+      requests.lock().unwrap().push(request);
+      request.desired <= 10 * MIB
+    })
+}
+
+#[test]
+fn memory_growth_denied_beyond_quota() -> Result<(), errors::Error> {
+  let requests = Arc::new(Mutex::new(Vec::new()));
+  let engine = builder(requests.clone()).build()?;
+  let host = WapcHost::new(Box::new(engine), None)?;
+
+  // the initial page plus 9 MiB fit into the quota
+  for _ in 0..9 {
+    host.call("grow", b"")?;
+  }
+  match host.call("grow", b"") {
+    Err(errors::Error::GuestCallFailure(msg)) => assert!(msg.starts_with(OUT_OF_MEMORY_PREFIX), "{msg}"),
+    res => panic!("the guest call should have failed, got {:?}", res),
+  }
+
+  let requests = requests.lock().unwrap();
+  assert_eq!(requests.len(), 11, "memory creation plus 10 growths");
+  assert_eq!(requests[0].current, 0);
+  assert!(requests.iter().all(|request| request.module_id == host.id()));
+  assert_eq!(requests.last().unwrap().desired, 10 * MIB + 64 * 1024);
+  Ok(())
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn memory_growth_denied_beyond_quota_async() -> Result<(), errors::Error> {
+  let requests = Arc::new(Mutex::new(Vec::new()));
+  let engine = builder(requests.clone()).build_async()?;
+  let host = WapcHostAsync::new(Box::new(engine), None).await?;
+
+  for _ in 0..9 {
+    host.call("grow", b"").await?;
+  }
+  match host.call("grow", b"").await {
+    Err(errors::Error::GuestCallFailure(msg)) => assert!(msg.starts_with(OUT_OF_MEMORY_PREFIX), "{msg}"),
+    res => panic!("the guest call should have failed, got {:?}", res),
+  }
+
+  assert!(requests
+    .lock()
+    .unwrap()
+    .iter()
+    .all(|request| request.module_id == host.id()));
+  Ok(())
+}
@@ -0,0 +1,94 @@
+use std::fs::read;
+
+use wapc::{errors, WapcHost};
+use wapc_codec::messagepack::{deserialize, serialize};
+use wasmtime_provider::errors::Error;
+use wasmtime_provider::WasmtimeEngineProviderBuilder;
+
+const GUEST: &str = "../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm";
+
+fn tiny_reservations(buf: &[u8]) -> WasmtimeEngineProviderBuilder<'_> {
+  WasmtimeEngineProviderBuilder::new()
+    .module_bytes(buf)
+    .memory_reservation(16 << 20)
+    .memory_guard_size(64 << 10)
+}
+
+#[test]
+fn runs_with_tiny_reservations() -> Result<(), errors::Error> {
+  let buf = read(GUEST)?;
+  let engine = tiny_reservations(&buf).build()?;
+  let guest = WapcHost::new(Box::new(engine), None)?;
+
+  let callresult = guest.call("echo", &serialize("hello world").unwrap())?;
+  let result: String = deserialize(&callresult).unwrap();
+  assert_eq!(result, "hello world");
+  Ok(())
+}
+
+#[test]
+fn invalid_settings_are_rejected() {
+  let module = br#"(module (memory (export "memory") 1))"#;
+
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(module)
+    .memory_reservation(1000)
+    .build_pre();
+  assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(module)
+    .memory_reservation(0)
+    .memory_may_move(false)
+    .build_pre();
+  assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(module)
+    .engine(wasmtime::Engine::default())
+    .memory_guard_size(0)
+    .build_pre();
+  assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+}
+
+// Size of the virtual memory of the process, in bytes
+#[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+fn virtual_memory_size() -> u64 {
+  let status = std::fs::read_to_string("/proc/self/status").unwrap();
+  let kb: u64 = status
+    .lines()
+    .find_map(|line| line.strip_prefix("VmSize:"))
+    .and_then(|value| value.trim().strip_suffix("kB"))
+    .unwrap()
+    .trim()
+    .parse()
+    .unwrap();
+  kb * 1024
+}
+
+#[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+#[test]
+fn tiny_reservations_lower_the_virtual_memory() -> Result<(), errors::Error> {
+  let buf = read(GUEST)?;
+
+  // the compilation is done beforehand, only the instantiation is measured
+  let default_engine = WasmtimeEngineProviderBuilder::new().module_bytes(&buf).build()?;
+  let before = virtual_memory_size();
+  let default_guest = WapcHost::new(Box::new(default_engine), None)?;
+  let default_growth = virtual_memory_size().saturating_sub(before);
+
+  let tiny_engine = tiny_reservations(&buf).build()?;
+  let before = virtual_memory_size();
+  let tiny_guest = WapcHost::new(Box::new(tiny_engine), None)?;
+  let tiny_growth = virtual_memory_size().saturating_sub(before);
+
+  println!(
+    "virtual memory growth: {} bytes by default, {} bytes with tiny reservations",
+    default_growth, tiny_growth
+  );
+  assert!(default_growth >= 4 << 30);
+  assert!(tiny_growth < 1 << 30);
+
+  drop((default_guest, tiny_guest));
+  Ok(())
+}
@@ -0,0 +1,94 @@
+use std::fs::read;
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+use wapc::{errors, WapcHost};
+use wasmtime_provider::errors::Error;
+use wasmtime_provider::WasmtimeEngineProviderBuilder;
+
+const MODULE: &str = "../../wasm/crates/wasm-basic/build/wasm_basic.wasm";
+const OTHER_MODULE: &str = "../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm";
+
+fn sha256(bytes: &[u8]) -> String {
+  hex::encode(Sha256::digest(bytes))
+}
+
+#[test]
+fn matching_digest_is_accepted() -> Result<(), errors::Error> {
+  let module_bytes = read(MODULE)?;
+  let digest = sha256(&module_bytes).to_uppercase();
+
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .expect_sha256(&digest)
+    .build()?;
+  let host = WapcHost::new(Box::new(engine), Some(Box::new(|_, _, _, _, _| Ok(vec![]))))?;
+  host.call("ping", b"hello")?;
+
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_path(Path::new(MODULE))
+    .expect_sha256(&digest)
+    .build();
+  assert!(engine.is_ok());
+  Ok(())
+}
+
+#[test]
+fn mismatching_digest_is_rejected() -> Result<(), errors::Error> {
+  let module_bytes = read(MODULE)?;
+  let expected = sha256(&read(OTHER_MODULE)?);
+
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .expect_sha256(&expected)
+    .build_pre();
+  match result {
+    Err(Error::ModuleDigestMismatch { expected: e, actual }) => {
+      assert_eq!(e, expected);
+      assert_eq!(actual, sha256(&module_bytes));
+    }
+    _ => panic!("the module should have been rejected"),
+  }
+
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_path(Path::new(MODULE))
+    .expect_sha256(&expected)
+    .build_pre();
+  assert!(matches!(result, Err(Error::ModuleDigestMismatch { .. })));
+
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .expect_sha256("not-a-digest")
+    .build_pre();
+  assert!(matches!(result, Err(Error::InvalidSha256(_))));
+  Ok(())
+}
+
+#[test]
+fn replace_checks_the_expected_digest() -> Result<(), errors::Error> {
+  let module_bytes = read(MODULE)?;
+  let other_bytes = read(OTHER_MODULE)?;
+
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .build()?;
+  let expected_digest = engine.expected_module_digest();
+  let host = WapcHost::new(Box::new(engine), Some(Box::new(|_, _, _, _, _| Ok(vec![]))))?;
+
+  expected_digest.set(&sha256(&module_bytes))?;
+  let result = host.replace_module(&other_bytes);
+  assert!(
+    matches!(&result, Err(errors::Error::ReplacementFailed(msg)) if msg.contains("digest mismatch")),
+    "{result:?}"
+  );
+  // the original module is still in place
+  host.call("ping", b"hello")?;
+
+  expected_digest.set(&sha256(&other_bytes))?;
+  host.replace_module(&other_bytes)?;
+
+  expected_digest.clear();
+  host.replace_module(&module_bytes)?;
+  host.call("ping", b"hello")?;
+  Ok(())
+}
@@ -0,0 +1,83 @@
+use std::path::Path;
+
+use wapc::{errors, WapcHost};
+use wasmtime_provider::WasmtimeEngineProviderBuilder;
+
+#[cfg(feature = "async")]
+use wapc::WapcHostAsync;
+
+const MODULE_PATH: &str = "../../wasm/crates/wasm-basic/build/wasm_basic.wasm";
+
+fn ping(builder: WasmtimeEngineProviderBuilder) -> Result<Vec<u8>, errors::Error> {
+  let engine = builder.build()?;
+  let host = WapcHost::new(Box::new(engine), Some(Box::new(|_, _, _, _, _| Ok(vec![]))))?;
+  host.call("ping", b"hello")
+}
+
+#[test]
+fn load_module_from_path() -> Result<(), errors::Error> {
+  let response = ping(WasmtimeEngineProviderBuilder::new().module_path(Path::new(MODULE_PATH)))?;
+  assert_eq!(response, b"hello");
+  Ok(())
+}
+
+#[test]
+fn load_precompiled_module_from_path() -> Result<(), errors::Error> {
+  let engine = wasmtime::Engine::default();
+  let artifact = engine
+    .precompile_module(&std::fs::read(MODULE_PATH)?)
+    .map_err(wasmtime_provider::errors::Error::from)?;
+  let path = std::env::temp_dir().join(format!("wapc-module-path-{}.cwasm", std::process::id()));
+  std::fs::write(&path, artifact)?;
+
+  let response = ping(
+    WasmtimeEngineProviderBuilder::new()
+      .engine(engine)
+      .module_path(&path)
+      .precompiled_module(true),
+  );
+  std::fs::remove_file(&path)?;
+  assert_eq!(response?, b"hello");
+  Ok(())
+}
+
+#[test]
+fn missing_module_error_includes_path() {
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_path(Path::new("missing/module.wasm"))
+    .build();
+
+  match result {
+    Err(e @ wasmtime_provider::errors::Error::ModulePath { .. }) => {
+      assert!(e.to_string().contains("missing/module.wasm"), "{e}");
+    }
+    Err(e) => panic!("unexpected error: {e}"),
+    Ok(_) => panic!("the build should have failed"),
+  }
+}
+
+#[test]
+fn module_path_and_bytes_are_exclusive() {
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(b"(module)")
+    .module_path(Path::new(MODULE_PATH))
+    .build();
+
+  assert!(matches!(
+    result,
+    Err(wasmtime_provider::errors::Error::BuilderInvalidConfig(_))
+  ));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn load_module_from_path_async() -> Result<(), errors::Error> {
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_path(Path::new(MODULE_PATH))
+    .build_async()?;
+  let host_callback: Box<wapc::HostCallbackAsync> = Box::new(|_, _, _, _, _| Box::pin(async { Ok(vec![]) }));
+  let host = WapcHostAsync::new(Box::new(engine), Some(host_callback)).await?;
+
+  assert_eq!(host.call("ping", b"hello").await?, b"hello");
+  Ok(())
+}
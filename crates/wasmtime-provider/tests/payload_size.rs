@@ -0,0 +1,122 @@
+use wapc::errors::Error;
+use wapc::WapcHost;
+use wasmtime_provider::WasmtimeEngineProviderBuilder;
+
+#[cfg(feature = "async")]
+use wapc::WapcHostAsync;
+
+// a guest with one page of memory, running `body` inside of `__guest_call`
+fn guest(body: &str) -> String {
+  format!(
+    r#"
+(module
+  (import "wapc" "__host_call" (func $host_call (param i32 i32 i32 i32 i32 i32 i32 i32) (result i32)))
+  (import "wapc" "__guest_error" (func $guest_error (param i32 i32)))
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    {}))
+"#,
+    body
+  )
+}
+
+const HUGE_RESPONSE: &str = "(call $guest_response (i32.const 0) (i32.const 0x7fffffff)) (i32.const 1)";
+const RESPONSE: &str = "(call $guest_response (i32.const 0) (i32.const 32)) (i32.const 1)";
+const HUGE_ERROR: &str = "(call $guest_error (i32.const 0) (i32.const -1)) (i32.const 0)";
+const OUT_OF_BOUNDS_RESPONSE: &str = "(call $guest_response (i32.const 65530) (i32.const 32)) (i32.const 1)";
+const HOST_CALL: &str = "(drop (call $host_call
+    (i32.const 0) (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 0) (i32.const 1)
+    (i32.const 0) (i32.const 32)))
+  (i32.const 0)";
+
+fn call(builder: WasmtimeEngineProviderBuilder, body: &str) -> Result<Vec<u8>, Error> {
+  let wat = guest(body);
+  let engine = builder.module_bytes(wat.as_bytes()).build()?;
+  let host = WapcHost::new(
+    Box::new(engine),
+    Some(Box::new(|_, _, _, _, _| {
+      panic!("the host callback should not be invoked");
+    })),
+  )?;
+  host.call("test", b"")
+}
+
+fn assert_guest_failure(result: Result<Vec<u8>, Error>, expected: &str) {
+  match result {
+    Err(Error::GuestCallFailure(msg)) => assert_eq!(msg, expected),
+    res => panic!("the guest call should have failed, got {:?}", res),
+  }
+}
+
+#[test]
+fn guest_response_larger_than_memory() {
+  assert_guest_failure(
+    call(WasmtimeEngineProviderBuilder::new(), HUGE_RESPONSE),
+    "guest response of 2147483647 bytes exceeds the guest memory size of 65536 bytes",
+  );
+}
+
+#[test]
+fn guest_response_larger_than_max_payload_size() {
+  assert_guest_failure(
+    call(WasmtimeEngineProviderBuilder::new().max_payload_size(16), RESPONSE),
+    "guest response of 32 bytes exceeds the maximum payload size of 16 bytes",
+  );
+  assert_eq!(
+    call(WasmtimeEngineProviderBuilder::new().max_payload_size(32), RESPONSE).unwrap(),
+    vec![0; 32]
+  );
+}
+
+#[test]
+fn guest_response_out_of_bounds() {
+  assert_guest_failure(
+    call(WasmtimeEngineProviderBuilder::new(), OUT_OF_BOUNDS_RESPONSE),
+    "guest response of 32 bytes at 65530 is out of the guest memory bounds",
+  );
+}
+
+#[test]
+fn guest_error_larger_than_memory() {
+  assert_guest_failure(
+    call(WasmtimeEngineProviderBuilder::new(), HUGE_ERROR),
+    "guest error of 4294967295 bytes exceeds the guest memory size of 65536 bytes",
+  );
+}
+
+#[test]
+fn host_call_payload_larger_than_max_payload_size() {
+  assert_guest_failure(
+    call(WasmtimeEngineProviderBuilder::new().max_payload_size(16), HOST_CALL),
+    "host call payload of 32 bytes exceeds the maximum payload size of 16 bytes",
+  );
+}
+
+#[cfg(feature = "async")]
+async fn call_async(builder: WasmtimeEngineProviderBuilder<'_>, body: &str) -> Result<Vec<u8>, Error> {
+  let wat = guest(body);
+  let engine = builder.module_bytes(wat.as_bytes()).build_async()?;
+  let host_callback: Box<wapc::HostCallbackAsync> = Box::new(|_, _, _, _, _| {
+    panic!("the host callback should not be invoked");
+  });
+  let host = WapcHostAsync::new(Box::new(engine), Some(host_callback)).await?;
+  host.call("test", b"").await
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn payloads_checked_async() {
+  assert_guest_failure(
+    call_async(WasmtimeEngineProviderBuilder::new(), HUGE_RESPONSE).await,
+    "guest response of 2147483647 bytes exceeds the guest memory size of 65536 bytes",
+  );
+  assert_guest_failure(
+    call_async(WasmtimeEngineProviderBuilder::new(), HUGE_ERROR).await,
+    "guest error of 4294967295 bytes exceeds the guest memory size of 65536 bytes",
+  );
+  assert_guest_failure(
+    call_async(WasmtimeEngineProviderBuilder::new().max_payload_size(16), HOST_CALL).await,
+    "host call payload of 32 bytes exceeds the maximum payload size of 16 bytes",
+  );
+}
@@ -0,0 +1,107 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use wasmtime_provider::{WasmtimeEngineProviderBuilder, WasmtimeEngineProviderPre};
+
+const MODULE_A: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param i32 i32) (result i32) (i32.const 1))
+  (func (export "a")))
+"#;
+
+const MODULE_B: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param i32 i32) (result i32) (i32.const 1))
+  (func (export "b")))
+"#;
+
+fn cache_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("wapc-precompile-{}-{}", name, std::process::id()));
+  let _ = std::fs::remove_dir_all(&dir);
+  dir
+}
+
+fn artifacts(dir: &Path) -> HashSet<PathBuf> {
+  std::fs::read_dir(dir)
+    .unwrap()
+    .map(|entry| entry.unwrap().path())
+    .collect()
+}
+
+fn build(module: &str, dir: &Path, force_recompile: bool) -> WasmtimeEngineProviderPre {
+  WasmtimeEngineProviderBuilder::new()
+    .module_bytes(module.as_bytes())
+    .precompile_cache_dir(dir)
+    .force_recompile(force_recompile)
+    .build_pre()
+    .unwrap()
+}
+
+// Build both modules once, returns the paths of their artifacts
+fn populate(dir: &Path) -> (PathBuf, PathBuf) {
+  build(MODULE_A, dir, false);
+  let after_a = artifacts(dir);
+  assert_eq!(after_a.len(), 1);
+  build(MODULE_B, dir, false);
+  let after_b = artifacts(dir);
+  assert_eq!(after_b.len(), 2);
+
+  let a = after_a.into_iter().next().unwrap();
+  let b = after_b.into_iter().find(|path| *path != a).unwrap();
+  (a, b)
+}
+
+#[test]
+fn second_build_loads_the_artifact() {
+  let dir = cache_dir("load");
+  let (a, b) = populate(&dir);
+
+  // the artifact of the module A is replaced by the one of the module B: loading it instead
+  // of compiling the module A gives away the cache hit
+  std::fs::copy(&b, &a).unwrap();
+  let pre = build(MODULE_A, &dir, false);
+  assert!(pre.has_export("b"));
+  assert!(!pre.has_export("a"));
+
+  // unless the compilation is forced, which also replaces the artifact
+  let pre = build(MODULE_A, &dir, true);
+  assert!(pre.has_export("a"));
+  let pre = build(MODULE_A, &dir, false);
+  assert!(pre.has_export("a"));
+
+  std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn corrupted_artifact_is_compiled_again() {
+  let dir = cache_dir("corrupted");
+  let (a, _) = populate(&dir);
+
+  std::fs::write(&a, b"not a precompiled module").unwrap();
+  let pre = build(MODULE_A, &dir, false);
+  assert!(pre.has_export("a"));
+  assert_ne!(std::fs::read(&a).unwrap(), b"not a precompiled module");
+
+  let pre = build(MODULE_A, &dir, false);
+  assert!(pre.has_export("a"));
+
+  std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn provided_module_is_rejected() {
+  let engine = wasmtime::Engine::default();
+  let module = wasmtime::Module::new(&engine, MODULE_A).unwrap();
+  let result = WasmtimeEngineProviderBuilder::new()
+    .engine(engine)
+    .module(module)
+    .precompile_cache_dir(cache_dir("rejected"))
+    .build_pre();
+
+  assert!(matches!(
+    result,
+    Err(wasmtime_provider::errors::Error::BuilderInvalidConfig(_))
+  ));
+}
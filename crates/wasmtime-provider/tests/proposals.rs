@@ -0,0 +1,78 @@
+use wasmtime_provider::errors::Error;
+use wasmtime_provider::WasmtimeEngineProviderBuilder;
+
+const SIMD_MODULE: &str = r#"
+(module
+  (func (export "splat") (param i32) (result i32)
+    local.get 0
+    i32x4.splat
+    i32x4.extract_lane 0))
+"#;
+
+const SHARED_MEMORY_MODULE: &str = r#"
+(module
+  (memory (export "memory") 1 1 shared))
+"#;
+
+#[test]
+fn simd_module_loads_when_simd_is_enabled() {
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(SIMD_MODULE.as_bytes())
+    .wasm_simd(true)
+    .build_pre();
+  assert!(result.is_ok());
+}
+
+#[test]
+fn simd_module_fails_to_load_when_simd_is_disabled() {
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(SIMD_MODULE.as_bytes())
+    .wasm_simd(false)
+    .build_pre();
+  assert!(matches!(result, Err(Error::Generic(_))));
+}
+
+#[test]
+fn shared_memory_requires_threads() {
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(SHARED_MEMORY_MODULE.as_bytes())
+    .wasm_threads(true)
+    .build_pre();
+  assert!(result.is_ok());
+
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(SHARED_MEMORY_MODULE.as_bytes())
+    .wasm_threads(false)
+    .build_pre();
+  assert!(matches!(result, Err(Error::Generic(_))));
+}
+
+#[test]
+fn bulk_memory_can_be_disabled() {
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(br#"(module (func (export "hello")))"#)
+    .wasm_bulk_memory(false)
+    .wasm_multi_memory(false)
+    .build_pre();
+  assert!(result.is_ok());
+}
+
+#[test]
+fn relaxed_simd_requires_simd() {
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(br#"(module (func (export "hello")))"#)
+    .wasm_simd(false)
+    .wasm_relaxed_simd(true)
+    .build_pre();
+  assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+}
+
+#[test]
+fn proposals_cannot_be_used_with_custom_engine() {
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(br#"(module (func (export "hello")))"#)
+    .engine(wasmtime::Engine::default())
+    .wasm_simd(false)
+    .build_pre();
+  assert!(matches!(result, Err(Error::BuilderInvalidConfig(_))));
+}
@@ -0,0 +1,69 @@
+use std::fs::read;
+use std::sync::Mutex;
+
+use wapc::{errors, WapcHost};
+use wasmtime_provider::WasmtimeEngineProviderBuilder;
+
+const MODULE: &str = "../../wasm/crates/wasm-basic/build/wasm_basic.wasm";
+
+// Keeps the messages logged by the crate
+struct Logger(Mutex<Vec<String>>);
+
+impl log::Log for Logger {
+  fn enabled(&self, _metadata: &log::Metadata<'_>) -> bool {
+    true
+  }
+
+  fn log(&self, record: &log::Record<'_>) {
+    if record.target().starts_with("wasmtime_provider") {
+      self.0.lock().unwrap().push(record.args().to_string());
+    }
+  }
+
+  fn flush(&self) {}
+}
+
+static LOGGER: Logger = Logger(Mutex::new(Vec::new()));
+
+#[test]
+fn name_is_included_in_the_logs() -> Result<(), errors::Error> {
+  log::set_logger(&LOGGER).unwrap();
+  log::set_max_level(log::LevelFilter::Trace);
+
+  let module_bytes = read(MODULE)?;
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .name("billing")
+    .build()?;
+  assert_eq!(engine.name(), "billing");
+  let host = WapcHost::new(Box::new(engine), Some(Box::new(|_, _, _, _, _| Ok(vec![]))))?;
+
+  host.replace_module(&module_bytes)?;
+  // the guest traps on unknown operations
+  assert!(host.call("unknown", b"").is_err());
+
+  let messages = LOGGER.0.lock().unwrap();
+  let hot_swap = messages.iter().find(|message| message.contains("HOT SWAP")).unwrap();
+  assert!(hot_swap.starts_with("[billing] "), "{hot_swap}");
+  // the tracing events forwarded to the logger carry the name as a field
+  assert!(
+    messages
+      .iter()
+      .all(|message| message.starts_with("[billing] ") || message.contains("provider=billing")),
+    "{messages:?}"
+  );
+  Ok(())
+}
+
+#[test]
+fn name_defaults_to_the_module_hash() -> Result<(), errors::Error> {
+  let module_bytes = read(MODULE)?;
+  let pre = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .build_pre()?;
+
+  let hash = pre.module_hash().unwrap();
+  assert_eq!(pre.name(), &hash[..12]);
+  assert_eq!(pre.rehydrate()?.name(), pre.name());
+  Ok(())
+}
@@ -0,0 +1,50 @@
+use std::fs::read;
+
+use wapc::{errors, WapcHost};
+
+#[cfg(feature = "async")]
+use wapc::WapcHostAsync;
+
+const REACTOR_GUEST: &str = "../../wasm/wasi_reactor.wat";
+
+#[test]
+fn reactor_is_initialized() -> Result<(), errors::Error> {
+  let module_bytes = read(REACTOR_GUEST)?;
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .build()?;
+  let host = WapcHost::new(Box::new(engine), None)?;
+
+  let response = host.call("init_log", b"")?;
+  assert_eq!(String::from_utf8(response).unwrap(), "_initialize wapc_init ");
+  Ok(())
+}
+
+#[test]
+fn reactor_initialization_respects_the_init_deadline() -> Result<(), errors::Error> {
+  let module_bytes = read(REACTOR_GUEST)?;
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .enable_epoch_interruptions(0, 1000)
+    .build()?;
+
+  match WapcHost::new(Box::new(engine), None) {
+    Err(errors::Error::InitFailed(msg)) => assert!(msg.contains("_initialize"), "{}", msg),
+    res => panic!("the initialization should have been interrupted, got {:?}", res),
+  }
+  Ok(())
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn reactor_is_initialized_async() -> Result<(), errors::Error> {
+  let module_bytes = read(REACTOR_GUEST)?;
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .build_async()?;
+  let host = WapcHostAsync::new(Box::new(engine), None).await?;
+
+  let response = host.call("init_log", b"").await?;
+  assert_eq!(String::from_utf8(response).unwrap(), "_initialize wapc_init ");
+  Ok(())
+}
@@ -0,0 +1,53 @@
+use std::fs::read;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use wapc::{errors, ModuleState, WapcHost};
+use wasmtime_provider::WasmtimeEngineProviderBuilder;
+
+const MODULE: &str = "../../wasm/crates/wasm-basic/build/wasm_basic.wasm";
+const ROUNDS: u32 = 500;
+
+// Average time taken by `create` to return an initialized host
+fn average(mut create: impl FnMut() -> Result<WapcHost, errors::Error>) -> Result<Duration, errors::Error> {
+  // warm up the allocator and the instance pool of the engine
+  for _ in 0..20 {
+    let _ = create()?;
+  }
+  let start = Instant::now();
+  for _ in 0..ROUNDS {
+    let _ = create()?;
+  }
+  Ok(start.elapsed() / ROUNDS)
+}
+
+#[test]
+fn rehydrate_cost() -> Result<(), errors::Error> {
+  let module_bytes = read(MODULE)?;
+  let pre = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .build_pre()?;
+  let provider = pre.rehydrate()?;
+
+  let clone = average(|| WapcHost::new(Box::new(provider.clone()), None))?;
+  let rehydrate = average(|| WapcHost::new(Box::new(pre.rehydrate()?), None))?;
+  let with_host = average(|| {
+    let state = Arc::new(ModuleState::with_callback(None));
+    WapcHost::new_with_state(Box::new(pre.rehydrate_with_host(state.clone())?), state)
+  })?;
+
+  println!(
+    "clone: {}μs, rehydrate: {}μs, rehydrate_with_host: {}μs",
+    clone.as_micros(),
+    rehydrate.as_micros(),
+    with_host.as_micros()
+  );
+  // `rehydrate_with_host` skips the second `Store` created by `init`, allow some noise
+  assert!(
+    with_host <= rehydrate + rehydrate / 4,
+    "rehydrate_with_host ({:?}) is slower than rehydrate ({:?})",
+    with_host,
+    rehydrate
+  );
+  Ok(())
+}
@@ -0,0 +1,64 @@
+use wapc::{errors, WapcHost};
+use wapc_codec::messagepack::serialize;
+use wasmtime_provider::WasmtimeEngineProviderBuilder;
+
+const MODULE1: &str = "../../wasm/crates/wasm-calc-hash/module1/build/module1_hash.wasm";
+const MODULE2: &str = "../../wasm/crates/wasm-calc-hash/module2/build/module2_hash.wasm";
+
+// fails during the waPC initialization
+const FAILING_INIT_GUEST: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "wapc_init") unreachable)
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (i32.const 1)))
+"#;
+
+// An engine whose pooling allocator can hold the instances of two stores at most:
+// the one of the running module and the one of the module being swapped in
+fn pooling_engine() -> wasmtime::Engine {
+  let mut pooling = wasmtime::PoolingAllocationConfig::default();
+  pooling.total_core_instances(2).total_memories(2).total_tables(2);
+  let mut config = wasmtime::Config::new();
+  config.allocation_strategy(wasmtime::InstanceAllocationStrategy::Pooling(pooling));
+  wasmtime::Engine::new(&config).unwrap()
+}
+
+fn new_host(module_bytes: &[u8], engine: wasmtime::Engine) -> Result<WapcHost, errors::Error> {
+  let provider = WasmtimeEngineProviderBuilder::new()
+    .engine(engine)
+    .module_bytes(module_bytes)
+    .build()?;
+  WapcHost::new(Box::new(provider), Some(Box::new(|_, _, _, _, _| Ok(vec![]))))
+}
+
+fn payload() -> Vec<u8> {
+  serialize(std::collections::HashMap::from([("first_name", "John Doe")])).unwrap()
+}
+
+#[test]
+fn swaps_release_the_previous_store() -> Result<(), errors::Error> {
+  let modules = [std::fs::read(MODULE1)?, std::fs::read(MODULE2)?];
+  let host = new_host(&modules[0], pooling_engine())?;
+
+  // the pool would be exhausted if the instances of the previous modules were kept alive
+  for i in 0..50 {
+    host.replace_module(&modules[i % 2])?;
+    host.call("serdes_example", &payload())?;
+  }
+  Ok(())
+}
+
+#[test]
+fn failed_swap_keeps_the_running_module() -> Result<(), errors::Error> {
+  let module_bytes = std::fs::read(MODULE1)?;
+  let host = new_host(&module_bytes, wasmtime::Engine::default())?;
+  let before = host.call("serdes_example", &payload())?;
+
+  assert!(matches!(
+    host.replace_module(FAILING_INIT_GUEST.as_bytes()),
+    Err(errors::Error::ReplacementFailed(_))
+  ));
+  assert_eq!(host.call("serdes_example", &payload())?, before);
+  Ok(())
+}
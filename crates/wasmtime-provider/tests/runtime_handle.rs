@@ -0,0 +1,37 @@
+#![cfg(feature = "async")]
+
+use std::fs::read;
+use std::sync::Arc;
+
+use wapc::{errors, ModuleStateAsync, WapcHostAsync};
+use wapc_codec::messagepack::{deserialize, serialize};
+use wasmtime_provider::WasmtimeEngineProviderBuilder;
+
+#[test]
+fn provider_built_outside_of_a_runtime() -> Result<(), errors::Error> {
+  let buf = read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm")?;
+  let runtime = tokio::runtime::Runtime::new().unwrap();
+  let handle = runtime.handle().clone();
+
+  // the provider is built, initialized and cloned on a thread without any runtime
+  let engine = std::thread::spawn(move || {
+    let pre = WasmtimeEngineProviderBuilder::new()
+      .module_bytes(&buf)
+      .runtime_handle(handle.clone())
+      .build_async_pre()
+      .unwrap();
+    let state = Arc::new(ModuleStateAsync::with_callback(None));
+    let engine = handle.block_on(pre.rehydrate_with_host(state)).unwrap();
+    engine.clone()
+  })
+  .join()
+  .unwrap();
+
+  runtime.block_on(async {
+    let host = WapcHostAsync::new(Box::new(engine), None).await?;
+    let callresult = host.call("echo", &serialize("hello world").unwrap()).await?;
+    let result: String = deserialize(&callresult).unwrap();
+    assert_eq!(result, "hello world");
+    Ok(())
+  })
+}
@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use wapc::{errors, ModuleState, WapcHost, WebAssemblyEngineProvider};
+use wasmtime_provider::WasmtimeEngineProviderBuilder;
+
+#[cfg(feature = "async")]
+use wapc::WapcHostAsync;
+
+// keeps a counter inside of its memory and another one inside of an exported global,
+// both start from 100 after `wapc_init`. Each call increments them and returns them
+const COUNTER_GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (memory (export "memory") 1)
+  (global $calls (export "calls") (mut i32) (i32.const 0))
+  (func (export "wapc_init")
+    (i32.store (i32.const 0) (i32.const 100))
+    (global.set $calls (i32.const 100)))
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (i32.store (i32.const 0) (i32.add (i32.load (i32.const 0)) (i32.const 1)))
+    (global.set $calls (i32.add (global.get $calls) (i32.const 1)))
+    (i32.store (i32.const 4) (global.get $calls))
+    (call $guest_response (i32.const 0) (i32.const 8))
+    (i32.const 1)))
+"#;
+
+// grows its memory by one page at each call and returns the number of pages
+const GROWING_GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (drop (memory.grow (i32.const 1)))
+    (i32.store (i32.const 0) (memory.size))
+    (call $guest_response (i32.const 0) (i32.const 4))
+    (i32.const 1)))
+"#;
+
+fn counters(response: &[u8]) -> (i32, i32) {
+  (
+    i32::from_le_bytes(response[..4].try_into().unwrap()),
+    i32::from_le_bytes(response[4..].try_into().unwrap()),
+  )
+}
+
+#[test]
+fn counter_keeps_growing_by_default() -> Result<(), errors::Error> {
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(COUNTER_GUEST.as_bytes())
+    .build()?;
+  let host = WapcHost::new(Box::new(engine), None)?;
+
+  assert_eq!(counters(&host.call("count", b"")?), (101, 101));
+  assert_eq!(counters(&host.call("count", b"")?), (102, 102));
+  Ok(())
+}
+
+#[test]
+fn counter_is_reset_between_calls() -> Result<(), errors::Error> {
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(COUNTER_GUEST.as_bytes())
+    .reset_memory_between_calls(true)
+    .build()?;
+  let host = WapcHost::new(Box::new(engine), None)?;
+
+  assert_eq!(counters(&host.call("count", b"")?), (101, 101));
+  assert_eq!(counters(&host.call("count", b"")?), (101, 101));
+  Ok(())
+}
+
+#[test]
+fn memory_size_is_reset_between_calls() -> Result<(), errors::Error> {
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(GROWING_GUEST.as_bytes())
+    .reset_memory_between_calls(true)
+    .build()?;
+  let host = WapcHost::new(Box::new(engine), None)?;
+
+  for _ in 0..100 {
+    assert_eq!(host.call("grow", b"")?, 2_i32.to_le_bytes());
+  }
+  Ok(())
+}
+
+#[test]
+fn snapshot_and_restore() -> Result<(), errors::Error> {
+  let pre = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(COUNTER_GUEST.as_bytes())
+    .build_pre()?;
+  let state = Arc::new(ModuleState::with_callback(None));
+  let mut engine = pre.rehydrate_with_host(state.clone())?;
+
+  engine.call(0, 0).unwrap();
+  let snapshot = engine.snapshot().unwrap();
+  assert_eq!(snapshot.memory_size(), 64 * 1024);
+
+  engine.call(0, 0).unwrap();
+  engine.call(0, 0).unwrap();
+  assert_eq!(counters(&state.get_guest_response().unwrap()), (103, 103));
+
+  engine.restore(&snapshot)?;
+  engine.call(0, 0).unwrap();
+  assert_eq!(counters(&state.get_guest_response().unwrap()), (102, 102));
+  Ok(())
+}
+
+#[test]
+fn snapshot_requires_initialization() -> Result<(), errors::Error> {
+  let mut engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(COUNTER_GUEST.as_bytes())
+    .build()?;
+  assert!(engine.snapshot().is_none());
+  Ok(())
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn counter_is_reset_between_calls_async() -> Result<(), errors::Error> {
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(COUNTER_GUEST.as_bytes())
+    .reset_memory_between_calls(true)
+    .build_async()?;
+  let host = WapcHostAsync::new(Box::new(engine), None).await?;
+
+  assert_eq!(counters(&host.call("count", b"").await?), (101, 101));
+  assert_eq!(counters(&host.call("count", b"").await?), (101, 101));
+  Ok(())
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn memory_size_is_reset_between_calls_async() -> Result<(), errors::Error> {
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(GROWING_GUEST.as_bytes())
+    .reset_memory_between_calls(true)
+    .build_async()?;
+  let host = WapcHostAsync::new(Box::new(engine), None).await?;
+
+  for _ in 0..100 {
+    assert_eq!(host.call("grow", b"").await?, 2_i32.to_le_bytes());
+  }
+  Ok(())
+}
@@ -0,0 +1,61 @@
+use std::fs::read;
+
+use wapc::{errors, WapcHost};
+use wapc_codec::messagepack::{deserialize, serialize};
+use wasmtime_provider::WasmtimeEngineProviderBuilder;
+
+fn echo(strategy: wasmtime::Strategy) -> Result<(), errors::Error> {
+  let buf = read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm")?;
+
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&buf)
+    .strategy(strategy)
+    .build()?;
+  let guest = WapcHost::new(Box::new(engine), None)?;
+
+  let callresult = guest.call("echo", &serialize("hello world").unwrap())?;
+  let result: String = deserialize(&callresult).unwrap();
+  assert_eq!(result, "hello world");
+  Ok(())
+}
+
+#[test]
+fn runs_with_cranelift() -> Result<(), errors::Error> {
+  echo(wasmtime::Strategy::Cranelift)
+}
+
+#[cfg(all(feature = "winch", target_arch = "x86_64"))]
+#[test]
+fn runs_with_winch() -> Result<(), errors::Error> {
+  echo(wasmtime::Strategy::Winch)
+}
+
+#[test]
+fn strategy_cannot_be_used_with_custom_engine() {
+  let engine = wasmtime::Engine::default();
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(br#"(module (func (export "hello")))"#)
+    .engine(engine)
+    .strategy(wasmtime::Strategy::Cranelift)
+    .build_pre();
+
+  assert!(matches!(
+    result,
+    Err(wasmtime_provider::errors::Error::BuilderInvalidConfig(_))
+  ));
+}
+
+#[cfg(feature = "cache")]
+#[test]
+fn winch_cannot_be_used_with_cache() {
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(br#"(module (func (export "hello")))"#)
+    .enable_cache(None)
+    .strategy(wasmtime::Strategy::Winch)
+    .build_pre();
+
+  assert!(matches!(
+    result,
+    Err(wasmtime_provider::errors::Error::BuilderInvalidConfig(_))
+  ));
+}
@@ -0,0 +1,106 @@
+#![cfg(feature = "tracing")]
+
+use std::fmt::Debug;
+use std::fs::read;
+use std::sync::{Arc, Mutex};
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::Layer;
+use wapc::{errors, WapcHost};
+
+// A span or an event, with its fields
+#[derive(Debug)]
+struct Record {
+  name: String,
+  fields: Vec<(String, String)>,
+}
+
+impl Record {
+  fn field(&self, name: &str) -> Option<&str> {
+    self.fields.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+  }
+}
+
+#[derive(Default)]
+struct FieldCollector(Vec<(String, String)>);
+
+impl Visit for FieldCollector {
+  fn record_str(&mut self, field: &Field, value: &str) {
+    self.0.push((field.name().to_owned(), value.to_owned()));
+  }
+
+  fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+    self.0.push((field.name().to_owned(), format!("{:?}", value)));
+  }
+}
+
+// Keeps the spans and the events emitted while the guest is running
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<Record>>>);
+
+impl<S: Subscriber> Layer<S> for Recorder {
+  fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+    let mut fields = FieldCollector::default();
+    attrs.record(&mut fields);
+    self.0.lock().unwrap().push(Record {
+      name: attrs.metadata().name().to_owned(),
+      fields: fields.0,
+    });
+  }
+
+  fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+    let mut fields = FieldCollector::default();
+    event.record(&mut fields);
+    self.0.lock().unwrap().push(Record {
+      name: event.metadata().name().to_owned(),
+      fields: fields.0,
+    });
+  }
+}
+
+#[test]
+fn host_call_span_is_emitted() -> Result<(), errors::Error> {
+  let module_bytes = read("../../wasm/crates/wasm-basic/build/wasm_basic.wasm")?;
+  let recorder = Recorder::default();
+  let subscriber = tracing_subscriber::registry().with(recorder.clone());
+
+  let payload = b"secret payload";
+  tracing::subscriber::with_default(subscriber, || -> Result<(), errors::Error> {
+    let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+      .module_bytes(&module_bytes)
+      .name("basic")
+      .build()?;
+    let host = WapcHost::new(Box::new(engine), Some(Box::new(|_, _, _, _, _| Ok(vec![]))))?;
+    host.call("ping", payload)?;
+    Ok(())
+  })?;
+
+  let records = recorder.0.lock().unwrap();
+  let host_call = records
+    .iter()
+    .find(|record| record.name == "wapc.host_call")
+    .expect("the host call span should have been emitted");
+  assert_eq!(host_call.field("ns"), Some("sample:namespace"));
+  assert_eq!(host_call.field("op"), Some("pong"));
+  assert_eq!(host_call.field("payload_len"), Some("14"));
+  assert!(host_call.field("module_id").is_some());
+  assert_eq!(host_call.field("provider"), Some("basic"));
+
+  let guest_response = records
+    .iter()
+    .find(|record| record.name == "wapc.guest_response")
+    .expect("the guest response event should have been emitted");
+  assert_eq!(guest_response.field("len"), Some("14"));
+  assert_eq!(guest_response.field("provider"), Some("basic"));
+
+  // the payloads are never recorded
+  let secret = String::from_utf8_lossy(payload);
+  assert!(records
+    .iter()
+    .all(|record| record.fields.iter().all(|(_, value)| !value.contains(secret.as_ref()))));
+
+  Ok(())
+}
@@ -0,0 +1,160 @@
+use wapc::{errors, WapcHost};
+use wasmtime_provider::errors::{OUT_OF_FUEL_PREFIX, OUT_OF_MEMORY_PREFIX, STACK_OVERFLOW_PREFIX, UNREACHABLE_PREFIX};
+
+#[cfg(feature = "async")]
+use wapc::WapcHostAsync;
+
+// grows the memory beyond its maximum, then aborts like a guest failing to allocate
+const OOM_GUEST: &str = r#"
+(module
+  (memory (export "memory") 1 2)
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (if (i32.eq (memory.grow (i32.const 10)) (i32.const -1))
+      (then unreachable))
+    (i32.const 1)))
+"#;
+
+const UNREACHABLE_GUEST: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    unreachable))
+"#;
+
+const STACK_OVERFLOW_GUEST: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (func $recurse (result i32)
+    (call $recurse))
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (call $recurse)))
+"#;
+
+const LOOP_GUEST: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (loop $forever (br $forever))
+    (i32.const 1)))
+"#;
+
+fn guest_error(wat: &str, builder: wasmtime_provider::WasmtimeEngineProviderBuilder) -> String {
+  let engine = builder.module_bytes(wat.as_bytes()).build().unwrap();
+  let host = WapcHost::new(Box::new(engine), None).unwrap();
+
+  match host.call("run", b"") {
+    Err(errors::Error::GuestCallFailure(msg)) => msg,
+    res => panic!("the guest call should have failed, got {:?}", res),
+  }
+}
+
+#[test]
+fn out_of_memory_trap() {
+  let msg = guest_error(OOM_GUEST, wasmtime_provider::WasmtimeEngineProviderBuilder::new());
+  assert!(msg.starts_with(OUT_OF_MEMORY_PREFIX), "{msg}");
+}
+
+#[test]
+fn unreachable_trap() {
+  let msg = guest_error(
+    UNREACHABLE_GUEST,
+    wasmtime_provider::WasmtimeEngineProviderBuilder::new(),
+  );
+  assert!(msg.starts_with(UNREACHABLE_PREFIX), "{msg}");
+}
+
+#[test]
+fn stack_overflow_trap() {
+  let msg = guest_error(
+    STACK_OVERFLOW_GUEST,
+    wasmtime_provider::WasmtimeEngineProviderBuilder::new(),
+  );
+  assert!(msg.starts_with(STACK_OVERFLOW_PREFIX), "{msg}");
+}
+
+#[test]
+fn out_of_fuel_trap() {
+  let mut config = wasmtime::Config::new();
+  config.consume_fuel(true);
+  let engine = wasmtime::Engine::new(&config).unwrap();
+
+  // the store starts without any fuel
+  let msg = guest_error(
+    LOOP_GUEST,
+    wasmtime_provider::WasmtimeEngineProviderBuilder::new().engine(engine),
+  );
+  assert!(msg.starts_with(OUT_OF_FUEL_PREFIX), "{msg}");
+}
+
+#[test]
+fn init_trap_is_mapped() {
+  let wat = r#"
+    (module
+      (memory (export "memory") 1)
+      (func (export "wapc_init") unreachable)
+      (func (export "__guest_call") (param i32 i32) (result i32) (i32.const 1)))
+  "#;
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(wat.as_bytes())
+    .build()
+    .unwrap();
+
+  match WapcHost::new(Box::new(engine), None) {
+    Err(errors::Error::InitFailed(msg)) => assert!(msg.starts_with(UNREACHABLE_PREFIX), "{msg}"),
+    res => panic!("the initialization should have failed, got {:?}", res.err()),
+  }
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn out_of_memory_trap_async() {
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(OOM_GUEST.as_bytes())
+    .build_async()
+    .unwrap();
+  let host = WapcHostAsync::new(Box::new(engine), None).await.unwrap();
+
+  match host.call("run", b"").await {
+    Err(errors::Error::GuestCallFailure(msg)) => assert!(msg.starts_with(OUT_OF_MEMORY_PREFIX), "{msg}"),
+    res => panic!("the guest call should have failed, got {:?}", res),
+  }
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn stack_overflow_trap_async() {
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(STACK_OVERFLOW_GUEST.as_bytes())
+    .build_async()
+    .unwrap();
+  let host = WapcHostAsync::new(Box::new(engine), None).await.unwrap();
+
+  match host.call("run", b"").await {
+    Err(errors::Error::GuestCallFailure(msg)) => assert!(msg.starts_with(STACK_OVERFLOW_PREFIX), "{msg}"),
+    res => panic!("the guest call should have failed, got {:?}", res),
+  }
+}
+
+#[test]
+fn func_deadline_exceeded() {
+  let mut config = wasmtime::Config::new();
+  config.epoch_interruption(true);
+  let engine = wasmtime::Engine::new(&config).unwrap();
+
+  let ticker = engine.clone();
+  std::thread::spawn(move || loop {
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    ticker.increment_epoch();
+  });
+
+  let msg = guest_error(
+    LOOP_GUEST,
+    wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+      .engine(engine)
+      .enable_epoch_interruptions(100, 2),
+  );
+  assert_eq!(
+    msg,
+    "guest code interrupted, func execution deadline of 2 epoch ticks exceeded"
+  );
+}
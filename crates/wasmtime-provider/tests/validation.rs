@@ -0,0 +1,115 @@
+use std::fs::read;
+
+use wasmtime_provider::errors::Error;
+use wasmtime_provider::{ExternKind, ValidationProblem, WasmtimeEngineProviderBuilder};
+
+const PLAIN_MODULE: &str = r#"
+(module
+  (import "env" "log" (func (param i32)))
+  (func (export "add") (param i32 i32) (result i32)
+    local.get 0
+    local.get 1
+    i32.add))
+"#;
+
+const MISMATCHED_GUEST: &str = r#"
+(module
+  (import "wapc" "__host_call" (func (param i32 i32) (result i32)))
+  (import "wapc" "__guest_response" (func (param i32 i32)))
+  (import "host" "now" (func (result i64)))
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param i32) (result i32)
+    i32.const 1))
+"#;
+
+#[test]
+fn plain_module() -> Result<(), Error> {
+  let report = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(PLAIN_MODULE.as_bytes())
+    .validate()?;
+
+  assert!(!report.is_valid());
+  assert!(report.wapc_imports.is_empty());
+  assert_eq!(
+    report.problems,
+    vec![
+      ValidationProblem::MissingExport {
+        name: "__guest_call".to_owned(),
+        kind: ExternKind::Func,
+      },
+      ValidationProblem::MissingExport {
+        name: "memory".to_owned(),
+        kind: ExternKind::Memory,
+      },
+      ValidationProblem::UnknownImport {
+        module: "env".to_owned(),
+        name: "log".to_owned(),
+      },
+    ]
+  );
+  assert_eq!(
+    report.to_string(),
+    "the module does not export the `__guest_call` function; \
+     the module does not export the `memory` memory; \
+     the import `env.log` is not provided by the host"
+  );
+  Ok(())
+}
+
+#[test]
+fn signature_mismatches() -> Result<(), Error> {
+  let report = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(MISMATCHED_GUEST.as_bytes())
+    .bridge_namespaces(&["host"])
+    .validate()?;
+
+  assert_eq!(report.wapc_imports, vec!["__host_call", "__guest_response"]);
+  assert_eq!(
+    report.problems,
+    vec![
+      ValidationProblem::ExportSignatureMismatch {
+        name: "__guest_call".to_owned(),
+        expected: "(i32, i32) -> i32".to_owned(),
+        found: "(i32) -> i32".to_owned(),
+      },
+      ValidationProblem::ImportSignatureMismatch {
+        module: "wapc".to_owned(),
+        name: "__host_call".to_owned(),
+        expected: "(i32, i32, i32, i32, i32, i32, i32, i32) -> i32".to_owned(),
+        found: "(i32, i32) -> i32".to_owned(),
+      },
+    ]
+  );
+  Ok(())
+}
+
+#[test]
+fn wapc_guest() -> Result<(), Error> {
+  let buf = read("../../wasm/crates/wapc-guest-test/build/wapc_guest_test.wasm").unwrap();
+  let report = WasmtimeEngineProviderBuilder::new().module_bytes(&buf).validate()?;
+
+  assert!(report.is_valid(), "{}", report);
+  assert!(report.wapc_imports.iter().any(|import| import == "__guest_request"));
+  Ok(())
+}
+
+#[test]
+fn validate_on_build() {
+  let module = r#"(module (func (export "_start")))"#;
+
+  // the module can be built, the failure would show up during the initialization
+  assert!(WasmtimeEngineProviderBuilder::new()
+    .module_bytes(module.as_bytes())
+    .build()
+    .is_ok());
+
+  match WasmtimeEngineProviderBuilder::new()
+    .module_bytes(module.as_bytes())
+    .validate_on_build(true)
+    .build()
+  {
+    Err(Error::InvalidModule(report)) => assert_eq!(report.problems.len(), 2),
+    Err(e) => panic!("unexpected error: {}", e),
+    Ok(_) => panic!("the build should have failed"),
+  }
+}
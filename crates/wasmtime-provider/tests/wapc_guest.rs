@@ -60,6 +60,53 @@ fn runs_wasm_calc_hash() -> Result<(), errors::Error> {
   Ok(())
 }
 
+#[test]
+fn replaces_module_with_precompiled_one() -> Result<(), errors::Error> {
+  let module_bytes1 = std::fs::read("../../wasm/crates/wasm-calc-hash/module1/build/module1_hash.wasm")?;
+  let module_bytes2 = std::fs::read("../../wasm/crates/wasm-calc-hash/module2/build/module2_hash.wasm")?;
+
+  let engine = wasmtime::Engine::default();
+  let precompiled2 = engine
+    .precompile_module(&module_bytes2)
+    .map_err(wasmtime_provider::errors::Error::from)?;
+  let provider = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .engine(engine)
+    .module_bytes(&module_bytes1)
+    .build()?;
+  let host = WapcHost::new(
+    Box::new(provider),
+    Some(Box::new(move |_id, _bd, _ns, _op, _payload| Ok(vec![]))),
+  )?;
+
+  let serbytes: Vec<u8> = serialize(&PersonSend {
+    first_name: "John Doe".to_string(),
+  })
+  .unwrap();
+  let res: PersonHashedRecv = deserialize(&host.call(WAPC_FUNCTION_NAME, &serbytes)?).unwrap();
+
+  // the module must have been precompiled by a compatible engine
+  let mut config = wasmtime::Config::new();
+  config.consume_fuel(true);
+  let incompatible = wasmtime::Engine::new(&config)
+    .and_then(|engine| engine.precompile_module(&module_bytes2))
+    .map_err(wasmtime_provider::errors::Error::from)?;
+  assert!(matches!(
+    host.replace_module_precompiled(&incompatible),
+    Err(errors::Error::ReplacementFailed(_))
+  ));
+  assert!(matches!(
+    host.replace_module_precompiled(&module_bytes2),
+    Err(errors::Error::ReplacementFailed(_))
+  ));
+
+  host.replace_module_precompiled(&precompiled2)?;
+  let res2: PersonHashedRecv = deserialize(&host.call(WAPC_FUNCTION_NAME, &serbytes)?).unwrap();
+  assert_ne!(res, res2);
+  assert_eq!(res2.first_name, "John Doe");
+
+  Ok(())
+}
+
 #[cfg(feature = "async")]
 async fn host_callback_async(
   _id: u64,
@@ -113,3 +160,78 @@ async fn runs_wapc_guest_async() -> Result<(), errors::Error> {
 
   Ok(())
 }
+
+#[test]
+fn collects_call_timings() -> Result<(), errors::Error> {
+  let module_bytes = std::fs::read("../../wasm/crates/wasm-calc-hash/module1/build/module1_hash.wasm")?;
+
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .enable_call_timings()
+    .build()?;
+  let timings = engine.call_timings_handle().expect("call timings should be enabled");
+  assert!(timings.last().is_none());
+
+  let host = WapcHost::new(
+    Box::new(engine),
+    Some(Box::new(move |_id, _bd, _ns, _op, _payload| {
+      std::thread::sleep(std::time::Duration::from_millis(1));
+      Ok(vec![])
+    })),
+  )?;
+
+  let person = PersonSend {
+    first_name: "John Doe".to_string(),
+  };
+  host.call(WAPC_FUNCTION_NAME, &serialize(&person).unwrap())?;
+
+  let last = timings.last().expect("timings of the last call should be available");
+  assert_eq!(last.host_call_count, 1);
+  assert!(last.wasm_time > std::time::Duration::ZERO);
+  assert!(last.host_time >= std::time::Duration::from_millis(1));
+
+  Ok(())
+}
+
+#[test]
+fn call_timings_are_disabled_by_default() -> Result<(), errors::Error> {
+  let module_bytes = std::fs::read("../../wasm/crates/wasm-calc-hash/module1/build/module1_hash.wasm")?;
+
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .build()?;
+  assert!(engine.call_timings_handle().is_none());
+  assert!(engine.last_call_timings().is_none());
+
+  Ok(())
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn collects_call_timings_async() -> Result<(), errors::Error> {
+  let module_bytes = std::fs::read("../../wasm/crates/wasm-calc-hash/module1/build/module1_hash.wasm")?;
+
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .enable_call_timings()
+    .build_async()?;
+  let timings = engine.call_timings_handle().expect("call timings should be enabled");
+
+  let host_callback: Box<wapc::HostCallbackAsync> = Box::new(move |id, bd, ns, op, payload| {
+    let fut = host_callback_async(id, bd, ns, op, payload);
+    Box::pin(fut)
+  });
+  let host = WapcHostAsync::new(Box::new(engine), Some(host_callback)).await?;
+
+  let person = PersonSend {
+    first_name: "John Doe".to_string(),
+  };
+  host.call(WAPC_FUNCTION_NAME, &serialize(&person).unwrap()).await?;
+
+  let last = timings.last().expect("timings of the last call should be available");
+  assert_eq!(last.host_call_count, 1);
+  assert!(last.wasm_time > std::time::Duration::ZERO);
+  assert!(last.host_time > std::time::Duration::ZERO);
+
+  Ok(())
+}
@@ -0,0 +1,72 @@
+use wapc::{errors, WapcHost};
+use wasmtime_provider::errors::Error;
+use wasmtime_provider::WasmtimeEngineProviderBuilder;
+
+#[cfg(feature = "async")]
+use wapc::WapcHostAsync;
+
+// Keeps the waPC buffers inside of `__wapc_memory`: its main memory has no room for them.
+// Forwards its payload to the host, then replaces the first byte of the host response.
+const SCRATCH_MEMORY_GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_request" (func $guest_request (param i32 i32)))
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (import "wapc" "__host_call" (func $host_call (param i32 i32 i32 i32 i32 i32 i32 i32) (result i32)))
+  (import "wapc" "__host_response_len" (func $host_response_len (result i32)))
+  (import "wapc" "__host_response" (func $host_response (param i32)))
+  (memory $heap (export "memory") 0 0)
+  (memory $wapc (export "__wapc_memory") 1)
+  (func (export "__guest_call") (param $op_len i32) (param $msg_len i32) (result i32)
+    (local $len i32)
+    (call $guest_request (i32.const 0) (i32.const 0x100))
+    (drop (call $host_call
+      (i32.const 0) (i32.const 0)
+      (i32.const 0) (i32.const 0)
+      (i32.const 0) (local.get $op_len)
+      (i32.const 0x100) (local.get $msg_len)))
+    (local.set $len (call $host_response_len))
+    (call $host_response (i32.const 0x8000))
+    (i32.store8 $wapc (i32.const 0x8000) (i32.const 0x21))
+    (call $guest_response (i32.const 0x8000) (local.get $len))
+    (i32.const 1)))
+"#;
+
+#[test]
+fn buffers_use_the_dedicated_memory() -> Result<(), errors::Error> {
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(SCRATCH_MEMORY_GUEST.as_bytes())
+    .build()?;
+  let host = WapcHost::new(
+    Box::new(engine),
+    Some(Box::new(|_, _, _, operation, payload| {
+      assert_eq!(operation, "upper");
+      Ok(payload.to_ascii_uppercase())
+    })),
+  )?;
+
+  assert_eq!(host.call("upper", b"hello world")?, b"!ELLO WORLD");
+  Ok(())
+}
+
+#[test]
+fn dedicated_memory_requires_multi_memory() {
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(SCRATCH_MEMORY_GUEST.as_bytes())
+    .wasm_multi_memory(false)
+    .build_pre();
+  assert!(matches!(result, Err(Error::Generic(_))));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn buffers_use_the_dedicated_memory_async() -> Result<(), errors::Error> {
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(SCRATCH_MEMORY_GUEST.as_bytes())
+    .build_async()?;
+  let host_callback: Box<wapc::HostCallbackAsync> =
+    Box::new(|_, _, _, _, payload| Box::pin(async move { Ok(payload.to_ascii_uppercase()) }));
+  let host = WapcHostAsync::new(Box::new(engine), Some(host_callback)).await?;
+
+  assert_eq!(host.call("upper", b"hello world").await?, b"!ELLO WORLD");
+  Ok(())
+}
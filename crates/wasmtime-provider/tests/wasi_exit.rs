@@ -0,0 +1,76 @@
+#![cfg(feature = "wasi")]
+
+use std::sync::Arc;
+
+use wapc::{errors, ModuleState, WapcHost, WebAssemblyEngineProvider};
+
+#[cfg(feature = "async")]
+use wapc::{ModuleStateAsync, WebAssemblyEngineProviderAsync};
+
+// mimics tinygo >= 0.35.0, whose `_start` function always invokes `proc_exit`
+const EXIT_ON_START_GUEST: &str = r#"
+(module
+  (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+  (memory (export "memory") 1)
+  (func (export "_start")
+    (call $proc_exit (i32.const 0)))
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (i32.const 1)))
+"#;
+
+const EXIT_ON_CALL_GUEST: &str = r#"
+(module
+  (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (call $proc_exit (i32.const 2))
+    (i32.const 1)))
+"#;
+
+fn builder(wat: &str) -> wasmtime_provider::WasmtimeEngineProviderBuilder<'_> {
+  wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(wat.as_bytes())
+    .wasi_params(wapc::WasiParams::default())
+}
+
+#[test]
+fn exit_code_zero_during_init() -> Result<(), errors::Error> {
+  let state = Arc::new(ModuleState::with_callback(None));
+  let engine = builder(EXIT_ON_START_GUEST).build_pre()?.rehydrate_with_host(state)?;
+
+  assert_eq!(engine.last_exit_code(), Some(0));
+  Ok(())
+}
+
+#[test]
+fn exit_code_during_call() -> Result<(), errors::Error> {
+  let state = Arc::new(ModuleState::with_callback(None));
+  let mut engine = builder(EXIT_ON_CALL_GUEST).build_pre()?.rehydrate_with_host(state)?;
+  assert_eq!(engine.last_exit_code(), None);
+
+  assert_eq!(engine.call(0, 0).unwrap(), 0);
+  assert_eq!(engine.last_exit_code(), Some(2));
+
+  let host = WapcHost::new(Box::new(builder(EXIT_ON_CALL_GUEST).build()?), None)?;
+  match host.call("run", b"") {
+    Err(errors::Error::GuestCallFailure(msg)) => assert_eq!(msg, "guest exited with code 2"),
+    res => panic!("the guest call should have failed, got {:?}", res),
+  }
+
+  Ok(())
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn exit_code_during_call_async() -> Result<(), errors::Error> {
+  let state = Arc::new(ModuleStateAsync::with_callback(None));
+  let mut engine = builder(EXIT_ON_CALL_GUEST)
+    .build_async_pre()?
+    .rehydrate_with_host(state)
+    .await?;
+
+  assert_eq!(engine.call(0, 0).await.unwrap(), 0);
+  assert_eq!(engine.last_exit_code(), Some(2));
+
+  Ok(())
+}
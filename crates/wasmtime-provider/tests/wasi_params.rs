@@ -0,0 +1,174 @@
+#![cfg(feature = "wasi")]
+
+use std::fs::read;
+
+use wapc::{errors, WapcHost, WasiParams};
+
+const ENVIRON_GUEST: &str = "../../wasm/wasi_environ.wat";
+
+// Returns the NUL separated entries sent back by the guest
+fn guest_entries(wasi_params: WasiParams, operation: &str) -> Result<Vec<String>, errors::Error> {
+  let module_bytes = read(ENVIRON_GUEST)?;
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .wasi_params(wasi_params)
+    .build()?;
+  let host = WapcHost::new(Box::new(engine), None)?;
+
+  let response = host.call(operation, b"")?;
+  Ok(
+    String::from_utf8(response)
+      .unwrap()
+      .split_terminator('\0')
+      .map(ToOwned::to_owned)
+      .collect(),
+  )
+}
+
+#[test]
+fn env_is_not_inherited_by_default() -> Result<(), errors::Error> {
+  std::env::set_var("WAPC_TEST_NOT_INHERITED", "host");
+
+  let env = guest_entries(
+    WasiParams::default().env_vars(vec![("GREETING".to_owned(), "hello".to_owned())]),
+    "env",
+  )?;
+  assert_eq!(env, vec!["GREETING=hello".to_owned()]);
+
+  Ok(())
+}
+
+#[test]
+fn inherit_env_with_allowlist() -> Result<(), errors::Error> {
+  std::env::set_var("WAPC_TEST_INHERITED", "host");
+  std::env::set_var("WAPC_TEST_OVERRIDDEN", "host");
+  std::env::set_var("WAPC_TEST_FILTERED", "host");
+
+  let env = guest_entries(
+    WasiParams::default()
+      .env_vars(vec![("WAPC_TEST_OVERRIDDEN".to_owned(), "explicit".to_owned())])
+      .inherit_env(true)
+      .env_allowlist(vec![
+        "WAPC_TEST_INHERITED".to_owned(),
+        "WAPC_TEST_OVERRIDDEN".to_owned(),
+      ]),
+    "env",
+  )?;
+
+  // the explicit values win over the inherited ones
+  assert_eq!(
+    env,
+    vec![
+      "WAPC_TEST_INHERITED=host".to_owned(),
+      "WAPC_TEST_OVERRIDDEN=explicit".to_owned()
+    ]
+  );
+
+  Ok(())
+}
+
+#[test]
+fn inherit_whole_env() -> Result<(), errors::Error> {
+  std::env::set_var("WAPC_TEST_WHOLE_ENV", "host");
+
+  let env = guest_entries(WasiParams::default().inherit_env(true), "env")?;
+  assert!(env.contains(&"WAPC_TEST_WHOLE_ENV=host".to_owned()));
+
+  Ok(())
+}
+
+#[test]
+fn inherit_args() -> Result<(), errors::Error> {
+  let args = guest_entries(WasiParams::default().inherit_args(true), "args")?;
+  assert_eq!(args, std::env::args().collect::<Vec<_>>());
+
+  // the explicit arguments win over the inherited ones
+  let args = guest_entries(
+    WasiParams::default()
+      .argv(vec!["guest".to_owned(), "--verbose".to_owned()])
+      .inherit_args(true),
+    "args",
+  )?;
+  assert_eq!(args, vec!["guest".to_owned(), "--verbose".to_owned()]);
+
+  Ok(())
+}
+
+#[test]
+fn wasi_ctx_hook() -> Result<(), errors::Error> {
+  let module_bytes = read(ENVIRON_GUEST)?;
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .wasi_params(WasiParams::default().env_vars(vec![("GREETING".to_owned(), "hello".to_owned())]))
+    .with_wasi_ctx(|ctx_builder| {
+      ctx_builder.env("FROM_HOOK", "yes")?;
+      Ok(())
+    })
+    .build()?;
+
+  // the hook runs again when the store is created by the init
+  let host = WapcHost::new(Box::new(engine), None)?;
+  let response = host.call("env", b"")?;
+  assert_eq!(String::from_utf8(response).unwrap(), "GREETING=hello\0FROM_HOOK=yes\0");
+
+  Ok(())
+}
+
+#[test]
+fn wasi_ctx_hook_failure() -> Result<(), errors::Error> {
+  let module_bytes = read(ENVIRON_GUEST)?;
+  let result = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .with_wasi_ctx(|_| Err(anyhow::anyhow!("boom")))
+    .build();
+
+  assert!(matches!(
+    result,
+    Err(wasmtime_provider::errors::Error::WasiInitCtxError(msg)) if msg == "boom"
+  ));
+  Ok(())
+}
+
+#[test]
+fn env_overrides_between_calls() -> Result<(), errors::Error> {
+  let module_bytes = read(ENVIRON_GUEST)?;
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .wasi_params(WasiParams::default().env_vars(vec![("GREETING".to_owned(), "hello".to_owned())]))
+    .build()?;
+  let env_overrides = engine.wasi_env_overrides();
+  let host = WapcHost::new(Box::new(engine), None)?;
+
+  env_overrides.set("REQUEST_ID", "1");
+  let response = host.call("env", b"")?;
+  assert_eq!(String::from_utf8(response).unwrap(), "GREETING=hello\0REQUEST_ID=1\0");
+
+  env_overrides.set("REQUEST_ID", "2");
+  let response = host.call("env", b"")?;
+  assert_eq!(String::from_utf8(response).unwrap(), "GREETING=hello\0REQUEST_ID=2\0");
+
+  env_overrides.clear();
+  let response = host.call("env", b"")?;
+  assert_eq!(String::from_utf8(response).unwrap(), "GREETING=hello\0");
+
+  Ok(())
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn wasi_ctx_hook_async() -> Result<(), errors::Error> {
+  let module_bytes = read(ENVIRON_GUEST)?;
+  let engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .with_wasi_ctx_async(|ctx_builder| {
+      ctx_builder.env("FROM_HOOK", "yes")?;
+      Ok(())
+    })
+    .build_async()?;
+
+  let host = wapc::WapcHostAsync::new(Box::new(engine), None).await?;
+  let response = host.call("env", b"").await?;
+  assert_eq!(String::from_utf8(response).unwrap(), "FROM_HOOK=yes\0");
+
+  Ok(())
+}
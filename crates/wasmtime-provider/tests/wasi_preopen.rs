@@ -0,0 +1,93 @@
+#![cfg(feature = "wasi")]
+
+use std::fs::read;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use wapc::{errors, ModuleState};
+
+#[cfg(feature = "async")]
+use wapc::ModuleStateAsync;
+
+const READ_FILE_GUEST: &str = "../../wasm/wasi_read_file.wat";
+
+// Directory holding the `data.txt` file read by the guest
+fn data_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("wapc-preopen-{}-{}", name, std::process::id()));
+  std::fs::create_dir_all(&dir).unwrap();
+  std::fs::write(dir.join("data.txt"), b"hello from the host").unwrap();
+  dir
+}
+
+// Invoke the guest, bypassing the waPC host which would take ownership of the provider
+fn guest_call(engine: &mut wasmtime_provider::WasmtimeEngineProvider) -> i32 {
+  engine
+    .with_store(|store, instance| {
+      let call = instance
+        .get_typed_func::<(i32, i32), i32>(&mut *store, "__guest_call")
+        .unwrap();
+      call.call(&mut *store, (0, 0)).unwrap()
+    })
+    .unwrap()
+}
+
+#[test]
+fn guest_reads_dir_pushed_after_init() -> Result<(), errors::Error> {
+  let module_bytes = read(READ_FILE_GUEST)?;
+  let pre = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .wasi_params(wapc::WasiParams::default())
+    .build_pre()?;
+  let dir = data_dir("sync");
+
+  // there's no context to change before the initialization
+  let mut engine = pre.rehydrate()?;
+  assert!(engine.wasi_ctx_mut().is_none());
+  assert!(engine.push_preopened_dir(&dir, "/data").is_err());
+
+  let state = Arc::new(ModuleState::with_callback(None));
+  let mut engine = pre.rehydrate_with_host(state.clone())?;
+
+  // the guest has no preopened directory yet
+  assert_eq!(guest_call(&mut engine), 0);
+
+  engine.push_preopened_dir(&dir, "/data")?;
+  assert_eq!(guest_call(&mut engine), 1);
+  assert_eq!(state.get_guest_response().unwrap(), b"hello from the host");
+
+  assert!(engine.push_preopened_dir(dir.join("missing"), "/missing").is_err());
+
+  std::fs::remove_dir_all(dir)?;
+  Ok(())
+}
+
+#[cfg(feature = "async")]
+#[tokio::test(flavor = "multi_thread")]
+async fn guest_reads_dir_pushed_after_init_async() -> Result<(), errors::Error> {
+  let module_bytes = read(READ_FILE_GUEST)?;
+  let state = Arc::new(ModuleStateAsync::with_callback(None));
+  let mut engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .wasi_params(wapc::WasiParams::default())
+    .build_async_pre()?
+    .rehydrate_with_host(state.clone())
+    .await?;
+  let dir = data_dir("async");
+
+  engine.push_preopened_dir(&dir, "/data")?;
+  let result = engine
+    .with_store_async(|store, instance| {
+      Box::pin(async move {
+        let call = instance
+          .get_typed_func::<(i32, i32), i32>(&mut *store, "__guest_call")
+          .unwrap();
+        call.call_async(&mut *store, (0, 0)).await.unwrap()
+      })
+    })
+    .await;
+  assert_eq!(result, Some(1));
+  assert_eq!(state.get_guest_response().await.unwrap(), b"hello from the host");
+
+  std::fs::remove_dir_all(dir)?;
+  Ok(())
+}
@@ -0,0 +1,74 @@
+#![cfg(not(feature = "wasi"))]
+
+use std::fs::read;
+use std::sync::Arc;
+
+use wapc::{errors, ModuleState, WapcHost};
+use wasmtime_provider::WasmtimeEngineProviderBuilder;
+
+const WASI_BASIC: &str = "../../wasm/crates/wasi-basic/build/wasi_basic.wasm";
+
+// calls `proc_exit` when invoked
+const EXIT_GUEST: &str = r#"
+(module
+  (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (call $proc_exit (i32.const 3))
+    (i32.const 1)))
+"#;
+
+fn host_callback(
+  _id: u64,
+  _bd: &str,
+  _ns: &str,
+  _op: &str,
+  payload: &[u8],
+) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+  Ok(payload.to_vec())
+}
+
+#[test]
+fn wasi_imports_require_stubs() -> Result<(), errors::Error> {
+  let module_bytes = read(WASI_BASIC)?;
+  let result = WasmtimeEngineProviderBuilder::new().module_bytes(&module_bytes).build();
+  assert!(result.is_err());
+  Ok(())
+}
+
+#[test]
+fn runs_wasi_basic_with_stubs() -> Result<(), errors::Error> {
+  let module_bytes = read(WASI_BASIC)?;
+  let builder = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(&module_bytes)
+    .stub_wasi_imports(true);
+  assert!(builder.validate()?.is_valid());
+
+  let engine = builder.build()?;
+  let host = WapcHost::new(Box::new(engine), Some(Box::new(host_callback)))?;
+
+  // the message printed by the guest is dropped
+  assert_eq!(host.call("ping", b"hello")?, b"hello");
+  Ok(())
+}
+
+#[test]
+fn proc_exit_stub_traps() -> Result<(), errors::Error> {
+  let mut engine = WasmtimeEngineProviderBuilder::new()
+    .module_bytes(EXIT_GUEST.as_bytes())
+    .stub_wasi_imports(true)
+    .build_pre()?
+    .rehydrate_with_host(Arc::new(ModuleState::with_callback(None)))?;
+
+  // invoke the guest directly, the waPC host doesn't keep the whole error chain
+  let err = engine
+    .with_store(|store, instance| {
+      let call = instance
+        .get_typed_func::<(i32, i32), i32>(&mut *store, "__guest_call")
+        .unwrap();
+      call.call(&mut *store, (0, 0)).unwrap_err()
+    })
+    .unwrap();
+  assert!(format!("{:?}", err).contains("proc_exit(3) invoked"), "{err:?}");
+  Ok(())
+}
@@ -0,0 +1,67 @@
+use wapc::{errors, WapcHost};
+use wasmtime_provider::WasmtimeEngineProviderBuilder;
+
+// hands over a response that ends past the guest memory
+const OUT_OF_BOUNDS_GUEST: &str = r#"
+(module
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (call $guest_response (i32.const 65530) (i32.const 100))
+    (i32.const 0)))
+"#;
+
+const UNREACHABLE_GUEST: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    unreachable))
+"#;
+
+#[test]
+fn out_of_bounds_guest_response() -> Result<(), errors::Error> {
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_wat(OUT_OF_BOUNDS_GUEST)
+    .build()?;
+  let host = WapcHost::new(Box::new(engine), None)?;
+
+  match host.call("run", b"") {
+    Err(errors::Error::GuestCallFailure(msg)) => assert!(msg.contains("out of the guest memory bounds"), "{msg}"),
+    res => panic!("the guest call should have failed, got {:?}", res),
+  }
+  Ok(())
+}
+
+#[test]
+fn unreachable_trap() -> Result<(), errors::Error> {
+  let engine = WasmtimeEngineProviderBuilder::new()
+    .module_wat(UNREACHABLE_GUEST)
+    .build()?;
+  let host = WapcHost::new(Box::new(engine), None)?;
+
+  match host.call("run", b"") {
+    Err(errors::Error::GuestCallFailure(msg)) => {
+      assert!(msg.starts_with(wasmtime_provider::errors::UNREACHABLE_PREFIX), "{msg}");
+    }
+    res => panic!("the guest call should have failed, got {:?}", res),
+  }
+  Ok(())
+}
+
+#[test]
+fn invalid_wat() {
+  let result = WasmtimeEngineProviderBuilder::new().module_wat("(module (func").build();
+  assert!(matches!(result, Err(wasmtime_provider::errors::Error::Wat(_))));
+}
+
+#[test]
+fn module_wat_and_bytes_are_exclusive() {
+  let result = WasmtimeEngineProviderBuilder::new()
+    .module_wat(UNREACHABLE_GUEST)
+    .module_bytes(UNREACHABLE_GUEST.as_bytes())
+    .build();
+  assert!(matches!(
+    result,
+    Err(wasmtime_provider::errors::Error::BuilderInvalidConfig(_))
+  ));
+}
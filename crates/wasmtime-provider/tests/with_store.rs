@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use wapc::{errors, ModuleState};
+
+#[cfg(feature = "async")]
+use wapc::ModuleStateAsync;
+
+// waPC guest exporting an extra function that is not part of the waPC protocol
+const GUEST: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "__guest_call") (param i32 i32) (result i32)
+    (i32.const 1))
+  (func (export "add") (param i32 i32) (result i32)
+    (i32.add (local.get 0) (local.get 1))))
+"#;
+
+#[test]
+fn with_store_calls_extra_export() -> Result<(), errors::Error> {
+  let pre = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(GUEST.as_bytes())
+    .build_pre()?;
+
+  // the store is not bound to any instance before the initialization
+  let mut engine = pre.rehydrate()?;
+  assert!(engine.with_store(|_, _| ()).is_none());
+
+  let mut engine = pre.rehydrate_with_host(Arc::new(ModuleState::with_callback(None)))?;
+  let sum = engine.with_store(|store, instance| {
+    assert!(store.data().host().is_some());
+    let add = instance.get_typed_func::<(i32, i32), i32>(&mut *store, "add").unwrap();
+    add.call(&mut *store, (1, 2)).unwrap()
+  });
+  assert_eq!(sum, Some(3));
+
+  Ok(())
+}
+
+#[cfg(feature = "wasi")]
+#[test]
+fn with_store_exposes_wasi_ctx() -> Result<(), errors::Error> {
+  let mut engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(GUEST.as_bytes())
+    .wasi_params(wapc::WasiParams::default())
+    .build_pre()?
+    .rehydrate_with_host(Arc::new(ModuleState::with_callback(None)))?;
+
+  let has_stdout = engine.with_store(|store, _| store.data().wasi_ctx().table().contains_key(1));
+  assert_eq!(has_stdout, Some(true));
+
+  Ok(())
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn with_store_async_calls_extra_export() -> Result<(), errors::Error> {
+  let mut engine = wasmtime_provider::WasmtimeEngineProviderBuilder::new()
+    .module_bytes(GUEST.as_bytes())
+    .build_async_pre()?
+    .rehydrate_with_host(Arc::new(ModuleStateAsync::with_callback(None)))
+    .await?;
+
+  let sum = engine
+    .with_store_async(|store, instance| {
+      Box::pin(async move {
+        let add = instance.get_typed_func::<(i32, i32), i32>(&mut *store, "add").unwrap();
+        add.call_async(&mut *store, (1, 2)).await.unwrap()
+      })
+    })
+    .await;
+  assert_eq!(sum, Some(3));
+
+  Ok(())
+}
@@ -0,0 +1,20 @@
+wit_bindgen::generate!({
+  world: "wapc",
+  path: "../../../crates/wasmtime-provider/wit",
+});
+
+use wapc::guest::host::{console_log, host_call};
+
+struct Component;
+
+impl Guest for Component {
+  fn guest_call(operation: String, payload: Vec<u8>) -> Result<Vec<u8>, String> {
+    console_log(&operation);
+    match operation.as_str() {
+      "ping" => host_call("binding", "sample:namespace", "pong", &payload),
+      _ => Ok(payload),
+    }
+  }
+}
+
+export!(Component);
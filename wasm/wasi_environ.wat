@@ -0,0 +1,34 @@
+;; waPC guest replying with its WASI environment variables, or with its command line
+;; arguments when invoked with the `args` operation. The entries are separated by NUL bytes.
+(module
+  (import "wapc" "__guest_request" (func $guest_request (param i32 i32)))
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (import "wapc" "__guest_error" (func $guest_error (param i32 i32)))
+  (import "wasi_snapshot_preview1" "environ_sizes_get" (func $environ_sizes_get (param i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "environ_get" (func $environ_get (param i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "args_sizes_get" (func $args_sizes_get (param i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "args_get" (func $args_get (param i32 i32) (result i32)))
+
+  ;; memory layout:
+  ;; - 0: operation name
+  ;; - 256: payload
+  ;; - 1024: number of entries
+  ;; - 1028: size of the entries
+  ;; - 2048: pointers to the entries
+  ;; - 16384: entries
+  (memory (export "memory") 4)
+
+  (func (export "__guest_call") (param $op_len i32) (param $msg_len i32) (result i32)
+    (call $guest_request (i32.const 0) (i32.const 256))
+
+    ;; operation starting with `a`
+    (if (i32.eq (i32.load8_u (i32.const 0)) (i32.const 97))
+      (then
+        (drop (call $args_sizes_get (i32.const 1024) (i32.const 1028)))
+        (drop (call $args_get (i32.const 2048) (i32.const 16384))))
+      (else
+        (drop (call $environ_sizes_get (i32.const 1024) (i32.const 1028)))
+        (drop (call $environ_get (i32.const 2048) (i32.const 16384)))))
+
+    (call $guest_response (i32.const 16384) (i32.load (i32.const 1028)))
+    (i32.const 1)))
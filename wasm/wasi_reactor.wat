@@ -0,0 +1,31 @@
+;; waPC guest built like a WASI reactor: its state is set up by `_initialize`, it traps when
+;; invoked without being initialized. It replies with the names of the start functions that
+;; have been invoked, in order, separated by spaces.
+(module
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+
+  ;; memory layout:
+  ;; - 0: names of the start functions
+  ;; - 1024: start functions invoked so far
+  (memory (export "memory") 1)
+  (data (i32.const 0) "_initialize wapc_init ")
+
+  (global $initialized (mut i32) (i32.const 0))
+  (global $log_len (mut i32) (i32.const 0))
+
+  (func $log (param $ptr i32) (param $len i32)
+    (memory.copy (i32.add (i32.const 1024) (global.get $log_len)) (local.get $ptr) (local.get $len))
+    (global.set $log_len (i32.add (global.get $log_len) (local.get $len))))
+
+  (func (export "_initialize")
+    (global.set $initialized (i32.const 1))
+    (call $log (i32.const 0) (i32.const 12)))
+
+  (func (export "wapc_init")
+    (call $log (i32.const 12) (i32.const 10)))
+
+  (func (export "__guest_call") (param $op_len i32) (param $msg_len i32) (result i32)
+    (if (i32.eqz (global.get $initialized))
+      (then (unreachable)))
+    (call $guest_response (i32.const 1024) (global.get $log_len))
+    (i32.const 1)))
@@ -0,0 +1,34 @@
+;; waPC guest replying with the contents of the `data.txt` file, looked up inside of the first
+;; preopened directory, which is file descriptor 3.
+(module
+  (import "wapc" "__guest_response" (func $guest_response (param i32 i32)))
+  (import "wapc" "__guest_error" (func $guest_error (param i32 i32)))
+  (import "wasi_snapshot_preview1" "path_open"
+    (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+
+  ;; memory layout:
+  ;; - 256: path of the file
+  ;; - 1024: error message
+  ;; - 2048: file descriptor of the opened file
+  ;; - 2052: number of bytes read
+  ;; - 2056: iovec, pointing to the file contents
+  ;; - 4096: file contents
+  (memory (export "memory") 1)
+  (data (i32.const 256) "data.txt")
+  (data (i32.const 1024) "cannot open the file")
+
+  (func (export "__guest_call") (param $op_len i32) (param $msg_len i32) (result i32)
+    ;; open the file for reading (rights: fd_read)
+    (if (call $path_open (i32.const 3) (i32.const 0) (i32.const 256) (i32.const 8) (i32.const 0)
+          (i64.const 2) (i64.const 0) (i32.const 0) (i32.const 2048))
+      (then
+        (call $guest_error (i32.const 1024) (i32.const 20))
+        (return (i32.const 0))))
+
+    (i32.store (i32.const 2056) (i32.const 4096))
+    (i32.store (i32.const 2060) (i32.const 4096))
+    (drop (call $fd_read (i32.load (i32.const 2048)) (i32.const 2056) (i32.const 1) (i32.const 2052)))
+
+    (call $guest_response (i32.const 4096) (i32.load (i32.const 2052)))
+    (i32.const 1)))